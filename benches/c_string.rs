@@ -0,0 +1,32 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+// The C string that a statement caches against the one that would otherwise
+// be created for every cursor, update or stream, run with
+// `cargo bench --bench c_string`.
+
+use {
+    criterion::{black_box, criterion_group, criterion_main, Criterion},
+    rdfox_rs::{Namespaces, Statement},
+    std::ffi::CString,
+};
+
+fn c_string(c: &mut Criterion) {
+    let prefixes = Namespaces::empty().unwrap();
+    let text = format!(
+        "SELECT ?s ?p ?o WHERE {{\n{}}}",
+        (0..100).map(|n| format!("  ?s <https://ekgf.org/p{n}> ?o{n} .\n")).collect::<String>()
+    );
+    let statement = Statement::new(&prefixes, text.into()).unwrap();
+
+    let mut group = c.benchmark_group("c_string");
+    group.bench_function("cached", |b| {
+        b.iter(|| black_box(statement.as_c_string().unwrap().as_ptr()))
+    });
+    group.bench_function("uncached", |b| {
+        b.iter(|| black_box(CString::new(statement.as_str()).unwrap()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, c_string);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+// Writing and importing the deterministic triples of `rdfox_rs::datagen`,
+// run with `cargo bench --features test-util --bench datagen`. Importing
+// needs an RDFox license, see `rdfox_rs::find_license`.
+
+use {
+    criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput},
+    ekg_namespace::{Graph, Namespace},
+    iref::Iri,
+    rdfox_rs::{
+        datagen::{import_triples, write_ntriples, TripleGenerator},
+        DataStore,
+        Parameters,
+        PersistenceMode,
+        RoleCreds,
+        Server,
+    },
+};
+
+const TRIPLES: usize = 10_000;
+
+fn generator() -> TripleGenerator {
+    TripleGenerator::new(42).entities(1000).predicates(10).literal_ratio(0.3)
+}
+
+fn datagen(c: &mut Criterion) {
+    let mut group = c.benchmark_group("datagen");
+    group.throughput(Throughput::Elements(TRIPLES as u64));
+
+    let mut buffer = Vec::new();
+    group.bench_function("write_ntriples", |b| {
+        b.iter(|| {
+            buffer.clear();
+            black_box(write_ntriples(generator().triples(TRIPLES), &mut buffer).unwrap())
+        })
+    });
+
+    #[cfg(feature = "rdfox-7-0")]
+    let server_params =
+        Parameters::empty().unwrap().persist_datastore(PersistenceMode::Off).unwrap();
+    #[cfg(not(feature = "rdfox-7-0"))]
+    let server_params = Parameters::empty()
+        .unwrap()
+        .persist_datastore(PersistenceMode::Off)
+        .unwrap()
+        .persist_roles(PersistenceMode::Off)
+        .unwrap();
+    let server =
+        Server::start_with_parameters(RoleCreds::insecure_default(), Some(server_params)).unwrap();
+    let server_connection = server.connection_with_default_role().unwrap();
+    let data_store = DataStore::declare_with_parameters("datagen", Parameters::empty().unwrap())
+        .unwrap();
+    server_connection.create_data_store(&data_store).unwrap();
+    let connection = server_connection.connect_to_data_store(&data_store).unwrap();
+    let graph = Graph::declare(
+        Namespace::declare_iref_iri("graph:", Iri::new("https://ekgf.org/graph/").unwrap())
+            .unwrap(),
+        "datagen",
+    );
+    group.bench_function("import_triples", |b| {
+        b.iter(|| {
+            black_box(import_triples(generator().triples(TRIPLES), 1000, &connection, &graph))
+                .unwrap()
+        })
+    });
+    group.finish();
+
+    drop(connection);
+    server_connection.delete_data_store(&data_store).unwrap();
+}
+
+criterion_group!(benches, datagen);
+criterion_main!(benches);
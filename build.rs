@@ -9,15 +9,19 @@ use {
     bindgen::{CodegenConfig, RustTarget},
     lazy_static::lazy_static,
     std::{
+        collections::BTreeSet,
         env,
         fs::File,
         io::{BufReader, Write},
         option_env,
-        path::PathBuf,
+        path::{Path, PathBuf},
         process::Command,
     },
 };
 
+#[path = "src/diagnostics/symbol_names.rs"]
+mod symbol_names;
+
 const ARCH: &str = env::consts::ARCH;
 const FAMILY: &str = env::consts::FAMILY;
 const OS: &str = env::consts::OS;
@@ -118,6 +122,8 @@ const BLOCK_LIST_ITEMS: &[&str] = &[
     "^_Tp$",
 ];
 const ALLOW_LIST_ITEMS: &[&str] = &["^RDFOX_.*", "^C.*"];
+// Functions are only generated for the ones that we actually use, see
+// `used_rdfox_functions()`
 // const ALLOW_LIST_ITEMS: &[&str] = &[".*"];
 
 const RUSTFMT_CONFIG: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/.rustfmt.toml");
@@ -468,6 +474,23 @@ fn check_cpp() {
 #[cfg(not(any(target_os = "macos", target_os = "linux")))]
 fn check_llvm_via_brew() -> Option<String> { None }
 
+/// The names of the RDFox C functions that are used anywhere in the given
+/// directory of Rust sources (recursively)
+fn used_rdfox_functions(dir: &Path) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    for entry in std::fs::read_dir(dir).unwrap_or_else(|_| panic!("Could not read {}", dir.display())) {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            names.append(&mut used_rdfox_functions(&path));
+        } else if path.extension().map_or(false, |ext| ext == "rs") {
+            let source = std::fs::read_to_string(&path)
+                .unwrap_or_else(|_| panic!("Could not read {}", path.display()));
+            names.append(&mut symbol_names::rdfox_function_names(source.as_str()));
+        }
+    }
+    names
+}
+
 // The CRDFox.h file misses the `#include <cstddef>` statement which is
 // needed to define the symbol `nullptr_t`. This is only an issue on Linux,
 // things compile fine on Darwin.
@@ -497,7 +520,7 @@ fn main() {
     let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
 
     println!("cargo:rerun-if-changed=build.rs");
-    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=src");
 
     add_llvm_path();
     add_clang_path();
@@ -591,7 +614,10 @@ fn main() {
     for item in ALLOW_LIST_ITEMS {
         builder = builder.allowlist_type(item);
         builder = builder.allowlist_var(item);
-        builder = builder.allowlist_function(item);
+    }
+    let used_functions = used_rdfox_functions(Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/src")));
+    for function in &used_functions {
+        builder = builder.allowlist_function(format!("^{function}$"));
     }
 
     // let command_line_flags = builder.command_line_flags();
@@ -609,4 +635,17 @@ fn main() {
     bindings
         .write_to_file(out_path.join("bindings.rs"))
         .expect("Couldn't write bindings!");
+
+    // Write the names of the functions that the bindings declare (and that we
+    // therefore expect libRDFox to have) to the $OUT_DIR/rdfox_symbols.rs
+    // file, see `rdfox_rs::diagnostics::verify_symbols()`.
+    let declared_functions = symbol_names::declared_function_names(bindings.to_string().as_str());
+    for function in used_functions.difference(&declared_functions) {
+        println!("cargo:warning=RDFox function {function} is used but not declared in CRDFox.h");
+    }
+    std::fs::write(
+        out_path.join("rdfox_symbols.rs"),
+        symbol_names::symbols_source(used_functions.intersection(&declared_functions)),
+    )
+    .expect("Couldn't write RDFox symbols!");
 }
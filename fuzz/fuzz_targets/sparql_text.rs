@@ -0,0 +1,35 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+//
+// Run with `cargo +nightly fuzz run sparql_text`
+//
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    rdfox_rs::{detokenize, no_comments, split_statements, tokenize, SparqlTokenKind},
+};
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let tokens = tokenize(&text);
+    assert_eq!(detokenize(&tokens), text);
+
+    let output = no_comments(&text);
+    let mut from = 0_usize;
+    for token in tokens {
+        if matches!(token.kind, SparqlTokenKind::Iri | SparqlTokenKind::String) {
+            let found = output[from..]
+                .find(token.text)
+                .unwrap_or_else(|| panic!("{:?} is missing in {output:?}", token.text));
+            from += found + token.text.len();
+        }
+    }
+    assert_eq!(no_comments(&output), output);
+
+    let statements = split_statements(&text);
+    assert_eq!(statements.join(";"), text);
+    for statement in statements {
+        assert_eq!(split_statements(statement), vec![statement]);
+    }
+});
@@ -5,78 +5,225 @@ use {
     crate::{FactDomain, GraphConnection, Namespaces, Parameters, Statement, Transaction},
     ekg_namespace::{Class, consts::DEFAULT_GRAPH_RDFOX},
     indoc::formatdoc,
+    iref::IriBuf,
+    serde::Serialize,
     std::{ops::Deref, sync::Arc},
 };
 
-/// Some simple queries about a [`Class`](Class)
+/// Some simple queries about a [`Class`](Class), see
+/// [`ClassReport::collect`] for all of them at once.
 #[derive(Debug, Clone)]
 pub struct ClassReport<'a>(pub &'a Class);
 
 impl<'a> std::fmt::Display for ClassReport<'a> {
-    /// TODO: Generate a decent looking set of class metrics
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { self.0.fmt(f) }
 }
 
+/// The numbers of a class as collected by [`ClassReport::collect`], shown
+/// as a small table by `Display`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClassMetrics {
+    pub class:                         String,
+    pub number_of_individuals:         usize,
+    /// The number of distinct predicates used by the individuals
+    pub number_of_distinct_predicates: usize,
+    /// The number of individuals in each graph that has any
+    pub instances_per_graph:           Vec<(String, usize)>,
+    /// Some of the individuals (that are IRIs)
+    pub sample_instances:              Vec<String>,
+}
+
+impl std::fmt::Display for ClassMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Class {}", self.class)?;
+        writeln!(f, "  {:<28} {:>10}", "individuals", self.number_of_individuals)?;
+        writeln!(
+            f,
+            "  {:<28} {:>10}",
+            "distinct predicates", self.number_of_distinct_predicates
+        )?;
+        for (graph, count) in self.instances_per_graph.iter() {
+            writeln!(f, "  {:<28} {count:>10}", format!("in <{graph}>"))?;
+        }
+        for instance in self.sample_instances.iter() {
+            writeln!(f, "  e.g. <{instance}>")?;
+        }
+        Ok(())
+    }
+}
+
 impl<'a> ClassReport<'a> {
+    /// The number of distinct individuals of the class, counted by RDFox
     pub fn number_of_individuals(
         &self,
         tx: &Arc<Transaction>,
     ) -> Result<usize, ekg_error::Error> {
-        let default_graph = DEFAULT_GRAPH_RDFOX.deref().as_display_iri();
-        let prefixes = Namespaces::builder()
-            .declare(self.0.namespace.clone())
-            .build()?;
+        let pattern = self.pattern("");
+        self.count(tx, "DISTINCT ?thing", pattern.as_str())
+    }
+
+    pub fn number_of_individuals_in_graph(
+        &self,
+        tx: &Arc<Transaction>,
+        graph_connection: &GraphConnection,
+    ) -> Result<usize, ekg_error::Error> {
+        let graph = graph_connection.graph.as_display_iri();
+        let class = self.0;
+        self.count(
+            tx,
+            "DISTINCT ?thing",
+            format!("GRAPH {graph} {{ ?thing a {class} }}").as_str(),
+        )
+    }
+
+    /// The number of distinct predicates that the individuals of the class
+    /// have (`rdf:type` included)
+    pub fn number_of_distinct_predicates(
+        &self,
+        tx: &Arc<Transaction>,
+    ) -> Result<usize, ekg_error::Error> {
+        let pattern = self.pattern("?thing ?predicate ?object .");
+        self.count(tx, "DISTINCT ?predicate", pattern.as_str())
+    }
+
+    /// The number of individuals of the class in each graph that has any,
+    /// ordered by graph
+    pub fn instances_per_graph(
+        &self,
+        tx: &Arc<Transaction>,
+    ) -> Result<Vec<(IriBuf, usize)>, ekg_error::Error> {
         let sparql = formatdoc! {r##"
-            SELECT DISTINCT ?thing
+            SELECT ?graph (COUNT(DISTINCT ?thing) AS ?count)
             WHERE {{
-                {{
-                    GRAPH ?graph {{
-                        ?thing a {self}
-                    }}
-                }} UNION {{
-                        ?thing a {self}
-                    BIND({default_graph} AS ?graph)
-                }}
+            {}
             }}
-            "##
+            GROUP BY ?graph
+            ORDER BY ?graph
+            "##,
+            self.pattern("")
         };
-        tracing::debug!(target: "sparql", "\n{sparql}");
-        let count_result = Statement::new(&prefixes, sparql.into())?
+        self.statement(sparql)?
             .cursor(
                 &tx.connection,
                 &Parameters::empty()?.fact_domain(FactDomain::ALL)?,
             )?
-            .count(tx);
-        #[allow(clippy::let_and_return)]
-        count_result
+            .collect_into::<(IriBuf, usize)>(tx, usize::MAX)
     }
 
-    pub fn number_of_individuals_in_graph(
+    /// At most the given number of individuals of the class (that are IRIs),
+    /// in IRI order
+    pub fn sample_instances(
         &self,
         tx: &Arc<Transaction>,
-        graph_connection: &GraphConnection,
-    ) -> Result<usize, ekg_error::Error> {
-        let graph = graph_connection.graph.as_display_iri();
-        let prefixes = Namespaces::builder()
-            .declare(self.0.namespace.clone())
-            .build()?;
+        max: usize,
+    ) -> Result<Vec<IriBuf>, ekg_error::Error> {
         let sparql = formatdoc! {r##"
             SELECT DISTINCT ?thing
             WHERE {{
-                GRAPH {graph} {{
-                    ?thing a {self}
-                }}
+            {}
+                FILTER(isIRI(?thing))
             }}
-            "##
+            ORDER BY ?thing
+            LIMIT {max}
+            "##,
+            self.pattern("")
         };
+        Ok(self
+            .statement(sparql)?
+            .cursor(
+                &tx.connection,
+                &Parameters::empty()?.fact_domain(FactDomain::ALL)?,
+            )?
+            .collect_into::<(IriBuf,)>(tx, max)?
+            .into_iter()
+            .map(|(iri,)| iri)
+            .collect())
+    }
+
+    /// All the metrics of the class, with at most five sample instances
+    pub fn collect(&self, tx: &Arc<Transaction>) -> Result<ClassMetrics, ekg_error::Error> {
+        Ok(ClassMetrics {
+            class:                         self.0.to_string(),
+            number_of_individuals:         self.number_of_individuals(tx)?,
+            number_of_distinct_predicates: self.number_of_distinct_predicates(tx)?,
+            instances_per_graph:           self
+                .instances_per_graph(tx)?
+                .into_iter()
+                .map(|(graph, count)| (graph.as_str().to_string(), count))
+                .collect(),
+            sample_instances:              self
+                .sample_instances(tx, 5)?
+                .into_iter()
+                .map(|iri| iri.as_str().to_string())
+                .collect(),
+        })
+    }
+
+    /// The individuals of the class in `?thing`, with their graph in
+    /// `?graph`, and the given extra triple patterns
+    fn pattern(&self, extra: &str) -> String {
+        let default_graph = DEFAULT_GRAPH_RDFOX.deref().as_display_iri();
+        let class = self.0;
+        formatdoc! {r##"
+                {{
+                    GRAPH ?graph {{
+                        ?thing a {class} .
+                        {extra}
+                    }}
+                }} UNION {{
+                    ?thing a {class} .
+                    {extra}
+                    BIND({default_graph} AS ?graph)
+                }}"##
+        }
+    }
+
+    fn statement(&self, sparql: String) -> Result<Statement, ekg_error::Error> {
+        let prefixes = Namespaces::builder()
+            .declare(self.0.namespace.clone())
+            .build()?;
         tracing::debug!(target: "sparql", "\n{sparql}");
-        let count_result = Statement::new(&prefixes, sparql.into())?
+        Statement::new(&prefixes, sparql.into())
+    }
+
+    fn count(
+        &self,
+        tx: &Arc<Transaction>,
+        what: &str,
+        pattern: &str,
+    ) -> Result<usize, ekg_error::Error> {
+        let counts = self
+            .statement(format!("SELECT (COUNT({what}) AS ?count)\nWHERE {{\n{pattern}\n}}"))?
             .cursor(
-                &graph_connection.data_store_connection,
+                &tx.connection,
                 &Parameters::empty()?.fact_domain(FactDomain::ALL)?,
             )?
-            .count(tx);
-        #[allow(clippy::let_and_return)]
-        count_result
+            .collect_into::<(usize,)>(tx, 1)?;
+        Ok(counts.first().map_or(0, |(count,)| *count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClassMetrics;
+
+    #[test_log::test]
+    fn test_display() {
+        let metrics = ClassMetrics {
+            class:                         "story:UserStory".to_string(),
+            number_of_individuals:         1,
+            number_of_distinct_predicates: 12,
+            instances_per_graph:           vec![("https://ekgf.org/graph/test".to_string(), 1)],
+            sample_instances:              vec!["https://ekgf.org/id/story".to_string()],
+        };
+        assert_eq!(
+            metrics.to_string(),
+            "Class story:UserStory\n  individuals                           1\n  distinct \
+             predicates                  12\n  in <https://ekgf.org/graph/test>          1\n  \
+             e.g. <https://ekgf.org/id/story>\n"
+        );
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!(json["number_of_distinct_predicates"], 12);
+        assert_eq!(json["instances_per_graph"][0][1], 1);
     }
 }
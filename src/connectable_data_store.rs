@@ -4,20 +4,44 @@
 use {
     ::r2d2::{ManageConnection, Pool},
     crate::{DataStore, DataStoreConnection, ServerConnection},
+    ekg_namespace::consts::LOG_TARGET_DATABASE,
     std::sync::{
         Arc,
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+        Weak,
     }
     ,
 };
 
+/// The identifier of the last [`ConnectableDataStore`] that was made
+static LAST_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Options for building a connection pool for a [`DataStore`].
+#[derive(Debug, Clone, Default)]
+pub struct PoolOptions {
+    /// Mark connections as "destroy" when they are returned to the pool,
+    /// see [`ConnectableDataStore::new`]
+    pub release_on_return_to_pool: bool,
+    /// The maximum number of connections in the pool, defaults to the
+    /// number of threads of the server
+    pub max_size: Option<u32>,
+}
+
 /// A pool-able connectable [`DataStore`]
 pub struct ConnectableDataStore {
+    /// See [`ConnectableDataStore::id`]
+    id: u64,
     data_store: Arc<DataStore>,
     server_connection: Arc<ServerConnection>,
     /// Indicates that we want to release all connections on return to the pool
     /// (used to shutdown gracefully)
     release_on_return_to_pool: AtomicBool,
+    /// Set by [`ConnectableDataStore::initiate_shutdown`], no new connections
+    /// are made after that
+    shutting_down: AtomicBool,
+    /// All connections made for the pool, to see which ones still exist
+    connections: Mutex<Vec<Weak<DataStoreConnection>>>,
 }
 
 impl ConnectableDataStore {
@@ -29,17 +53,64 @@ impl ConnectableDataStore {
         release_on_return_to_pool: bool,
     ) -> Self {
         Self {
+            id: LAST_ID.fetch_add(1, Ordering::Relaxed) + 1,
             data_store: data_store.clone(),
             server_connection: server_connection.clone(),
             release_on_return_to_pool: AtomicBool::new(release_on_return_to_pool),
+            shutting_down: AtomicBool::new(false),
+            connections: Mutex::default(),
         }
     }
 
+    /// The identifier of this connectable data store, unique within the
+    /// process, which identifies the pool that it's the manager of
+    pub fn id(&self) -> u64 { self.id }
+
+    /// Start shutting the pool down: from now on connections that are
+    /// returned to the pool are destroyed and no new ones are made. See
+    /// [`PoolExt::shutdown`](crate::PoolExt::shutdown) to also wait for all
+    /// connections to be gone.
+    pub fn initiate_shutdown(&self) {
+        tracing::info!(
+            target: LOG_TARGET_DATABASE,
+            "Shutting down the connection pool of {}",
+            self.data_store
+        );
+        self.shutting_down.store(true, Ordering::Relaxed);
+        self.release_on_return_to_pool.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`ConnectableDataStore::initiate_shutdown`] has been called
+    pub fn is_shutting_down(&self) -> bool { self.shutting_down.load(Ordering::Relaxed) }
+
+    /// The number of connections made for the pool that still exist, idle
+    /// in the pool, checked out or (as clones of the `Arc`) held elsewhere
+    pub fn live_connections(&self) -> usize {
+        let mut connections = self
+            .connections
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        connections.retain(|connection| connection.strong_count() > 0);
+        connections.len()
+    }
+
     /// Build an `r2d2::Pool` for the given `DataStore` and `ServerConnection`
     pub fn build_pool(self) -> Result<Pool<ConnectableDataStore>, ekg_error::Error> {
-        let cds = Pool::builder()
-            .max_size(self.server_connection.get_number_of_threads()?)
-            .build(self)?;
+        self.build_pool_with_options(&PoolOptions::default())
+    }
+
+    /// Build an `r2d2::Pool` for the given `DataStore` and `ServerConnection`
+    /// with the given options (where `options.release_on_return_to_pool` is
+    /// ignored, that one is given to [`ConnectableDataStore::new`]).
+    pub fn build_pool_with_options(
+        self,
+        options: &PoolOptions,
+    ) -> Result<Pool<ConnectableDataStore>, ekg_error::Error> {
+        let max_size = match options.max_size {
+            Some(max_size) => max_size,
+            None => self.server_connection.get_number_of_threads()?,
+        };
+        let cds = Pool::builder().max_size(max_size).build(self)?;
         Ok(cds)
     }
 }
@@ -49,13 +120,46 @@ impl ManageConnection for ConnectableDataStore {
     type Error = ekg_error::Error;
 
     fn connect(&self) -> Result<Self::Connection, Self::Error> {
-        self.server_connection
-            .connect_to_data_store(&self.data_store)
+        if self.is_shutting_down() {
+            return Err(ekg_error::Error::Exception {
+                action:  format!("connecting to {}", self.data_store),
+                message: "the connection pool is shutting down".to_string(),
+            });
+        }
+        let connection = self
+            .server_connection
+            .connect_to_data_store(&self.data_store)?;
+        connection.check_online()?;
+        self.connections
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(Arc::downgrade(&connection));
+        Ok(connection)
     }
 
-    fn is_valid(&self, _conn: &mut Self::Connection) -> Result<(), Self::Error> { Ok(()) }
+    /// Refuses to hand out connections that no longer work, whose data
+    /// store has been replaced (see [`DataStoreConnection::validate`]),
+    /// whose data store has been taken offline (see
+    /// [`ServerConnection::set_data_store_offline`]) or that still have a
+    /// transaction, so that the pool replaces them with new ones
+    fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        if conn.has_active_transaction() {
+            return Err(ekg_error::Error::Exception {
+                action:  format!("validating {conn}"),
+                message: "the connection still has a transaction".to_string(),
+            });
+        }
+        conn.validate()?;
+        conn.check_online()
+    }
 
-    fn has_broken(&self, _conn: &mut Self::Connection) -> bool {
-        self.release_on_return_to_pool.load(Ordering::Relaxed)
+    /// Connections are destroyed on their return to the pool when the pool
+    /// is shutting down or when they still have a transaction (which keeps
+    /// them alive until it's dropped), so that they're never handed out
+    /// while in use. Whether their data store has been replaced is checked
+    /// by [`ConnectableDataStore::is_valid`] when they're handed out, since
+    /// this should only do quick checks that don't call into RDFox.
+    fn has_broken(&self, conn: &mut Self::Connection) -> bool {
+        self.release_on_return_to_pool.load(Ordering::Relaxed) || conn.has_active_transaction()
     }
 }
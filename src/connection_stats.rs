@@ -0,0 +1,275 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{typed_error::typed_error_name, ExceptionKind, RDFoxException},
+    serde::{Deserialize, Serialize},
+    std::{
+        any::Any,
+        collections::BTreeMap,
+        fmt::{Debug, Display, Formatter},
+        sync::{
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+            Mutex,
+        },
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// The cumulative counters of a
+/// [`DataStoreConnection`](crate::DataStoreConnection), updated with relaxed
+/// atomic increments, see [`ConnectionStatsSnapshot`].
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionStats {
+    transactions_begun:       AtomicUsize,
+    transactions_committed:   AtomicUsize,
+    transactions_rolled_back: AtomicUsize,
+    cursors_created:          AtomicUsize,
+    rows_consumed:            AtomicUsize,
+    imports:                  AtomicUsize,
+    bytes_imported:           AtomicUsize,
+    updates:                  AtomicUsize,
+    /// Milliseconds since the UNIX epoch, 0 means never
+    last_activity:            AtomicU64,
+    /// Only touched when something went wrong
+    errors:                   Mutex<BTreeMap<String, usize>>,
+}
+
+/// A copy of the statistics of a
+/// [`DataStoreConnection`](crate::DataStoreConnection) since it was created,
+/// see [`DataStoreConnection::stats_snapshot`](crate::DataStoreConnection::stats_snapshot).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionStatsSnapshot {
+    pub transactions_begun:       usize,
+    pub transactions_committed:   usize,
+    pub transactions_rolled_back: usize,
+    pub cursors_created:          usize,
+    /// The number of rows (not solutions, so regardless of their
+    /// multiplicity) read from cursors
+    pub rows_consumed:            usize,
+    /// The number of import operations (files, buffers or axioms), RDFox
+    /// does not tell us how many facts each of them added or deleted
+    pub imports:                  usize,
+    /// The number of bytes imported from buffers
+    pub bytes_imported:           usize,
+    pub updates:                  usize,
+    pub last_activity:            Option<SystemTime>,
+    /// The number of errors per kind of error (like `InvalidSparql`,
+    /// `QueryTimedOut` or `Exception`), see the exception kinds of RDFox in
+    /// [`ExceptionKind`]
+    pub errors:                   BTreeMap<String, usize>,
+}
+
+impl Display for ConnectionStatsSnapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "transactions={}/{}/{} (begun/committed/rolled back) cursors={} rows={} imports={} \
+             ({} bytes) updates={}",
+            self.transactions_begun,
+            self.transactions_committed,
+            self.transactions_rolled_back,
+            self.cursors_created,
+            self.rows_consumed,
+            self.imports,
+            self.bytes_imported,
+            self.updates,
+        )?;
+        if !self.errors.is_empty() {
+            let errors = self
+                .errors
+                .iter()
+                .map(|(kind, count)| format!("{kind}={count}"))
+                .collect::<Vec<_>>()
+                .join(",");
+            write!(f, " errors={errors}")?;
+        }
+        if let Some(last_activity) = self.last_activity {
+            if let Ok(ago) = last_activity.elapsed() {
+                write!(f, " last-activity={}ms ago", ago.as_millis())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ConnectionStats {
+    fn increment(&self, counter: &AtomicUsize, by: usize) {
+        counter.fetch_add(by, Ordering::Relaxed);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+        self.last_activity.store(now, Ordering::Relaxed);
+    }
+
+    pub(crate) fn transaction_begun(&self) { self.increment(&self.transactions_begun, 1) }
+
+    pub(crate) fn transaction_committed(&self) { self.increment(&self.transactions_committed, 1) }
+
+    pub(crate) fn transaction_rolled_back(&self) {
+        self.increment(&self.transactions_rolled_back, 1)
+    }
+
+    pub(crate) fn cursor_created(&self) { self.increment(&self.cursors_created, 1) }
+
+    pub(crate) fn rows_consumed(&self, rows: usize) { self.increment(&self.rows_consumed, rows) }
+
+    pub(crate) fn imported(&self, bytes: usize) {
+        self.increment(&self.imports, 1);
+        self.bytes_imported.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn updated(&self) { self.increment(&self.updates, 1) }
+
+    /// Count the error (if any) of the given result by its kind, see
+    /// [`error_kind`]
+    pub(crate) fn record<T, E: Debug + 'static>(&self, result: Result<T, E>) -> Result<T, E> {
+        if let Err(ref err) = result {
+            *self
+                .errors
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .entry(error_kind(err))
+                .or_default() += 1;
+        }
+        result
+    }
+
+    pub(crate) fn snapshot(&self) -> ConnectionStatsSnapshot {
+        let last_activity = self.last_activity.load(Ordering::Relaxed);
+        ConnectionStatsSnapshot {
+            transactions_begun:       self.transactions_begun.load(Ordering::Relaxed),
+            transactions_committed:   self.transactions_committed.load(Ordering::Relaxed),
+            transactions_rolled_back: self.transactions_rolled_back.load(Ordering::Relaxed),
+            cursors_created:          self.cursors_created.load(Ordering::Relaxed),
+            rows_consumed:            self.rows_consumed.load(Ordering::Relaxed),
+            imports:                  self.imports.load(Ordering::Relaxed),
+            bytes_imported:           self.bytes_imported.load(Ordering::Relaxed),
+            updates:                  self.updates.load(Ordering::Relaxed),
+            last_activity:            (last_activity > 0)
+                .then(|| UNIX_EPOCH + Duration::from_millis(last_activity)),
+            errors:                   self
+                .errors
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clone(),
+        }
+    }
+}
+
+/// The kind of the given error: the [`ExceptionKind`] of an exception that
+/// RDFox threw (or its name, like `FileNotFoundException`, if it's of no
+/// particular kind), the name of one of our own error types (like
+/// `QueryTimedOut`) or else the name of its variant (like `Exception`)
+pub(crate) fn error_kind<E: Debug + 'static>(err: &E) -> String {
+    if let Some(error) = (err as &dyn Any).downcast_ref::<ekg_error::Error>() {
+        if let Some(exception) = RDFoxException::from_error(error) {
+            return match exception.kind {
+                ExceptionKind::Other => exception.name,
+                kind => variant_name(&kind),
+            };
+        }
+        if let Some(name) = typed_error_name(error) {
+            return name.to_string();
+        }
+    }
+    variant_name(err)
+}
+
+/// The name of the variant of the given value, the first word of its debug
+/// representation
+fn variant_name<E: Debug>(value: &E) -> String {
+    let debug = format!("{value:?}");
+    debug
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()
+        .filter(|kind| !kind.is_empty())
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{error_kind, ConnectionStats},
+        crate::{ExceptionKind, QueryTimedOut, RDFoxException},
+        std::time::Duration,
+    };
+
+    #[test_log::test]
+    fn test_error_kind() {
+        let untyped = ekg_error::Error::Exception {
+            action:  "testing".to_string(),
+            message: "failure".to_string(),
+        };
+        assert_eq!(error_kind(&untyped), "Exception");
+        assert_eq!(error_kind(&ekg_error::Error::InvalidPrefixName), "InvalidPrefixName");
+        let exception = |name: &str, kind: ExceptionKind| {
+            ekg_error::Error::from(RDFoxException {
+                action: "testing".to_string(),
+                name: name.to_string(),
+                message: "failure".to_string(),
+                kind,
+            })
+        };
+        assert_eq!(
+            error_kind(&exception("ParsingException", ExceptionKind::InvalidSparql {
+                line:   Some(1),
+                column: None,
+            })),
+            "InvalidSparql"
+        );
+        assert_eq!(
+            error_kind(&exception("AuthorizationException", ExceptionKind::AccessDenied)),
+            "AccessDenied"
+        );
+        assert_eq!(
+            error_kind(&exception("FileNotFoundException", ExceptionKind::Other)),
+            "FileNotFoundException"
+        );
+        let timed_out = ekg_error::Error::from(QueryTimedOut {
+            elapsed: Duration::from_secs(1),
+            query:   "SELECT * { ?s ?p ?o }".to_string(),
+        });
+        assert_eq!(error_kind(&timed_out), "QueryTimedOut");
+        assert_eq!(error_kind(&"not an ekg_error::Error"), "Unknown");
+    }
+
+    #[test_log::test]
+    fn test_connection_stats() {
+        let stats = ConnectionStats::default();
+        assert_eq!(stats.snapshot().last_activity, None);
+
+        stats.transaction_begun();
+        stats.cursor_created();
+        stats.rows_consumed(4);
+        stats.imported(100);
+        stats.imported(0);
+        stats.transaction_rolled_back();
+        let failed: Result<(), ekg_error::Error> = Err(ekg_error::Error::Exception {
+            action:  "testing".to_string(),
+            message: "failure".to_string(),
+        });
+        assert!(stats.record(failed).is_err());
+        assert!(stats.record(Ok::<_, ekg_error::Error>(())).is_ok());
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.transactions_begun, 1);
+        assert_eq!(snapshot.transactions_committed, 0);
+        assert_eq!(snapshot.transactions_rolled_back, 1);
+        assert_eq!(snapshot.cursors_created, 1);
+        assert_eq!(snapshot.rows_consumed, 4);
+        assert_eq!(snapshot.imports, 2);
+        assert_eq!(snapshot.bytes_imported, 100);
+        assert_eq!(snapshot.errors.get("Exception"), Some(&1));
+        assert!(snapshot.last_activity.is_some());
+
+        let shown = snapshot.to_string();
+        assert!(shown.starts_with(
+            "transactions=1/0/1 (begun/committed/rolled back) cursors=1 rows=4 imports=2 (100 \
+             bytes) updates=0 errors=Exception=1"
+        ));
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serde_json::from_str::<super::ConnectionStatsSnapshot>(&json).unwrap(), snapshot);
+    }
+}
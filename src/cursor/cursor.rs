@@ -7,15 +7,40 @@ use {
         DataStoreConnection,
         Parameters,
         rdfox_api::{CCursor, CCursor_destroy, CDataStoreConnection_createCursor},
+        ResultSemantics,
+        SelectResult,
+        server::ServerDependent,
+        sparql_text::abbreviate,
+        statement::MAX_TOKEN_SIZE_IN_LOG,
         Statement,
         Transaction,
     },
     ekg_namespace::consts::LOG_TARGET_DATABASE,
-    std::{ffi::CString, fmt::Debug, ptr, sync::Arc}
+    std::{
+        cell::{Cell, RefCell},
+        fmt::Debug,
+        ptr,
+        sync::Arc,
+    }
     ,
-    super::{CursorRow, OpenedCursor},
+    super::{
+        query_evaluation::QueryEvaluation,
+        CursorRow,
+        CursorRows,
+        CursorStatistics,
+        FromCursorRow,
+        OpenedCursor,
+    },
 };
 
+/// The initial size of the buffer that a cursor reads lexical forms into,
+/// see [`Cursor::lexical_form_buffer_size`]
+pub const INITIAL_LEXICAL_FORM_BUFFER_SIZE: usize = 1024;
+
+/// The largest lexical form buffer that is kept when a cursor is reopened,
+/// see [`Cursor::lexical_form_buffer_size`]
+pub const MAX_RETAINED_LEXICAL_FORM_BUFFER_SIZE: usize = 65536;
+
 /// A Cursor handles a query result.
 ///
 /// [RDFox documentation](https://docs.oxfordsemantic.tech/apis.html#cursors)
@@ -24,17 +49,41 @@ pub struct Cursor {
     pub inner: *mut CCursor,
     pub(crate) connection: Arc<DataStoreConnection>,
     statement: Statement,
+    /// See [`Cursor::statistics`]
+    pub(crate) statistics: Cell<CursorStatistics>,
+    /// The buffer that the lexical forms of values are read into, reused
+    /// for all values of all rows and grown when a value doesn't fit, see
+    /// [`Cursor::lexical_form_buffer_size`]
+    pub(crate) lexical_form_buffer: RefCell<Vec<u8>>,
+    dependent: ServerDependent,
 }
 
+/// A cursor can be moved to another thread (for instance to consume it on
+/// a blocking thread pool), as long as it's used by one thread at a time
+// SAFETY: `inner` is owned by this cursor alone and RDFox lets a cursor be
+// used from any thread, just not from two at once. `Cursor` is not `Sync`,
+// so the `Cell` and `RefCell` fields move along with it and are never
+// shared, and the other fields are `Send` themselves.
+unsafe impl Send for Cursor {}
+
 impl Drop for Cursor {
     fn drop(&mut self) {
-        unsafe {
-            if !self.inner.is_null() {
+        if self.inner.is_null() {
+            return;
+        }
+        // RDFox has already freed the cursors of a stopped server
+        if self.dependent.is_stopped() {
+            tracing::warn!(
+                target: LOG_TARGET_DATABASE,
+                "Leaking cursor since the server has been stopped"
+            );
+        } else {
+            unsafe {
                 CCursor_destroy(self.inner);
-                self.inner = ptr::null_mut();
-                tracing::debug!(target: LOG_TARGET_DATABASE, "Dropped cursor");
             }
+            tracing::debug!(target: LOG_TARGET_DATABASE, "Dropped cursor");
         }
+        self.inner = ptr::null_mut();
     }
 }
 
@@ -46,15 +95,19 @@ impl Cursor {
         statement: &Statement,
     ) -> Result<Self, ekg_error::Error> {
         assert!(!connection.inner.is_null());
+        let dependent = connection
+            .server_connection
+            .server()
+            .dependent("creating a cursor")?;
         let mut c_cursor: *mut CCursor = ptr::null_mut();
-        let c_query = CString::new(statement.text.as_str()).unwrap();
-        let c_query_len = c_query.as_bytes().len();
+        let c_query = statement.as_c_string()?;
+        let c_query_len = c_query.to_bytes().len();
         tracing::trace!(
             target: LOG_TARGET_DATABASE,
-            sparql = ?c_query,
+            sparql = %abbreviate(statement.as_str(), MAX_TOKEN_SIZE_IN_LOG),
             "Starting a cursor"
         );
-        database_call!(
+        connection.stats.record(database_call!(
             "Starting a cursor",
             CDataStoreConnection_createCursor(
                 connection.inner,
@@ -63,11 +116,15 @@ impl Cursor {
                 parameters.inner.as_ref().cast_const(),
                 &mut c_cursor,
             )
-        )?;
+        ))?;
+        connection.stats.cursor_created();
         let cursor = Cursor {
             inner: c_cursor,
             connection: connection.clone(),
             statement: statement.clone(),
+            statistics: Cell::default(),
+            lexical_form_buffer: RefCell::default(),
+            dependent,
         };
         tracing::debug!(
             target: LOG_TARGET_DATABASE,
@@ -79,8 +136,117 @@ impl Cursor {
 
     pub fn sparql_string(&self) -> &str { self.statement.text.as_str() }
 
+    pub(crate) fn statement(&self) -> &Statement { &self.statement }
+
+    /// The statistics of the last evaluation of the query of this cursor,
+    /// as far as its rows have been read, so after [`Cursor::consume`] (or
+    /// any other method that reads the cursor) they cover the whole result
+    pub fn statistics(&self) -> CursorStatistics { self.statistics.get() }
+
+    /// The size of the buffer that the lexical forms of values are read
+    /// into: it starts at [`INITIAL_LEXICAL_FORM_BUFFER_SIZE`] and grows to
+    /// fit the longest value read so far (it's reset when the cursor is
+    /// reopened after reading a value of more than
+    /// [`MAX_RETAINED_LEXICAL_FORM_BUFFER_SIZE`] bytes)
+    pub fn lexical_form_buffer_size(&self) -> usize { self.lexical_form_buffer.borrow().len() }
+
+    /// (Re-)open this cursor in the given transaction, evaluating its query
+    /// again from the start, and return the [`OpenedCursor`] with the
+    /// multiplicity of its first row (zero if there is none).
+    ///
+    /// A cursor does not have to be recreated to run its query again: every
+    /// call to `reopen` (and to [`Cursor::consume`], [`Cursor::iter`],
+    /// [`Cursor::count`] and so on, which all call it) re-executes it, in
+    /// the same or in a later transaction of its connection, against the
+    /// data as seen by that transaction. The returned `OpenedCursor`
+    /// borrows this cursor mutably, so it has to be dropped before the
+    /// cursor can be reopened.
+    pub fn reopen(
+        &mut self,
+        tx: &Arc<Transaction>,
+    ) -> Result<(OpenedCursor<'_>, usize), ekg_error::Error> {
+        OpenedCursor::new(self, tx.clone())
+    }
+
+    /// Count the solutions of this cursor, i.e. the sum of the
+    /// multiplicities of its rows, by simply advancing it: unlike
+    /// [`Cursor::consume`] there is no maximum number of rows and nothing is
+    /// materialised, so it works in constant memory for results of any size.
+    ///
+    /// This counts duplicate solutions as often as they occur, to count
+    /// distinct solutions let RDFox do it with a `SELECT (COUNT(DISTINCT
+    /// ...) AS ?count)` query, or read a [`SelectResult`] with
+    /// [`ResultSemantics::Set`].
     pub fn count(&mut self, tx: &Arc<Transaction>) -> Result<usize, ekg_error::Error> {
-        self.consume(tx, 1000000000, |_row| Ok(()))
+        self.instrumented(|cursor, evaluation| cursor.count_rows(tx, evaluation))
+    }
+
+    fn count_rows(
+        &mut self,
+        tx: &Arc<Transaction>,
+        evaluation: &QueryEvaluation,
+    ) -> Result<usize, ekg_error::Error> {
+        let connection = self.connection.clone();
+        let timed_out = |err| evaluation.timed_out(err);
+        let (mut opened_cursor, mut multiplicity) = self.reopen(tx).map_err(timed_out)?;
+        let mut rows = 0_usize;
+        let mut count = 0_usize;
+        while multiplicity > 0 {
+            evaluation.check()?;
+            rows += 1;
+            count += multiplicity;
+            multiplicity = opened_cursor.advance().map_err(timed_out)?;
+        }
+        connection.stats.rows_consumed(rows);
+        Ok(count)
+    }
+
+    /// Open the cursor and iterate over its rows, each one materialised as
+    /// an [`OwnedCursorRow`](crate::OwnedCursorRow), so that you can use
+    /// `for row in cursor.iter(&tx)? { ... }`, `take(10)`, `collect()` and so
+    /// on rather than [`Cursor::consume`].
+    ///
+    /// Rows with a multiplicity greater than one are yielded once, check
+    /// [`OwnedCursorRow::multiplicity`](crate::OwnedCursorRow::multiplicity)
+    /// if you need to count them.
+    pub fn iter(&mut self, tx: &Arc<Transaction>) -> Result<CursorRows<'_>, ekg_error::Error> {
+        CursorRows::new(self, tx)
+    }
+
+    /// Read all solutions of this cursor as a [`SelectResult`], for
+    /// instance to turn them into SPARQLWrapper-style bindings with
+    /// [`SelectResult::to_simple_bindings`].
+    pub fn select_result(&mut self, tx: &Arc<Transaction>) -> Result<SelectResult, ekg_error::Error> {
+        SelectResult::from_cursor(self, tx)
+    }
+
+    /// Read all solutions of this cursor as a [`SelectResult`] with the
+    /// given [`ResultSemantics`], see
+    /// [`SelectResult::from_cursor_with_semantics`].
+    pub fn select_result_with_semantics(
+        &mut self,
+        tx: &Arc<Transaction>,
+        semantics: &ResultSemantics,
+    ) -> Result<SelectResult, ekg_error::Error> {
+        SelectResult::from_cursor_with_semantics(self, tx, semantics)
+    }
+
+    /// Read (at most `max_row`) rows of this cursor, each one converted
+    /// into a `T`, for instance a tuple like `(IriBuf, String, Option<i64>)`
+    /// or a struct that implements [`FromCursorRow`].
+    ///
+    /// Rows with a multiplicity greater than one are collected once.
+    pub fn collect_into<T: FromCursorRow>(
+        &mut self,
+        tx: &Arc<Transaction>,
+        max_row: usize,
+    ) -> Result<Vec<T>, ekg_error::Error> {
+        let mut collected = Vec::new();
+        self.consume(tx, max_row, |row| {
+            collected.push(T::from_cursor_row(row)?);
+            Ok::<(), ekg_error::Error>(())
+        })?;
+        Ok(collected)
     }
 
     #[tracing::instrument(
@@ -91,26 +257,58 @@ impl Cursor {
     )
     )]
     pub fn consume<T, E>(
+        &mut self,
+        tx: &Arc<Transaction>,
+        max_row: usize,
+        f: T,
+    ) -> Result<usize, E>
+        where
+            T: FnMut(&CursorRow) -> Result<(), E>,
+            E: From<ekg_error::Error> + Debug + 'static,
+    {
+        self.instrumented(|cursor, evaluation| cursor.consume_rows(tx, max_row, f, evaluation))
+    }
+
+    /// Evaluate the query of this cursor with the given function, which
+    /// returns the number of solutions, as a [`QueryEvaluation`]: reported
+    /// to the [`Instrumentation`](crate::Instrumentation) of the connection
+    /// and registered as an operation that can be cancelled
+    fn instrumented<E, F>(&mut self, evaluate: F) -> Result<usize, E>
+        where
+            F: FnOnce(&mut Self, &QueryEvaluation) -> Result<usize, E>,
+            E: Debug + 'static,
+    {
+        let mut evaluation = QueryEvaluation::start(self);
+        let result = evaluate(self, &evaluation);
+        evaluation.finish_with(&result);
+        result
+    }
+
+    fn consume_rows<T, E>(
         &mut self,
         tx: &Arc<Transaction>,
         max_row: usize,
         mut f: T,
+        evaluation: &QueryEvaluation,
     ) -> Result<usize, E>
         where
             T: FnMut(&CursorRow) -> Result<(), E>,
             E: From<ekg_error::Error> + Debug,
     {
-        let sparql_str = self.statement.text.clone();
-        let (mut opened_cursor, mut multiplicity) = OpenedCursor::new(self, tx.clone())?;
+        let sparql_str = abbreviate(self.statement.as_str(), MAX_TOKEN_SIZE_IN_LOG).into_owned();
+        let connection = self.connection.clone();
+        let timed_out = |err| evaluation.timed_out(err);
+        let (mut opened_cursor, mut multiplicity) = self.reopen(tx).map_err(timed_out)?;
         let mut rowid = 0_usize;
         let mut count = 0_usize;
         while multiplicity > 0_usize {
+            evaluation.check()?;
             if multiplicity >= max_row {
                 return Err(
                     ekg_error::Error::MultiplicityExceededMaximumNumberOfRows {
                         maxrow: max_row,
                         multiplicity,
-                        query: sparql_str,
+                        query: sparql_str.clone(),
                     }
                         .into(),
                 );
@@ -119,7 +317,7 @@ impl Cursor {
             if rowid >= max_row {
                 return Err(ekg_error::Error::ExceededMaximumNumberOfRows {
                     maxrow: max_row,
-                    query: sparql_str,
+                    query: sparql_str.clone(),
                 }
                     .into());
             }
@@ -134,8 +332,73 @@ impl Cursor {
                 tracing::error!("Error while consuming row: {:?}", err);
                 Err(err)?;
             }
-            multiplicity = opened_cursor.advance()?;
+            multiplicity = opened_cursor.advance().map_err(timed_out)?;
+        }
+        connection.stats.rows_consumed(rowid);
+        Ok(count)
+    }
+
+    /// Consume one page of this cursor: skip the first `offset` answers (in
+    /// RDFox itself) and pass at most `max_rows` rows to the given function.
+    ///
+    /// Returns the number of answers in this page (i.e. the sum of the
+    /// multiplicities of its rows) so the next page starts at `offset` plus
+    /// that number, the last page is the one that returns zero. Unlike
+    /// [`Cursor::consume`] reaching `max_rows` is not an error.
+    pub fn consume_from<T, E>(
+        &mut self,
+        tx: &Arc<Transaction>,
+        offset: usize,
+        max_rows: usize,
+        f: T,
+    ) -> Result<usize, E>
+        where
+            T: FnMut(&CursorRow) -> Result<(), E>,
+            E: From<ekg_error::Error> + Debug + 'static,
+    {
+        self.instrumented(|cursor, evaluation| {
+            cursor.consume_page(tx, offset, max_rows, f, evaluation)
+        })
+    }
+
+    fn consume_page<T, E>(
+        &mut self,
+        tx: &Arc<Transaction>,
+        offset: usize,
+        max_rows: usize,
+        mut f: T,
+        evaluation: &QueryEvaluation,
+    ) -> Result<usize, E>
+        where
+            T: FnMut(&CursorRow) -> Result<(), E>,
+            E: From<ekg_error::Error> + Debug,
+    {
+        let connection = self.connection.clone();
+        let timed_out = |err| evaluation.timed_out(err);
+        let (mut opened_cursor, mut multiplicity) =
+            OpenedCursor::new_at(self, tx.clone(), offset).map_err(timed_out)?;
+        let mut rowid = 0_usize;
+        let mut count = 0_usize;
+        while multiplicity > 0 && rowid < max_rows {
+            evaluation.check()?;
+            rowid += 1;
+            count += multiplicity;
+            let row = CursorRow {
+                opened: &opened_cursor,
+                multiplicity: &multiplicity,
+                count: &count,
+                rowid: &rowid,
+            };
+            if let Err(err) = f(&row) {
+                tracing::error!("Error while consuming row: {:?}", err);
+                Err(err)?;
+            }
+            // Never advance beyond the end of the page (or of the answer)
+            if rowid < max_rows {
+                multiplicity = opened_cursor.advance().map_err(timed_out)?;
+            }
         }
+        connection.stats.rows_consumed(rowid);
         Ok(count)
     }
 
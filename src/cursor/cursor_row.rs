@@ -2,12 +2,20 @@
 //---------------------------------------------------------------
 
 use {
-    crate::{database_call, OpenedCursor, rdfox_api::CCursor_appendResourceLexicalForm},
+    super::{from_lexical_value::integer_fits_in_64_bits, FromLexicalValue, ResourceValue},
+    crate::{
+        cursor::INITIAL_LEXICAL_FORM_BUFFER_SIZE,
+        database_call,
+        OpenedCursor,
+        rdfox_api::{CCursor_appendResourceLexicalForm, CCursor_getResourceValue},
+        Term,
+    },
     ekg_namespace::{
         consts::LOG_TARGET_DATABASE,
         DataType,
         Literal,
     },
+    std::{ffi::CStr, os::raw::c_char},
     tracing::event_enabled,
 };
 
@@ -20,18 +28,26 @@ pub struct CursorRow<'a> {
     pub rowid: &'a usize,
 }
 
+/// Describe a value for logging, also when its datatype is not one that we
+/// know (yet), which never panics
+fn describe_value(datatype_id: u8, lexical_form: &str) -> String {
+    match DataType::from_datatype_id(datatype_id) {
+        Ok(DataType::UnboundValue) => "UNDEF".to_string(),
+        Ok(data_type) => format!("{data_type:?}:{lexical_form}"),
+        Err(_) => format!("<<unsupported:{datatype_id}>>:{lexical_form}"),
+    }
+}
+
 impl<'a> std::fmt::Debug for CursorRow<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Row(")?;
         for term_index in 0..self.opened.arity {
-            match self.lexical_value(term_index) {
-                Ok(some_value) => {
-                    if let Some(value) = some_value {
-                        write!(f, "{term_index}={:?}:{value:},", value.data_type)?;
-                    } else {
-                        write!(f, "{term_index}=UNDEF,")?
-                    }
-                }
+            let value = self.with_raw_lexical_form(term_index, |datatype_id, buffer| {
+                let lexical_form = Self::lexical_form(term_index, buffer)?;
+                Ok(describe_value(datatype_id, lexical_form.as_str()))
+            });
+            match value {
+                Ok(value) => write!(f, "{term_index}={value},")?,
                 Err(err) => write!(f, "{term_index}=ERROR: {err:?},")?,
             }
         }
@@ -41,31 +57,100 @@ impl<'a> std::fmt::Debug for CursorRow<'a> {
 
 impl<'a> CursorRow<'a> {
     /// Returns the resource bound to the given index in the current answer row.
+    ///
+    /// Not every datatype that RDFox returns (such as `xsd:double` or
+    /// `xsd:date`) has a [`Literal`] representation, the error then points
+    /// at [`CursorRow::get`] and [`CursorRow::term`], which handle them all.
+    /// Integers that do not fit in 64 bits are returned as strings.
     fn lexical_value_with_id(&self, term_index: usize) -> Result<Option<Literal>, ekg_error::Error> {
-        let mut buffer = [0u8; 102400]; // TODO: Make this dependent on returned info about buffer size too small
+        self.with_lexical_form(term_index, |data_type, buffer| {
+            let lexical_form = Self::lexical_form(term_index, buffer)?;
+            if !integer_fits_in_64_bits(term_index, data_type, lexical_form.as_str())? {
+                tracing::warn!(
+                    target: LOG_TARGET_DATABASE,
+                    "{data_type:?} {lexical_form} in column #{term_index} does not fit in 64 \
+                     bits, returning it as a string"
+                );
+                return Literal::from_type_and_c_buffer(DataType::String, buffer);
+            }
+            Literal::from_type_and_c_buffer(data_type, buffer).map_err(|err| {
+                match err {
+                    ekg_error::Error::Unknown => {
+                        ekg_error::Error::Exception {
+                            action:  format!("getting column #{term_index} as a literal"),
+                            message: format!(
+                                "datatype {data_type:?} has no literal representation, use \
+                                 CursorRow::get or CursorRow::term instead"
+                            ),
+                        }
+                    }
+                    err => err,
+                }
+            })
+        })
+    }
+
+    /// Get the resource bound to the given index in the current answer row
+    /// in lexical form and pass its datatype and the buffer holding the
+    /// (NUL-terminated) lexical form to the given function.
+    fn with_lexical_form<T, F>(&self, term_index: usize, f: F) -> Result<T, ekg_error::Error>
+        where F: FnOnce(DataType, &[u8]) -> Result<T, ekg_error::Error> {
+        self.with_raw_lexical_form(term_index, |datatype_id, buffer| {
+            let data_type = DataType::from_datatype_id(datatype_id).map_err(|_| {
+                ekg_error::Error::Exception {
+                    action:  format!("getting the value of column #{term_index}"),
+                    message: format!("unsupported datatype id {datatype_id}"),
+                }
+            })?;
+            f(data_type, buffer)
+        })
+    }
+
+    /// Like [`CursorRow::with_lexical_form`] but with the datatype id as
+    /// RDFox returns it, which may be one that [`DataType`] does not know.
+    fn with_raw_lexical_form<T, F>(&self, term_index: usize, f: F) -> Result<T, ekg_error::Error>
+        where F: FnOnce(u8, &[u8]) -> Result<T, ekg_error::Error> {
+        // The buffer of the cursor is only in use already when `f` reads
+        // another value, which then gets a buffer of its own
+        let mut cursor_buffer = self.opened.cursor.lexical_form_buffer.try_borrow_mut();
+        let mut own_buffer = Vec::new();
+        let buffer = match cursor_buffer.as_deref_mut() {
+            Ok(buffer) => buffer,
+            Err(_) => &mut own_buffer,
+        };
+        if buffer.is_empty() {
+            buffer.resize(INITIAL_LEXICAL_FORM_BUFFER_SIZE, 0);
+        }
         let mut lexical_form_size = 0_usize;
         let mut datatype_id: u8 = DataType::UnboundValue as u8;
         let mut resource_resolved = false;
-        // tracing::trace!(
-        //     target: LOG_TARGET_DATABASE,
-        //     "CCursor_appendResourceLexicalForm({term_index}):"
-        // );
 
-        // CCursor_appendResourceLexicalForm(cursor, termIndex, lexicalFormBuffer,
-        // sizeof(lexicalFormBuffer), &lexicalFormSize, &datatypeID, &resourceResolved);
-
-        database_call!(
-            "getting a resource value in lexical form",
-            CCursor_appendResourceLexicalForm(
-                self.opened.cursor.inner,
-                term_index,
-                buffer.as_mut_ptr() as *mut i8,
-                buffer.len(),
-                &mut lexical_form_size,
-                &mut datatype_id as *mut u8,
-                &mut resource_resolved,
-            )
-        )?;
+        // RDFox returns the size of the whole lexical form, also when it
+        // did not fit, so grow the buffer and ask again until it does
+        loop {
+            database_call!(
+                "getting a resource value in lexical form",
+                CCursor_appendResourceLexicalForm(
+                    self.opened.cursor.inner,
+                    term_index,
+                    buffer.as_mut_ptr() as *mut i8,
+                    buffer.len(),
+                    &mut lexical_form_size,
+                    &mut datatype_id as *mut u8,
+                    &mut resource_resolved,
+                )
+            )?;
+            if lexical_form_size < buffer.len() {
+                break;
+            }
+            tracing::trace!(
+                target: LOG_TARGET_DATABASE,
+                "Growing the lexical form buffer from {} to fit {lexical_form_size} bytes",
+                buffer.len()
+            );
+            buffer.resize((lexical_form_size + 1).next_power_of_two(), 0);
+        }
+        buffer[lexical_form_size] = 0;
         if !resource_resolved {
             tracing::error!(
                 target: LOG_TARGET_DATABASE,
@@ -74,8 +159,6 @@ impl<'a> CursorRow<'a> {
             return Err(ekg_error::Error::Unknown); // TODO: Make more specific error
         }
 
-        let data_type = DataType::from_datatype_id(datatype_id)?;
-
         if event_enabled!(tracing::Level::TRACE) {
             tracing::trace!(
                 target: LOG_TARGET_DATABASE,
@@ -84,7 +167,7 @@ impl<'a> CursorRow<'a> {
             );
         }
 
-        Literal::from_type_and_c_buffer(data_type, &buffer)
+        f(datatype_id, &buffer[..=lexical_form_size])
     }
 
     /// Get the value in lexical form of a term in the current solution /
@@ -100,4 +183,146 @@ impl<'a> CursorRow<'a> {
         }
         self.lexical_value_with_id(term_index)
     }
+
+    /// Get the value in lexical form of the answer variable with the given
+    /// name (with or without the leading `?`) in the current row.
+    pub fn lexical_value_by_name(&self, name: &str) -> Result<Option<Literal>, ekg_error::Error> {
+        self.lexical_value(self.opened.get_answer_variable_index(name)?)
+    }
+
+    /// Get the value of a term in the current row with the given term index
+    /// as RDFox stores it, with IRIs split into namespace and local name (see
+    /// [`ResourceValue::as_curie`]), or `None` if it is unbound.
+    ///
+    /// Values that RDFox stores in a binary representation, such as numbers
+    /// and dates, are returned in their lexical form.
+    pub fn resource_value(
+        &self,
+        term_index: usize,
+    ) -> Result<Option<ResourceValue>, ekg_error::Error> {
+        let mut data: *const c_char = std::ptr::null();
+        let mut data_size = 0_usize;
+        let mut namespace: *const c_char = std::ptr::null();
+        let mut namespace_size = 0_usize;
+        let mut datatype_id: u8 = DataType::UnboundValue as u8;
+        let mut resource_resolved = false;
+        database_call!(
+            "getting a resource value",
+            CCursor_getResourceValue(
+                self.opened.cursor.inner,
+                term_index,
+                &mut data,
+                &mut data_size,
+                &mut namespace,
+                &mut namespace_size,
+                &mut datatype_id as *mut u8,
+                &mut resource_resolved,
+            )
+        )?;
+        if !resource_resolved {
+            return Err(ekg_error::Error::Exception {
+                action:  format!("getting the resource value of column #{term_index}"),
+                message: "the resource could not be resolved".to_string(),
+            });
+        }
+        let data_type = DataType::from_datatype_id(datatype_id).map_err(|_| {
+            ekg_error::Error::Exception {
+                action:  format!("getting the resource value of column #{term_index}"),
+                message: format!("unsupported datatype id {datatype_id}"),
+            }
+        })?;
+        if matches!(data_type, DataType::UnboundValue) {
+            return Ok(None);
+        }
+        if !ResourceValue::is_textual(data_type) {
+            return self.with_lexical_form(term_index, |data_type, buffer| {
+                let lexical_form = Self::lexical_form(term_index, buffer)?;
+                Ok(Some(ResourceValue::from_lexical_form(data_type, lexical_form)))
+            });
+        }
+        // The buffers belong to the cursor and change when it advances
+        unsafe {
+            ResourceValue::from(
+                data_type,
+                namespace as *const u8,
+                namespace_size,
+                data as *const u8,
+                data_size,
+            )
+        }
+        .map(Some)
+    }
+
+    /// Get the value of a term in the current row with the given term index
+    /// as a [`Term`], or `None` if it is unbound.
+    ///
+    /// Language-tagged literals keep their tag. Literals of datatypes other
+    /// than `xsd:string`, the numeric types, `xsd:boolean` and `xsd:anyURI`
+    /// are returned as plain literals.
+    pub fn term(&self, term_index: usize) -> Result<Option<Term>, ekg_error::Error> {
+        self.get::<Option<Term>>(term_index)
+    }
+
+    fn lexical_form(term_index: usize, buffer: &[u8]) -> Result<String, ekg_error::Error> {
+        Ok(CStr::from_bytes_until_nul(buffer)
+            .map_err(|err| ekg_error::Error::Exception {
+                action:  format!("getting the lexical form of column #{term_index}"),
+                message: format!("{err}"),
+            })?
+            .to_string_lossy()
+            .into_owned())
+    }
+
+    /// Get the value of a term in the current row with the given term index
+    /// as the given Rust type, for instance `row.get::<i64>(0)?`, checking
+    /// that its datatype fits that type (see [`FromLexicalValue`]).
+    ///
+    /// Unbound values are an error, unless you ask for an `Option<T>`, as
+    /// in `row.get::<Option<String>>(1)?`.
+    pub fn get<T: FromLexicalValue>(&self, term_index: usize) -> Result<T, ekg_error::Error> {
+        self.with_lexical_form(term_index, |data_type, buffer| {
+            if matches!(data_type, DataType::UnboundValue) {
+                return T::from_unbound_value(term_index);
+            }
+            let lexical_form = Self::lexical_form(term_index, buffer)?;
+            T::from_lexical_value(term_index, data_type, lexical_form.as_str())
+        })
+    }
+
+    /// Get the value of the answer variable with the given name (with or
+    /// without the leading `?`) in the current row as the given Rust type,
+    /// like [`CursorRow::get`] does.
+    pub fn get_by_name<T: FromLexicalValue>(&self, name: &str) -> Result<T, ekg_error::Error> {
+        let term_index = self.opened.get_answer_variable_index(name)?;
+        self.get::<T>(term_index).map_err(|err| {
+            match err {
+                ekg_error::Error::Exception { action, message } => {
+                    ekg_error::Error::Exception {
+                        action: format!(
+                            "{action} (?{})",
+                            name.trim_start_matches(['?', '$'])
+                        ),
+                        message,
+                    }
+                }
+                err => err,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::describe_value, ekg_namespace::DataType};
+
+    #[test_log::test]
+    fn test_describe_value() {
+        assert_eq!(describe_value(DataType::UnboundValue as u8, ""), "UNDEF");
+        assert_eq!(
+            describe_value(DataType::Float as u8, "1.5"),
+            "Float:1.5"
+        );
+        // A datatype id that a future RDFox version might return
+        assert_eq!(describe_value(250, "?"), "<<unsupported:250>>:?");
+    }
 }
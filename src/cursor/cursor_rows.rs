@@ -0,0 +1,88 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    super::{query_evaluation::QueryEvaluation, CursorRow, OpenedCursor, OwnedCursorRow},
+    crate::{Cursor, Transaction},
+    std::sync::Arc,
+};
+
+/// An iterator over the rows of an opened [`Cursor`], see
+/// [`Cursor::iter`].
+///
+/// It reports to the [`Instrumentation`](crate::Instrumentation) of the
+/// connection and can be cancelled like [`Cursor::consume`]. After the first
+/// error the iterator is exhausted.
+#[derive(Debug)]
+pub struct CursorRows<'a> {
+    opened:       OpenedCursor<'a>,
+    multiplicity: usize,
+    rowid:        usize,
+    /// The sum of the multiplicities of the rows read so far
+    count:        usize,
+    failed:       bool,
+    evaluation:   QueryEvaluation,
+}
+
+impl<'a> CursorRows<'a> {
+    pub(crate) fn new(
+        cursor: &'a mut Cursor,
+        tx: &Arc<Transaction>,
+    ) -> Result<Self, ekg_error::Error> {
+        let mut evaluation = QueryEvaluation::start(cursor);
+        let (opened, multiplicity) = match OpenedCursor::new(cursor, tx.clone()) {
+            Ok(opened) => opened,
+            Err(err) => {
+                let err = evaluation.timed_out(err);
+                evaluation.finish(Some(&err));
+                return Err(err);
+            },
+        };
+        Ok(Self { opened, multiplicity, rowid: 0, count: 0, failed: false, evaluation })
+    }
+
+    fn read_row(&mut self) -> Result<OwnedCursorRow, ekg_error::Error> {
+        self.evaluation.check()?;
+        self.rowid += 1;
+        self.count += self.multiplicity;
+        let row = CursorRow {
+            opened:       &self.opened,
+            multiplicity: &self.multiplicity,
+            count:        &self.count,
+            rowid:        &self.rowid,
+        };
+        let values = (0..self.opened.arity)
+            .map(|term_index| row.lexical_value(term_index))
+            .collect::<Result<Vec<_>, _>>()?;
+        let owned = OwnedCursorRow::new(
+            values,
+            self.multiplicity,
+            self.rowid,
+            self.opened.variable_indices.clone(),
+        );
+        self.evaluation.row_read(self.multiplicity);
+        // Only advance while there are rows, see `OpenedCursor::advance`
+        self.multiplicity = self
+            .opened
+            .advance()
+            .map_err(|err| self.evaluation.timed_out(err))?;
+        Ok(owned)
+    }
+}
+
+impl<'a> Iterator for CursorRows<'a> {
+    type Item = Result<OwnedCursorRow, ekg_error::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.multiplicity == 0 {
+            self.evaluation.finish(None);
+            return None;
+        }
+        let result = self.read_row();
+        if let Err(err) = &result {
+            self.failed = true;
+            self.evaluation.finish(Some(err));
+        }
+        Some(result)
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::{Display, Formatter},
+        time::Duration,
+    },
+};
+
+/// What it took to evaluate the query of a [`Cursor`](crate::Cursor) the
+/// last time it was opened, as far as it has been read, see
+/// [`Cursor::statistics`](crate::Cursor::statistics).
+///
+/// The C API of RDFox has no evaluation statistics of its own, so these
+/// are measured around the calls to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CursorStatistics {
+    /// How long RDFox took to open the cursor and find the first row
+    pub opened_in: Duration,
+    /// How long RDFox took to find all the other rows
+    pub advancing: Duration,
+    /// The number of rows read
+    pub rows:      usize,
+    /// The sum of the multiplicities of the rows read
+    pub solutions: usize,
+}
+
+impl CursorStatistics {
+    /// The total time spent in RDFox
+    pub fn elapsed(&self) -> Duration { self.opened_in + self.advancing }
+
+    pub(crate) fn opened(elapsed: Duration, multiplicity: usize) -> Self {
+        Self {
+            opened_in: elapsed,
+            advancing: Duration::ZERO,
+            rows:      usize::from(multiplicity > 0),
+            solutions: multiplicity,
+        }
+    }
+
+    pub(crate) fn advanced(&mut self, elapsed: Duration, multiplicity: usize) {
+        self.advancing += elapsed;
+        self.rows += usize::from(multiplicity > 0);
+        self.solutions += multiplicity;
+    }
+}
+
+impl Display for CursorStatistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "rows={} solutions={} opened-in={}µs advancing={}µs",
+            self.rows,
+            self.solutions,
+            self.opened_in.as_micros(),
+            self.advancing.as_micros()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::CursorStatistics, std::time::Duration};
+
+    #[test_log::test]
+    fn test_cursor_statistics() {
+        let mut statistics = CursorStatistics::opened(Duration::from_micros(30), 2);
+        statistics.advanced(Duration::from_micros(5), 1);
+        statistics.advanced(Duration::from_micros(5), 0);
+        assert_eq!(statistics.rows, 2);
+        assert_eq!(statistics.solutions, 3);
+        assert_eq!(statistics.elapsed(), Duration::from_micros(40));
+        assert_eq!(
+            statistics.to_string(),
+            "rows=2 solutions=3 opened-in=30µs advancing=10µs"
+        );
+        assert_eq!(
+            CursorStatistics::opened(Duration::ZERO, 0),
+            CursorStatistics::default()
+        );
+    }
+}
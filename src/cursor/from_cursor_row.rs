@@ -0,0 +1,70 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use super::{CursorRow, FromLexicalValue};
+
+/// Conversion of a [`CursorRow`] into a native Rust value, see
+/// [`Cursor::collect_into`](crate::Cursor::collect_into).
+///
+/// Tuples of up to twelve [`FromLexicalValue`] types are mapped by column
+/// order, so `(IriBuf, String, Option<i64>)` takes the first three columns.
+/// For a struct, implement it by answer variable name with
+/// [`CursorRow::get_by_name`]:
+///
+/// ```no_run
+/// use rdfox_rs::{CursorRow, FromCursorRow};
+///
+/// struct Person {
+///     name: String,
+///     age:  Option<u16>,
+/// }
+///
+/// impl FromCursorRow for Person {
+///     fn from_cursor_row(row: &CursorRow) -> Result<Self, ekg_error::Error> {
+///         Ok(Self {
+///             name: row.get_by_name("name")?,
+///             age:  row.get_by_name("age")?,
+///         })
+///     }
+/// }
+/// ```
+pub trait FromCursorRow: Sized {
+    fn from_cursor_row(row: &CursorRow) -> Result<Self, ekg_error::Error>;
+}
+
+fn check_arity<T>(row: &CursorRow, expected: usize) -> Result<(), ekg_error::Error> {
+    if row.opened.arity < expected {
+        return Err(ekg_error::Error::Exception {
+            action:  format!("getting a row as {}", std::any::type_name::<T>()),
+            message: format!(
+                "the query has {} column(s), expected at least {expected}",
+                row.opened.arity
+            ),
+        });
+    }
+    Ok(())
+}
+
+macro_rules! from_tuple {
+    ($arity:literal: $($t:ident $index:tt),+) => {
+        impl<$($t: FromLexicalValue),+> FromCursorRow for ($($t,)+) {
+            fn from_cursor_row(row: &CursorRow) -> Result<Self, ekg_error::Error> {
+                check_arity::<Self>(row, $arity)?;
+                Ok(($(row.get::<$t>($index)?,)+))
+            }
+        }
+    };
+}
+
+from_tuple!(1: A 0);
+from_tuple!(2: A 0, B 1);
+from_tuple!(3: A 0, B 1, C 2);
+from_tuple!(4: A 0, B 1, C 2, D 3);
+from_tuple!(5: A 0, B 1, C 2, D 3, E 4);
+from_tuple!(6: A 0, B 1, C 2, D 3, E 4, F 5);
+from_tuple!(7: A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+from_tuple!(8: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+from_tuple!(9: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+from_tuple!(10: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+from_tuple!(11: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+from_tuple!(12: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
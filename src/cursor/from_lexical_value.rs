@@ -0,0 +1,461 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    super::UnexpectedDataType,
+    crate::Term,
+    ekg_namespace::DataType,
+    iref::{Iri, IriBuf},
+    std::str::FromStr,
+};
+
+/// Conversion of the lexical form of a value in a
+/// [`CursorRow`](crate::CursorRow) into a native Rust type, see
+/// [`CursorRow::get`](crate::CursorRow::get).
+pub trait FromLexicalValue: Sized {
+    /// Convert the given lexical form of a bound value with the given
+    /// datatype, or fail with [`UnexpectedDataType`] if the datatype or
+    /// the value does not fit the Rust type.
+    fn from_lexical_value(
+        term_index: usize,
+        data_type: DataType,
+        lexical_form: &str,
+    ) -> Result<Self, ekg_error::Error>;
+
+    /// What to return for an unbound value, which is an error unless
+    /// you asked for an `Option<T>`.
+    fn from_unbound_value(term_index: usize) -> Result<Self, ekg_error::Error> {
+        Err(ekg_error::Error::Exception {
+            action:  format!(
+                "getting column #{term_index} as {}",
+                std::any::type_name::<Self>()
+            ),
+            message: "value is unbound".to_string(),
+        })
+    }
+}
+
+fn unexpected_data_type<T>(
+    term_index: usize,
+    expected: &str,
+    actual: DataType,
+) -> Result<T, ekg_error::Error> {
+    Err(UnexpectedDataType {
+        expected: expected.to_string(),
+        actual,
+        term_index,
+        lexical_form: None,
+    }
+    .into())
+}
+
+fn parse<T: FromStr>(
+    term_index: usize,
+    data_type: DataType,
+    lexical_form: &str,
+) -> Result<T, ekg_error::Error> {
+    lexical_form.trim().parse::<T>().map_err(|_| {
+        UnexpectedDataType {
+            expected: std::any::type_name::<T>().to_string(),
+            actual: data_type,
+            term_index,
+            lexical_form: Some(lexical_form.to_string()),
+        }
+        .into()
+    })
+}
+
+/// Split the lexical form that RDFox gives for an `rdf:PlainLiteral`, such
+/// as `Bonjour@fr`, into the text and its language tag (if it has one)
+pub(crate) fn split_language_tag(data_type: DataType, lexical_form: &str) -> (&str, Option<&str>) {
+    match (data_type, lexical_form.rsplit_once('@')) {
+        (DataType::PlainLiteral, Some((text, ""))) => (text, None),
+        (DataType::PlainLiteral, Some((text, language))) => (text, Some(language)),
+        _ => (lexical_form, None),
+    }
+}
+
+/// The XSD datatype IRI of the given (non-string) literal datatype
+fn xsd_datatype_iri(data_type: DataType) -> Option<&'static str> {
+    match data_type {
+        DataType::Boolean => Some("http://www.w3.org/2001/XMLSchema#boolean"),
+        DataType::DateTime => Some("http://www.w3.org/2001/XMLSchema#dateTime"),
+        DataType::DateTimeStamp => Some("http://www.w3.org/2001/XMLSchema#dateTimeStamp"),
+        DataType::Time => Some("http://www.w3.org/2001/XMLSchema#time"),
+        DataType::Date => Some("http://www.w3.org/2001/XMLSchema#date"),
+        DataType::YearMonth => Some("http://www.w3.org/2001/XMLSchema#gYearMonth"),
+        DataType::Year => Some("http://www.w3.org/2001/XMLSchema#gYear"),
+        DataType::MonthDay => Some("http://www.w3.org/2001/XMLSchema#gMonthDay"),
+        DataType::Day => Some("http://www.w3.org/2001/XMLSchema#gDay"),
+        DataType::Month => Some("http://www.w3.org/2001/XMLSchema#gMonth"),
+        DataType::Duration => Some("http://www.w3.org/2001/XMLSchema#duration"),
+        DataType::YearMonthDuration => {
+            Some("http://www.w3.org/2001/XMLSchema#yearMonthDuration")
+        }
+        DataType::DayTimeDuration => Some("http://www.w3.org/2001/XMLSchema#dayTimeDuration"),
+        DataType::Integer => Some("http://www.w3.org/2001/XMLSchema#integer"),
+        DataType::Long => Some("http://www.w3.org/2001/XMLSchema#long"),
+        DataType::Int => Some("http://www.w3.org/2001/XMLSchema#int"),
+        DataType::Short => Some("http://www.w3.org/2001/XMLSchema#short"),
+        DataType::Byte => Some("http://www.w3.org/2001/XMLSchema#byte"),
+        DataType::NonNegativeInteger => Some("http://www.w3.org/2001/XMLSchema#nonNegativeInteger"),
+        DataType::PositiveInteger => Some("http://www.w3.org/2001/XMLSchema#positiveInteger"),
+        DataType::NonPositiveInteger => Some("http://www.w3.org/2001/XMLSchema#nonPositiveInteger"),
+        DataType::NegativeInteger => Some("http://www.w3.org/2001/XMLSchema#negativeInteger"),
+        DataType::UnsignedLong => Some("http://www.w3.org/2001/XMLSchema#unsignedLong"),
+        DataType::UnsignedInt => Some("http://www.w3.org/2001/XMLSchema#unsignedInt"),
+        DataType::UnsignedShort => Some("http://www.w3.org/2001/XMLSchema#unsignedShort"),
+        DataType::UnsignedByte => Some("http://www.w3.org/2001/XMLSchema#unsignedByte"),
+        DataType::Double => Some("http://www.w3.org/2001/XMLSchema#double"),
+        DataType::Float => Some("http://www.w3.org/2001/XMLSchema#float"),
+        DataType::Decimal => Some("http://www.w3.org/2001/XMLSchema#decimal"),
+        DataType::AnyUri => Some("http://www.w3.org/2001/XMLSchema#anyURI"),
+        _ => None,
+    }
+}
+
+fn is_integer(data_type: DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Integer |
+            DataType::Long |
+            DataType::Int |
+            DataType::Short |
+            DataType::Byte |
+            DataType::NonNegativeInteger |
+            DataType::PositiveInteger |
+            DataType::NonPositiveInteger |
+            DataType::NegativeInteger |
+            DataType::UnsignedLong |
+            DataType::UnsignedInt |
+            DataType::UnsignedShort |
+            DataType::UnsignedByte
+    )
+}
+
+/// Whether the given lexical form of a value of an integer datatype fits
+/// the 64 bits that a [`Literal`](ekg_namespace::Literal) holds integers in,
+/// unsigned for the non-negative datatypes (values of other datatypes always
+/// fit), or an error if it's not an
+/// integer at all.
+pub(crate) fn integer_fits_in_64_bits(
+    term_index: usize,
+    data_type: DataType,
+    lexical_form: &str,
+) -> Result<bool, ekg_error::Error> {
+    if !is_integer(data_type) {
+        return Ok(true);
+    }
+    let lexical_form = lexical_form.trim();
+    let digits = lexical_form.strip_prefix(['-', '+']).unwrap_or(lexical_form);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ekg_error::Error::Exception {
+            action:  format!("getting column #{term_index} as a literal"),
+            message: format!("\"{lexical_form}\" is not a valid {data_type:?}"),
+        });
+    }
+    if matches!(
+        data_type,
+        DataType::NonNegativeInteger |
+            DataType::PositiveInteger |
+            DataType::UnsignedLong |
+            DataType::UnsignedInt |
+            DataType::UnsignedShort |
+            DataType::UnsignedByte
+    ) {
+        Ok(lexical_form.parse::<u64>().is_ok())
+    } else {
+        Ok(lexical_form.parse::<i64>().is_ok())
+    }
+}
+
+/// Any integer datatype converts to any Rust integer type as long as the
+/// value fits, so asking for a `u64` when the value is a negative
+/// `xsd:integer` is an error (it does not wrap), as is asking for an `i8`
+/// when the value is 1000.
+macro_rules! from_integer {
+    ($($t:ty),*) => {$(
+        impl FromLexicalValue for $t {
+            fn from_lexical_value(
+                term_index: usize,
+                data_type: DataType,
+                lexical_form: &str,
+            ) -> Result<Self, ekg_error::Error> {
+                if is_integer(data_type) {
+                    parse(term_index, data_type, lexical_form)
+                } else {
+                    unexpected_data_type(term_index, "an integer", data_type)
+                }
+            }
+        }
+    )*};
+}
+
+from_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Floating point numbers can be read from any numeric datatype, including
+/// `xsd:double`, `xsd:float` and `xsd:decimal`
+macro_rules! from_float {
+    ($($t:ty),*) => {$(
+        impl FromLexicalValue for $t {
+            fn from_lexical_value(
+                term_index: usize,
+                data_type: DataType,
+                lexical_form: &str,
+            ) -> Result<Self, ekg_error::Error> {
+                if is_integer(data_type) ||
+                    matches!(data_type, DataType::Double | DataType::Float | DataType::Decimal)
+                {
+                    parse(term_index, data_type, lexical_form)
+                } else {
+                    unexpected_data_type(term_index, "a number", data_type)
+                }
+            }
+        }
+    )*};
+}
+
+from_float!(f32, f64);
+
+impl FromLexicalValue for bool {
+    fn from_lexical_value(
+        term_index: usize,
+        data_type: DataType,
+        lexical_form: &str,
+    ) -> Result<Self, ekg_error::Error> {
+        match (data_type, lexical_form.trim()) {
+            (DataType::Boolean, "true" | "1") => Ok(true),
+            (DataType::Boolean, "false" | "0") => Ok(false),
+            (DataType::Boolean, _) => parse(term_index, data_type, lexical_form),
+            _ => unexpected_data_type(term_index, "a boolean", data_type),
+        }
+    }
+}
+
+/// Any bound value can be read as a `String`, which gives its lexical form
+/// (without quotes, angle brackets or language tag)
+impl FromLexicalValue for String {
+    fn from_lexical_value(
+        _term_index: usize,
+        data_type: DataType,
+        lexical_form: &str,
+    ) -> Result<Self, ekg_error::Error> {
+        Ok(split_language_tag(data_type, lexical_form).0.to_string())
+    }
+}
+
+/// Any bound value can be read as a [`Term`], language-tagged literals
+/// keep their tag
+impl FromLexicalValue for Term {
+    fn from_lexical_value(
+        _term_index: usize,
+        data_type: DataType,
+        lexical_form: &str,
+    ) -> Result<Self, ekg_error::Error> {
+        match data_type {
+            DataType::IriReference => Term::iri(lexical_form),
+            DataType::BlankNode => Ok(Term::blank_node(lexical_form.trim_start_matches("_:"))),
+            data_type => {
+                match (split_language_tag(data_type, lexical_form), xsd_datatype_iri(data_type)) {
+                    ((text, Some(language)), _) => {
+                        Ok(Term::language_tagged_literal(text, language))
+                    }
+                    (_, Some(datatype)) => {
+                        Ok(Term::typed_literal(lexical_form, Iri::new(datatype).unwrap()))
+                    }
+                    ((text, None), None) => Ok(Term::string(text)),
+                }
+            }
+        }
+    }
+}
+
+impl FromLexicalValue for IriBuf {
+    fn from_lexical_value(
+        term_index: usize,
+        data_type: DataType,
+        lexical_form: &str,
+    ) -> Result<Self, ekg_error::Error> {
+        match data_type {
+            DataType::IriReference | DataType::AnyUri => {
+                IriBuf::new(lexical_form.to_string()).map_err(|_| {
+                    ekg_error::Error::Exception {
+                        action:  format!("getting column #{term_index} as an IRI"),
+                        message: format!("<{lexical_form}> is not a valid IRI"),
+                    }
+                })
+            }
+            _ => unexpected_data_type(term_index, "an IRI", data_type),
+        }
+    }
+}
+
+/// Returns `None` for unbound values rather than an error
+impl<T: FromLexicalValue> FromLexicalValue for Option<T> {
+    fn from_lexical_value(
+        term_index: usize,
+        data_type: DataType,
+        lexical_form: &str,
+    ) -> Result<Self, ekg_error::Error> {
+        T::from_lexical_value(term_index, data_type, lexical_form).map(Some)
+    }
+
+    fn from_unbound_value(_term_index: usize) -> Result<Self, ekg_error::Error> { Ok(None) }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{integer_fits_in_64_bits, FromLexicalValue},
+        crate::{Term, UnexpectedDataType},
+        ekg_namespace::DataType,
+    };
+
+    #[test_log::test]
+    fn test_integer_coercion() {
+        assert_eq!(
+            i64::from_lexical_value(0, DataType::Integer, "-42").unwrap(),
+            -42
+        );
+        assert_eq!(
+            u64::from_lexical_value(0, DataType::Integer, "42").unwrap(),
+            42
+        );
+        assert_eq!(
+            i64::from_lexical_value(0, DataType::UnsignedLong, "42").unwrap(),
+            42
+        );
+        // Negative values never wrap into unsigned types
+        assert!(u64::from_lexical_value(0, DataType::Integer, "-1").is_err());
+        assert!(u8::from_lexical_value(0, DataType::Int, "-1").is_err());
+        // Out of range values are errors too
+        assert!(i8::from_lexical_value(0, DataType::Integer, "1000").is_err());
+        assert!(
+            u64::from_lexical_value(0, DataType::Integer, "18446744073709551616").is_err()
+        );
+        assert_eq!(
+            UnexpectedDataType::from_error(
+                &i8::from_lexical_value(2, DataType::Integer, "1000").unwrap_err()
+            )
+            .unwrap()
+            .lexical_form
+            .as_deref(),
+            Some("1000")
+        );
+        // And so are other datatypes
+        let err = i64::from_lexical_value(3, DataType::String, "42").unwrap_err();
+        let unexpected = UnexpectedDataType::from_error(&err).unwrap();
+        assert_eq!(unexpected.expected, "an integer");
+        assert!(matches!(unexpected.actual, DataType::String));
+        assert_eq!(unexpected.term_index, 3);
+        assert_eq!(unexpected.lexical_form, None);
+        assert!(i64::from_lexical_value(0, DataType::Double, "42.0").is_err());
+    }
+
+    #[test_log::test]
+    fn test_integer_width() {
+        assert!(integer_fits_in_64_bits(0, DataType::Integer, "9223372036854775807").unwrap());
+        assert!(integer_fits_in_64_bits(0, DataType::UnsignedLong, "18446744073709551615").unwrap());
+        assert!(!integer_fits_in_64_bits(0, DataType::Integer, "9223372036854775808").unwrap());
+        assert!(!integer_fits_in_64_bits(0, DataType::Integer, "-9223372036854775809").unwrap());
+        assert!(integer_fits_in_64_bits(0, DataType::Int, "abc").is_err());
+        assert!(integer_fits_in_64_bits(0, DataType::Int, "-").is_err());
+        assert!(integer_fits_in_64_bits(0, DataType::String, "abc").unwrap());
+        // Wide integers are still available through `get` and `term`
+        assert_eq!(
+            i128::from_lexical_value(0, DataType::Integer, "9223372036854775808").unwrap(),
+            9223372036854775808
+        );
+        assert!(i32::from_lexical_value(0, DataType::Int, "abc").is_err());
+    }
+
+    #[test_log::test]
+    fn test_other_types() {
+        assert!(bool::from_lexical_value(0, DataType::Boolean, "true").unwrap());
+        assert!(!bool::from_lexical_value(0, DataType::Boolean, "0").unwrap());
+        assert!(bool::from_lexical_value(0, DataType::String, "true").is_err());
+        assert_eq!(
+            f64::from_lexical_value(0, DataType::Integer, "3").unwrap(),
+            3.0
+        );
+        assert_eq!(
+            String::from_lexical_value(0, DataType::String, "abc").unwrap(),
+            "abc"
+        );
+        assert!(iref::IriBuf::from_lexical_value(0, DataType::IriReference, "https://ekgf.org/").is_ok());
+        assert!(iref::IriBuf::from_lexical_value(0, DataType::String, "https://ekgf.org/").is_err());
+    }
+
+    #[test_log::test]
+    fn test_floating_point_and_temporal() {
+        assert_eq!(
+            f64::from_lexical_value(0, DataType::Double, "1.5E0").unwrap(),
+            1.5
+        );
+        assert_eq!(
+            f32::from_lexical_value(0, DataType::Float, "-0.25").unwrap(),
+            -0.25
+        );
+        assert_eq!(
+            f64::from_lexical_value(0, DataType::Double, "INF").unwrap(),
+            f64::INFINITY
+        );
+        assert!(f64::from_lexical_value(0, DataType::Date, "2023-01-01").is_err());
+        assert_eq!(
+            Term::from_lexical_value(0, DataType::Double, "1.5E0")
+                .unwrap()
+                .to_string(),
+            r#""1.5E0"^^<http://www.w3.org/2001/XMLSchema#double>"#
+        );
+        for (data_type, lexical_form, local_name) in [
+            (DataType::Date, "2023-01-01", "date"),
+            (DataType::Time, "12:00:00", "time"),
+            (DataType::DateTimeStamp, "2023-01-01T12:00:00Z", "dateTimeStamp"),
+            (DataType::YearMonthDuration, "P1Y2M", "yearMonthDuration"),
+            (DataType::DayTimeDuration, "PT1H", "dayTimeDuration"),
+        ] {
+            assert_eq!(
+                Term::from_lexical_value(0, data_type, lexical_form)
+                    .unwrap()
+                    .to_string(),
+                format!(r#""{lexical_form}"^^<http://www.w3.org/2001/XMLSchema#{local_name}>"#)
+            );
+        }
+    }
+
+    #[test_log::test]
+    fn test_language_tags() {
+        assert_eq!(
+            Term::from_lexical_value(0, DataType::PlainLiteral, "Bonjour@fr").unwrap(),
+            Term::language_tagged_literal("Bonjour", "fr")
+        );
+        assert_eq!(
+            Term::from_lexical_value(0, DataType::PlainLiteral, "me@home@en-GB")
+                .unwrap()
+                .to_string(),
+            r#""me@home"@en-GB"#
+        );
+        assert_eq!(
+            Term::from_lexical_value(0, DataType::PlainLiteral, "untagged@").unwrap(),
+            Term::string("untagged")
+        );
+        assert_eq!(
+            Term::from_lexical_value(0, DataType::String, "me@home").unwrap(),
+            Term::string("me@home")
+        );
+        assert_eq!(
+            String::from_lexical_value(0, DataType::PlainLiteral, "Bonjour@fr").unwrap(),
+            "Bonjour"
+        );
+    }
+
+    #[test_log::test]
+    fn test_unbound() {
+        assert!(i64::from_unbound_value(0).is_err());
+        assert_eq!(Option::<i64>::from_unbound_value(0).unwrap(), None);
+        assert_eq!(
+            Option::<i64>::from_lexical_value(0, DataType::Integer, "1").unwrap(),
+            Some(1)
+        );
+    }
+}
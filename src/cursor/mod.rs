@@ -1,9 +1,32 @@
 // Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
 //---------------------------------------------------------------
 
-pub use {cursor::Cursor, cursor_row::CursorRow, opened_cursor::OpenedCursor};
+pub use {
+    cursor::{Cursor, INITIAL_LEXICAL_FORM_BUFFER_SIZE, MAX_RETAINED_LEXICAL_FORM_BUFFER_SIZE},
+    cursor_row::CursorRow,
+    cursor_rows::CursorRows,
+    cursor_statistics::CursorStatistics,
+    from_cursor_row::FromCursorRow,
+    from_lexical_value::FromLexicalValue,
+    opened_cursor::OpenedCursor,
+    owned_cursor_row::OwnedCursorRow,
+    resource_value::ResourceValue,
+    triples::Triples,
+    unexpected_data_type::UnexpectedDataType,
+    unknown_variable_name::UnknownVariableName,
+};
 
 #[allow(clippy::module_inception)]
 mod cursor;
 mod cursor_row;
+mod cursor_rows;
+mod cursor_statistics;
+mod from_cursor_row;
+mod from_lexical_value;
 mod opened_cursor;
+mod owned_cursor_row;
+mod query_evaluation;
+mod resource_value;
+mod triples;
+mod unexpected_data_type;
+mod unknown_variable_name;
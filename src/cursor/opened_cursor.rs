@@ -4,7 +4,10 @@
 use {
     crate::{
         Cursor,
+        CursorStatistics,
+        cursor::MAX_RETAINED_LEXICAL_FORM_BUFFER_SIZE,
         database_call,
+        ProjectionMismatch,
         rdfox_api::{
             CCursor,
             CCursor_advance,
@@ -13,9 +16,10 @@ use {
             CCursor_open,
         },
         Transaction,
+        UnknownVariableName,
     },
     ekg_namespace::consts::LOG_TARGET_DATABASE,
-    std::{ptr, sync::Arc},
+    std::{collections::HashMap, ptr, sync::Arc, time::Instant},
 };
 
 #[derive(Debug)]
@@ -25,6 +29,13 @@ pub struct OpenedCursor<'a> {
     /// the arity (i.e., the number of columns) of the answers that the
     /// cursor computes.
     pub arity: usize,
+    /// the name of each answer variable (without the `?`), by column
+    pub(crate) variable_names: Arc<Vec<String>>,
+    /// the index of each answer variable (by name, without the `?`)
+    pub(crate) variable_indices: Arc<HashMap<String, usize>>,
+    /// The last row has been read, so advancing further returns 0 without
+    /// asking RDFox
+    exhausted: bool,
 }
 
 impl<'a> OpenedCursor<'a> {
@@ -35,15 +46,63 @@ impl<'a> OpenedCursor<'a> {
         cursor: &'a mut Cursor,
         tx: Arc<Transaction>,
     ) -> Result<(Self, usize), ekg_error::Error> {
+        Self::new_at(cursor, tx, 0)
+    }
+
+    /// Like [`OpenedCursor::new`] but skipping the given number of answers
+    /// first (so the SPARQL `OFFSET` is applied by RDFox, not in Rust).
+    pub(crate) fn new_at(
+        cursor: &'a mut Cursor,
+        tx: Arc<Transaction>,
+        skip_to_offset: usize,
+    ) -> Result<(Self, usize), ekg_error::Error> {
+        tx.check_active("opening a cursor")?;
+        let buffer = cursor.lexical_form_buffer.get_mut();
+        if buffer.len() > MAX_RETAINED_LEXICAL_FORM_BUFFER_SIZE {
+            *buffer = Vec::new();
+        }
         let c_cursor = cursor.inner;
-        let multiplicity = Self::open(cursor.inner)?;
+        let started = Instant::now();
+        let multiplicity = Self::open(cursor.inner, skip_to_offset)?;
+        cursor
+            .statistics
+            .set(CursorStatistics::opened(started.elapsed(), multiplicity));
         let arity = Self::arity(c_cursor)?;
-        let opened_cursor = OpenedCursor { tx, cursor, arity };
+        let variables = (0..arity)
+            .map(|index| Self::answer_variable_name(c_cursor, index))
+            .collect::<Result<Vec<_>, ekg_error::Error>>()?;
+        if let Some(expected) = cursor.statement().expected_variables() {
+            ProjectionMismatch::check(expected, &variables)?;
+        }
+        let variable_indices = variables
+            .iter()
+            .enumerate()
+            .map(|(index, variable)| (variable.clone(), index))
+            .collect::<HashMap<_, _>>();
+        let opened_cursor = OpenedCursor {
+            tx,
+            cursor,
+            arity,
+            variable_names: Arc::new(variables),
+            variable_indices: Arc::new(variable_indices),
+            exhausted: multiplicity == 0,
+        };
         Ok((opened_cursor, multiplicity))
     }
 
-    fn open(c_cursor: *mut CCursor) -> Result<usize, ekg_error::Error> {
-        let skip_to_offset = 0_usize;
+    /// An `OpenedCursor` for a cursor that has been opened before with
+    /// the given arity and answer variables, without opening it again
+    pub(crate) fn attach(
+        cursor: &'a Cursor,
+        tx: Arc<Transaction>,
+        arity: usize,
+        variable_names: Arc<Vec<String>>,
+        variable_indices: Arc<HashMap<String, usize>>,
+    ) -> Self {
+        OpenedCursor { tx, cursor, arity, variable_names, variable_indices, exhausted: false }
+    }
+
+    fn open(c_cursor: *mut CCursor, skip_to_offset: usize) -> Result<usize, ekg_error::Error> {
         let mut multiplicity = 0_usize;
         database_call!(
             "opening a cursor",
@@ -51,7 +110,7 @@ impl<'a> OpenedCursor<'a> {
         )?;
         tracing::debug!(
             target: LOG_TARGET_DATABASE,
-            "CCursor_open ok multiplicity={multiplicity}"
+            "CCursor_open ok skip_to_offset={skip_to_offset} multiplicity={multiplicity}"
         );
         Ok(multiplicity)
     }
@@ -67,14 +126,25 @@ impl<'a> OpenedCursor<'a> {
         Ok(arity)
     }
 
-    /// TODO: Check why this panics when called after previous call returned
-    /// zero
+    /// Move to the next row and return its multiplicity, 0 when there are
+    /// no more rows (also when called again after that). Fails with
+    /// [`TransactionAlreadyFinished`](crate::TransactionAlreadyFinished)
+    /// once the transaction of the cursor has been committed or rolled back.
     pub fn advance(&mut self) -> Result<usize, ekg_error::Error> {
+        self.tx.check_active("advancing a cursor")?;
+        if self.exhausted {
+            return Ok(0);
+        }
         let mut multiplicity = 0_usize;
+        let started = Instant::now();
         database_call!(
             "advancing the cursor",
             CCursor_advance(self.cursor.inner, &mut multiplicity)
         )?;
+        let mut statistics = self.cursor.statistics.get();
+        statistics.advanced(started.elapsed(), multiplicity);
+        self.cursor.statistics.set(statistics);
+        self.exhausted = multiplicity == 0;
         tracing::trace!(
             target: LOG_TARGET_DATABASE,
             "cursor {:?} advanced, multiplicity={multiplicity}",
@@ -93,15 +163,80 @@ impl<'a> OpenedCursor<'a> {
         Transaction::begin_read_only(&self.cursor.connection)?.execute_and_rollback(|_tx| f(self))
     }
 
+    /// The statistics of the evaluation of the query so far, see
+    /// [`Cursor::statistics`]
+    pub fn statistics(&self) -> CursorStatistics { self.cursor.statistics() }
+
+    /// The names of the answer variables (without the `?`) in the order of
+    /// the columns, as read once when the cursor was opened
+    pub fn variable_names(&self) -> &[String] { self.variable_names.as_slice() }
+
     /// Get the variable name used in the executed SPARQL statement representing
     /// the given column in the output.
     pub fn get_answer_variable_name(&self, index: usize) -> Result<String, ekg_error::Error> {
+        self.variable_names.get(index).cloned().ok_or_else(|| {
+            ekg_error::Error::Exception {
+                action:  format!("getting the name of answer variable #{index}"),
+                message: format!("the cursor has only {} answer variables", self.arity),
+            }
+        })
+    }
+
+    fn answer_variable_name(c_cursor: *mut CCursor, index: usize) -> Result<String, ekg_error::Error> {
         let mut c_buf: *const std::os::raw::c_char = ptr::null();
         database_call!(
             "getting a variable name",
-            CCursor_getAnswerVariableName(self.cursor.inner, index, &mut c_buf)
+            CCursor_getAnswerVariableName(c_cursor, index, &mut c_buf)
         )?;
         let c_name = unsafe { std::ffi::CStr::from_ptr(c_buf) };
         Ok(c_name.to_str().unwrap().to_owned())
     }
+
+    /// Get the column index of the answer variable with the given name
+    /// (with or without the leading `?` or `$`), or fail with
+    /// [`UnknownVariableName`].
+    pub fn get_answer_variable_index(&self, name: &str) -> Result<usize, ekg_error::Error> {
+        Self::variable_index(&self.variable_indices, name)
+    }
+
+    pub(crate) fn variable_index(
+        variable_indices: &HashMap<String, usize>,
+        name: &str,
+    ) -> Result<usize, ekg_error::Error> {
+        let name = name.trim_start_matches(['?', '$']);
+        variable_indices.get(name).copied().ok_or_else(|| {
+            let mut available = variable_indices.iter().collect::<Vec<_>>();
+            available.sort_by_key(|(_, index)| **index);
+            UnknownVariableName {
+                name:      name.to_string(),
+                available: available.into_iter().map(|(name, _)| name.clone()).collect(),
+            }
+            .into()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::OpenedCursor,
+        crate::UnknownVariableName,
+        std::collections::HashMap,
+    };
+
+    #[test_log::test]
+    fn test_variable_index() {
+        let variable_indices =
+            HashMap::from([("s".to_string(), 0), ("p".to_string(), 1), ("o".to_string(), 2)]);
+        assert_eq!(OpenedCursor::variable_index(&variable_indices, "?p").unwrap(), 1);
+        assert_eq!(OpenedCursor::variable_index(&variable_indices, "$o").unwrap(), 2);
+        let err = OpenedCursor::variable_index(&variable_indices, "?g").unwrap_err();
+        assert_eq!(
+            UnknownVariableName::from_error(&err),
+            Some(UnknownVariableName {
+                name:      "g".to_string(),
+                available: vec!["s".to_string(), "p".to_string(), "o".to_string()],
+            })
+        );
+    }
 }
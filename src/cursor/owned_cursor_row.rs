@@ -0,0 +1,46 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::OpenedCursor,
+    ekg_namespace::Literal,
+    std::{collections::HashMap, sync::Arc},
+};
+
+/// A row of a [`Cursor`](crate::Cursor) with all its values read
+/// eagerly, as yielded by [`Cursor::iter`](crate::Cursor::iter), so unlike a
+/// [`CursorRow`](crate::CursorRow) it does not borrow the opened cursor.
+#[derive(Debug, Clone)]
+pub struct OwnedCursorRow {
+    /// The lexical value of each column, `None` when unbound
+    pub values:       Vec<Option<Literal>>,
+    /// The number of times this row occurs in the answer (the row itself
+    /// is yielded only once)
+    pub multiplicity: usize,
+    /// The 1-based number of this row
+    pub rowid:        usize,
+    variable_indices: Arc<HashMap<String, usize>>,
+}
+
+impl OwnedCursorRow {
+    pub(crate) fn new(
+        values: Vec<Option<Literal>>,
+        multiplicity: usize,
+        rowid: usize,
+        variable_indices: Arc<HashMap<String, usize>>,
+    ) -> Self {
+        Self { values, multiplicity, rowid, variable_indices }
+    }
+
+    /// Get the value in lexical form of the term with the given index
+    pub fn lexical_value(&self, term_index: usize) -> Option<&Literal> {
+        self.values.get(term_index).and_then(Option::as_ref)
+    }
+
+    /// Get the value in lexical form of the answer variable with the given
+    /// name (with or without the leading `?`).
+    pub fn lexical_value_by_name(&self, name: &str) -> Result<Option<&Literal>, ekg_error::Error> {
+        let term_index = OpenedCursor::variable_index(&self.variable_indices, name)?;
+        Ok(self.lexical_value(term_index))
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{
+        connection_stats::error_kind,
+        operation_registry::{OperationGuard, OperationKind},
+        sparql_text::abbreviate,
+        statement::MAX_TOKEN_SIZE_IN_LOG,
+        Cursor,
+        DataStoreConnection,
+        Outcome,
+        QueryTimedOut,
+        StatementEvent,
+    },
+    std::{fmt::Debug, sync::Arc, time::Instant},
+};
+
+/// The reading of a cursor, by [`Cursor::count`], [`Cursor::consume`] and
+/// friends or by an iterator ([`CursorRows`](crate::CursorRows) or
+/// [`Triples`](crate::Triples)), which is reported to the
+/// [`Instrumentation`](crate::Instrumentation) of the connection and
+/// registered as an operation (see
+/// [`DataStoreConnection::active_operations`]) from the moment the cursor
+/// is opened until all rows have been read, reading them fails or (for an
+/// iterator) it's dropped.
+#[derive(Debug)]
+pub(crate) struct QueryEvaluation {
+    connection: Arc<DataStoreConnection>,
+    event:      StatementEvent,
+    operation:  OperationGuard,
+    /// The text of the query, with overly long tokens abbreviated
+    query:      String,
+    started:    Instant,
+    /// The sum of the multiplicities of the rows read so far
+    count:      usize,
+    finished:   bool,
+}
+
+impl QueryEvaluation {
+    pub(crate) fn start(cursor: &Cursor) -> Self {
+        let connection = cursor.connection.clone();
+        let statement = cursor.statement();
+        Self {
+            event: connection.statement_started(OperationKind::Query, statement),
+            operation: connection.operations.register(OperationKind::Query, statement),
+            query: abbreviate(statement.as_str(), MAX_TOKEN_SIZE_IN_LOG).into_owned(),
+            started: Instant::now(),
+            count: 0,
+            finished: false,
+            connection,
+        }
+    }
+
+    /// Fail if the operation has been cancelled, to be called before each
+    /// row
+    pub(crate) fn check(&self) -> Result<(), ekg_error::Error> { self.operation.check() }
+
+    /// Turn the given error into a [`QueryTimedOut`] error if it's the
+    /// exception that RDFox throws when a query runs out of time
+    pub(crate) fn timed_out(&self, err: ekg_error::Error) -> ekg_error::Error {
+        QueryTimedOut::map(err, self.started.elapsed(), self.query.as_str())
+    }
+
+    /// A row with the given multiplicity has been read
+    pub(crate) fn row_read(&mut self, multiplicity: usize) {
+        self.count += multiplicity;
+        self.connection.stats.rows_consumed(1);
+    }
+
+    /// Report the outcome, the number of solutions read so far or the
+    /// given error, unless that has been done already
+    pub(crate) fn finish(&mut self, error: Option<&ekg_error::Error>) {
+        self.report(Outcome {
+            duration:   self.started.elapsed(),
+            rows:       error.is_none().then_some(self.count),
+            error_kind: error.map(error_kind),
+        })
+    }
+
+    /// Report the outcome of reading all rows at once, the number of
+    /// solutions or the error of the given result
+    pub(crate) fn finish_with<E: Debug + 'static>(&mut self, result: &Result<usize, E>) {
+        self.report(Outcome::new(self.started, result, result.as_ref().ok().copied()))
+    }
+
+    fn report(&mut self, outcome: Outcome) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        self.connection
+            .instrumentation()
+            .on_statement_finish(&self.event, &outcome);
+    }
+}
+
+impl Drop for QueryEvaluation {
+    fn drop(&mut self) { self.finish(None) }
+}
@@ -0,0 +1,177 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::Namespaces,
+    ekg_namespace::DataType,
+    iref::Iri,
+    std::fmt::Display,
+};
+
+/// A value of a [`CursorRow`](crate::CursorRow) as RDFox stores it, see
+/// [`CursorRow::resource_value`](crate::CursorRow::resource_value).
+///
+/// RDFox keeps IRIs split into a namespace (up to and including the last
+/// `/` or `#`) and a local name, so turning them into CURIEs does not need
+/// the IRI to be split again. Other values have an empty namespace and their
+/// lexical form as local name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceValue {
+    pub data_type:  DataType,
+    pub namespace:  String,
+    pub local_name: String,
+}
+
+impl ResourceValue {
+    /// Whether RDFox returns values of the given datatype as text, in which
+    /// case [`ResourceValue::from`] can be used, rather than in a binary
+    /// representation such as that of numbers and dates
+    pub(crate) fn is_textual(data_type: DataType) -> bool {
+        matches!(
+            data_type,
+            DataType::IriReference |
+                DataType::BlankNode |
+                DataType::String |
+                DataType::PlainLiteral |
+                DataType::AnyUri
+        )
+    }
+
+    /// A value of any other datatype than those that
+    /// [`ResourceValue::is_textual`] accepts, given as its lexical form
+    pub(crate) fn from_lexical_form(data_type: DataType, lexical_form: String) -> Self {
+        Self { data_type, namespace: String::new(), local_name: lexical_form }
+    }
+
+    /// Copy the buffers that RDFox returned, which are only valid until the
+    /// cursor advances, into a `ResourceValue`, for the datatypes that
+    /// [`ResourceValue::is_textual`] accepts
+    ///
+    /// # Safety
+    ///
+    /// Both pointers have to point at (at least) the given number of bytes,
+    /// or be null when that number is 0.
+    pub(crate) unsafe fn from(
+        data_type: DataType,
+        namespace: *const u8,
+        namespace_len: usize,
+        local_name: *const u8,
+        local_name_len: usize,
+    ) -> Result<Self, ekg_error::Error> {
+        let to_string = |data: *const u8, len: usize| -> Result<String, ekg_error::Error> {
+            if len == 0 {
+                return Ok(String::new());
+            }
+            let bytes = std::slice::from_raw_parts(data, len);
+            String::from_utf8(bytes.to_vec()).map_err(|err| {
+                ekg_error::Error::Exception {
+                    action:  "getting a resource value".to_string(),
+                    message: format!("{err}"),
+                }
+            })
+        };
+        Ok(Self {
+            data_type,
+            namespace: to_string(namespace, namespace_len)?,
+            local_name: to_string(local_name, local_name_len)?,
+        })
+    }
+
+    /// The whole value, the namespace followed by the local name
+    pub fn lexical_form(&self) -> String { format!("{}{}", self.namespace, self.local_name) }
+
+    /// The IRI as a CURIE like `skos:Concept`, or `None` if this is not an
+    /// IRI or none of the given namespaces fits it.
+    ///
+    /// A namespace with exactly the IRI of the namespace part is used as is,
+    /// otherwise this falls back to [`Namespaces::shorten`].
+    pub fn as_curie(&self, namespaces: &Namespaces) -> Option<String> {
+        if !matches!(self.data_type, DataType::IriReference) {
+            return None;
+        }
+        if Namespaces::is_local_name(self.local_name.as_str()) {
+            if let Some(name) = namespaces.name_of(self.namespace.as_str()) {
+                return Some(format!("{name}:{}", self.local_name));
+            }
+        }
+        namespaces.shorten(Iri::new(self.lexical_form().as_str()).ok()?)
+    }
+}
+
+impl Display for ResourceValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.data_type {
+            DataType::IriReference => write!(f, "<{}{}>", self.namespace, self.local_name),
+            _ => write!(f, "{}{}", self.namespace, self.local_name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::ResourceValue,
+        crate::Namespaces,
+        ekg_namespace::{DataType, Namespace},
+        iref::Iri,
+    };
+
+    fn iri_value(namespace: &str, local_name: &str) -> ResourceValue {
+        unsafe {
+            ResourceValue::from(
+                DataType::IriReference,
+                namespace.as_ptr(),
+                namespace.len(),
+                local_name.as_ptr(),
+                local_name.len(),
+            )
+            .unwrap()
+        }
+    }
+
+    #[test_log::test]
+    fn test_as_curie() {
+        let namespace = |name: &str, iri: &str| {
+            Namespace::declare_iref_iri(name, Iri::new(iri).unwrap()).unwrap()
+        };
+        let namespaces = Namespaces::empty()
+            .unwrap()
+            .add_namespace(&namespace("skos:", "http://www.w3.org/2004/02/skos/core#"))
+            .unwrap()
+            .add_namespace(&namespace("ekgf:", "https://ekgf.org/"))
+            .unwrap()
+            .add_namespace(&namespace("story:", "https://ekgf.org/ontology/user-story/"))
+            .unwrap();
+        for (namespace, local_name) in [
+            ("http://www.w3.org/2004/02/skos/core#", "prefLabel"),
+            ("https://ekgf.org/ontology/user-story/", "UserStory"),
+            ("https://ekgf.org/", "Thing"),
+            // Split differently than any namespace that fits
+            ("https://ekgf.org/ontology/", "Other"),
+            ("https://example.com/", "Unknown"),
+        ] {
+            let value = iri_value(namespace, local_name);
+            let iri = value.lexical_form();
+            assert_eq!(value.as_curie(&namespaces), namespaces.shorten(Iri::new(&iri).unwrap()));
+        }
+        assert_eq!(
+            iri_value("http://www.w3.org/2004/02/skos/core#", "prefLabel")
+                .as_curie(&namespaces)
+                .as_deref(),
+            Some("skos:prefLabel")
+        );
+
+        let data = "skos";
+        let literal = unsafe {
+            ResourceValue::from(DataType::String, std::ptr::null(), 0, data.as_ptr(), data.len())
+        }
+        .unwrap();
+        assert_eq!(literal.as_curie(&namespaces), None);
+        assert_eq!(literal.to_string(), "skos");
+
+        assert!(!ResourceValue::is_textual(DataType::Integer));
+        let integer = ResourceValue::from_lexical_form(DataType::Integer, "42".to_string());
+        assert_eq!(integer.as_curie(&namespaces), None);
+        assert_eq!(integer.lexical_form(), "42");
+    }
+}
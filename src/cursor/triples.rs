@@ -0,0 +1,127 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    super::{query_evaluation::QueryEvaluation, CursorRow, OpenedCursor},
+    crate::{Cursor, Term, Transaction},
+    std::{collections::HashMap, sync::Arc},
+};
+
+/// An iterator over the triples of a graph, see
+/// [`GraphConnection::export_triples`](crate::GraphConnection::export_triples).
+///
+/// It owns the cursor of a `SELECT ?s ?p ?o` query and reads one row at a
+/// time, so the graph is never held in memory as a whole. It reports to the
+/// [`Instrumentation`](crate::Instrumentation) of the connection and can be
+/// cancelled like [`Cursor::consume`]. After the first error the iterator
+/// is exhausted.
+#[derive(Debug)]
+pub struct Triples {
+    cursor:       Cursor,
+    tx:           Arc<Transaction>,
+    /// The arity and answer variables once the cursor has been opened
+    opened:       Option<(usize, Arc<Vec<String>>, Arc<HashMap<String, usize>>)>,
+    multiplicity: usize,
+    rowid:        usize,
+    count:        usize,
+    failed:       bool,
+    /// From the moment the cursor is opened
+    evaluation:   Option<QueryEvaluation>,
+}
+
+impl Triples {
+    pub(crate) fn new(cursor: Cursor, tx: &Arc<Transaction>) -> Self {
+        Self {
+            cursor,
+            tx: tx.clone(),
+            opened: None,
+            multiplicity: 0,
+            rowid: 0,
+            count: 0,
+            failed: false,
+            evaluation: None,
+        }
+    }
+
+    fn open(&mut self) -> Result<(), ekg_error::Error> {
+        let evaluation = self.evaluation.insert(QueryEvaluation::start(&self.cursor));
+        let (opened, multiplicity) = OpenedCursor::new(&mut self.cursor, self.tx.clone())
+            .map_err(|err| evaluation.timed_out(err))?;
+        self.opened = Some((
+            opened.arity,
+            opened.variable_names.clone(),
+            opened.variable_indices.clone(),
+        ));
+        self.multiplicity = multiplicity;
+        Ok(())
+    }
+
+    fn read_triple(&mut self) -> Result<(Term, Term, Term), ekg_error::Error> {
+        let evaluation = self.evaluation.as_mut().unwrap();
+        evaluation.check()?;
+        let (arity, variable_names, variable_indices) = self.opened.clone().unwrap();
+        let mut opened = OpenedCursor::attach(
+            &self.cursor,
+            self.tx.clone(),
+            arity,
+            variable_names,
+            variable_indices,
+        );
+        self.rowid += 1;
+        self.count += self.multiplicity;
+        let row = CursorRow {
+            opened:       &opened,
+            multiplicity: &self.multiplicity,
+            count:        &self.count,
+            rowid:        &self.rowid,
+        };
+        let term = |term_index: usize| {
+            row.term(term_index)?.ok_or_else(|| {
+                ekg_error::Error::Exception {
+                    action:  format!("exporting triple #{}", self.rowid),
+                    message: format!("column #{term_index} is unbound"),
+                }
+            })
+        };
+        let triple = (term(0)?, term(1)?, term(2)?);
+        evaluation.row_read(self.multiplicity);
+        // Only advance while there are rows, the attached cursor does not
+        // know when it is exhausted
+        self.multiplicity = opened.advance().map_err(|err| evaluation.timed_out(err))?;
+        Ok(triple)
+    }
+
+    fn fail(&mut self, err: &ekg_error::Error) {
+        self.failed = true;
+        if let Some(evaluation) = self.evaluation.as_mut() {
+            evaluation.finish(Some(err));
+        }
+    }
+}
+
+impl Iterator for Triples {
+    type Item = Result<(Term, Term, Term), ekg_error::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        if self.opened.is_none() {
+            if let Err(err) = self.open() {
+                self.fail(&err);
+                return Some(Err(err));
+            }
+        }
+        if self.multiplicity == 0 {
+            if let Some(evaluation) = self.evaluation.as_mut() {
+                evaluation.finish(None);
+            }
+            return None;
+        }
+        let result = self.read_triple();
+        if let Err(err) = &result {
+            self.fail(err);
+        }
+        Some(result)
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    ekg_namespace::DataType,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// A value in a [`CursorRow`](crate::CursorRow) could not be converted to
+/// the asked for Rust type, see
+/// [`FromLexicalValue`](crate::FromLexicalValue).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnexpectedDataType {
+    /// What the Rust type can be read from, like `an integer` or `i8`
+    pub expected:     String,
+    #[serde(with = "data_type_id")]
+    pub actual:       DataType,
+    /// The index of the column in the row
+    pub term_index:   usize,
+    /// The lexical form, if the datatype fits but the value does not (it's
+    /// out of range or invalid)
+    pub lexical_form: Option<String>,
+}
+
+impl PartialEq for UnexpectedDataType {
+    fn eq(&self, other: &Self) -> bool {
+        self.expected == other.expected &&
+            self.actual as u8 == other.actual as u8 &&
+            self.term_index == other.term_index &&
+            self.lexical_form == other.lexical_form
+    }
+}
+
+impl Eq for UnexpectedDataType {}
+
+impl Display for UnexpectedDataType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.lexical_form {
+            Some(lexical_form) => {
+                write!(
+                    f,
+                    "UnexpectedDataType: column #{}: {:?} \"{lexical_form}\" is out of range or \
+                     invalid for {}",
+                    self.term_index, self.actual, self.expected
+                )
+            },
+            None => {
+                write!(
+                    f,
+                    "UnexpectedDataType: column #{}: unexpected datatype {:?}, expected {}",
+                    self.term_index, self.actual, self.expected
+                )
+            },
+        }
+    }
+}
+
+impl std::error::Error for UnexpectedDataType {}
+
+impl TypedError for UnexpectedDataType {
+    const NAME: &'static str = "UnexpectedDataType";
+
+    fn action(&self) -> String { format!("getting column #{}", self.term_index) }
+}
+
+impl From<UnexpectedDataType> for ekg_error::Error {
+    fn from(value: UnexpectedDataType) -> Self { value.to_error() }
+}
+
+impl UnexpectedDataType {
+    /// The [`UnexpectedDataType`] error that the given error was made from,
+    /// if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
+
+/// A [`DataType`] as the datatype id that RDFox uses for it
+mod data_type_id {
+    use {
+        ekg_namespace::DataType,
+        serde::{de::Error, Deserialize, Deserializer, Serializer},
+    };
+
+    pub(super) fn serialize<S: Serializer>(
+        data_type: &DataType,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*data_type as u8)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DataType, D::Error> {
+        let datatype_id = u8::deserialize(deserializer)?;
+        DataType::from_datatype_id(datatype_id)
+            .map_err(|_| D::Error::custom(format!("unknown datatype id {datatype_id}")))
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// A value was asked for by the name of a variable that the query does not
+/// project, see
+/// [`OpenedCursor::get_answer_variable_index`](crate::OpenedCursor::get_answer_variable_index).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnknownVariableName {
+    /// The name that was asked for, without `?` or `$`
+    pub name:      String,
+    /// The answer variables (without `?`) in the order of their columns
+    pub available: Vec<String>,
+}
+
+impl Display for UnknownVariableName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "UnknownVariableName: unknown variable name ?{}, available are: ", self.name)?;
+        let available = self
+            .available
+            .iter()
+            .map(|name| format!("?{name}"))
+            .collect::<Vec<_>>();
+        write!(f, "{}", available.join(", "))
+    }
+}
+
+impl std::error::Error for UnknownVariableName {}
+
+impl TypedError for UnknownVariableName {
+    const NAME: &'static str = "UnknownVariableName";
+
+    fn action(&self) -> String { format!("looking up answer variable ?{}", self.name) }
+}
+
+impl From<UnknownVariableName> for ekg_error::Error {
+    fn from(value: UnknownVariableName) -> Self { value.to_error() }
+}
+
+impl UnknownVariableName {
+    /// The [`UnknownVariableName`] error that the given error was made from,
+    /// if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{NonUtf8Path, Parameters},
+    std::path::{Path, PathBuf},
+};
+
+/// The parameters of a data source that reads a delimited file such as a
+/// CSV file, see
+/// [`DataStoreConnection::register_data_source`](crate::DataStoreConnection::register_data_source).
+///
+/// The columns of the file are mapped to the columns of a tuple table with
+/// [`DataStoreConnection::create_tuple_table`](crate::DataStoreConnection::create_tuple_table),
+/// using parameters like `dataSourceName`, `columns`, `1` (for instance
+/// `{id}` for the value of column `id`) and `1.datatype`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataSourceParameters {
+    file:      PathBuf,
+    delimiter: char,
+    header:    bool,
+    quote:     Option<char>,
+}
+
+impl DataSourceParameters {
+    /// A comma-separated file with a header line, the file has to be
+    /// accessible to the server (see
+    /// [`ParametersBuilder::sandbox_directory`](crate::ParametersBuilder::sandbox_directory))
+    pub fn csv(file: &Path) -> Self {
+        Self { file: file.to_path_buf(), delimiter: ',', header: true, quote: None }
+    }
+
+    pub fn delimiter(self, delimiter: char) -> Self { Self { delimiter, ..self } }
+
+    /// Whether the first line has the names of the columns, otherwise the
+    /// columns are named `1`, `2` and so on
+    pub fn header(self, header: bool) -> Self { Self { header, ..self } }
+
+    /// The character that values with a delimiter in them are quoted with
+    pub fn quote(self, quote: char) -> Self { Self { quote: Some(quote), ..self } }
+
+    /// The parameters to register the data source with, failing with
+    /// [`NonUtf8Path`] if the path of the file is not valid UTF-8
+    pub fn build(&self) -> Result<Parameters, ekg_error::Error> {
+        let file = self
+            .file
+            .to_str()
+            .ok_or_else(|| NonUtf8Path { path: self.file.clone() })?;
+        let parameters = Parameters::empty()?;
+        parameters.set_string("type", "delimitedFile")?;
+        parameters.set_string("file", file)?;
+        parameters.set_string("delimiter", delimiter_name(self.delimiter).as_str())?;
+        parameters.set_string("header", if self.header { "true" } else { "false" })?;
+        if let Some(quote) = self.quote {
+            parameters.set_string("quote", quote.to_string().as_str())?;
+        }
+        Ok(parameters)
+    }
+}
+
+/// RDFox names the whitespace delimiters rather than taking them literally
+fn delimiter_name(delimiter: char) -> String {
+    match delimiter {
+        ' ' => "space".to_string(),
+        '\t' => "tab".to_string(),
+        delimiter => delimiter.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::DataSourceParameters, std::path::Path};
+
+    #[test_log::test]
+    fn test_csv() {
+        let parameters = DataSourceParameters::csv(Path::new("people.tsv"))
+            .delimiter('\t')
+            .header(false)
+            .build()
+            .unwrap();
+        assert_eq!(parameters.get_string("type", "").unwrap(), "delimitedFile");
+        assert_eq!(parameters.get_string("file", "").unwrap(), "people.tsv");
+        assert_eq!(parameters.get_string("delimiter", "").unwrap(), "tab");
+        assert_eq!(parameters.get_string("header", "").unwrap(), "false");
+        assert!(!parameters.contains_key("quote"));
+    }
+}
@@ -1,7 +1,9 @@
 use {
     crate::{
         connectable_data_store::ConnectableDataStore,
+        DataStoreBuilder,
         Parameters,
+        PersistenceMode,
         server_connection::ServerConnection,
     },
     owo_colors::OwoColorize,
@@ -17,6 +19,7 @@ use {
 ///
 /// See <https://docs.oxfordsemantic.tech/data-stores.html>
 #[derive(Debug, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DataStore {
     pub name: String,
     pub parameters: Parameters,
@@ -29,6 +32,9 @@ impl Display for DataStore {
 }
 
 impl DataStore {
+    /// Declare a data store with typed options, see [`DataStoreBuilder`]
+    pub fn builder() -> DataStoreBuilder { DataStoreBuilder::default() }
+
     pub fn declare_with_parameters(
         name: &str,
         parameters: Parameters,
@@ -39,10 +45,19 @@ impl DataStore {
         }))
     }
 
+    /// The parameters that this data store has been declared with
+    pub fn parameters(&self) -> &Parameters { &self.parameters }
+
+    /// The persistence of this data store as declared in its parameters, or
+    /// `None` if they don't declare it (so that of the server applies)
+    pub fn persistence(&self) -> Option<PersistenceMode> { self.parameters.persistence() }
+
     pub fn create(self, server_connection: &Arc<ServerConnection>) -> Result<(), ekg_error::Error> {
         server_connection.create_data_store(&self).map(|_| ())
     }
 
+    /// Build a connection pool for this data store, first creating it
+    /// (unless it exists already) if `create` is true.
     pub fn pool_for(
         self: &Arc<DataStore>,
         server_connection: &Arc<ServerConnection>,
@@ -50,7 +65,7 @@ impl DataStore {
         release_on_return_to_pool: bool,
     ) -> Result<Pool<ConnectableDataStore>, ekg_error::Error> {
         if create {
-            server_connection.create_data_store(self)?;
+            server_connection.create_data_store_if_missing(self)?;
         }
 
         let cds = ConnectableDataStore::new(self, server_connection, release_on_return_to_pool);
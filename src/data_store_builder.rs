@@ -0,0 +1,127 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{
+        DataStore,
+        DataStoreType,
+        EqualityMode,
+        InvalidDataStoreName,
+        ParametersBuilder,
+        PersistenceMode,
+    },
+    std::sync::Arc,
+};
+
+/// Declares a [`DataStore`] with typed options, checking them before the
+/// data store is created on a server, for instance:
+///
+/// ```no_run
+/// use rdfox_rs::{DataStore, DataStoreType, PersistenceMode};
+///
+/// let data_store = DataStore::builder()
+///     .name("example")
+///     .data_store_type(DataStoreType::ParallelNN)
+///     .persistence(PersistenceMode::Off)
+///     .build()?;
+/// # Ok::<(), ekg_error::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct DataStoreBuilder {
+    name:       Option<String>,
+    parameters: ParametersBuilder,
+}
+
+impl DataStoreBuilder {
+    /// The name of the data store. RDFox takes any name that is not empty,
+    /// its REST API percent-encodes it in URLs. It is passed to RDFox as a
+    /// C string, so it can't contain NUL characters. [`build`](Self::build)
+    /// fails with [`InvalidDataStoreName`] otherwise.
+    pub fn name(self, name: &str) -> Self { Self { name: Some(name.to_string()), ..self } }
+
+    pub fn data_store_type(self, data_store_type: DataStoreType) -> Self {
+        Self { parameters: self.parameters.data_store_type(data_store_type), ..self }
+    }
+
+    pub fn persistence(self, mode: PersistenceMode) -> Self {
+        Self { parameters: self.parameters.persistence(mode), ..self }
+    }
+
+    pub fn equality_mode(self, mode: EqualityMode) -> Self {
+        Self { parameters: self.parameters.equality(mode), ..self }
+    }
+
+    pub fn base_iri(self, iri: &str) -> Self {
+        Self { parameters: self.parameters.base_iri(iri), ..self }
+    }
+
+    /// Any other parameters, see [`ParametersBuilder`]
+    pub fn parameters<F>(self, f: F) -> Self
+        where F: FnOnce(ParametersBuilder) -> ParametersBuilder {
+        Self { parameters: f(self.parameters), ..self }
+    }
+
+    /// The data store, or the first invalid name, value or unsupported
+    /// parameter
+    pub fn build(self) -> Result<Arc<DataStore>, ekg_error::Error> {
+        let name = self.name.unwrap_or_default();
+        check_data_store_name(name.as_str())?;
+        DataStore::declare_with_parameters(name.as_str(), self.parameters.build()?)
+    }
+}
+
+/// Check the given data store name, see [`DataStoreBuilder::name`]
+pub(crate) fn check_data_store_name(name: &str) -> Result<(), ekg_error::Error> {
+    let invalid = |reason: &str| {
+        Err(InvalidDataStoreName { name: name.to_string(), reason: reason.to_string() }.into())
+    };
+    if name.is_empty() {
+        return invalid("is empty");
+    }
+    if name.contains('\0') {
+        return invalid("contains a NUL character");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::check_data_store_name,
+        crate::{DataStore, DataStoreType, InvalidDataStoreName, Parameter, PersistenceMode},
+    };
+
+    #[test_log::test]
+    fn test_check_data_store_name() {
+        for name in ["example", "from-json", "with space", "a/b", "naïve", ".hidden"] {
+            assert!(check_data_store_name(name).is_ok(), "{name}");
+        }
+        for name in ["", "nul\0"] {
+            let err = check_data_store_name(name).unwrap_err();
+            let invalid = InvalidDataStoreName::from_error(&err).unwrap();
+            assert_eq!(invalid.name, name);
+        }
+    }
+
+    #[test_log::test]
+    fn test_builder() {
+        let data_store = DataStore::builder()
+            .name("built")
+            .data_store_type(DataStoreType::ParallelWW)
+            .persistence(PersistenceMode::Off)
+            .build()
+            .unwrap();
+        assert_eq!(data_store.name, "built");
+        assert_eq!(data_store.persistence(), Some(PersistenceMode::Off));
+        assert_eq!(
+            data_store
+                .parameters()
+                .get_string(Parameter::DataStoreType.name().unwrap(), "")
+                .unwrap(),
+            "parallel-ww"
+        );
+        assert!(DataStore::builder().build().is_err());
+        assert!(DataStore::builder().name("a\0b").build().is_err());
+        assert!(DataStore::builder().name("ok").base_iri("not an iri").build().is_err());
+    }
+}
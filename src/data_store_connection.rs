@@ -2,28 +2,59 @@
 //---------------------------------------------------------------
 
 use {
-    colored::Colorize,
     crate::{
+        connection_stats::{ConnectionStats, ConnectionStatsSnapshot},
         database_call,
         DataStore,
+        ExceptionKind,
+        data_store_state::{self, OnlineCheck},
+        directory_import::{self, ImportOptions, ImportSummary},
+        ExportScope,
         FactDomain,
+        ImportEvent,
+        Instrumentation,
         Namespaces,
+        NonUtf8Path,
+        operation_registry::{OperationInfo, OperationKind, OperationRegistry},
+        Outcome,
         Parameters,
+        PersistenceMode,
+        PersistenceRequired,
+        PoolOptions,
+        prepared_cursors::PreparedCursors,
+        ProofTree,
+        RdfFormat,
+        RDFoxException,
+        server::ServerDependent,
         rdfox_api::{
             CDataStoreConnection,
+            CDataStoreConnection_compact,
+            CDataStoreConnection_createTupleTable,
+            CDataStoreConnection_deleteTupleTable,
+            CDataStoreConnection_deregisterDataSource,
             CDataStoreConnection_destroy,
             CDataStoreConnection_evaluateUpdate,
+            CDataStoreConnection_getComponentInfo,
             CDataStoreConnection_getName,
             CDataStoreConnection_getUniqueID,
             CDataStoreConnection_importAxiomsFromTriples,
+            CDataStoreConnection_importDataFromBuffer,
             CDataStoreConnection_importDataFromFile,
-            CStatementResult,
-            CUpdateType,
+            CDataStoreConnection_recomputeMaterialization,
+            CDataStoreConnection_registerDataSource,
         },
         ServerConnection,
+        sparql_text::bind_variables,
         Statement,
+        StatementEvent,
+        statistics::{read_component_info, DataStoreStatistics},
+        StreamedResult,
         Streamer,
+        Term,
         Transaction,
+        UnsupportedOnThisRDFoxVersion,
+        UpdateResult,
+        UpdateType,
     },
     ekg_namespace::{
         consts::{
@@ -31,27 +62,32 @@ use {
             DEFAULT_GRAPH_RDFOX,
             LOG_TARGET_DATABASE,
             LOG_TARGET_FILES,
-            TEXT_TURTLE,
         },
         Graph,
         Namespace,
     },
-    fancy_regex::Regex,
-    ignore::{types::TypesBuilder, WalkBuilder},
+    flate2::{read::MultiGzDecoder, write::GzEncoder, Compression},
     indoc::formatdoc,
     iref::Iri,
     mime::Mime,
     std::{
         ffi::{CStr, CString},
         fmt::{Debug, Display, Formatter},
-        io::Write,
+        fs::File,
+        io::{BufReader, BufWriter, Write},
         mem::MaybeUninit,
         ops::Deref,
-        os::unix::ffi::OsStrExt,
         path::Path,
         ptr::{self, null_mut},
-        sync::Arc,
-        time::Instant,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+            Mutex,
+            OnceLock,
+            RwLock,
+            Weak,
+        },
+        time::{Duration, Instant},
     },
 };
 
@@ -63,6 +99,18 @@ pub struct DataStoreConnection {
     pub(crate) inner: *mut CDataStoreConnection,
     started_at: Instant,
     pub number: usize,
+    prepared_cursors: Mutex<PreparedCursors>,
+    pub(crate) stats: ConnectionStats,
+    pub(crate) operations: Arc<OperationRegistry>,
+    pub(crate) online_check: OnlineCheck,
+    /// Overrides the instrumentation of the server connection if set
+    instrumentation: RwLock<Option<Arc<dyn Instrumentation>>>,
+    /// The unique id of the data store when this connection was made
+    unique_id: OnceLock<String>,
+    /// The outermost transaction that was begun on this connection last,
+    /// see [`Transaction::is_nested`]
+    pub(crate) active_transaction: Mutex<Weak<Transaction>>,
+    dependent: ServerDependent,
 }
 
 unsafe impl Sync for DataStoreConnection {}
@@ -90,13 +138,29 @@ impl Drop for DataStoreConnection {
         let duration = self.started_at.elapsed();
 
         let self_msg = format!("{self}");
-        unsafe {
-            CDataStoreConnection_destroy(self.inner.cast());
+        let prepared_cursors = self
+            .prepared_cursors
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // RDFox has already freed everything of a stopped server, so
+        // destroying our pointers now would be a use-after-free
+        if self.dependent.is_stopped() {
+            tracing::warn!(
+                target: LOG_TARGET_DATABASE,
+                "Leaking {self_msg} since the server has been stopped"
+            );
+            prepared_cursors.leak();
+        } else {
+            prepared_cursors.clear();
+            unsafe {
+                CDataStoreConnection_destroy(self.inner.cast());
+            }
         }
         self.inner = null_mut();
         tracing::debug!(
             target: LOG_TARGET_DATABASE,
             duration = ?duration,
+            stats = %self.stats.snapshot(),
             "Dropped {self_msg}",
         );
     }
@@ -106,7 +170,9 @@ impl DataStoreConnection {
     pub(crate) fn new(
         server_connection: &Arc<ServerConnection>,
         data_store: &Arc<DataStore>,
+        dependent: ServerDependent,
         inner: *mut CDataStoreConnection,
+        maintenance: bool,
     ) -> Self {
         Self {
             data_store: data_store.clone(),
@@ -114,22 +180,119 @@ impl DataStoreConnection {
             inner,
             started_at: Instant::now(),
             number: Self::get_number(),
+            prepared_cursors: Mutex::default(),
+            stats: ConnectionStats::default(),
+            operations: Arc::default(),
+            online_check: if maintenance { OnlineCheck::maintenance() } else { OnlineCheck::default() },
+            instrumentation: RwLock::default(),
+            unique_id: OnceLock::new(),
+            active_transaction: Mutex::new(Weak::new()),
+            dependent,
         }
     }
 
     pub fn same(self: &Arc<Self>, other: &Arc<Self>) -> bool { self.number == other.number }
 
     fn get_number() -> usize {
-        use std::sync::atomic::{AtomicUsize, Ordering};
         static COUNTER: AtomicUsize = AtomicUsize::new(1);
         COUNTER.fetch_add(1, Ordering::Relaxed)
     }
 
+    /// The statistics of this connection since it was created, like the
+    /// number of transactions, cursors and imports.
+    pub fn stats_snapshot(&self) -> ConnectionStatsSnapshot { self.stats.snapshot() }
+
+    /// The [`Instrumentation`] of this connection, or else the one of its
+    /// server connection
+    pub fn instrumentation(&self) -> Arc<dyn Instrumentation> {
+        match self.instrumentation.read().unwrap().as_ref() {
+            Some(instrumentation) => instrumentation.clone(),
+            None => self.server_connection.instrumentation(),
+        }
+    }
+
+    /// Use the given [`Instrumentation`] for this connection only, `None`
+    /// to go back to the one of the server connection
+    pub fn set_instrumentation(&self, instrumentation: Option<Arc<dyn Instrumentation>>) {
+        *self.instrumentation.write().unwrap() = instrumentation;
+    }
+
+    /// Tell the instrumentation that the given statement is being executed,
+    /// returns the event to pass to `on_statement_finish` when it's done
+    pub(crate) fn statement_started(
+        &self,
+        kind: OperationKind,
+        statement: &Statement,
+    ) -> StatementEvent {
+        let event = StatementEvent {
+            connection: self.number,
+            kind,
+            fingerprint: PreparedCursors::fingerprint(statement.as_str()),
+        };
+        self.instrumentation().on_statement_start(&event);
+        event
+    }
+
+    /// The operations (reading a cursor, evaluating an update or streaming
+    /// results) that are running on this connection right now, in the
+    /// order in which they started.
+    pub fn active_operations(&self) -> Vec<OperationInfo> { self.operations.active() }
+
+    /// Ask the operation with the given identifier (see
+    /// [`DataStoreConnection::active_operations`]) to stop, which it does
+    /// with an [`OperationCancelled`](crate::OperationCancelled) error the
+    /// next time it gets control back from RDFox, i.e. at its next row or
+    /// its next chunk of streamed data. Returns false if there is no such
+    /// operation (anymore) or if it's an update, which can't be cancelled
+    /// (see [`OperationKind::is_cancellable`](crate::OperationKind::is_cancellable)).
+    pub fn cancel_operation(&self, id: u64) -> bool { self.operations.cancel(id) }
+
+    /// Whether this is an administrative connection that ignores the
+    /// offline state of its data store, see
+    /// [`ServerConnection::connect_to_data_store_for_maintenance`].
+    pub fn is_maintenance(&self) -> bool { self.online_check.is_maintenance() }
+
+    /// Fail with a `DataStoreOffline` error if the data store has been taken
+    /// offline (see [`ServerConnection::set_data_store_offline`]), reading
+    /// its state at most once per [`DataStoreConnection::online_check_ttl`].
+    pub fn check_online(self: &Arc<Self>) -> Result<(), ekg_error::Error> {
+        self.online_check.check(self, None)
+    }
+
+    /// Let every new transaction on this connection fail with a
+    /// `DataStoreOffline` error while the data store is offline (off by
+    /// default, ignored by maintenance connections).
+    pub fn set_online_check(&self, enabled: bool) { self.online_check.set_enabled(enabled) }
+
+    /// How long a connection trusts the offline/online state of its data
+    /// store that it read last, defaults to
+    /// [`DEFAULT_ONLINE_CHECK_TTL`](crate::DEFAULT_ONLINE_CHECK_TTL)
+    pub fn online_check_ttl() -> Duration { data_store_state::online_check_ttl() }
+
+    /// Set how long all connections trust the offline/online state of their
+    /// data store that they read last
+    pub fn set_online_check_ttl(ttl: Duration) { data_store_state::set_online_check_ttl(ttl) }
+
+    /// The numbers of facts, rules and resources of the data store and
+    /// the memory it uses, as far as RDFox reports them.
+    pub fn statistics(&self) -> Result<DataStoreStatistics, ekg_error::Error> {
+        assert!(
+            !self.inner.is_null(),
+            "invalid datastore connection"
+        );
+        self.check_not_stopped("getting the statistics of a data store")?;
+        let component_info = read_component_info("getting the statistics of a data store", |info| {
+            unsafe { CDataStoreConnection_getComponentInfo(self.inner, true, info) }
+        })?;
+        Ok(DataStoreStatistics::new(self.data_store.name.as_str(), component_info))
+    }
+
     pub fn get_id(&self) -> Result<String, ekg_error::Error> {
         assert!(
             !self.inner.is_null(),
             "invalid datastore connection"
         );
+        self.check_not_stopped("getting the name of a datastore connection")?;
         let mut name: *const std::os::raw::c_char = ptr::null();
         database_call!(
             "getting the name of a datastore connection",
@@ -144,6 +307,7 @@ impl DataStoreConnection {
             !self.inner.is_null(),
             "invalid datastore connection"
         );
+        self.check_not_stopped("getting the unique id of a datastore connection")?;
         let mut unique_id: *const std::os::raw::c_char = ptr::null();
         database_call!(
             "Getting the unique id of datastore connection",
@@ -153,73 +317,432 @@ impl DataStoreConnection {
         Ok(c_str.to_str().unwrap().into())
     }
 
-    /// Import RDF data from the given file into the given graph.
-    ///
-    /// NOTE: Only supports turtle files at the moment.
+    /// Check that this connection still works and that its data store has
+    /// not been replaced (deleted and created again under the same name,
+    /// which gives it a new unique id) or deleted since the connection was
+    /// made, see [`DataStoreConnection::is_stale`].
+    pub fn validate(&self) -> Result<(), ekg_error::Error> {
+        if self.is_stale()? {
+            return Err(ekg_error::Error::Exception {
+                action:  format!("validating {self}"),
+                message: format!(
+                    "{} has been replaced or deleted since the connection was made",
+                    self.data_store
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether the data store of this connection has been replaced or
+    /// deleted since the connection was made, in which case it no longer
+    /// sees the data of the data store with its name. See
+    /// [`DataStoreConnection::validate`].
+    pub fn is_stale(&self) -> Result<bool, ekg_error::Error> {
+        match self.get_unique_id() {
+            Ok(unique_id) => Ok(*self.unique_id.get_or_init(|| unique_id.clone()) != unique_id),
+            // The only resource that getting the unique id can miss is the
+            // data store
+            Err(error)
+                if matches!(
+                    RDFoxException::kind_of(&error),
+                    Some(ExceptionKind::DataStoreNotFound | ExceptionKind::UnknownResource)
+                ) =>
+            {
+                Ok(true)
+            },
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Whether a transaction on this connection is still around, so that
+    /// the connection is in use even if nothing else refers to it
+    pub(crate) fn has_active_transaction(&self) -> bool {
+        self.active_transaction
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .upgrade()
+            .is_some()
+    }
+
+    /// Import RDF data from the given file into the given graph, detecting
+    /// its format from the file extension (see [`RdfFormat::from_path`]),
+    /// falling back to Turtle.
     pub fn import_data_from_file<P>(&self, file: P, graph: &Graph) -> Result<(), ekg_error::Error>
         where P: AsRef<Path> {
+        let format = RdfFormat::from_path_or_turtle(file.as_ref());
+        self.import_data_from_file_with_format(file, graph, format)
+    }
+
+    /// Import RDF data in the given format from the given file into the
+    /// given graph, decompressing it first if it's a `*.gz` file.
+    pub fn import_data_from_file_with_format<P>(
+        &self,
+        file: P,
+        graph: &Graph,
+        format: RdfFormat,
+    ) -> Result<(), ekg_error::Error>
+        where P: AsRef<Path> {
+        self.import_file(file.as_ref(), graph, format, UpdateType::Addition)
+    }
+
+    /// Import RDF data from the given file into the given graph like
+    /// [`DataStoreConnection::import_data_from_file`] does, but adding or
+    /// deleting the data depending on the given update type, so
+    /// `UpdateType::Deletion` removes the triples in the file from the
+    /// graph.
+    pub fn import_data_from_file_with_update_type<P>(
+        &self,
+        file: P,
+        graph: &Graph,
+        update_type: UpdateType,
+    ) -> Result<(), ekg_error::Error>
+        where P: AsRef<Path> {
+        let format = RdfFormat::from_path_or_turtle(file.as_ref());
+        self.import_file(file.as_ref(), graph, format, update_type)
+    }
+
+    fn import_file(
+        &self,
+        file: &Path,
+        graph: &Graph,
+        format: RdfFormat,
+        update_type: UpdateType,
+    ) -> Result<(), ekg_error::Error> {
         assert!(
             !self.inner.is_null(),
             "invalid datastore connection"
         );
+        self.check_not_stopped("importing data from a file")?;
+        if RdfFormat::is_gzipped(file) {
+            return self.import_data_from_gz_file(file, graph, format, update_type);
+        }
 
-        let rdf_file = file.as_ref().as_os_str().as_bytes();
         tracing::trace!(
             target: LOG_TARGET_DATABASE,
             conn = self.number,
-            "Importing file {} into {:} of {:}",
-            file.as_ref().display(),
+            "Importing file {} ({format}, {update_type:?}) into {:} of {:}",
+            file.display(),
             graph,
             self
         );
 
         let c_graph_name = graph.as_c_string()?;
-        let file_name = CString::new(rdf_file).unwrap();
-        let format_name = CString::new(TEXT_TURTLE.as_ref()).unwrap();
+        let file_name = NonUtf8Path::c_string(file)?;
+        let format_name = CString::new(format.as_str()).unwrap();
 
-        database_call!(
+        let started = Instant::now();
+        let result = self.stats.record(database_call!(
             format!("Importing data from {file_name:?} (format={format_name:?})").as_str(),
             CDataStoreConnection_importDataFromFile(
                 self.inner,
                 c_graph_name.as_ptr() as *const std::os::raw::c_char,
-                CUpdateType::UPDATE_TYPE_ADDITION,
+                update_type.as_c_update_type(),
                 file_name.as_ptr() as *const std::os::raw::c_char,
                 format_name.as_ptr() as *const std::os::raw::c_char,
             )
-        )?;
+        ));
+        self.instrumentation().on_import(
+            &ImportEvent {
+                connection: self.number,
+                graph:      graph.to_string(),
+                file:       Some(file.to_path_buf()),
+                bytes:      0,
+            },
+            &Outcome::new(started, &result, None),
+        );
+        result?;
+        self.stats.imported(0);
         tracing::debug!(
             target: LOG_TARGET_DATABASE,
             conn = self.number,
             "Imported file {} into {:}",
-            file.as_ref().display(),
+            file.display(),
+            graph
+        );
+        Ok(())
+    }
+
+    /// Import the Datalog rules (and facts) in the given file, in the
+    /// format of RDFox (`application/x.datalog`), like
+    /// `[?x, a, :Parent] :- [?x, :hasChild, ?y] .`
+    ///
+    /// RDFox derives the new facts when the transaction is committed (or
+    /// [`Transaction::flush_writes`] is called), see also
+    /// [`DataStoreConnection::recompute_materialization`].
+    pub fn import_rules_from_file<P>(&self, file: P) -> Result<(), ekg_error::Error>
+        where P: AsRef<Path> {
+        self.import_file(
+            file.as_ref(),
+            DEFAULT_GRAPH_RDFOX.deref(),
+            RdfFormat::Datalog,
+            UpdateType::Addition,
+        )
+    }
+
+    /// Import the Datalog rules (and facts) in the given buffer, see
+    /// [`DataStoreConnection::import_rules_from_file`].
+    pub fn import_rules_from_buffer(&self, rules: &[u8]) -> Result<(), ekg_error::Error> {
+        self.import_rules(rules, UpdateType::Addition)
+    }
+
+    /// Import the Datalog rules (and facts) in the given string, see
+    /// [`DataStoreConnection::import_rules_from_file`].
+    pub fn import_rules_from_str(&self, rules: &str) -> Result<(), ekg_error::Error> {
+        self.import_rules(rules.as_bytes(), UpdateType::Addition)
+    }
+
+    /// Delete the given Datalog rules (and facts) again, together with
+    /// everything that was derived from them (alone).
+    pub fn delete_rules_from_str(&self, rules: &str) -> Result<(), ekg_error::Error> {
+        self.import_rules(rules.as_bytes(), UpdateType::Deletion)
+    }
+
+    fn import_rules(&self, rules: &[u8], update_type: UpdateType) -> Result<(), ekg_error::Error> {
+        let format = RdfFormat::Datalog.as_str().parse::<Mime>().map_err(|err| {
+            ekg_error::Error::Exception {
+                action:  "importing Datalog rules".to_string(),
+                message: err.to_string(),
+            }
+        })?;
+        self.import_data_from_buffer_with_update_type(
+            rules,
+            &format,
+            DEFAULT_GRAPH_RDFOX.deref(),
+            update_type,
+        )
+    }
+
+    /// Derive all facts from scratch, from all rules and explicitly
+    /// asserted facts, rather than incrementally like RDFox does at the end
+    /// of every transaction. Useful after changing the axioms or when in
+    /// doubt about the state of the materialisation.
+    pub fn recompute_materialization(&self) -> Result<(), ekg_error::Error> {
+        assert!(
+            !self.inner.is_null(),
+            "invalid datastore connection"
+        );
+        self.check_not_stopped("recomputing the materialisation")?;
+        let started_at = Instant::now();
+        self.stats.record(database_call!(
+            "recomputing the materialisation",
+            CDataStoreConnection_recomputeMaterialization(self.inner)
+        ))?;
+        tracing::info!(
+            target: LOG_TARGET_DATABASE,
+            conn = self.number,
+            duration = ?started_at.elapsed(),
+            "Recomputed the materialisation of {}",
+            self.data_store
+        );
+        Ok(())
+    }
+
+    /// The persistence of the data store: its own `persistence` parameter
+    /// or, when it doesn't declare one, the default of the server.
+    pub fn persistence(&self) -> PersistenceMode {
+        self.data_store
+            .persistence()
+            .unwrap_or_else(|| self.server_connection.server().persistence())
+    }
+
+    /// Compact a persisted data store: rewrite its files without the deleted
+    /// facts and reclaim the memory they took. RDFox writes every committed
+    /// transaction to those files, so there is no separate checkpoint or
+    /// flush.
+    ///
+    /// Works the same in all supported RDFox versions, for data stores whose
+    /// [`persistence`](Self::persistence) is `file` or `file-sequence`. An
+    /// in-memory data store, whether it declared
+    /// [`PersistenceMode::Off`](crate::PersistenceMode::Off) or inherited it
+    /// from the server, fails with
+    /// [`PersistenceRequired`](crate::PersistenceRequired).
+    pub fn compact(&self) -> Result<(), ekg_error::Error> {
+        let action = "compacting the data store";
+        if self.persistence() == PersistenceMode::Off {
+            return Err(PersistenceRequired {
+                data_store: self.data_store.name.clone(),
+                action:     action.to_string(),
+            }
+            .into());
+        }
+        self.check_not_stopped(action)?;
+        let started_at = Instant::now();
+        self.stats.record(database_call!(action, CDataStoreConnection_compact(self.inner)))?;
+        tracing::info!(
+            target: LOG_TARGET_DATABASE,
+            conn = self.number,
+            duration = ?started_at.elapsed(),
+            "Compacted {}",
+            self.data_store
+        );
+        Ok(())
+    }
+
+    /// Import RDF data in the given format (for instance `text/turtle`) from
+    /// the given buffer into the given graph.
+    pub fn import_data_from_buffer(
+        &self,
+        content: &[u8],
+        format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        self.import_data_from_buffer_with_update_type(content, format, graph, UpdateType::Addition)
+    }
+
+    /// Import RDF data in the given format from the given string into the
+    /// given graph.
+    pub fn import_data_from_str(
+        &self,
+        content: &str,
+        format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        self.import_data_from_buffer(content.as_bytes(), format, graph)
+    }
+
+    /// Import RDF data in the given format from the given buffer into the
+    /// given graph, adding or deleting the data depending on the given
+    /// update type.
+    pub fn import_data_from_buffer_with_update_type(
+        &self,
+        content: &[u8],
+        format: &Mime,
+        graph: &Graph,
+        update_type: UpdateType,
+    ) -> Result<(), ekg_error::Error> {
+        assert!(
+            !self.inner.is_null(),
+            "invalid datastore connection"
+        );
+        self.check_not_stopped("importing data from a buffer")?;
+        tracing::trace!(
+            target: LOG_TARGET_DATABASE,
+            conn = self.number,
+            "Importing {} bytes ({format}, {update_type:?}) into {:} of {:}",
+            content.len(),
+            graph,
+            self
+        );
+
+        let c_graph_name = graph.as_c_string()?;
+        let format_name = CString::new(format.as_ref())?;
+
+        let started = Instant::now();
+        let result = self.stats.record(database_call!(
+            format!("Importing data from a buffer (format={format})").as_str(),
+            CDataStoreConnection_importDataFromBuffer(
+                self.inner,
+                c_graph_name.as_ptr() as *const std::os::raw::c_char,
+                update_type.as_c_update_type(),
+                content.as_ptr() as *const std::os::raw::c_char,
+                content.len(),
+                format_name.as_ptr() as *const std::os::raw::c_char,
+            )
+        ));
+        self.instrumentation().on_import(
+            &ImportEvent {
+                connection: self.number,
+                graph:      graph.to_string(),
+                file:       None,
+                bytes:      content.len(),
+            },
+            &Outcome::new(started, &result, None),
+        );
+        result?;
+        self.stats.imported(content.len());
+        tracing::debug!(
+            target: LOG_TARGET_DATABASE,
+            conn = self.number,
+            "Imported {} bytes into {:}",
+            content.len(),
             graph
         );
         Ok(())
     }
 
+    /// Decompress the given file into a temporary file, import that and
+    /// remove it again. All gzip members are read, as in files made by
+    /// concatenating gzipped files or by parallel compressors like `pigz`.
+    fn import_data_from_gz_file(
+        &self,
+        file: &Path,
+        graph: &Graph,
+        format: RdfFormat,
+        update_type: UpdateType,
+    ) -> Result<(), ekg_error::Error> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(1);
+        let io_error = |err: std::io::Error| {
+            ekg_error::Error::Exception {
+                action:  format!("decompressing {}", file.display()),
+                message: err.to_string(),
+            }
+        };
+        let temp_file = std::env::temp_dir().join(format!(
+            "rdfox-rs-{}-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            file.file_stem().unwrap_or_default().to_string_lossy()
+        ));
+        tracing::trace!(
+            target: LOG_TARGET_FILES,
+            "Decompressing {} into {}",
+            file.display(),
+            temp_file.display()
+        );
+        let result = std::fs::File::open(file)
+            .and_then(|compressed| {
+                let mut decoder = MultiGzDecoder::new(BufReader::new(compressed));
+                let mut decompressed = std::fs::File::create(&temp_file)?;
+                std::io::copy(&mut decoder, &mut decompressed)
+            })
+            .map_err(io_error)
+            .and_then(|_| self.import_file(&temp_file, graph, format, update_type));
+        let _ = std::fs::remove_file(&temp_file);
+        result
+    }
+
     pub fn import_axioms_from_triples(
         &self,
         source_graph: &Graph,
         target_graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        self.import_axioms_from_triples_with_update_type(
+            source_graph,
+            target_graph,
+            UpdateType::Addition,
+        )
+    }
+
+    /// Add (or delete, depending on the given update type) the OWL axioms
+    /// that are in the triples of the source graph to (or from) the target
+    /// graph.
+    pub fn import_axioms_from_triples_with_update_type(
+        &self,
+        source_graph: &Graph,
+        target_graph: &Graph,
+        update_type: UpdateType,
     ) -> Result<(), ekg_error::Error> {
         assert!(
             !self.inner.is_null(),
             "invalid datastore connection"
         );
+        self.check_not_stopped("importing axioms")?;
 
         let c_source_graph_name = source_graph.as_c_string()?;
         let c_target_graph_name = target_graph.as_c_string()?;
 
-        database_call!(
+        self.stats.record(database_call!(
             "importing axioms",
             CDataStoreConnection_importAxiomsFromTriples(
                 self.inner,
                 c_source_graph_name.as_ptr() as *const std::os::raw::c_char,
                 false,
                 c_target_graph_name.as_ptr() as *const std::os::raw::c_char,
-                CUpdateType::UPDATE_TYPE_ADDITION,
+                update_type.as_c_update_type(),
             )
-        )?;
+        ))?;
+        self.stats.imported(0);
         tracing::debug!(
             target: LOG_TARGET_DATABASE,
             conn = self.number,
@@ -230,92 +753,171 @@ impl DataStoreConnection {
         Ok(())
     }
 
-    /// Read all RDF files (currently it supports .ttl and .nt files) from
-    /// the given directory, applying ignore files like `.gitignore`.
-    ///
-    /// Returns the number of loaded files.
-    ///
-    /// TODO: Support all the types that RDFox supports (and more)
-    /// TODO: Support '*.gz' files
-    /// TODO: Parallelize appropriately in sync with number of threads that
-    /// RDFox uses
-    pub fn import_rdf_from_directory(
+    /// Register an external data source (such as a CSV file, see
+    /// [`DataSourceParameters`](crate::DataSourceParameters)) under the given
+    /// name, so that tuple tables can be created for it with
+    /// [`DataStoreConnection::create_tuple_table`].
+    pub fn register_data_source(
         &self,
-        root: &Path,
-        graph: &Graph,
-    ) -> Result<u16, ekg_error::Error> {
-        let mut count = 0u16;
-        let regex = Regex::new(r"^.*.ttl$").unwrap();
-
+        name: &str,
+        parameters: &Parameters,
+    ) -> Result<(), ekg_error::Error> {
+        self.check_not_stopped("registering a data source")?;
+        let c_name = CString::new(name)?;
+        database_call!(
+            format!("registering data source {name} in {}", self.data_store).as_str(),
+            CDataStoreConnection_registerDataSource(
+                self.inner,
+                c_name.as_ptr(),
+                parameters.inner.cast_const(),
+            )
+        )?;
         tracing::debug!(
-            target: LOG_TARGET_FILES,
-            "Read all RDF files from directory {}",
-            format!("{:?}", &root).green()
+            target: LOG_TARGET_DATABASE,
+            conn = self.number,
+            "Registered data source {name} with {parameters}"
         );
+        Ok(())
+    }
+
+    /// Deregister the data source with the given name. RDFox refuses while
+    /// tuple tables still mount it, delete those first with
+    /// [`DataStoreConnection::delete_tuple_table`].
+    pub fn deregister_data_source(&self, name: &str) -> Result<(), ekg_error::Error> {
+        self.check_not_stopped("deregistering a data source")?;
+        let c_name = CString::new(name)?;
+        database_call!(
+            format!("deregistering data source {name} in {}", self.data_store).as_str(),
+            CDataStoreConnection_deregisterDataSource(self.inner, c_name.as_ptr())
+        )
+    }
+
+    /// Create a tuple table with the given name (an IRI), for instance one
+    /// with parameter `dataSourceName` that mounts a registered data source
+    /// (see [`DataStoreConnection::register_data_source`]). Rules and
+    /// queries read it with the `TT <name> { ?x ?y }` syntax.
+    pub fn create_tuple_table(
+        &self,
+        name: &str,
+        parameters: &Parameters,
+    ) -> Result<(), ekg_error::Error> {
+        self.check_not_stopped("creating a tuple table")?;
+        let c_name = CString::new(name)?;
+        database_call!(
+            format!("creating tuple table {name} in {}", self.data_store).as_str(),
+            CDataStoreConnection_createTupleTable(
+                self.inner,
+                c_name.as_ptr(),
+                parameters.inner.cast_const(),
+            )
+        )?;
         tracing::debug!(
-            target: LOG_TARGET_FILES,
-            "WalkBuilder::new({:?}), searching for {:?}",
-            root,
-            regex
+            target: LOG_TARGET_DATABASE,
+            conn = self.number,
+            "Created tuple table {name} with {parameters}"
         );
+        Ok(())
+    }
 
-        let mut builder = TypesBuilder::new();
-        builder.add("rdf", "*.nt").unwrap();
-        builder.add("rdf", "*.ttl").unwrap();
-        let file_types = builder.select("rdf").build().unwrap();
-
-        let iter = WalkBuilder::new(root)
-            .standard_filters(true)
-            .ignore(false)
-            .git_global(true)
-            .git_ignore(true)
-            .git_exclude(true)
-            .follow_links(false)
-            .parents(false)
-            .threads(6)
-            .types(file_types)
-            .build();
-
-        for rdf_file in iter {
-            match rdf_file {
-                Ok(dir_entry) => {
-                    let file_type = dir_entry.file_type().unwrap();
-                    if file_type.is_dir() {
-                        continue;
-                    }
-                    let rdf_file = dir_entry.path();
-                    // tracing::debug!("entry {:?}", dir_entry);
-                    self.import_data_from_file(rdf_file, graph)?;
-                    count += 1;
-                }
-                Err(error) => {
-                    tracing::error!(target: LOG_TARGET_FILES, "error {:?}", error);
-                    return Err(ekg_error::Error::WalkError(error));
-                }
+    /// Delete the tuple table with the given name, as created with
+    /// [`DataStoreConnection::create_tuple_table`]. The data source it
+    /// mounted stays registered.
+    pub fn delete_tuple_table(&self, name: &str) -> Result<(), ekg_error::Error> {
+        self.check_not_stopped("deleting a tuple table")?;
+        let c_name = CString::new(name)?;
+        database_call!(
+            format!("deleting tuple table {name} in {}", self.data_store).as_str(),
+            CDataStoreConnection_deleteTupleTable(self.inner, c_name.as_ptr())
+        )
+    }
+
+    /// Read all RDF files (in any of the formats of [`RdfFormat`], possibly
+    /// gzip-compressed) from the given directory, applying ignore files like
+    /// `.gitignore`, one at a time and stopping at the first one that fails.
+    pub fn import_rdf_from_directory(
+        &self,
+        root: &Path,
+        graph: &Graph,
+    ) -> Result<ImportSummary, ekg_error::Error> {
+        self.import_rdf_from_directory_with_options(root, graph, &ImportOptions::default())
+    }
+
+    /// Read all RDF files from the given directory like
+    /// [`DataStoreConnection::import_rdf_from_directory`] does, with the
+    /// given parallelism, progress callback and error handling.
+    pub fn import_rdf_from_directory_with_options(
+        &self,
+        root: &Path,
+        graph: &Graph,
+        options: &ImportOptions,
+    ) -> Result<ImportSummary, ekg_error::Error> {
+        let files = directory_import::rdf_files(root)?;
+        let pool = match options.parallelism > 1 && files.len() > 1 {
+            true => {
+                Some(
+                    self.server_connection
+                        .shared_pool_for(self.data_store.name.as_str(), &PoolOptions::default())?,
+                )
             }
-        }
-        Ok(count)
+            false => None,
+        };
+        // The first worker imports with this connection, the others with a
+        // pooled connection if there's one free
+        let connect = |worker: usize| {
+            Ok(match worker {
+                0 => Some(None),
+                _ => pool.as_ref().and_then(|pool| pool.try_get()).map(Some),
+            })
+        };
+        let summary = directory_import::import_files(
+            files.as_slice(),
+            options,
+            connect,
+            |pooled, file| {
+                match pooled {
+                    Some(pooled) => pooled.import_data_from_file(file, graph),
+                    None => self.import_data_from_file(file, graph),
+                }
+            },
+        )?;
+        tracing::info!(
+            target: LOG_TARGET_FILES,
+            conn = self.number,
+            files = summary.files,
+            failures = summary.failures.len(),
+            bytes = summary.bytes,
+            elapsed = ?summary.elapsed,
+            "Imported directory {} into graph {graph}",
+            root.display()
+        );
+        Ok(summary)
     }
 
+    /// Evaluate the given update statement and return how many facts it
+    /// changed.
     // noinspection DuplicatedCode
     pub fn evaluate_update(
         &self,
         statement: &Statement,
         parameters: &Parameters,
-    ) -> Result<CStatementResult, ekg_error::Error> {
+    ) -> Result<UpdateResult, ekg_error::Error> {
         assert!(
             !self.inner.is_null(),
             "invalid datastore connection"
         );
+        self.check_not_stopped("evaluating an update statement")?;
         // let c_base_iri = if let Some(base_iri) = base_iri {
         //     CString::new(base_iri.as_str()).unwrap()
         // } else {
         //     CString::new(DEFAULT_BASE_IRI).unwrap()
         // };
+        let _operation = self.operations.register(OperationKind::Update, statement);
         let statement_text = statement.as_c_string()?;
-        let statement_text_len = statement_text.as_bytes().len();
+        let statement_text_len = statement_text.to_bytes().len();
         let mut statement_result = MaybeUninit::uninit();
-        database_call!(
+        let event = self.statement_started(OperationKind::Update, statement);
+        let started = Instant::now();
+        let result = self.stats.record(database_call!(
             "evaluating an update statement",
             CDataStoreConnection_evaluateUpdate(
                 self.inner,
@@ -324,26 +926,39 @@ impl DataStoreConnection {
                 parameters.inner.as_ref().cast_const(),
                 statement_result.as_mut_ptr(),
             )
-        )?;
-        let statement_result = unsafe { statement_result.assume_init() };
-        tracing::trace!("Evaluated update statement: {statement_result:?}",);
-        Ok(statement_result)
+        ));
+        self.instrumentation()
+            .on_statement_finish(&event, &Outcome::new(started, &result, None));
+        result?;
+        self.stats.updated();
+        let update_result = UpdateResult::new(unsafe { &statement_result.assume_init() });
+        tracing::trace!("Evaluated update statement: {update_result}");
+        Ok(update_result)
     }
 
+    /// Evaluate the given statement and stream its results in the given
+    /// format to the given writer.
+    ///
+    /// Without `parameters` all facts (i.e. fact domain `ALL`) are used,
+    /// pass for instance `Parameters::empty()?.fact_domain(FactDomain::ASSERTED)?`
+    /// to only use the explicitly asserted ones.
     pub fn evaluate_to_stream<'a, W>(
         self: &Arc<Self>,
         writer: W,
         statement: &'a Statement,
+        parameters: Option<&'a Parameters>,
         mime_type: &'static Mime,
         base_iri: Option<&Iri>,
     ) -> Result<Streamer<'a, W>, ekg_error::Error>
         where
             W: 'a + Write,
     {
+        self.check_not_stopped("streaming the results of a statement")?;
         Streamer::run(
             self,
             writer,
             statement,
+            parameters,
             mime_type,
             Namespace::declare_from_str(
                 "base",
@@ -355,60 +970,207 @@ impl DataStoreConnection {
         )
     }
 
-    pub fn get_triples_count(
+    /// Evaluate the given statement in the given (still open) transaction of
+    /// this connection and stream its results in the given format to the
+    /// given writer, see [`DataStoreConnection::evaluate_to_stream`].
+    ///
+    /// RDFox evaluates every statement of a connection in the transaction
+    /// that is open on it, so the results reflect the snapshot of that
+    /// transaction including its own uncommitted changes, consistent with
+    /// any cursor read in it. Fails with
+    /// [`TransactionAlreadyFinished`](crate::TransactionAlreadyFinished) if
+    /// the transaction has ended.
+    pub fn evaluate_to_stream_in_transaction<'a, W>(
         self: &Arc<Self>,
         tx: &Arc<Transaction>,
-        fact_domain: FactDomain,
+        writer: W,
+        statement: &'a Statement,
+        parameters: Option<&'a Parameters>,
+        mime_type: &'static Mime,
+        base_iri: Option<&Iri>,
+    ) -> Result<Streamer<'a, W>, ekg_error::Error>
+        where
+            W: 'a + Write,
+    {
+        assert_eq!(
+            tx.connection.number, self.number,
+            "transaction is for another connection"
+        );
+        tx.check_active("streaming the results of a statement")?;
+        self.evaluate_to_stream(writer, statement, parameters, mime_type, base_iri)
+    }
+
+    /// Evaluate the given statement and return its complete results in the
+    /// given format (like `application/n-quads`) as bytes, see
+    /// [`DataStoreConnection::evaluate_to_string`] for text formats.
+    pub fn evaluate_to_vec(
+        self: &Arc<Self>,
+        statement: &Statement,
+        parameters: Option<&Parameters>,
+        mime_type: &'static Mime,
+        base_iri: Option<&Iri>,
+    ) -> Result<StreamedResult<Vec<u8>>, ekg_error::Error> {
+        let mut streamer =
+            self.evaluate_to_stream(Vec::new(), statement, parameters, mime_type, base_iri)?;
+        Ok(StreamedResult {
+            body:                std::mem::take(&mut streamer.writer),
+            number_of_solutions: streamer.number_of_solutions(),
+        })
+    }
+
+    /// Evaluate the given statement and return its complete results in the
+    /// given (text) format as a string, without having to set up a writer
+    /// and a [`Streamer`] like [`DataStoreConnection::evaluate_to_stream`].
+    ///
+    /// Fails if the results are not valid UTF-8.
+    pub fn evaluate_to_string(
+        self: &Arc<Self>,
+        statement: &Statement,
+        parameters: Option<&Parameters>,
+        mime_type: &'static Mime,
+        base_iri: Option<&Iri>,
+    ) -> Result<StreamedResult<String>, ekg_error::Error> {
+        let result = self.evaluate_to_vec(statement, parameters, mime_type, base_iri)?;
+        let body = String::from_utf8(result.body).map_err(|err| {
+            ekg_error::Error::Exception {
+                action:  format!("reading the results of a statement as {mime_type}"),
+                message: err.to_string(),
+            }
+        })?;
+        Ok(StreamedResult { body, number_of_solutions: result.number_of_solutions })
+    }
+
+    /// Export the explicitly asserted facts in the given graphs to the given
+    /// file in the given format (like `application/n-quads`), gzipped if its
+    /// name ends with `.gz`, and return the number of exported facts.
+    pub fn export_data_to_file<P>(
+        self: &Arc<Self>,
+        file: P,
+        format: &'static Mime,
+        scope: ExportScope,
+    ) -> Result<usize, ekg_error::Error>
+        where P: AsRef<Path> {
+        let file = file.as_ref();
+        let io_error = |err: std::io::Error| {
+            ekg_error::Error::Exception {
+                action:  format!("exporting data to {}", file.display()),
+                message: err.to_string(),
+            }
+        };
+        let statement = scope.statement(ExportScope::is_quad_format(format))?;
+        let parameters = Parameters::empty()?.fact_domain(FactDomain::ASSERTED)?;
+        let mut writer = BufWriter::new(File::create(file).map_err(io_error)?);
+        let count = if RdfFormat::is_gzipped(file) {
+            let mut encoder = GzEncoder::new(&mut writer, Compression::default());
+            let count = self.export_to(&mut encoder, &statement, &parameters, format)?;
+            encoder.finish().map_err(io_error)?;
+            count
+        } else {
+            self.export_to(&mut writer, &statement, &parameters, format)?
+        };
+        writer.flush().map_err(io_error)?;
+        tracing::info!(
+            target: LOG_TARGET_DATABASE,
+            conn = self.number,
+            "Exported {count} facts to {}",
+            file.display()
+        );
+        Ok(count)
+    }
+
+    fn export_to<W: Write>(
+        self: &Arc<Self>,
+        writer: W,
+        statement: &Statement,
+        parameters: &Parameters,
+        format: &'static Mime,
     ) -> Result<usize, ekg_error::Error> {
-        let default_graph = DEFAULT_GRAPH_RDFOX.deref().as_display_iri();
-        Statement::new(
-            &Namespaces::empty()?,
-            formatdoc!(
-                r##"
-                SELECT ?graph ?s ?p ?o
-                WHERE {{
-                    {{
-                        GRAPH ?graph {{ ?s ?p ?o }}
-                    }} UNION {{
-                        ?s ?p ?o .
-                        BIND({default_graph} AS ?graph)
-                    }}
-                }}
-            "##
+        let streamer = self.evaluate_to_stream(writer, statement, Some(parameters), format, None)?;
+        Ok(streamer.number_of_solutions())
+    }
+
+    /// Delete all (explicitly asserted) triples in the given graph, in a
+    /// read/write transaction of its own, and return how many there were.
+    ///
+    /// Use [`DataStoreConnection::clear_graph_in_transaction`] in an
+    /// existing transaction.
+    pub fn clear_graph(self: &Arc<Self>, graph: &Graph) -> Result<usize, ekg_error::Error> {
+        Transaction::begin_read_write(self)?
+            .update_and_commit(|ref tx| self.clear_graph_in_transaction(tx, graph))
+    }
+
+    /// Delete all (explicitly asserted) triples in the given graph using the
+    /// given read/write transaction and return how many there were.
+    ///
+    /// RDFox's default graph ([`DEFAULT_GRAPH_RDFOX`]) is cleared with
+    /// `CLEAR DEFAULT`, any other graph with `CLEAR GRAPH <iri>`.
+    pub fn clear_graph_in_transaction(
+        self: &Arc<Self>,
+        tx: &Arc<Transaction>,
+        graph: &Graph,
+    ) -> Result<usize, ekg_error::Error> {
+        let graph_iri = graph.as_display_iri().to_string();
+        let is_default_graph = graph_iri == DEFAULT_GRAPH_RDFOX.deref().as_display_iri().to_string();
+        let (pattern, target) = if is_default_graph {
+            ("?s ?p ?o".to_string(), "DEFAULT".to_string())
+        } else {
+            (
+                format!("GRAPH {graph_iri} {{ ?s ?p ?o }}"),
+                format!("GRAPH {graph_iri}"),
             )
-                .into(),
+        };
+        let prefixes = Namespaces::empty()?;
+        let count = Statement::new(
+            &prefixes,
+            format!("SELECT ?s ?p ?o WHERE {{ {pattern} }}").into(),
         )?
             .cursor(
                 self,
-                &Parameters::empty()?.fact_domain(fact_domain)?,
+                &Parameters::empty()?.fact_domain(FactDomain::ASSERTED)?,
             )?
-            .count(tx)
+            .count(tx)?;
+        tx.evaluate_update(
+            &Statement::new(&prefixes, format!("CLEAR {target}").into())?,
+            &Parameters::empty()?,
+            true,
+        )?;
+        tracing::debug!(
+            target: LOG_TARGET_DATABASE,
+            conn = self.number,
+            "Cleared {count} triples from {graph}"
+        );
+        Ok(count)
     }
 
-    pub fn get_subjects_count(
+    /// The number of triples in all graphs, counted by RDFox with SPARQL
+    /// `COUNT` so that only the count crosses the FFI.
+    pub fn get_triples_count(
+        self: &Arc<Self>,
+        tx: &Arc<Transaction>,
+        fact_domain: FactDomain,
+    ) -> Result<usize, ekg_error::Error> {
+        self.count_matches(
+            tx,
+            fact_domain,
+            "*",
+            Statement::all_graphs_pattern("?s", "?p", "?o", "?graph").as_str(),
+        )
+    }
+
+    /// Like [`DataStoreConnection::get_triples_count`] but reading every
+    /// triple through a cursor, which is far slower on large stores.
+    pub fn get_triples_count_with_cursor(
         self: &Arc<Self>,
         tx: &Arc<Transaction>,
         fact_domain: FactDomain,
     ) -> Result<usize, ekg_error::Error> {
-        let default_graph = DEFAULT_GRAPH_RDFOX.deref().as_display_iri();
         Statement::new(
             &Namespaces::empty()?,
-            formatdoc!(
-                r##"
-                SELECT DISTINCT ?subject
-                WHERE {{
-                    {{
-                        GRAPH ?graph {{
-                            ?subject ?p ?o
-                        }}
-                    }} UNION {{
-                        ?subject ?p ?o .
-                        BIND({default_graph} AS ?graph)
-                    }}
-                }}
-            "##
+            format!(
+                "SELECT ?graph ?s ?p ?o\nWHERE {{\n{}\n}}",
+                Statement::all_graphs_pattern("?s", "?p", "?o", "?graph")
             )
-                .into(),
+            .into(),
         )?
             .cursor(
                 self,
@@ -417,36 +1179,50 @@ impl DataStoreConnection {
             .count(tx)
     }
 
+    /// The number of distinct subjects in all graphs
+    pub fn get_subjects_count(
+        self: &Arc<Self>,
+        tx: &Arc<Transaction>,
+        fact_domain: FactDomain,
+    ) -> Result<usize, ekg_error::Error> {
+        self.count_matches(
+            tx,
+            fact_domain,
+            "DISTINCT ?subject",
+            Statement::all_graphs_pattern("?subject", "?p", "?o", "?graph").as_str(),
+        )
+    }
+
+    /// The number of distinct predicates in all graphs
     pub fn get_predicates_count(
         self: &Arc<Self>,
         tx: &Arc<Transaction>,
         fact_domain: FactDomain,
     ) -> Result<usize, ekg_error::Error> {
-        let default_graph = DEFAULT_GRAPH_RDFOX.deref().as_display_iri();
-        Statement::new(
+        self.count_matches(
+            tx,
+            fact_domain,
+            "DISTINCT ?predicate",
+            Statement::all_graphs_pattern("?s", "?predicate", "?o", "?graph").as_str(),
+        )
+    }
+
+    /// Evaluate `SELECT (COUNT(<what>) AS ?count) WHERE { <pattern> }` and
+    /// return the count, where `what` is `*` or `DISTINCT ?variable`.
+    pub(crate) fn count_matches(
+        self: &Arc<Self>,
+        tx: &Arc<Transaction>,
+        fact_domain: FactDomain,
+        what: &str,
+        pattern: &str,
+    ) -> Result<usize, ekg_error::Error> {
+        let counts = Statement::new(
             &Namespaces::empty()?,
-            formatdoc!(
-                r##"
-                SELECT DISTINCT ?predicate
-                WHERE {{
-                    {{
-                        GRAPH ?graph {{
-                            ?s ?predicate ?o
-                        }}
-                    }} UNION {{
-                        ?s ?predicate ?o .
-                        BIND({default_graph} AS ?graph)
-                    }}
-                }}
-            "##
-            )
-                .into(),
+            format!("SELECT (COUNT({what}) AS ?count)\nWHERE {{\n{pattern}\n}}").into(),
         )?
-            .cursor(
-                self,
-                &Parameters::empty()?.fact_domain(fact_domain)?,
-            )?
-            .count(tx)
+        .cursor(self, &Parameters::empty()?.fact_domain(fact_domain)?)?
+        .collect_into::<(usize,)>(tx, 1)?;
+        Ok(counts.first().map_or(0, |(count,)| *count))
     }
 
     pub fn get_ontologies_count(
@@ -480,4 +1256,87 @@ impl DataStoreConnection {
             )?
             .count(tx)
     }
+
+    /// Check whether the given graph pattern has at least one match, with the
+    /// given variables (names with or without the `?`) bound to the given
+    /// terms, which are escaped like [`Statement::new_with_bindings`] does.
+    ///
+    /// The C API of RDFox can't bind values into a cursor that it has
+    /// already created, so the terms take the place of their variables in
+    /// the text of the `ASK` statement, where RDFox can look them up in its
+    /// indexes. That statement is prepared only once per connection (see
+    /// [`DataStoreConnection::prepared_statement_count`]) for as long as the
+    /// pattern and the terms stay the same, so checking the same thing
+    /// repeatedly is cheap.
+    pub fn exists(
+        &self,
+        tx: &Arc<Transaction>,
+        pattern: &str,
+        bindings: &[(&str, &Term)],
+    ) -> Result<bool, ekg_error::Error> {
+        assert!(
+            !self.inner.is_null(),
+            "invalid datastore connection"
+        );
+        self.check_not_stopped("checking whether a graph pattern has a match")?;
+        assert_eq!(
+            tx.connection.number, self.number,
+            "transaction is for another connection"
+        );
+        let text =
+            bind_variables(format!("ASK {{ {pattern} }}").as_str(), bindings).map_err(|message| {
+                ekg_error::Error::Exception {
+                    action: "binding the variables of a graph pattern".to_string(),
+                    message,
+                }
+            })?;
+        let parameters = Parameters::empty()?;
+        let multiplicity = self.prepared_cursors.lock().unwrap().open(
+            self.inner,
+            parameters.inner.as_ref().cast_const(),
+            text.as_str(),
+        )?;
+        Ok(multiplicity > 0)
+    }
+
+    /// Explain why the given fact holds, as a tree of the rules and premise
+    /// facts that derived it.
+    ///
+    /// None of the supported versions of RDFox expose their explanation
+    /// facility (the `explain` shell command and the `/explanation` REST
+    /// endpoint) in the C API, so for now this always fails with
+    /// [`UnsupportedOnThisRDFoxVersion`].
+    pub fn explain_fact(
+        &self,
+        tx: &Arc<Transaction>,
+        subject: &Term,
+        predicate: &Term,
+        object: &Term,
+        graph: Option<&Graph>,
+    ) -> Result<ProofTree, ekg_error::Error> {
+        assert_eq!(
+            tx.connection.number, self.number,
+            "transaction is for another connection"
+        );
+        let fact = match graph {
+            Some(graph) => format!("{subject} {predicate} {object} {}", graph.as_display_iri()),
+            None => format!("{subject} {predicate} {object}"),
+        };
+        tracing::warn!(
+            target: LOG_TARGET_DATABASE,
+            conn = self.number,
+            "Cannot explain {fact}: not supported by the C API of RDFox"
+        );
+        Err(UnsupportedOnThisRDFoxVersion {
+            action:  format!("explaining {fact}"),
+            missing: "explanation facility".to_string(),
+        }
+        .into())
+    }
+
+    /// The number of statements that have been prepared by
+    /// [`DataStoreConnection::exists`] on this connection.
+    pub fn prepared_statement_count(&self) -> usize {
+        self.prepared_cursors.lock().unwrap().preparations()
+    }
 }
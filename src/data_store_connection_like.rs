@@ -0,0 +1,463 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{
+        DataStoreConnection,
+        GraphConnection,
+        Parameters,
+        RdfFormat,
+        SelectResult,
+        Statement,
+        Term,
+        Transaction,
+    },
+    ekg_namespace::Graph,
+    flate2::read::MultiGzDecoder,
+    mime::Mime,
+    std::{
+        io::{Read, Write},
+        path::Path,
+        sync::Arc,
+    },
+};
+
+/// What a connection to a data store can do, whether it is an embedded
+/// [`DataStoreConnection`] or (with feature `remote`) a connection to an
+/// RDFox endpoint over its REST API, so code can be written for both.
+///
+/// Only [`DataStoreConnectionLike::data_store_name`],
+/// [`DataStoreConnectionLike::select`], [`DataStoreConnectionLike::update`],
+/// [`DataStoreConnectionLike::import_data_from_str`] and
+/// [`DataStoreConnectionLike::evaluate_to_string`] have to be implemented,
+/// the other methods are built on top of those unless a connection can do
+/// better.
+pub trait DataStoreConnectionLike {
+    /// The name of the data store
+    fn data_store_name(&self) -> &str;
+
+    /// The embedded connection behind this one, if any, which is what
+    /// [`Statement::cursor`] needs
+    fn embedded_connection(&self) -> Option<&Arc<DataStoreConnection>> { None }
+
+    /// Evaluate the given `SELECT` query and return all its solutions
+    fn select(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<SelectResult, ekg_error::Error>;
+
+    /// Evaluate the given `SELECT` query and pass (at most `max_row`) rows to
+    /// the given function, each one with the values of the answer variables
+    /// in order (`None` when unbound) and its multiplicity. Returns the
+    /// number of rows.
+    fn consume(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+        max_row: usize,
+        f: &mut dyn FnMut(&[Option<Term>], usize) -> Result<(), ekg_error::Error>,
+    ) -> Result<usize, ekg_error::Error> {
+        let result = self.select(statement, parameters)?;
+        let mut rows = 0_usize;
+        for solution in result.solutions.iter().take(max_row) {
+            let row = result
+                .variables
+                .iter()
+                .map(|variable| solution.get(variable).cloned())
+                .collect::<Vec<_>>();
+            f(row.as_slice(), 1)?;
+            rows += 1;
+        }
+        Ok(rows)
+    }
+
+    /// The number of solutions of the given `SELECT` query, counting each
+    /// row as many times as its multiplicity
+    fn count(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<usize, ekg_error::Error> {
+        let mut count = 0_usize;
+        let mut add = |_row: &[Option<Term>], multiplicity: usize| {
+            count += multiplicity;
+            Ok::<(), ekg_error::Error>(())
+        };
+        self.consume(statement, parameters, usize::MAX, &mut add)?;
+        Ok(count)
+    }
+
+    /// Evaluate the given update statement
+    fn update(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<(), ekg_error::Error>;
+
+    /// Import RDF data in the given format from the given string into the
+    /// given graph
+    fn import_data_from_str(
+        &self,
+        content: &str,
+        format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error>;
+
+    /// Import RDF data in the given format from the given buffer into the
+    /// given graph
+    fn import_data_from_buffer(
+        &self,
+        content: &[u8],
+        format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        let content = std::str::from_utf8(content).map_err(|err| {
+            ekg_error::Error::Exception {
+                action:  format!("Importing {format} into {}", self.data_store_name()),
+                message: err.to_string(),
+            }
+        })?;
+        self.import_data_from_str(content, format, graph)
+    }
+
+    /// Import RDF data from the given file into the given graph, in the
+    /// format that goes with its extension (see [`RdfFormat::from_path`],
+    /// Turtle if not known), decompressing it first if it's a `*.gz` file
+    fn import_data_from_file(&self, file: &Path, graph: &Graph) -> Result<(), ekg_error::Error> {
+        let error = |message: String| {
+            ekg_error::Error::Exception {
+                action: format!("Importing {} into {}", file.display(), self.data_store_name()),
+                message,
+            }
+        };
+        let mut content = Vec::new();
+        let file_content = std::fs::read(file).map_err(|err| error(err.to_string()))?;
+        if RdfFormat::is_gzipped(file) {
+            MultiGzDecoder::new(file_content.as_slice())
+                .read_to_end(&mut content)
+                .map_err(|err| error(err.to_string()))?;
+        } else {
+            content = file_content;
+        }
+        let format = RdfFormat::from_path_or_turtle(file)
+            .as_str()
+            .parse::<Mime>()
+            .map_err(|err| error(err.to_string()))?;
+        self.import_data_from_buffer(content.as_slice(), &format, graph)
+    }
+
+    /// Evaluate the given statement and return its results in the given
+    /// (text) format
+    fn evaluate_to_string(
+        &self,
+        statement: &Statement,
+        mime_type: &'static Mime,
+    ) -> Result<String, ekg_error::Error>;
+
+    /// Evaluate the given statement and write its results in the given
+    /// format to the given writer
+    fn evaluate_to_stream(
+        &self,
+        writer: &mut dyn Write,
+        statement: &Statement,
+        mime_type: &'static Mime,
+    ) -> Result<(), ekg_error::Error> {
+        let body = self.evaluate_to_string(statement, mime_type)?;
+        writer.write_all(body.as_bytes()).map_err(|err| {
+            ekg_error::Error::Exception {
+                action:  format!(
+                    "Writing the results of a statement in {}",
+                    self.data_store_name()
+                ),
+                message: err.to_string(),
+            }
+        })
+    }
+}
+
+impl<T: DataStoreConnectionLike + ?Sized> DataStoreConnectionLike for &T {
+    fn data_store_name(&self) -> &str { (**self).data_store_name() }
+
+    fn embedded_connection(&self) -> Option<&Arc<DataStoreConnection>> {
+        (**self).embedded_connection()
+    }
+
+    fn select(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<SelectResult, ekg_error::Error> {
+        (**self).select(statement, parameters)
+    }
+
+    fn consume(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+        max_row: usize,
+        f: &mut dyn FnMut(&[Option<Term>], usize) -> Result<(), ekg_error::Error>,
+    ) -> Result<usize, ekg_error::Error> {
+        (**self).consume(statement, parameters, max_row, f)
+    }
+
+    fn count(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<usize, ekg_error::Error> {
+        (**self).count(statement, parameters)
+    }
+
+    fn update(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<(), ekg_error::Error> {
+        (**self).update(statement, parameters)
+    }
+
+    fn import_data_from_str(
+        &self,
+        content: &str,
+        format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        (**self).import_data_from_str(content, format, graph)
+    }
+
+    fn import_data_from_buffer(
+        &self,
+        content: &[u8],
+        format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        (**self).import_data_from_buffer(content, format, graph)
+    }
+
+    fn import_data_from_file(&self, file: &Path, graph: &Graph) -> Result<(), ekg_error::Error> {
+        (**self).import_data_from_file(file, graph)
+    }
+
+    fn evaluate_to_string(
+        &self,
+        statement: &Statement,
+        mime_type: &'static Mime,
+    ) -> Result<String, ekg_error::Error> {
+        (**self).evaluate_to_string(statement, mime_type)
+    }
+
+    fn evaluate_to_stream(
+        &self,
+        writer: &mut dyn Write,
+        statement: &Statement,
+        mime_type: &'static Mime,
+    ) -> Result<(), ekg_error::Error> {
+        (**self).evaluate_to_stream(writer, statement, mime_type)
+    }
+}
+
+impl DataStoreConnectionLike for Arc<DataStoreConnection> {
+    fn data_store_name(&self) -> &str { self.data_store.name.as_str() }
+
+    fn embedded_connection(&self) -> Option<&Arc<DataStoreConnection>> { Some(self) }
+
+    fn select(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<SelectResult, ekg_error::Error> {
+        Transaction::begin_read_only(self)?.execute_and_rollback(|tx| {
+            statement.cursor(self, parameters)?.select_result(&tx)
+        })
+    }
+
+    /// Reads the cursor in a read-only transaction of its own
+    fn consume(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+        max_row: usize,
+        f: &mut dyn FnMut(&[Option<Term>], usize) -> Result<(), ekg_error::Error>,
+    ) -> Result<usize, ekg_error::Error> {
+        Transaction::begin_read_only(self)?.execute_and_rollback(|tx| {
+            let mut row_values = Vec::new();
+            statement.cursor(self, parameters)?.consume(&tx, max_row, |row| {
+                row_values.clear();
+                for index in 0..row.opened.arity {
+                    row_values.push(row.term(index)?);
+                }
+                f(row_values.as_slice(), *row.multiplicity)
+            })
+        })
+    }
+
+    fn count(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<usize, ekg_error::Error> {
+        Transaction::begin_read_only(self)?
+            .execute_and_rollback(|tx| statement.cursor(self, parameters)?.count(&tx))
+    }
+
+    fn update(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<(), ekg_error::Error> {
+        self.evaluate_update(statement, parameters).map(|_| ())
+    }
+
+    fn import_data_from_str(
+        &self,
+        content: &str,
+        format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        DataStoreConnection::import_data_from_str(self, content, format, graph)
+    }
+
+    fn import_data_from_buffer(
+        &self,
+        content: &[u8],
+        format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        DataStoreConnection::import_data_from_buffer(self, content, format, graph)
+    }
+
+    fn import_data_from_file(&self, file: &Path, graph: &Graph) -> Result<(), ekg_error::Error> {
+        DataStoreConnection::import_data_from_file(self, file, graph)
+    }
+
+    fn evaluate_to_string(
+        &self,
+        statement: &Statement,
+        mime_type: &'static Mime,
+    ) -> Result<String, ekg_error::Error> {
+        DataStoreConnection::evaluate_to_string(self, statement, None, mime_type, None)
+            .map(|result| result.body)
+    }
+
+    fn evaluate_to_stream(
+        &self,
+        writer: &mut dyn Write,
+        statement: &Statement,
+        mime_type: &'static Mime,
+    ) -> Result<(), ekg_error::Error> {
+        DataStoreConnection::evaluate_to_stream(self, writer, statement, None, mime_type, None)
+            .map(|_streamer| ())
+    }
+}
+
+/// Delegates to the [`DataStoreConnection`] of the graph connection, so the
+/// import methods import into the graph they're given rather than into the
+/// graph of the graph connection.
+impl DataStoreConnectionLike for GraphConnection {
+    fn data_store_name(&self) -> &str {
+        DataStoreConnectionLike::data_store_name(&self.data_store_connection)
+    }
+
+    fn embedded_connection(&self) -> Option<&Arc<DataStoreConnection>> {
+        Some(&self.data_store_connection)
+    }
+
+    fn select(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<SelectResult, ekg_error::Error> {
+        DataStoreConnectionLike::select(&self.data_store_connection, statement, parameters)
+    }
+
+    fn consume(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+        max_row: usize,
+        f: &mut dyn FnMut(&[Option<Term>], usize) -> Result<(), ekg_error::Error>,
+    ) -> Result<usize, ekg_error::Error> {
+        DataStoreConnectionLike::consume(
+            &self.data_store_connection,
+            statement,
+            parameters,
+            max_row,
+            f,
+        )
+    }
+
+    fn count(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<usize, ekg_error::Error> {
+        DataStoreConnectionLike::count(&self.data_store_connection, statement, parameters)
+    }
+
+    fn update(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<(), ekg_error::Error> {
+        DataStoreConnectionLike::update(&self.data_store_connection, statement, parameters)
+    }
+
+    fn import_data_from_str(
+        &self,
+        content: &str,
+        format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        DataStoreConnectionLike::import_data_from_str(
+            &self.data_store_connection,
+            content,
+            format,
+            graph,
+        )
+    }
+
+    fn import_data_from_buffer(
+        &self,
+        content: &[u8],
+        format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        DataStoreConnectionLike::import_data_from_buffer(
+            &self.data_store_connection,
+            content,
+            format,
+            graph,
+        )
+    }
+
+    fn import_data_from_file(&self, file: &Path, graph: &Graph) -> Result<(), ekg_error::Error> {
+        DataStoreConnectionLike::import_data_from_file(&self.data_store_connection, file, graph)
+    }
+
+    fn evaluate_to_string(
+        &self,
+        statement: &Statement,
+        mime_type: &'static Mime,
+    ) -> Result<String, ekg_error::Error> {
+        DataStoreConnectionLike::evaluate_to_string(
+            &self.data_store_connection,
+            statement,
+            mime_type,
+        )
+    }
+
+    fn evaluate_to_stream(
+        &self,
+        writer: &mut dyn Write,
+        statement: &Statement,
+        mime_type: &'static Mime,
+    ) -> Result<(), ekg_error::Error> {
+        DataStoreConnectionLike::evaluate_to_stream(
+            &self.data_store_connection,
+            writer,
+            statement,
+            mime_type,
+        )
+    }
+}
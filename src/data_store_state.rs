@@ -0,0 +1,233 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{
+        typed_error::TypedError,
+        DataStoreConnection,
+        Namespaces,
+        Parameters,
+        Statement,
+        Term,
+        Transaction,
+    },
+    ekg_namespace::consts::LOG_TARGET_DATABASE,
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::{Display, Formatter},
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc,
+            Mutex,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+/// The reserved graph in which the offline/online state of a data store is
+/// recorded, see
+/// [`ServerConnection::set_data_store_offline`](crate::ServerConnection::set_data_store_offline).
+///
+/// It is left out of everything that reads all graphs, like the counts of
+/// [`DataStoreConnection`] and exports with
+/// [`ExportScope::AllGraphs`](crate::ExportScope::AllGraphs), so the state
+/// never shows up as data. Graph connections (and so
+/// [`GraphConnection::export_triples`](crate::GraphConnection::export_triples)
+/// and [`GraphConnection::diff`](crate::GraphConnection::diff)) only read
+/// their own graph.
+pub const DATA_STORE_STATE_GRAPH: &str = "https://ekgf.org/rdfox-rs/graph/data-store-state";
+
+const DATA_STORE_STATE_SUBJECT: &str = "https://ekgf.org/rdfox-rs/data-store";
+const OFFLINE_REASON: &str = "https://ekgf.org/rdfox-rs/offlineReason";
+
+/// How long a connection trusts the offline/online state it last read, by
+/// default
+pub const DEFAULT_ONLINE_CHECK_TTL: Duration = Duration::from_secs(1);
+
+static ONLINE_CHECK_TTL_MILLIS: AtomicU64 =
+    AtomicU64::new(DEFAULT_ONLINE_CHECK_TTL.as_millis() as u64);
+
+/// The data store has been taken offline (for maintenance), see
+/// [`ServerConnection::set_data_store_offline`](crate::ServerConnection::set_data_store_offline).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataStoreOffline {
+    pub data_store: String,
+    /// The reason given when the data store was taken offline
+    pub reason:     String,
+}
+
+impl Display for DataStoreOffline {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "DataStoreOffline: data store {} is offline: {}",
+            self.data_store, self.reason
+        )
+    }
+}
+
+impl std::error::Error for DataStoreOffline {}
+
+impl TypedError for DataStoreOffline {
+    const NAME: &'static str = "DataStoreOffline";
+
+    fn action(&self) -> String { format!("using data store {}", self.data_store) }
+}
+
+impl From<DataStoreOffline> for ekg_error::Error {
+    fn from(value: DataStoreOffline) -> Self { value.to_error() }
+}
+
+impl DataStoreOffline {
+    /// The [`DataStoreOffline`] error that the given error was made from, if
+    /// any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
+
+/// How long a connection trusts the offline/online state it last read,
+/// defaults to [`DEFAULT_ONLINE_CHECK_TTL`]
+pub(crate) fn online_check_ttl() -> Duration {
+    Duration::from_millis(ONLINE_CHECK_TTL_MILLIS.load(Ordering::Relaxed))
+}
+
+pub(crate) fn set_online_check_ttl(ttl: Duration) {
+    ONLINE_CHECK_TTL_MILLIS.store(ttl.as_millis() as u64, Ordering::Relaxed)
+}
+
+/// The updates that record the given state, no reason meaning online
+pub(crate) fn state_updates(reason: Option<&str>) -> Result<Vec<Statement>, ekg_error::Error> {
+    let mut updates = vec![Statement::new(
+        &Namespaces::empty()?,
+        format!(
+            "DELETE WHERE {{ GRAPH <{DATA_STORE_STATE_GRAPH}> {{ \
+             <{DATA_STORE_STATE_SUBJECT}> <{OFFLINE_REASON}> ?reason }} }}"
+        )
+        .into(),
+    )?];
+    if let Some(reason) = reason {
+        updates.push(Statement::new(
+            &Namespaces::empty()?,
+            format!(
+                "INSERT DATA {{ GRAPH <{DATA_STORE_STATE_GRAPH}> {{ \
+                 <{DATA_STORE_STATE_SUBJECT}> <{OFFLINE_REASON}> {} }} }}",
+                Term::string(reason)
+            )
+            .into(),
+        )?);
+    }
+    Ok(updates)
+}
+
+/// The reason why the data store is offline, if it is
+fn offline_reason(
+    connection: &Arc<DataStoreConnection>,
+    tx: &Arc<Transaction>,
+) -> Result<Option<String>, ekg_error::Error> {
+    let reasons = Statement::new(
+        &Namespaces::empty()?,
+        format!(
+            "SELECT ?reason WHERE {{ GRAPH <{DATA_STORE_STATE_GRAPH}> {{ \
+             <{DATA_STORE_STATE_SUBJECT}> <{OFFLINE_REASON}> ?reason }} }}"
+        )
+        .into(),
+    )?
+    .cursor(connection, &Parameters::empty()?)?
+    .collect_into::<(String,)>(tx, 1)?;
+    Ok(reasons.into_iter().next().map(|(reason,)| reason))
+}
+
+/// Whether (and when last) a connection checked that its data store is
+/// online. Maintenance connections never check.
+#[derive(Debug, Default)]
+pub(crate) struct OnlineCheck {
+    maintenance: bool,
+    /// Check at the beginning of every transaction
+    enabled:     AtomicBool,
+    /// When the state was last read and the offline reason at that time
+    last_read:   Mutex<Option<(Instant, Option<String>)>>,
+}
+
+impl OnlineCheck {
+    pub(crate) fn maintenance() -> Self { Self { maintenance: true, ..Default::default() } }
+
+    pub(crate) fn is_maintenance(&self) -> bool { self.maintenance }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        !self.maintenance && self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_enabled(&self, enabled: bool) { self.enabled.store(enabled, Ordering::Relaxed) }
+
+    /// Fail with [`DataStoreOffline`] if the data store of the given
+    /// connection is offline, reading the state in the given transaction
+    /// (or in a read-only transaction of its own) unless the state that was
+    /// read last is recent enough.
+    pub(crate) fn check(
+        &self,
+        connection: &Arc<DataStoreConnection>,
+        tx: Option<&Arc<Transaction>>,
+    ) -> Result<(), ekg_error::Error> {
+        if self.maintenance {
+            return Ok(());
+        }
+        let cached = self
+            .last_read
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+            .filter(|(read_at, _)| read_at.elapsed() < online_check_ttl())
+            .map(|(_, reason)| reason.clone());
+        let reason = match cached {
+            Some(reason) => reason,
+            None => {
+                let reason = match tx {
+                    Some(tx) => offline_reason(connection, tx)?,
+                    None => offline_reason(connection, &Transaction::begin_read_only(connection)?)?,
+                };
+                *self.last_read.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) =
+                    Some((Instant::now(), reason.clone()));
+                reason
+            },
+        };
+        match reason {
+            Some(reason) => {
+                tracing::warn!(
+                    target: LOG_TARGET_DATABASE,
+                    conn = connection.number,
+                    "Refusing to use offline {}: {reason}",
+                    connection.data_store
+                );
+                Err(DataStoreOffline { data_store: connection.data_store.name.clone(), reason }.into())
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{state_updates, DataStoreOffline};
+
+    #[test_log::test]
+    fn test_state_updates() {
+        let online = state_updates(None).unwrap();
+        assert_eq!(online.len(), 1);
+        assert!(online[0].as_str().contains("DELETE WHERE"));
+
+        let offline = state_updates(Some("bulk \"load\"")).unwrap();
+        assert_eq!(offline.len(), 2);
+        assert!(offline[1].as_str().contains(r#""bulk \"load\"""#), "{}", offline[1]);
+    }
+
+    #[test_log::test]
+    fn test_display() {
+        let offline = DataStoreOffline { data_store: "ekg".to_string(), reason: "bulk load".to_string() };
+        assert_eq!(
+            offline.to_string(),
+            "DataStoreOffline: data store ekg is offline: bulk load"
+        );
+        assert_eq!(DataStoreOffline::from_error(&offline.clone().into()), Some(offline));
+    }
+}
@@ -0,0 +1,13 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+//! Deterministic test data for benchmarks and load tests (feature
+//! `test-util`), see [`TripleGenerator`].
+
+pub use {
+    triple_generator::{Triple, TripleGenerator, Triples, DATAGEN_NAMESPACE},
+    writers::{import_triples, write_ntriples, write_ntriples_file},
+};
+
+mod split_mix;
+mod triple_generator;
+mod writers;
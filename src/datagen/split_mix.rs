@@ -0,0 +1,45 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+/// The SplitMix64 pseudo-random number generator: tiny, fast and, since
+/// it only uses wrapping 64-bit integer arithmetic, giving the same
+/// sequence for the same seed on every platform.
+#[derive(Debug, Clone)]
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self { Self { state: seed } }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound` (`bound` must not be zero)
+    pub(crate) fn below(&mut self, bound: usize) -> usize { (self.next_u64() % bound as u64) as usize }
+
+    /// A number in `[0, 1)` made of the 53 high bits of the next number
+    pub(crate) fn next_f64(&mut self) -> f64 { (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SplitMix64;
+
+    #[test_log::test]
+    fn test_split_mix() {
+        // The reference output of SplitMix64 for seed 1234567
+        let mut rng = SplitMix64::new(1234567);
+        assert_eq!(rng.next_u64(), 6457827717110365317);
+        assert_eq!(rng.next_u64(), 3203168211198807973);
+        assert_eq!(rng.next_u64(), 9817491932198370423);
+        let mut rng = SplitMix64::new(42);
+        assert!((0..1000).map(|_| rng.next_f64()).all(|x| (0.0..1.0).contains(&x)));
+        assert!((0..1000).map(|_| rng.below(7)).all(|x| x < 7));
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    super::split_mix::SplitMix64,
+    crate::{select_result::XSD_INTEGER, Term},
+    iref::{Iri, IriBuf},
+};
+
+/// The namespace of all generated IRIs
+pub const DATAGEN_NAMESPACE: &str = "https://ekgf.org/datagen/";
+
+/// A triple as generated by a [`TripleGenerator`]
+pub type Triple = (Term, Term, Term);
+
+/// Generates the same stream of random triples for the same seed and
+/// settings, on any platform, for benchmarks and load tests:
+///
+/// ```no_run
+/// use rdfox_rs::datagen::{write_ntriples, TripleGenerator};
+///
+/// let generator = TripleGenerator::new(42).entities(1000).predicates(10).literal_ratio(0.3);
+/// let mut buffer = Vec::new();
+/// write_ntriples(generator.triples(10_000), &mut buffer).unwrap();
+/// ```
+///
+/// Subjects are entities like `<https://ekgf.org/datagen/entity/17>`,
+/// predicates are like `<https://ekgf.org/datagen/predicate/3>` and objects
+/// are either entities or (with probability `literal_ratio`) `xsd:integer`
+/// or `xsd:string` literals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TripleGenerator {
+    seed:          u64,
+    entities:      usize,
+    predicates:    usize,
+    literal_ratio: f64,
+}
+
+impl TripleGenerator {
+    /// A generator with 1000 entities, 10 predicates and a literal ratio of
+    /// 0.25
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            entities: 1000,
+            predicates: 10,
+            literal_ratio: 0.25,
+        }
+    }
+
+    /// The number of distinct entities (at least one)
+    pub fn entities(mut self, entities: usize) -> Self {
+        self.entities = entities.max(1);
+        self
+    }
+
+    /// The number of distinct predicates (at least one)
+    pub fn predicates(mut self, predicates: usize) -> Self {
+        self.predicates = predicates.max(1);
+        self
+    }
+
+    /// The probability (between 0 and 1) that the object of a triple is a
+    /// literal rather than an entity
+    pub fn literal_ratio(mut self, literal_ratio: f64) -> Self {
+        self.literal_ratio = literal_ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The entity with the given number
+    pub fn entity(&self, number: usize) -> Term { named_term("entity", number) }
+
+    /// The predicate with the given number
+    pub fn predicate(&self, number: usize) -> Term { named_term("predicate", number) }
+
+    /// The given number of random triples (some of which may be duplicates)
+    pub fn triples(&self, count: usize) -> Triples {
+        Triples {
+            generator: self.clone(),
+            rng:       SplitMix64::new(self.seed),
+            remaining: count,
+        }
+    }
+
+    /// A star-shaped graph for reasoning benchmarks: each of the given
+    /// number of hubs points to its own `rays` entities with predicate 0.
+    /// Hubs are entities `0..hubs`, their rays are numbered after them.
+    pub fn star(&self, hubs: usize, rays: usize) -> impl Iterator<Item = Triple> + '_ {
+        (0..hubs).flat_map(move |hub| {
+            (0..rays).map(move |ray| {
+                (
+                    self.entity(hub),
+                    self.predicate(0),
+                    self.entity(hubs + hub * rays + ray),
+                )
+            })
+        })
+    }
+
+    /// A chain-shaped graph for reasoning benchmarks (like a transitive
+    /// closure): entity `n` points to entity `n + 1` with predicate 0, for
+    /// `n` in `0..length`.
+    pub fn chain(&self, length: usize) -> impl Iterator<Item = Triple> + '_ {
+        (0..length).map(move |n| (self.entity(n), self.predicate(0), self.entity(n + 1)))
+    }
+
+    fn object(&self, rng: &mut SplitMix64) -> Term {
+        if rng.next_f64() >= self.literal_ratio {
+            return self.entity(rng.below(self.entities));
+        }
+        let value = rng.next_u64() % 1_000_000;
+        if value % 2 == 0 {
+            Term::typed_literal(value.to_string().as_str(), Iri::new(XSD_INTEGER).unwrap())
+        } else {
+            Term::string(format!("literal {value}").as_str())
+        }
+    }
+}
+
+fn named_term(kind: &str, number: usize) -> Term {
+    Term::Iri(IriBuf::new(format!("{DATAGEN_NAMESPACE}{kind}/{number}")).unwrap())
+}
+
+/// The iterator returned by [`TripleGenerator::triples`]
+#[derive(Debug, Clone)]
+pub struct Triples {
+    generator: TripleGenerator,
+    rng:       SplitMix64,
+    remaining: usize,
+}
+
+impl Iterator for Triples {
+    type Item = Triple;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let subject = self.generator.entity(self.rng.below(self.generator.entities));
+        let predicate = self.generator.predicate(self.rng.below(self.generator.predicates));
+        let object = self.generator.object(&mut self.rng);
+        Some((subject, predicate, object))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) { (self.remaining, Some(self.remaining)) }
+}
+
+impl ExactSizeIterator for Triples {}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::TripleGenerator,
+        crate::{datagen::write_ntriples, Term},
+        std::collections::HashSet,
+    };
+
+    /// FNV-1a, so that the hash itself does not depend on the platform
+    fn hash(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+
+    fn generate(generator: &TripleGenerator, count: usize) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        assert_eq!(write_ntriples(generator.triples(count), &mut buffer).unwrap(), count);
+        buffer
+    }
+
+    #[test_log::test]
+    fn test_deterministic() {
+        let generator = TripleGenerator::new(42).entities(500).predicates(7).literal_ratio(0.3);
+        let first = generate(&generator, 10_000);
+        let second = generate(&generator.clone(), 10_000);
+        assert_eq!(hash(first.as_slice()), hash(second.as_slice()));
+        assert_eq!(first, second);
+        assert_eq!(first.iter().filter(|byte| **byte == b'\n').count(), 10_000);
+        // The same on every platform and with every Rust version, a change
+        // here breaks the repeatability of benchmarks against earlier runs
+        assert_eq!(hash(first.as_slice()), 0xc43c_a958_34ad_5c6e);
+
+        let other = generate(&TripleGenerator::new(43).entities(500).predicates(7), 10_000);
+        assert_ne!(hash(first.as_slice()), hash(other.as_slice()));
+    }
+
+    #[test_log::test]
+    fn test_settings() {
+        let generator = TripleGenerator::new(1).entities(20).predicates(3).literal_ratio(0.0);
+        let triples = generator.triples(1000).collect::<Vec<_>>();
+        assert_eq!(triples.len(), 1000);
+        assert!(triples.iter().all(|(_, _, o)| matches!(o, Term::Iri(_))));
+        let predicates = triples.iter().map(|(_, p, _)| p).collect::<HashSet<_>>();
+        assert_eq!(predicates.len(), 3);
+        let subjects = triples.iter().map(|(s, _, _)| s).collect::<HashSet<_>>();
+        assert!(subjects.len() <= 20);
+
+        let generator = generator.literal_ratio(1.0);
+        assert!(generator.triples(1000).all(|(_, _, o)| matches!(o, Term::Literal { .. })));
+    }
+
+    #[test_log::test]
+    fn test_shapes() {
+        let generator = TripleGenerator::new(0);
+        let star = generator.star(3, 4).collect::<Vec<_>>();
+        assert_eq!(star.len(), 12);
+        assert_eq!(star.iter().map(|(s, _, _)| s).collect::<HashSet<_>>().len(), 3);
+        assert_eq!(star.iter().map(|(_, _, o)| o).collect::<HashSet<_>>().len(), 12);
+
+        let chain = generator.chain(5).collect::<Vec<_>>();
+        assert_eq!(chain.len(), 5);
+        assert!(chain.windows(2).all(|pair| pair[0].2 == pair[1].0));
+        assert_eq!(
+            chain[0].0.to_string(),
+            "<https://ekgf.org/datagen/entity/0>"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    super::Triple,
+    crate::{DataStoreConnection, RdfFormat},
+    ekg_namespace::Graph,
+    mime::Mime,
+    std::{
+        fs::File,
+        io::{BufWriter, Write},
+        path::Path,
+    },
+};
+
+/// Write the given triples in N-Triples format, returns the number of
+/// triples written.
+pub fn write_ntriples<W: Write>(
+    triples: impl IntoIterator<Item = Triple>,
+    mut writer: W,
+) -> Result<usize, ekg_error::Error> {
+    let io_error = |err: std::io::Error| {
+        ekg_error::Error::Exception {
+            action:  "writing N-Triples".to_string(),
+            message: err.to_string(),
+        }
+    };
+    let mut count = 0_usize;
+    for (subject, predicate, object) in triples {
+        writeln!(writer, "{subject} {predicate} {object} .").map_err(io_error)?;
+        count += 1;
+    }
+    writer.flush().map_err(io_error)?;
+    Ok(count)
+}
+
+/// Write the given triples to the given (N-Triples) file, returns the
+/// number of triples written.
+pub fn write_ntriples_file<P: AsRef<Path>>(
+    triples: impl IntoIterator<Item = Triple>,
+    path: P,
+) -> Result<usize, ekg_error::Error> {
+    let file = File::create(path.as_ref()).map_err(|err| {
+        ekg_error::Error::Exception {
+            action:  format!("creating {}", path.as_ref().display()),
+            message: err.to_string(),
+        }
+    })?;
+    write_ntriples(triples, BufWriter::new(file))
+}
+
+/// Import the given triples into the given graph with
+/// [`DataStoreConnection::import_data_from_buffer`], in chunks of (at most)
+/// `chunk_size` triples, returns the number of triples imported.
+pub fn import_triples(
+    triples: impl IntoIterator<Item = Triple>,
+    chunk_size: usize,
+    connection: &DataStoreConnection,
+    graph: &Graph,
+) -> Result<usize, ekg_error::Error> {
+    let format: Mime = RdfFormat::NTriples.as_str().parse().unwrap();
+    let mut triples = triples.into_iter().peekable();
+    let mut buffer = Vec::new();
+    let mut count = 0_usize;
+    while triples.peek().is_some() {
+        buffer.clear();
+        count += write_ntriples(triples.by_ref().take(chunk_size.max(1)), &mut buffer)?;
+        connection.import_data_from_buffer(buffer.as_slice(), &format, graph)?;
+    }
+    Ok(count)
+}
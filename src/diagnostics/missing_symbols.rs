@@ -0,0 +1,130 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    ekg_namespace::consts::LOG_TARGET_DATABASE,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// The RDFox C functions that this crate uses but that could not be found
+/// in the loaded RDFox library, usually because its version is not the one
+/// this crate was built for.
+/// Get it back from an `ekg_error::Error` with [`MissingSymbols::from_error`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissingSymbols {
+    pub symbols: Vec<String>,
+}
+
+impl Display for MissingSymbols {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the RDFox library misses {} symbol(s) used by rdfox-rs: {}",
+            self.symbols.len(),
+            self.symbols.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for MissingSymbols {}
+
+impl TypedError for MissingSymbols {
+    const NAME: &'static str = "MissingSymbols";
+
+    fn action(&self) -> String { "verifying the RDFox library".to_string() }
+}
+
+impl From<MissingSymbols> for ekg_error::Error {
+    fn from(value: MissingSymbols) -> Self { value.to_error() }
+}
+
+impl MissingSymbols {
+    /// The [`MissingSymbols`] error that the given error was made from, if
+    /// any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+
+    /// Check each of the given symbols with `is_present`, fails with all the
+    /// symbols that are not.
+    pub(crate) fn check<'a>(
+        symbols: impl IntoIterator<Item = &'a str>,
+        is_present: impl Fn(&str) -> bool,
+    ) -> Result<(), MissingSymbols> {
+        let missing = symbols
+            .into_iter()
+            .filter(|symbol| !is_present(symbol))
+            .map(|symbol| symbol.to_owned())
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(())
+        }
+        let err = MissingSymbols { symbols: missing };
+        tracing::error!(target: LOG_TARGET_DATABASE, "{err}");
+        Err(err)
+    }
+}
+
+/// Check that the dynamically linked RDFox library has all the C functions
+/// (see [`RDFOX_SYMBOLS`](crate::diagnostics::RDFOX_SYMBOLS)) that this crate
+/// uses, so that a mismatched `libRDFox` is reported before the first call
+/// into it rather than crashing it.
+#[cfg(feature = "rdfox-dylib")]
+pub fn verify_symbols() -> Result<(), MissingSymbols> {
+    #[cfg(unix)]
+    let this: libloading::Library = libloading::os::unix::Library::this().into();
+    #[cfg(windows)]
+    let this: libloading::Library = libloading::os::windows::Library::this()
+        .map_err(|_| {
+            MissingSymbols {
+                symbols: super::RDFOX_SYMBOLS.iter().map(|symbol| symbol.to_string()).collect(),
+            }
+        })?
+        .into();
+    MissingSymbols::check(super::RDFOX_SYMBOLS.iter().copied(), |symbol| unsafe {
+        this.get::<*const ()>(symbol.as_bytes()).is_ok()
+    })
+}
+
+/// The static RDFox library is linked in completely so there is nothing to
+/// check.
+#[cfg(not(feature = "rdfox-dylib"))]
+pub fn verify_symbols() -> Result<(), MissingSymbols> { Ok(()) }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::MissingSymbols,
+        crate::diagnostics::{verify_symbols, RDFOX_SYMBOLS},
+    };
+
+    #[test_log::test]
+    fn test_generated_symbols() {
+        assert!(RDFOX_SYMBOLS.contains(&"CServer_startLocalServer"));
+        assert!(RDFOX_SYMBOLS.contains(&"CCursor_open"));
+        assert!(RDFOX_SYMBOLS.windows(2).all(|pair| pair[0] < pair[1]));
+        verify_symbols().unwrap();
+    }
+
+    #[test_log::test]
+    fn test_missing_symbols() {
+        let symbols = ["CCursor_open", "CCursor_fly", "CServer_dance"];
+        let err = MissingSymbols::check(symbols, |symbol| symbol == "CCursor_open").unwrap_err();
+        assert_eq!(err.symbols, vec!["CCursor_fly", "CServer_dance"]);
+        assert_eq!(
+            err.to_string(),
+            "the RDFox library misses 2 symbol(s) used by rdfox-rs: CCursor_fly, CServer_dance"
+        );
+        let error = ekg_error::Error::from(err.clone());
+        match &error {
+            ekg_error::Error::Exception { action, .. } => {
+                assert_eq!(action, "verifying the RDFox library")
+            },
+            err => panic!("unexpected error {err:?}"),
+        }
+        assert_eq!(MissingSymbols::from_error(&error), Some(err));
+        assert!(MissingSymbols::check(symbols, |_| true).is_ok());
+    }
+}
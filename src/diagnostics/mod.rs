@@ -0,0 +1,18 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+//! Checks on the RDFox library that we're linked to.
+
+pub use {
+    missing_symbols::{verify_symbols, MissingSymbols},
+    symbols::RDFOX_SYMBOLS,
+};
+
+mod missing_symbols;
+#[cfg(test)]
+mod symbol_names;
+
+/// The names of the RDFox C functions that this crate uses, generated by
+/// `build.rs`
+mod symbols {
+    include!(concat!(env!("OUT_DIR"), "/rdfox_symbols.rs"));
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+//! Finding the names of the RDFox C functions in Rust source text.
+//!
+//! This file is also included by `build.rs` (see `#[path]` over there) so
+//! it can only use `std`.
+
+use std::collections::BTreeSet;
+
+/// Whether the given identifier looks like the name of an RDFox C function,
+/// i.e. `C<Class>_<function>` like `CCursor_open`
+pub fn is_rdfox_function_name(identifier: &str) -> bool {
+    let Some((class, function)) = identifier.split_once('_') else {
+        return false;
+    };
+    let mut class_chars = class.chars();
+    class_chars.next() == Some('C') &&
+        class_chars.next().map_or(false, |c| c.is_ascii_uppercase()) &&
+        class_chars.all(|c| c.is_ascii_alphanumeric()) &&
+        function.chars().next().map_or(false, |c| c.is_ascii_lowercase()) &&
+        function.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// All identifiers in the given Rust source text that look like the name
+/// of an RDFox C function (see [`is_rdfox_function_name`])
+pub fn rdfox_function_names(source: &str) -> BTreeSet<String> {
+    source
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|identifier| is_rdfox_function_name(identifier))
+        .map(|identifier| identifier.to_owned())
+        .collect()
+}
+
+/// The names of all functions declared (as `pub fn <name>(`) in the given
+/// bindgen-generated source text
+pub fn declared_function_names(bindings: &str) -> BTreeSet<String> {
+    bindings
+        .split("pub fn ")
+        .skip(1)
+        .filter_map(|rest| rest.split_once('(').map(|(name, _)| name.trim()))
+        .filter(|name| !name.is_empty() && !name.contains(char::is_whitespace))
+        .map(|name| name.to_owned())
+        .collect()
+}
+
+/// The Rust source of the `RDFOX_SYMBOLS` constant for the given names
+pub fn symbols_source<'a>(names: impl IntoIterator<Item = &'a String>) -> String {
+    let mut source = String::from("pub const RDFOX_SYMBOLS: &[&str] = &[\n");
+    for name in names {
+        source.push_str(format!("    \"{name}\",\n").as_str());
+    }
+    source.push_str("];\n");
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{declared_function_names, is_rdfox_function_name, rdfox_function_names, symbols_source};
+
+    #[test_log::test]
+    fn test_rdfox_function_names() {
+        let source = r#"
+            use crate::rdfox_api::{CCursor_open, CCursor_advance, CPrefixes_DeclareResult};
+            database_call!("Opening a cursor", CCursor_open(self.inner, &mut multiplicity))?;
+            let x = ekg_error::Error::Exception; // CException_what
+            CServer_startLocalServer(params.inner.cast_const())
+        "#;
+        let names = rdfox_function_names(source);
+        assert_eq!(
+            names.into_iter().collect::<Vec<_>>(),
+            vec![
+                "CCursor_advance",
+                "CCursor_open",
+                "CException_what",
+                "CServer_startLocalServer"
+            ]
+        );
+        assert!(!is_rdfox_function_name("CCursor"));
+        assert!(!is_rdfox_function_name("Cursor_open"));
+        assert!(!is_rdfox_function_name("CPrefixes_DeclareResult"));
+        assert!(!is_rdfox_function_name("LOG_TARGET_DATABASE"));
+    }
+
+    #[test_log::test]
+    fn test_declared_function_names() {
+        let bindings = r#"
+            extern "C" {
+                pub fn CCursor_open(cursor: *mut CCursor, multiplicity: *mut usize) -> *const CException;
+                pub fn CCursor_advance(cursor: *mut CCursor, multiplicity: *mut usize) -> *const CException;
+            }
+            pub struct CCursor { pub _address: u8 }
+        "#;
+        let declared = declared_function_names(bindings);
+        assert_eq!(
+            declared.iter().collect::<Vec<_>>(),
+            vec!["CCursor_advance", "CCursor_open"]
+        );
+        assert_eq!(
+            symbols_source(&declared),
+            "pub const RDFOX_SYMBOLS: &[&str] = &[\n    \"CCursor_advance\",\n    \
+             \"CCursor_open\",\n];\n"
+        );
+    }
+}
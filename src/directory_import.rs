@@ -0,0 +1,272 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::RdfFormat,
+    colored::Colorize,
+    ekg_namespace::consts::LOG_TARGET_FILES,
+    ignore::{types::TypesBuilder, WalkBuilder},
+    std::{
+        fmt::{Debug, Formatter},
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Mutex,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+/// How
+/// [`DataStoreConnection::import_rdf_from_directory_with_options`](crate::DataStoreConnection::import_rdf_from_directory_with_options)
+/// imports the files of a directory
+pub struct ImportOptions {
+    /// The number of files imported at the same time, each by its own
+    /// thread. Apart from the calling thread they use connections of the
+    /// shared pool (see
+    /// [`ServerConnection::shared_pool_for`](crate::ServerConnection::shared_pool_for)),
+    /// so more threads than the pool has free connections don't help.
+    pub parallelism:       usize,
+    /// Called after each file (on the thread that imported it)
+    pub progress:          Option<Box<dyn Fn(ImportProgress) + Send + Sync>>,
+    /// Import the remaining files when one fails and return the failures in
+    /// [`ImportSummary::failures`], rather than stopping at the first one
+    pub continue_on_error: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self { Self { parallelism: 1, progress: None, continue_on_error: false } }
+}
+
+impl Debug for ImportOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImportOptions")
+            .field("parallelism", &self.parallelism)
+            .field("progress", &self.progress.is_some())
+            .field("continue_on_error", &self.continue_on_error)
+            .finish()
+    }
+}
+
+/// How far an import of a directory is, see [`ImportOptions::progress`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportProgress {
+    /// The number of files imported (or failed) so far
+    pub files_completed: usize,
+    /// The number of files found in the directory
+    pub files_total:     usize,
+    /// The size of the files imported so far
+    pub bytes:           u64,
+    pub elapsed:         Duration,
+}
+
+/// A file that could not be imported, see [`ImportOptions::continue_on_error`]
+#[derive(Debug)]
+pub struct ImportFailure {
+    pub file:  PathBuf,
+    pub error: ekg_error::Error,
+}
+
+/// The result of importing a directory
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    /// The number of files imported
+    pub files:    usize,
+    /// The size of the files imported (before decompression)
+    pub bytes:    u64,
+    pub elapsed:  Duration,
+    pub failures: Vec<ImportFailure>,
+}
+
+/// All RDF files (in any of the formats of [`RdfFormat`], possibly
+/// gzip-compressed) in the given directory, applying ignore files like
+/// `.gitignore`
+pub(crate) fn rdf_files(root: &Path) -> Result<Vec<PathBuf>, ekg_error::Error> {
+    tracing::debug!(
+        target: LOG_TARGET_FILES,
+        "Read all RDF files from directory {}",
+        format!("{:?}", &root).green()
+    );
+    tracing::debug!(
+        target: LOG_TARGET_FILES,
+        "WalkBuilder::new({:?}), searching for {:?}",
+        root,
+        RdfFormat::glob_patterns()
+    );
+
+    let mut builder = TypesBuilder::new();
+    for glob in RdfFormat::glob_patterns() {
+        builder.add("rdf", glob).unwrap();
+    }
+    let file_types = builder.select("rdf").build().unwrap();
+
+    let iter = WalkBuilder::new(root)
+        .standard_filters(true)
+        .ignore(false)
+        .git_global(true)
+        .git_ignore(true)
+        .git_exclude(true)
+        .follow_links(false)
+        .parents(false)
+        .types(file_types)
+        .build();
+
+    let mut files = Vec::new();
+    for rdf_file in iter {
+        match rdf_file {
+            Ok(dir_entry) => {
+                if dir_entry.file_type().is_some_and(|file_type| file_type.is_dir()) {
+                    continue;
+                }
+                files.push(dir_entry.into_path());
+            }
+            Err(error) => {
+                tracing::error!(target: LOG_TARGET_FILES, "error {:?}", error);
+                return Err(ekg_error::Error::WalkError(error));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Import the given files with `options.parallelism` threads. Each thread
+/// gets its worker number from 0 (the calling thread) and calls `connect`
+/// with it once; it then imports files with `import` until there are none
+/// left. A worker for which `connect` returns `None` does nothing.
+pub(crate) fn import_files<C, Connect, Import>(
+    files: &[PathBuf],
+    options: &ImportOptions,
+    connect: Connect,
+    import: Import,
+) -> Result<ImportSummary, ekg_error::Error>
+    where
+        Connect: Fn(usize) -> Result<Option<C>, ekg_error::Error> + Sync,
+        Import: Fn(&C, &Path) -> Result<(), ekg_error::Error> + Sync,
+{
+    let started = Instant::now();
+    let next = AtomicUsize::new(0);
+    let aborted = AtomicBool::new(false);
+    let summary = Mutex::new(ImportSummary::default());
+
+    let work = |worker: usize| -> Result<(), ekg_error::Error> {
+        let Some(connection) = connect(worker)? else {
+            tracing::debug!(target: LOG_TARGET_FILES, worker, "No connection for import worker");
+            return Ok(());
+        };
+        while !aborted.load(Ordering::Relaxed) {
+            let Some(file) = files.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                break;
+            };
+            let bytes = std::fs::metadata(file).map_or(0, |metadata| metadata.len());
+            let result = import(&connection, file);
+            let mut summary = summary.lock().unwrap();
+            match result {
+                Ok(()) => {
+                    summary.files += 1;
+                    summary.bytes += bytes;
+                }
+                Err(error) if options.continue_on_error => {
+                    tracing::warn!(
+                        target: LOG_TARGET_FILES,
+                        "Could not import {}: {error}",
+                        file.display()
+                    );
+                    summary.failures.push(ImportFailure { file: file.clone(), error });
+                }
+                Err(error) => {
+                    aborted.store(true, Ordering::Relaxed);
+                    return Err(error);
+                }
+            }
+            if let Some(progress) = options.progress.as_ref() {
+                progress(ImportProgress {
+                    files_completed: summary.files + summary.failures.len(),
+                    files_total:     files.len(),
+                    bytes:           summary.bytes,
+                    elapsed:         started.elapsed(),
+                });
+            }
+        }
+        Ok(())
+    };
+
+    let parallelism = options.parallelism.clamp(1, files.len().max(1));
+    let results = std::thread::scope(|scope| {
+        let work = &work;
+        let workers = (1..parallelism)
+            .map(|worker| scope.spawn(move || work(worker)))
+            .collect::<Vec<_>>();
+        let mut results = vec![work(0)];
+        for worker in workers {
+            results.push(worker.join().unwrap_or_else(|_| {
+                Err(ekg_error::Error::Exception {
+                    action:  "importing RDF files".to_string(),
+                    message: "an import thread panicked".to_string(),
+                })
+            }));
+        }
+        results
+    });
+    results.into_iter().collect::<Result<Vec<_>, _>>()?;
+
+    let mut summary = summary.into_inner().unwrap();
+    summary.elapsed = started.elapsed();
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{import_files, rdf_files, ImportOptions},
+        std::{
+            path::Path,
+            sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc,
+            },
+        },
+    };
+
+    #[test_log::test]
+    fn test_import_files() {
+        let dir = std::env::temp_dir().join(format!("rdfox-rs-import-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for number in 0..30 {
+            std::fs::write(dir.join(format!("{number}.ttl")), "<a> <b> <c> .").unwrap();
+        }
+        std::fs::write(dir.join("readme.txt"), "not RDF").unwrap();
+        let mut files = rdf_files(dir.as_path()).unwrap();
+        files.sort();
+        assert_eq!(files.len(), 30);
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let options = ImportOptions {
+            parallelism:       4,
+            progress:          Some(Box::new(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            })),
+            continue_on_error: true,
+        };
+        let import = |_: &(), file: &Path| {
+            if file.ends_with("7.ttl") || file.ends_with("17.ttl") {
+                return Err(ekg_error::Error::Exception {
+                    action:  "testing".to_string(),
+                    message: file.display().to_string(),
+                });
+            }
+            Ok(())
+        };
+        let summary = import_files(&files, &options, |_| Ok(Some(())), import).unwrap();
+        assert_eq!(summary.files, 28);
+        assert_eq!(summary.bytes, 28 * 13);
+        assert_eq!(summary.failures.len(), 2);
+        assert_eq!(calls.load(Ordering::Relaxed), 30);
+
+        // Stop at the first failure, with workers that get no connection
+        let options = ImportOptions { parallelism: 3, ..ImportOptions::default() };
+        let connect = |worker: usize| Ok((worker == 0).then_some(()));
+        assert!(import_files(&files, &options, connect, import).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
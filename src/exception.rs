@@ -4,8 +4,11 @@
 // extern crate libc;
 
 use {
-    crate::rdfox_api::{CException_getExceptionName, CException_what}
-    ,
+    crate::{
+        rdfox_api::{CException_getExceptionName, CException_what},
+        typed_error::TypedError,
+    },
+    serde::{Deserialize, Serialize},
     std::{
         ffi::CStr,
         fmt::{Display, Formatter},
@@ -25,10 +28,7 @@ impl CException {
                 if c_exception.is_null() {
                     Ok(())
                 } else {
-                    Err(ekg_error::Error::Exception {
-                        action: action.to_string(),
-                        message: format!("{:}", *c_exception).replace("RDFoxException: ", ""),
-                    })
+                    Err(RDFoxException::new(action, &*c_exception).into())
                 }
             });
             match result {
@@ -70,6 +70,134 @@ impl Display for CException {
     }
 }
 
+/// What kind of failure an [`RDFoxException`] is, as far as it's one that
+/// callers may want to deal with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExceptionKind {
+    DataStoreAlreadyExists,
+    DataStoreNotFound,
+    /// A role, tuple table, data source, graph, file or other resource (not
+    /// a data store) does not exist
+    UnknownResource,
+    /// The statement could not be parsed, at the given position if RDFox
+    /// mentions it
+    InvalidSparql {
+        line:   Option<usize>,
+        column: Option<usize>,
+    },
+    LicenseInvalid,
+    AccessDenied,
+    /// The transaction clashed with another one, retrying may help
+    TransactionConflict,
+    Other,
+}
+
+impl ExceptionKind {
+    /// Classify an exception by its exact name, the data store kinds by the
+    /// resource that the message is about
+    fn classify(name: &str, what: &str) -> Self {
+        let number_after = |word: &str| {
+            let lower = what.to_lowercase();
+            let (_, rest) = lower.split_once(word)?;
+            rest.trim_start()
+                .split(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse()
+                .ok()
+        };
+        let about_data_store = what.to_lowercase().contains("data store");
+        match name {
+            "DuplicateResourceException" if about_data_store => {
+                ExceptionKind::DataStoreAlreadyExists
+            },
+            "UnknownResourceException" if about_data_store => ExceptionKind::DataStoreNotFound,
+            "UnknownResourceException" => ExceptionKind::UnknownResource,
+            "ParsingException" => {
+                ExceptionKind::InvalidSparql {
+                    line:   number_after("line"),
+                    column: number_after("column"),
+                }
+            },
+            "LicenseException" => ExceptionKind::LicenseInvalid,
+            "AuthenticationException" |
+            "AuthorizationException" |
+            "AccessControlException" |
+            "PermissionDeniedException" => ExceptionKind::AccessDenied,
+            "TransactionConflictException" => ExceptionKind::TransactionConflict,
+            _ => ExceptionKind::Other,
+        }
+    }
+}
+
+/// An exception that RDFox threw, which is returned as an
+/// `ekg_error::Error::Exception`; get it back with
+/// [`RDFoxException::from_error`] to match on its [`ExceptionKind`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RDFoxException {
+    /// What we were doing at the time
+    pub action:  String,
+    /// The name of the exception, such as `ParsingException`
+    pub name:    String,
+    pub message: String,
+    pub kind:    ExceptionKind,
+}
+
+/// The name of RDFox's generic exception, which is left out of messages
+const GENERIC_EXCEPTION: &str = "RDFoxException";
+
+impl Display for RDFoxException {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.name == GENERIC_EXCEPTION {
+            writeln!(f, "{}", self.message)
+        } else {
+            writeln!(f, "{}: {}", self.name, self.message)
+        }
+    }
+}
+
+impl std::error::Error for RDFoxException {}
+
+impl TypedError for RDFoxException {
+    const NAME: &'static str = "RDFoxException";
+
+    fn action(&self) -> String { self.action.clone() }
+}
+
+impl From<RDFoxException> for ekg_error::Error {
+    fn from(value: RDFoxException) -> Self { value.to_error() }
+}
+
+impl RDFoxException {
+    fn new(action: &str, exception: &CException) -> Self {
+        let (name, message) = match (exception.name(), exception.what()) {
+            (Ok(name), Ok(what)) => (name.to_string(), what.to_string()),
+            _ => (GENERIC_EXCEPTION.to_string(), exception.to_string()),
+        };
+        Self::with_name(action, name, message)
+    }
+
+    fn with_name(action: &str, name: String, message: String) -> Self {
+        Self {
+            action: action.to_string(),
+            kind: ExceptionKind::classify(name.as_str(), message.as_str()),
+            name,
+            message,
+        }
+    }
+
+    /// The exception that the given error stems from, `None` if it's not
+    /// an exception that RDFox threw
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+
+    /// The [`ExceptionKind`] of the given error, `None` if it's not an
+    /// exception that RDFox threw
+    pub fn kind_of(error: &ekg_error::Error) -> Option<ExceptionKind> {
+        Self::from_error(error).map(|exception| exception.kind)
+    }
+}
+
 #[macro_export]
 macro_rules! database_call {
     ($function:expr) => {{
@@ -91,3 +219,59 @@ macro_rules! database_call {
         )
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ExceptionKind, RDFoxException};
+
+    fn exception(name: &str, message: &str) -> RDFoxException {
+        RDFoxException::with_name("testing", name.to_string(), message.to_string())
+    }
+
+    #[test_log::test]
+    fn test_classify() {
+        assert_eq!(
+            exception("ParsingException", "unexpected token at line 3, column 14").kind,
+            ExceptionKind::InvalidSparql { line: Some(3), column: Some(14) }
+        );
+        assert_eq!(
+            exception("DuplicateResourceException", "Data store 'ekg' already exists.").kind,
+            ExceptionKind::DataStoreAlreadyExists
+        );
+        assert_eq!(
+            exception("UnknownResourceException", "Data store 'ekg' does not exist.").kind,
+            ExceptionKind::DataStoreNotFound
+        );
+        assert_eq!(
+            exception("UnknownResourceException", "Role 'guest' does not exist.").kind,
+            ExceptionKind::UnknownResource
+        );
+        assert_eq!(
+            exception("AccessControlException", "The role 'reader' cannot write to 'ekg'.").kind,
+            ExceptionKind::AccessDenied
+        );
+        assert_eq!(exception("FileNotFoundException", "no such file").kind, ExceptionKind::Other);
+        assert_eq!(exception("RDFoxException", "the data store is busy").kind, ExceptionKind::Other);
+    }
+
+    #[test_log::test]
+    fn test_from_error() {
+        for (name, message) in [
+            ("AuthenticationException", "bad password"),
+            ("ParsingException", "unexpected token at line 3, column 14"),
+            ("RDFoxException", "oops"),
+        ] {
+            let exception = exception(name, message);
+            let back = RDFoxException::from_error(&exception.clone().into()).unwrap();
+            assert_eq!(back, exception);
+        }
+
+        // Errors that merely look like an exception of RDFox are not one
+        let lookalike = ekg_error::Error::Exception {
+            action:  "testing".to_string(),
+            message: "UnknownResourceException: data store ekg does not exist".to_string(),
+        };
+        assert_eq!(RDFoxException::kind_of(&lookalike), None);
+        assert_eq!(RDFoxException::kind_of(&ekg_error::Error::InvalidPrefixName), None);
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{Namespaces, Statement},
+    ekg_namespace::{consts::DEFAULT_GRAPH_RDFOX, Graph},
+    mime::Mime,
+    std::ops::Deref,
+};
+
+/// Which graphs
+/// [`DataStoreConnection::export_data_to_file`](crate::DataStoreConnection::export_data_to_file)
+/// exports.
+#[derive(Debug, Clone)]
+pub enum ExportScope {
+    /// The default graph and all named graphs but
+    /// [`DATA_STORE_STATE_GRAPH`](crate::DATA_STORE_STATE_GRAPH)
+    AllGraphs,
+    /// Only RDFox's default graph
+    DefaultGraph,
+    /// Only the given named graphs
+    Graphs(Vec<Graph>),
+}
+
+impl ExportScope {
+    /// Whether the given format keeps the graph of each triple, in the other
+    /// formats the triples of all exported graphs end up together
+    pub(crate) fn is_quad_format(format: &Mime) -> bool {
+        matches!(format.essence_str(), "application/n-quads" | "application/trig")
+    }
+
+    /// The query that selects the facts in scope, with the graph of each
+    /// triple in `?G` when `quads` is set
+    pub(crate) fn statement(&self, quads: bool) -> Result<Statement, ekg_error::Error> {
+        let default_graph = DEFAULT_GRAPH_RDFOX.deref().as_display_iri();
+        let pattern = match self {
+            ExportScope::AllGraphs => Statement::all_graphs_pattern("?S", "?P", "?O", "?G"),
+            ExportScope::DefaultGraph => format!("?S ?P ?O . BIND({default_graph} AS ?G)"),
+            ExportScope::Graphs(graphs) => {
+                let graphs = graphs
+                    .iter()
+                    .map(|graph| graph.as_display_iri().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("VALUES ?G {{ {graphs} }} GRAPH ?G {{ ?S ?P ?O }}")
+            }
+        };
+        let projection = if quads { "?S ?P ?O ?G" } else { "?S ?P ?O" };
+        Statement::new(
+            &Namespaces::empty()?,
+            format!("SELECT {projection} WHERE {{ {pattern} }}").into(),
+        )
+    }
+}
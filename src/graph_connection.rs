@@ -2,10 +2,26 @@
 //---------------------------------------------------------------
 
 use {
-    crate::{DataStoreConnection, FactDomain, Namespaces, Parameters, Statement, Transaction},
+    crate::{
+        DataStoreConnection,
+        FactDomain,
+        GraphDiff,
+        ImportOptions,
+        ImportSummary,
+        Namespaces,
+        Parameters,
+        RdfFormat,
+        Statement,
+        Term,
+        Transaction,
+        Triples,
+        UpdateType,
+    },
     ekg_namespace::{consts::LOG_TARGET_DATABASE, Graph},
     indoc::formatdoc,
+    mime::Mime,
     std::{
+        collections::HashSet,
         fmt::{Display, Formatter},
         path::Path,
         sync::Arc,
@@ -80,36 +96,118 @@ impl GraphConnection {
             .import_data_from_file(file, &self.graph)
     }
 
+    pub fn import_data_from_file_with_format<P>(
+        &self,
+        file: P,
+        format: RdfFormat,
+    ) -> Result<(), ekg_error::Error>
+        where P: AsRef<Path> {
+        self.data_store_connection
+            .import_data_from_file_with_format(file, &self.graph, format)
+    }
+
+    /// Import the given file into this graph, adding or deleting its triples
+    /// depending on the given update type.
+    pub fn import_data_from_file_with_update_type<P>(
+        &self,
+        file: P,
+        update_type: UpdateType,
+    ) -> Result<(), ekg_error::Error>
+        where P: AsRef<Path> {
+        self.data_store_connection
+            .import_data_from_file_with_update_type(file, &self.graph, update_type)
+    }
+
+    pub fn import_data_from_buffer(
+        &self,
+        content: &[u8],
+        format: &Mime,
+    ) -> Result<(), ekg_error::Error> {
+        self.data_store_connection
+            .import_data_from_buffer(content, format, &self.graph)
+    }
+
+    pub fn import_data_from_str(&self, content: &str, format: &Mime) -> Result<(), ekg_error::Error> {
+        self.data_store_connection
+            .import_data_from_str(content, format, &self.graph)
+    }
+
+    pub fn import_data_from_buffer_with_update_type(
+        &self,
+        content: &[u8],
+        format: &Mime,
+        update_type: UpdateType,
+    ) -> Result<(), ekg_error::Error> {
+        self.data_store_connection
+            .import_data_from_buffer_with_update_type(content, format, &self.graph, update_type)
+    }
+
     pub fn import_axioms(&self) -> Result<(), ekg_error::Error> {
+        self.import_axioms_with_update_type(UpdateType::Addition)
+    }
+
+    pub fn import_axioms_with_update_type(&self, update_type: UpdateType) -> Result<(), ekg_error::Error> {
         assert!(
             self.ontology_graph.is_some(),
             "no ontology graph specified"
         );
         self.data_store_connection
-            .import_axioms_from_triples(self.ontology_graph.as_ref().unwrap(), &self.graph)
+            .import_axioms_from_triples_with_update_type(
+                self.ontology_graph.as_ref().unwrap(),
+                &self.graph,
+                update_type,
+            )
     }
 
-    /// Read all RDF files (currently it supports .ttl and .nt files) from
-    /// the given directory, applying ignore files like `.gitignore`.
-    ///
-    /// Returns the number of loaded files.
-    ///
-    /// TODO: Support all the types that RDFox supports (and more)
-    /// TODO: Support '*.gz' files
-    /// TODO: Parallelize appropriately in sync with number of threads that
-    /// RDFox uses
-    pub fn import_rdf_from_directory(&self, root: &Path) -> Result<u16, ekg_error::Error> {
+    /// Read all RDF files (in any of the formats of [`RdfFormat`], possibly
+    /// gzip-compressed) from the given directory into this graph, see
+    /// [`DataStoreConnection::import_rdf_from_directory`].
+    pub fn import_rdf_from_directory(
+        &self,
+        root: &Path,
+    ) -> Result<ImportSummary, ekg_error::Error> {
         self.data_store_connection
             .import_rdf_from_directory(root, &self.graph)
     }
 
-    /// Get the number of triples using the given transaction.
-    ///
-    /// TODO: Implement this with SPARQL COUNT (and compare performance)
+    /// See [`DataStoreConnection::import_rdf_from_directory_with_options`]
+    pub fn import_rdf_from_directory_with_options(
+        &self,
+        root: &Path,
+        options: &ImportOptions,
+    ) -> Result<ImportSummary, ekg_error::Error> {
+        self.data_store_connection
+            .import_rdf_from_directory_with_options(root, &self.graph, options)
+    }
+
+    /// Delete all (explicitly asserted) triples in this graph using the given
+    /// read/write transaction and return how many there were.
+    pub fn clear(&self, tx: &Arc<Transaction>) -> Result<usize, ekg_error::Error> {
+        self.data_store_connection
+            .clear_graph_in_transaction(tx, &self.graph)
+    }
+
+    /// Get the number of triples using the given transaction, counted by
+    /// RDFox with SPARQL `COUNT`.
     pub fn get_triples_count(
         &self,
         tx: &Arc<Transaction>,
         fact_domain: FactDomain,
+    ) -> Result<usize, ekg_error::Error> {
+        self.data_store_connection.count_matches(
+            tx,
+            fact_domain,
+            "*",
+            format!("GRAPH {} {{ ?s ?p ?o }}", self.graph.as_display_iri()).as_str(),
+        )
+    }
+
+    /// Like [`GraphConnection::get_triples_count`] but reading every triple
+    /// through a cursor, which is far slower on large graphs.
+    pub fn get_triples_count_with_cursor(
+        &self,
+        tx: &Arc<Transaction>,
+        fact_domain: FactDomain,
     ) -> Result<usize, ekg_error::Error> {
         Statement::new(
             &Namespaces::empty()?,
@@ -132,6 +230,188 @@ impl GraphConnection {
             .count(tx)
     }
 
+    /// Iterate over all triples (asserted or derived) in the graph, read
+    /// one at a time from a cursor using the given transaction.
+    pub fn export_triples(&self, tx: &Arc<Transaction>) -> Result<Triples, ekg_error::Error> {
+        self.triples(tx, FactDomain::ALL)
+    }
+
+    fn triples(
+        &self,
+        tx: &Arc<Transaction>,
+        fact_domain: FactDomain,
+    ) -> Result<Triples, ekg_error::Error> {
+        let cursor = Statement::new(
+            &Namespaces::empty()?,
+            format!(
+                "SELECT ?s ?p ?o FROM {} WHERE {{ ?s ?p ?o }}",
+                self.graph.as_display_iri()
+            )
+            .into(),
+        )?
+        .cursor(
+            &self.data_store_connection,
+            &Parameters::empty()?.fact_domain(fact_domain)?,
+        )?;
+        Ok(Triples::new(cursor, tx))
+    }
+
+    /// What has to change in this graph to make its explicitly asserted
+    /// triples the same as those of the other graph, see
+    /// [`GraphConnection::apply_diff`].
+    ///
+    /// Blank nodes are compared by identity, so a blank node in this graph
+    /// only matches the very same blank node in the other graph (there is no
+    /// check for isomorphism).
+    pub fn diff(
+        &self,
+        other: &GraphConnection,
+        tx: &Arc<Transaction>,
+    ) -> Result<GraphDiff, ekg_error::Error> {
+        let ours = self
+            .triples(tx, FactDomain::ASSERTED)?
+            .collect::<Result<HashSet<_>, _>>()?;
+        let theirs = other
+            .triples(tx, FactDomain::ASSERTED)?
+            .collect::<Result<HashSet<_>, _>>()?;
+        Ok(GraphDiff::new(
+            theirs.difference(&ours).cloned().collect(),
+            ours.difference(&theirs).cloned().collect(),
+        ))
+    }
+
+    /// Delete the removed and insert the added triples of the given diff in
+    /// one read/write transaction.
+    ///
+    /// Blank nodes in added triples become new blank nodes, and removing
+    /// triples with blank nodes fails, as it does in `DELETE DATA`.
+    pub fn apply_diff(&self, diff: &GraphDiff) -> Result<(), ekg_error::Error> {
+        if diff.is_empty() {
+            return Ok(());
+        }
+        Transaction::begin_read_write_do(&self.data_store_connection, |tx| {
+            self.delete_triples(&tx, &diff.removed)?;
+            self.insert_triples(&tx, &diff.added)
+        })
+    }
+
+    /// Check whether the given subject has at least one triple in the graph.
+    pub fn exists_subject(
+        &self,
+        tx: &Arc<Transaction>,
+        subject: &Term,
+    ) -> Result<bool, ekg_error::Error> {
+        self.data_store_connection.exists(
+            tx,
+            format!(
+                "GRAPH {} {{ ?s ?p ?o }}",
+                self.graph.as_display_iri()
+            )
+            .as_str(),
+            &[("s", subject)],
+        )
+    }
+
+    /// Check whether the given triple exists in the graph.
+    pub fn exists_triple(
+        &self,
+        tx: &Arc<Transaction>,
+        subject: &Term,
+        predicate: &Term,
+        object: &Term,
+    ) -> Result<bool, ekg_error::Error> {
+        self.data_store_connection.exists(
+            tx,
+            format!(
+                "GRAPH {} {{ ?s ?p ?o }}",
+                self.graph.as_display_iri()
+            )
+            .as_str(),
+            &[
+                ("s", subject),
+                ("p", predicate),
+                ("o", object),
+            ],
+        )
+    }
+
+    /// Insert the given triple into the graph using the given read/write
+    /// transaction.
+    pub fn insert_triple(
+        &self,
+        tx: &Arc<Transaction>,
+        subject: &Term,
+        predicate: &Term,
+        object: &Term,
+    ) -> Result<(), ekg_error::Error> {
+        self.update_data(tx, "INSERT", &[(subject, predicate, object)])
+    }
+
+    /// Delete the given triple from the graph using the given read/write
+    /// transaction, it's not an error if it does not exist.
+    pub fn delete_triple(
+        &self,
+        tx: &Arc<Transaction>,
+        subject: &Term,
+        predicate: &Term,
+        object: &Term,
+    ) -> Result<(), ekg_error::Error> {
+        self.update_data(tx, "DELETE", &[(subject, predicate, object)])
+    }
+
+    /// Insert the given triples into the graph with one `INSERT DATA`
+    /// update, using the given read/write transaction.
+    pub fn insert_triples(
+        &self,
+        tx: &Arc<Transaction>,
+        triples: &[(Term, Term, Term)],
+    ) -> Result<(), ekg_error::Error> {
+        self.update_data(tx, "INSERT", &Self::borrowed(triples))
+    }
+
+    /// Delete the given triples from the graph with one `DELETE DATA`
+    /// update, using the given read/write transaction.
+    pub fn delete_triples(
+        &self,
+        tx: &Arc<Transaction>,
+        triples: &[(Term, Term, Term)],
+    ) -> Result<(), ekg_error::Error> {
+        self.update_data(tx, "DELETE", &Self::borrowed(triples))
+    }
+
+    fn borrowed(triples: &[(Term, Term, Term)]) -> Vec<(&Term, &Term, &Term)> {
+        triples.iter().map(|(s, p, o)| (s, p, o)).collect()
+    }
+
+    /// Evaluate an `INSERT DATA` or `DELETE DATA` update with the given
+    /// triples, which are escaped by the `Display` of [`Term`]
+    fn update_data(
+        &self,
+        tx: &Arc<Transaction>,
+        operation: &str,
+        triples: &[(&Term, &Term, &Term)],
+    ) -> Result<(), ekg_error::Error> {
+        if triples.is_empty() {
+            return Ok(());
+        }
+        let triples = triples
+            .iter()
+            .map(|(subject, predicate, object)| {
+                format!("        {subject} {predicate} {object} .\n")
+            })
+            .collect::<String>();
+        let update = Statement::new(
+            &Namespaces::empty()?,
+            format!(
+                "{operation} DATA {{\n    GRAPH {} {{\n{triples}    }}\n}}",
+                self.graph.as_display_iri()
+            )
+            .into(),
+        )?;
+        tx.evaluate_update(&update, &Parameters::empty()?, false)?;
+        Ok(())
+    }
+
     // pub fn get_subjects_count(&self, fact_domain: FactDomain) ->
     // Result<std::os::raw::c_ulong, ekg_error::Error> {     Statement::query(
     //         &Namespaces::default()?,
@@ -0,0 +1,68 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::Term,
+    std::fmt::{Display, Formatter},
+};
+
+/// The difference between two graphs, see
+/// [`GraphConnection::diff`](crate::GraphConnection::diff), with the
+/// triples in each list sorted on their N-Triples form.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphDiff {
+    /// The triples that are only in the other graph
+    pub added:   Vec<(Term, Term, Term)>,
+    /// The triples that are only in this graph
+    pub removed: Vec<(Term, Term, Term)>,
+}
+
+impl Display for GraphDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (s, p, o) in self.removed.iter() {
+            writeln!(f, "- {s} {p} {o} .")?;
+        }
+        for (s, p, o) in self.added.iter() {
+            writeln!(f, "+ {s} {p} {o} .")?;
+        }
+        Ok(())
+    }
+}
+
+impl GraphDiff {
+    pub fn new(mut added: Vec<(Term, Term, Term)>, mut removed: Vec<(Term, Term, Term)>) -> Self {
+        added.sort_by_cached_key(Self::sort_key);
+        removed.sort_by_cached_key(Self::sort_key);
+        Self { added, removed }
+    }
+
+    fn sort_key((s, p, o): &(Term, Term, Term)) -> String { format!("{s} {p} {o}") }
+
+    pub fn is_empty(&self) -> bool { self.added.is_empty() && self.removed.is_empty() }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::GraphDiff, crate::Term};
+
+    #[test_log::test]
+    fn test_display() {
+        let triple = |o: &str| {
+            (
+                Term::iri("https://ekgf.org/s").unwrap(),
+                Term::iri("https://ekgf.org/p").unwrap(),
+                Term::string(o),
+            )
+        };
+        let diff = GraphDiff::new(vec![triple("b"), triple("a")], vec![triple("c")]);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.added[0], triple("a"));
+        assert_eq!(
+            diff.to_string(),
+            "- <https://ekgf.org/s> <https://ekgf.org/p> \"c\" .\n\
+             + <https://ekgf.org/s> <https://ekgf.org/p> \"a\" .\n\
+             + <https://ekgf.org/s> <https://ekgf.org/p> \"b\" .\n"
+        );
+        assert!(GraphDiff::default().is_empty());
+    }
+}
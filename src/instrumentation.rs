@@ -0,0 +1,194 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{connection_stats::error_kind, OperationKind},
+    ekg_namespace::consts::LOG_TARGET_DATABASE,
+    std::{
+        fmt::Debug,
+        path::PathBuf,
+        time::{Duration, Instant},
+    },
+};
+
+/// Callbacks for metrics (like Prometheus counters and histograms) about
+/// what happens on a [`DataStoreConnection`](crate::DataStoreConnection),
+/// registered with
+/// [`ServerConnection::set_instrumentation`](crate::ServerConnection::set_instrumentation)
+/// for all its connections or with
+/// [`DataStoreConnection::set_instrumentation`](crate::DataStoreConnection::set_instrumentation)
+/// for one of them.
+///
+/// All callbacks do nothing by default. They are called on the thread that
+/// does the work, so they should be quick.
+pub trait Instrumentation: Debug + Send + Sync {
+    /// A query is read by [`Cursor::consume`](crate::Cursor::consume), an
+    /// update is evaluated or results are streamed
+    fn on_statement_start(&self, _event: &StatementEvent) {}
+
+    /// The statement of a call to `on_statement_start` is done, with the
+    /// number of solutions (if it's a query)
+    fn on_statement_finish(&self, _event: &StatementEvent, _outcome: &Outcome) {}
+
+    fn on_transaction_commit(&self, _event: &TransactionEvent) {}
+
+    fn on_transaction_rollback(&self, _event: &TransactionEvent) {}
+
+    /// A file or buffer has been imported (or failed to)
+    fn on_import(&self, _event: &ImportEvent, _outcome: &Outcome) {}
+}
+
+/// The execution of a statement, see [`Instrumentation::on_statement_start`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementEvent {
+    /// The number of the connection
+    pub connection:  usize,
+    pub kind:        OperationKind,
+    /// The fingerprint (hash) of the text of the statement, as in
+    /// [`OperationInfo`](crate::OperationInfo)
+    pub fingerprint: u64,
+}
+
+/// An import, see [`Instrumentation::on_import`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportEvent {
+    pub connection: usize,
+    /// The graph the data was imported into
+    pub graph:      String,
+    /// The file that was imported, `None` for a buffer
+    pub file:       Option<PathBuf>,
+    /// The size of the buffer, 0 for a file
+    pub bytes:      usize,
+}
+
+/// The end of a transaction, see [`Instrumentation::on_transaction_commit`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionEvent {
+    pub connection:  usize,
+    pub transaction: usize,
+    /// How long the transaction was open
+    pub duration:    Duration,
+}
+
+/// How a statement or import went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub duration:   Duration,
+    /// The number of solutions of a query
+    pub rows:       Option<usize>,
+    /// The kind of error (like `InvalidSparql`, `QueryTimedOut` or
+    /// `Exception`) if it failed
+    pub error_kind: Option<String>,
+}
+
+impl Outcome {
+    pub(crate) fn new<T, E: Debug + 'static>(
+        started: Instant,
+        result: &Result<T, E>,
+        rows: Option<usize>,
+    ) -> Self {
+        Self {
+            duration: started.elapsed(),
+            rows,
+            error_kind: result.as_ref().err().map(error_kind),
+        }
+    }
+}
+
+/// The [`Instrumentation`] that does nothing, the default
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoInstrumentation;
+
+impl Instrumentation for NoInstrumentation {}
+
+/// An [`Instrumentation`] that logs every event (with target `database`
+/// at debug level) as structured fields
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TracingInstrumentation;
+
+impl Instrumentation for TracingInstrumentation {
+    fn on_statement_start(&self, event: &StatementEvent) {
+        tracing::debug!(
+            target: LOG_TARGET_DATABASE,
+            conn = event.connection,
+            kind = %event.kind,
+            fingerprint = event.fingerprint,
+            "Started statement"
+        );
+    }
+
+    fn on_statement_finish(&self, event: &StatementEvent, outcome: &Outcome) {
+        tracing::debug!(
+            target: LOG_TARGET_DATABASE,
+            conn = event.connection,
+            kind = %event.kind,
+            fingerprint = event.fingerprint,
+            duration = ?outcome.duration,
+            rows = outcome.rows,
+            error = outcome.error_kind.as_deref(),
+            "Finished statement"
+        );
+    }
+
+    fn on_transaction_commit(&self, event: &TransactionEvent) {
+        tracing::debug!(
+            target: LOG_TARGET_DATABASE,
+            conn = event.connection,
+            txno = event.transaction,
+            duration = ?event.duration,
+            "Committed transaction"
+        );
+    }
+
+    fn on_transaction_rollback(&self, event: &TransactionEvent) {
+        tracing::debug!(
+            target: LOG_TARGET_DATABASE,
+            conn = event.connection,
+            txno = event.transaction,
+            duration = ?event.duration,
+            "Rolled back transaction"
+        );
+    }
+
+    fn on_import(&self, event: &ImportEvent, outcome: &Outcome) {
+        tracing::debug!(
+            target: LOG_TARGET_DATABASE,
+            conn = event.connection,
+            graph = %event.graph,
+            file = ?event.file,
+            bytes = event.bytes,
+            duration = ?outcome.duration,
+            error = outcome.error_kind.as_deref(),
+            "Imported data"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::Outcome,
+        crate::QueryTimedOut,
+        std::time::{Duration, Instant},
+    };
+
+    #[test_log::test]
+    fn test_outcome() {
+        let failed: Result<(), ekg_error::Error> = Err(ekg_error::Error::Exception {
+            action:  "testing".to_string(),
+            message: "failure".to_string(),
+        });
+        let outcome = Outcome::new(Instant::now(), &failed, None);
+        assert_eq!(outcome.error_kind.as_deref(), Some("Exception"));
+        let outcome = Outcome::new(Instant::now(), &Ok::<_, ekg_error::Error>(3), Some(3));
+        assert_eq!(outcome.error_kind, None);
+        assert_eq!(outcome.rows, Some(3));
+        let timed_out: Result<(), ekg_error::Error> = Err(QueryTimedOut {
+            elapsed: Duration::from_secs(1),
+            query:   "SELECT * WHERE { ?s ?p ?o }".to_string(),
+        }
+        .into());
+        let outcome = Outcome::new(Instant::now(), &timed_out, None);
+        assert_eq!(outcome.error_kind.as_deref(), Some("QueryTimedOut"));
+    }
+}
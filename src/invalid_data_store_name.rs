@@ -0,0 +1,45 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// A data store was declared with a name that RDFox would refuse, see
+/// [`DataStoreBuilder::name`](crate::DataStoreBuilder::name).
+/// Get it back from an `ekg_error::Error` with
+/// [`InvalidDataStoreName::from_error`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvalidDataStoreName {
+    pub name:   String,
+    /// What is wrong with the name
+    pub reason: String,
+}
+
+impl Display for InvalidDataStoreName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InvalidDataStoreName: {:?} {}", self.name, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidDataStoreName {}
+
+impl TypedError for InvalidDataStoreName {
+    const NAME: &'static str = "InvalidDataStoreName";
+
+    fn action(&self) -> String { format!("declaring data store {:?}", self.name) }
+}
+
+impl From<InvalidDataStoreName> for ekg_error::Error {
+    fn from(value: InvalidDataStoreName) -> Self { value.to_error() }
+}
+
+impl InvalidDataStoreName {
+    /// The [`InvalidDataStoreName`] error that the given error was made
+    /// from, if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
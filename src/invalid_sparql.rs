@@ -0,0 +1,61 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// RDFox could not parse a statement, see
+/// [`Statement::validate`](crate::Statement::validate).
+///
+/// The line number is the one in the text that was given to the statement,
+/// not counting the prefixes that were prepended to it. Get it back from an
+/// `ekg_error::Error` with [`InvalidSparql::from_error`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvalidSparql {
+    /// The line (starting at 1) if RDFox mentions one in the given text
+    pub line:      Option<usize>,
+    pub column:    Option<usize>,
+    /// The message of the exception that RDFox threw
+    pub message:   String,
+    /// The text of the offending line
+    pub line_text: Option<String>,
+}
+
+impl Display for InvalidSparql {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InvalidSparql: ")?;
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "line {line}, column {column}: ")?,
+            (Some(line), None) => write!(f, "line {line}: ")?,
+            _ => {},
+        }
+        write!(f, "{}", self.message)?;
+        if let (Some(line), Some(line_text)) = (self.line, &self.line_text) {
+            write!(f, "\n{line:0>4}: {line_text}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InvalidSparql {}
+
+impl TypedError for InvalidSparql {
+    const NAME: &'static str = "InvalidSparql";
+
+    fn action(&self) -> String { "parsing a SPARQL statement".to_string() }
+}
+
+impl From<InvalidSparql> for ekg_error::Error {
+    fn from(value: InvalidSparql) -> Self { value.to_error() }
+}
+
+impl InvalidSparql {
+    /// The [`InvalidSparql`] error that the given error was made from, if
+    /// any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
@@ -7,40 +7,186 @@
 extern crate core;
 
 pub use {
-    class_report::ClassReport,
-    connectable_data_store::ConnectableDataStore,
-    cursor::{Cursor, CursorRow, OpenedCursor},
+    class_report::{ClassMetrics, ClassReport},
+    connectable_data_store::{ConnectableDataStore, PoolOptions},
+    connection_stats::ConnectionStatsSnapshot,
+    cursor::{
+        Cursor,
+        CursorRow,
+        CursorRows,
+        CursorStatistics,
+        FromCursorRow,
+        FromLexicalValue,
+        OpenedCursor,
+        OwnedCursorRow,
+        ResourceValue,
+        Triples,
+        UnexpectedDataType,
+        UnknownVariableName,
+        INITIAL_LEXICAL_FORM_BUFFER_SIZE,
+        MAX_RETAINED_LEXICAL_FORM_BUFFER_SIZE,
+    },
+    data_source_parameters::DataSourceParameters,
     data_store::DataStore,
+    data_store_builder::DataStoreBuilder,
     data_store_connection::DataStoreConnection,
+    data_store_connection_like::DataStoreConnectionLike,
+    data_store_state::{DataStoreOffline, DATA_STORE_STATE_GRAPH, DEFAULT_ONLINE_CHECK_TTL},
+    directory_import::{ImportFailure, ImportOptions, ImportProgress, ImportSummary},
+    exception::{ExceptionKind, RDFoxException},
+    export_scope::ExportScope,
     graph_connection::GraphConnection,
-    license::{find_license, RDFOX_DEFAULT_LICENSE_FILE_NAME, RDFOX_HOME},
+    graph_diff::GraphDiff,
+    instrumentation::{
+        ImportEvent,
+        Instrumentation,
+        NoInstrumentation,
+        Outcome,
+        StatementEvent,
+        TracingInstrumentation,
+        TransactionEvent,
+    },
+    invalid_data_store_name::InvalidDataStoreName,
+    invalid_sparql::InvalidSparql,
+    license::{find_license, rdfox_home, RDFOX_DEFAULT_LICENSE_FILE_NAME, RDFOX_HOME},
+    license_not_found::LicenseNotFound,
     mime::Mime,
     namespaces::{Namespaces, NamespacesBuilder},
+    non_utf8_path::NonUtf8Path,
+    operation_cancelled::OperationCancelled,
+    operation_registry::{OperationInfo, OperationKind},
     parameters::{DataStoreType, FactDomain, Parameters, PersistenceMode},
-    role_creds::RoleCreds,
+    parameters_builder::{
+        EqualityMode,
+        Parameter,
+        ParametersBuilder,
+        UnsupportedParameterForVersion,
+        RDFOX_VERSION,
+    },
+    persistence_required::PersistenceRequired,
+    pool_ext::{PoolExt, RetryPolicy},
+    pool_shutdown_timed_out::PoolShutdownTimedOut,
+    projection_mismatch::ProjectionMismatch,
+    proof_tree::{ProofFact, ProofTree},
+    query_timed_out::QueryTimedOut,
+    rdf_format::RdfFormat,
+    result_semantics::ResultSemantics,
+    result_serializer::{CsvSerializer, ResultSerializer, SparqlJsonSerializer},
+    role_creds::{RoleCreds, RDFOX_PASSWORD, RDFOX_ROLE},
+    select_result::{BindingKind, SelectResult, SimpleBinding},
     server::Server,
-    server_connection::ServerConnection,
-    statement::Statement,
-    streamer::Streamer,
+    server_already_running::ServerAlreadyRunning,
+    server_connection::{Privilege, Resource, ServerConnection},
+    server_in_use::ServerInUse,
+    server_not_started::ServerNotStarted,
+    server_shut_down::ServerShutDown,
+    server_state::ServerState,
+    sparql_text::{
+        detokenize,
+        no_comments,
+        split_statements,
+        tokenize,
+        SparqlToken,
+        SparqlTokenKind,
+    },
+    statement::{Statement, DEFAULT_MAX_STATEMENT_SIZE},
+    statement_library::StatementLibrary,
+    statement_too_large::StatementTooLarge,
+    statistics::{
+        ComponentInfo,
+        ComponentValue,
+        DataStoreStatistics,
+        MemoryUse,
+        ServerStatistics,
+    },
+    stream_aborted::StreamAborted,
+    streamed_result::StreamedResult,
+    streamer::{Streamer, DEFAULT_MAX_REMAINING_BUFFER_SIZE},
+    table_writer::{TableWriter, DEFAULT_TABLE_MAX_ROWS, DEFAULT_TABLE_MAX_WIDTH},
+    term::Term,
     transaction::Transaction,
+    transaction_already_finished::TransactionAlreadyFinished,
+    unsupported_on_this_rdfox_version::UnsupportedOnThisRDFoxVersion,
+    update_result::UpdateResult,
+    update_type::UpdateType,
 };
+#[cfg(any(test, feature = "test-util"))]
+pub use mock_data_store_connection::MockDataStoreConnection;
 
 mod class_report;
 mod connectable_data_store;
+mod connection_stats;
 mod cursor;
+mod data_source_parameters;
 mod data_store;
+mod data_store_builder;
 mod data_store_connection;
+mod data_store_connection_like;
+mod data_store_state;
+#[cfg(any(test, feature = "test-util"))]
+pub mod datagen;
+pub mod diagnostics;
+mod directory_import;
 mod exception;
+mod export_scope;
 mod graph_connection;
+mod graph_diff;
+mod instrumentation;
+mod invalid_data_store_name;
+mod invalid_sparql;
 mod license;
+mod license_not_found;
+#[cfg(any(test, feature = "test-util"))]
+mod mock_data_store_connection;
 mod namespaces;
+mod non_utf8_path;
+mod operation_cancelled;
+mod operation_registry;
+#[cfg(feature = "oxrdf-interop")]
+mod oxrdf_interop;
 mod parameters;
+mod parameters_builder;
+mod persistence_required;
+mod pool_ext;
+mod pool_shutdown_timed_out;
+mod prepared_cursors;
+mod projection_mismatch;
+mod proof_tree;
+mod query_timed_out;
+mod rdf_format;
+#[cfg(feature = "remote")]
+pub mod remote;
+mod result_semantics;
+mod result_serializer;
 mod role_creds;
+mod select_result;
+#[cfg(feature = "serde")]
+mod serde_support;
 mod server;
+mod server_already_running;
 mod server_connection;
+mod server_in_use;
+mod server_not_started;
+mod server_shut_down;
+mod server_state;
+mod sparql_text;
 mod statement;
+mod statement_library;
+mod statement_too_large;
+mod statistics;
+mod stream_aborted;
+mod streamed_result;
 mod streamer;
+mod table_writer;
+mod term;
+#[cfg(feature = "tokio")]
+mod tokio_support;
 mod transaction;
+mod transaction_already_finished;
+mod typed_error;
+mod unsupported_on_this_rdfox_version;
+mod update_result;
+mod update_type;
 
 #[allow(dead_code)]
 #[allow(non_camel_case_types)]
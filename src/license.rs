@@ -2,51 +2,161 @@
 //---------------------------------------------------------------
 
 use {
+    crate::LicenseNotFound,
     ekg_namespace::consts::LOG_TARGET_DATABASE,
-    std::path::{Path, PathBuf},
+    std::{
+        fmt::{Display, Formatter},
+        ops::Deref,
+        path::{Path, PathBuf},
+        sync::OnceLock,
+    },
 };
 
-pub static RDFOX_HOME: &str = concat!(env!("HOME"), "/.RDFox");
 pub const RDFOX_DEFAULT_LICENSE_FILE_NAME: &str = "RDFox.lic";
 
-/// Find the license file in the given directory or in the home directory or
+/// The directory `~/.RDFox` as a string, which used to be that of the user
+/// that built this crate, see [`rdfox_home`]
+#[deprecated(note = "use rdfox_home(), which is resolved when it's called")]
+pub static RDFOX_HOME: RdfoxHome = RdfoxHome;
+
+/// The type of [`RDFOX_HOME`], which reads like the `&str` it used to be
+/// and forwards to [`rdfox_home`] (empty if `HOME` is not set)
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct RdfoxHome;
+
+impl Deref for RdfoxHome {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        static HOME: OnceLock<String> = OnceLock::new();
+        HOME.get_or_init(|| {
+            rdfox_home()
+                .map(|home| home.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+    }
+}
+
+impl Display for RdfoxHome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { f.write_str(self) }
+}
+
+/// The directory `~/.RDFox` of the user that runs this process (not of the
+/// one that built it), `None` if `HOME` is not set
+pub fn rdfox_home() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".RDFox"))
+}
+
+/// Find the license file in the given directory or in [`rdfox_home`] or
 /// check the environment variable RDFOX_LICENSE_CONTENT (which takes
 /// precedence).
 ///
 /// If the environment variable RDFOX_LICENSE_CONTENT is set, then the content
 /// of the license file is returned as the second element of the tuple.
+///
+/// Fails with [`LicenseNotFound`], which lists the files that were checked,
+/// if there is no license.
 pub fn find_license(
     dir: Option<&Path>,
 ) -> Result<(Option<PathBuf>, Option<String>), ekg_error::Error> {
-    if let Ok(license_content) = std::env::var("RDFOX_LICENSE_CONTENT") {
+    Ok(try_find_license(dir)?)
+}
+
+/// [`find_license`] with its own error type
+pub(crate) fn try_find_license(
+    dir: Option<&Path>,
+) -> Result<(Option<PathBuf>, Option<String>), LicenseNotFound> {
+    let content = std::env::var("RDFOX_LICENSE_CONTENT").ok();
+    find_license_in(dir, rdfox_home().as_deref(), content)
+}
+
+fn find_license_in(
+    dir: Option<&Path>,
+    rdfox_home: Option<&Path>,
+    content: Option<String>,
+) -> Result<(Option<PathBuf>, Option<String>), LicenseNotFound> {
+    if let Some(license_content) = content {
         tracing::info!(
             target: LOG_TARGET_DATABASE,
             "Using license content from environment variable RDFOX_LICENSE_CONTENT"
         );
         return Ok((None, Some(license_content)));
     }
-    if let Some(dir) = dir {
+    let mut checked = Vec::new();
+    for dir in dir.into_iter().chain(rdfox_home) {
         let license_file_name = dir.join(RDFOX_DEFAULT_LICENSE_FILE_NAME);
         tracing::info!(
             target: LOG_TARGET_DATABASE,
             "Checking license file {license_file_name:?}"
         );
-        if license_file_name.exists() {
+        if license_file_name.is_file() {
             return Ok((Some(license_file_name), None));
         }
-        // Now check home directory ~/.RDFox/RDFox.lic
-        //
-        let license_file_name = PathBuf::from(format!(
-            "{RDFOX_HOME}/{RDFOX_DEFAULT_LICENSE_FILE_NAME}"
+        checked.push(license_file_name);
+    }
+    Err(LicenseNotFound { checked })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{find_license_in, rdfox_home, RDFOX_DEFAULT_LICENSE_FILE_NAME, RDFOX_HOME},
+        crate::LicenseNotFound,
+        std::path::PathBuf,
+    };
+
+    #[test_log::test]
+    fn test_find_license() {
+        let temp = std::env::temp_dir().join(format!("rdfox-rs-license-{}", std::process::id()));
+        let (dir, home) = (temp.join("db"), temp.join("home/.RDFox"));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(&home).unwrap();
+        let (dir_license, home_license) = (
+            dir.join(RDFOX_DEFAULT_LICENSE_FILE_NAME),
+            home.join(RDFOX_DEFAULT_LICENSE_FILE_NAME),
+        );
+
+        let err = find_license_in(Some(&dir), Some(&home), None).unwrap_err();
+        assert_eq!(err.checked, vec![dir_license.clone(), home_license.clone()]);
+        assert!(err.to_string().ends_with(
+            format!("{}, {}", dir_license.display(), home_license.display()).as_str()
         ));
-        tracing::info!(
-            target: LOG_TARGET_DATABASE,
-            "Checking license file {license_file_name:?}"
+        assert_eq!(find_license_in(None, None, None).unwrap_err().checked, Vec::<PathBuf>::new());
+
+        std::fs::write(&home_license, "home").unwrap();
+        assert_eq!(
+            find_license_in(Some(&dir), Some(&home), None).unwrap(),
+            (Some(home_license.clone()), None)
         );
-        if license_file_name.exists() {
-            return Ok((Some(license_file_name), None));
-        }
+        assert_eq!(
+            find_license_in(None, Some(&home), None).unwrap(),
+            (Some(home_license), None)
+        );
+        std::fs::write(&dir_license, "dir").unwrap();
+        assert_eq!(
+            find_license_in(Some(&dir), Some(&home), None).unwrap(),
+            (Some(dir_license), None)
+        );
+        assert_eq!(
+            find_license_in(Some(&dir), Some(&home), Some("content".to_string())).unwrap(),
+            (None, Some("content".to_string()))
+        );
+        std::fs::remove_dir_all(&temp).unwrap();
+    }
+
+    #[test_log::test]
+    fn test_not_found_error() {
+        let not_found = LicenseNotFound { checked: vec![PathBuf::from("/nowhere/RDFox.lic")] };
+        let err = ekg_error::Error::from(not_found.clone());
+        assert_eq!(LicenseNotFound::from_error(&err), Some(not_found));
     }
 
-    Err(ekg_error::Error::RDFoxLicenseFileNotFound)
+    #[test_log::test]
+    #[allow(deprecated)]
+    fn test_rdfox_home() {
+        let home = rdfox_home().map(|home| home.to_string_lossy().into_owned());
+        assert_eq!(home.unwrap_or_default(), RDFOX_HOME.to_string());
+        assert_eq!(&*RDFOX_HOME, RDFOX_HOME.to_string().as_str());
+    }
 }
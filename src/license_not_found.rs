@@ -0,0 +1,55 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::{Display, Formatter},
+        path::PathBuf,
+    },
+};
+
+/// No RDFox license was found, see [`find_license`](crate::find_license).
+/// Get it back from an `ekg_error::Error` with
+/// [`LicenseNotFound::from_error`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LicenseNotFound {
+    /// The license files that were looked for, in order, after the
+    /// environment variable `RDFOX_LICENSE_CONTENT`
+    pub checked: Vec<PathBuf>,
+}
+
+impl Display for LicenseNotFound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "LicenseNotFound: no RDFox license found, checked environment variable \
+             RDFOX_LICENSE_CONTENT"
+        )?;
+        for path in self.checked.iter() {
+            write!(f, ", {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LicenseNotFound {}
+
+impl TypedError for LicenseNotFound {
+    const NAME: &'static str = "LicenseNotFound";
+
+    fn action(&self) -> String { "finding the RDFox license".to_string() }
+}
+
+impl From<LicenseNotFound> for ekg_error::Error {
+    fn from(value: LicenseNotFound) -> Self { value.to_error() }
+}
+
+impl LicenseNotFound {
+    /// The [`LicenseNotFound`] error that the given error was made from, if
+    /// any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
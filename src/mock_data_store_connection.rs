@@ -0,0 +1,293 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{
+        CsvSerializer,
+        DataStoreConnectionLike,
+        Parameters,
+        ResultSerializer,
+        SelectResult,
+        SparqlJsonSerializer,
+        Statement,
+        Term,
+    },
+    ekg_namespace::Graph,
+    mime::Mime,
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::Mutex,
+    },
+};
+
+/// A [`DataStoreConnectionLike`] (feature `test-util`) without RDFox behind
+/// it, for unit tests of code that is written against that trait.
+///
+/// Each `SELECT` query (whether evaluated with
+/// [`DataStoreConnectionLike::select`], `consume`, `count`,
+/// `evaluate_to_string` or `evaluate_to_stream`) gets the next result given
+/// to [`MockDataStoreConnection::with_rows`], in order, every row with
+/// multiplicity one, and the text of every statement is recorded. Imports,
+/// also from buffers and files, are recorded as text. There are no cursors,
+/// so [`Statement::cursor`] fails on a mock:
+///
+/// ```
+/// use {
+///     rdfox_rs::{
+///         DataStoreConnectionLike,
+///         MockDataStoreConnection,
+///         Namespaces,
+///         Parameters,
+///         Statement,
+///         Term,
+///     },
+///     std::sync::Arc,
+/// };
+///
+/// /// The downstream function under test
+/// fn labels(
+///     connection: &impl DataStoreConnectionLike,
+///     prefixes: &Arc<Namespaces>,
+/// ) -> Result<Vec<String>, ekg_error::Error> {
+///     let statement = Statement::new(prefixes, "SELECT ?label { ?s rdfs:label ?label }".into())?;
+///     Ok(connection
+///         .select(&statement, &Parameters::empty()?)?
+///         .solutions
+///         .iter()
+///         .filter_map(|solution| {
+///             match solution.get("label") {
+///                 Some(Term::Literal { lexical_form, .. }) => Some(lexical_form.clone()),
+///                 _ => None,
+///             }
+///         })
+///         .collect())
+/// }
+///
+/// let connection = MockDataStoreConnection::new("test").with_rows(
+///     &["label"],
+///     vec![vec![Some(Term::string("one"))], vec![Some(Term::string("two"))]],
+/// );
+/// let prefixes = Namespaces::default_namespaces()?;
+/// assert_eq!(labels(&connection, &prefixes)?, vec!["one", "two"]);
+/// let executed = connection.executed_statements();
+/// assert!(executed[0].ends_with("SELECT ?label { ?s rdfs:label ?label }"));
+/// # Ok::<(), ekg_error::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct MockDataStoreConnection {
+    name:     String,
+    results:  Mutex<VecDeque<(Vec<String>, Vec<Vec<Option<Term>>>)>>,
+    executed: Mutex<Vec<String>>,
+    imported: Mutex<Vec<(Graph, String)>>,
+}
+
+impl MockDataStoreConnection {
+    pub fn new(data_store_name: &str) -> Self {
+        Self {
+            name:     data_store_name.to_string(),
+            results:  Mutex::default(),
+            executed: Mutex::default(),
+            imported: Mutex::default(),
+        }
+    }
+
+    /// Add the result of the next query that has no result yet: rows with
+    /// the values of the given variables (without `?`), `None` when unbound
+    pub fn with_rows(self, variables: &[&str], rows: Vec<Vec<Option<Term>>>) -> Self {
+        self.push_rows(variables, rows);
+        self
+    }
+
+    /// Like [`MockDataStoreConnection::with_rows`], for a mock that's
+    /// already in use
+    pub fn push_rows(&self, variables: &[&str], rows: Vec<Vec<Option<Term>>>) {
+        let variables = variables.iter().map(|variable| variable.to_string()).collect();
+        self.results.lock().unwrap().push_back((variables, rows));
+    }
+
+    /// The text (with its prefixes) of every statement evaluated so far,
+    /// in order
+    pub fn executed_statements(&self) -> Vec<String> { self.executed.lock().unwrap().clone() }
+
+    /// The graph and content of every import so far, in order
+    pub fn imports(&self) -> Vec<(Graph, String)> { self.imported.lock().unwrap().clone() }
+
+    fn next_result(
+        &self,
+        statement: &Statement,
+    ) -> Result<(Vec<String>, Vec<Vec<Option<Term>>>), ekg_error::Error> {
+        self.executed.lock().unwrap().push(statement.as_str().to_string());
+        self.results.lock().unwrap().pop_front().ok_or_else(|| {
+            ekg_error::Error::Exception {
+                action:  format!("Evaluating a query in mock data store {}", self.name),
+                message: "no rows left, see MockDataStoreConnection::with_rows".to_string(),
+            }
+        })
+    }
+}
+
+impl DataStoreConnectionLike for MockDataStoreConnection {
+    fn data_store_name(&self) -> &str { self.name.as_str() }
+
+    fn select(
+        &self,
+        statement: &Statement,
+        _parameters: &Parameters,
+    ) -> Result<SelectResult, ekg_error::Error> {
+        let (variables, rows) = self.next_result(statement)?;
+        let solutions = rows
+            .into_iter()
+            .map(|row| {
+                variables
+                    .iter()
+                    .zip(row)
+                    .filter_map(|(variable, value)| value.map(|term| (variable.clone(), term)))
+                    .collect::<HashMap<_, _>>()
+            })
+            .collect();
+        Ok(SelectResult { variables, solutions })
+    }
+
+    fn update(
+        &self,
+        statement: &Statement,
+        _parameters: &Parameters,
+    ) -> Result<(), ekg_error::Error> {
+        self.executed.lock().unwrap().push(statement.as_str().to_string());
+        Ok(())
+    }
+
+    fn import_data_from_str(
+        &self,
+        content: &str,
+        _format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        self.imported.lock().unwrap().push((graph.clone(), content.to_string()));
+        Ok(())
+    }
+
+    /// Writes the next result as SPARQL results JSON or CSV
+    fn evaluate_to_string(
+        &self,
+        statement: &Statement,
+        mime_type: &'static Mime,
+    ) -> Result<String, ekg_error::Error> {
+        let (variables, rows) = self.next_result(statement)?;
+        let write = |serializer: &mut dyn ResultSerializer| {
+            serializer.start(variables.as_slice())?;
+            for row in rows.iter() {
+                serializer.row(row.as_slice(), 1)?;
+            }
+            serializer.finish()
+        };
+        let bytes = match mime_type.essence_str() {
+            "application/sparql-results+json" => {
+                let mut serializer = SparqlJsonSerializer::new(Vec::new());
+                write(&mut serializer)?;
+                serializer.into_inner()
+            },
+            "text/csv" => {
+                let mut serializer = CsvSerializer::new(Vec::new());
+                write(&mut serializer)?;
+                serializer.into_inner()
+            },
+            _ => {
+                return Err(ekg_error::Error::Exception {
+                    action:  format!("Evaluating a query in mock data store {}", self.name),
+                    message: format!("{mime_type} is not supported by the mock"),
+                })
+            },
+        };
+        Ok(String::from_utf8(bytes).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::MockDataStoreConnection,
+        crate::{DataStoreConnectionLike, Namespaces, Parameters, Statement, Term},
+        ekg_namespace::{consts::TEXT_TURTLE, Graph, Namespace},
+        iref::Iri,
+        mime::Mime,
+        std::path::Path,
+    };
+
+    lazy_static::lazy_static! {
+        static ref TEXT_CSV: Mime = mime::TEXT_CSV;
+    }
+
+    #[test_log::test]
+    fn test_mock() {
+        let prefixes = Namespaces::empty().unwrap();
+        let statement = Statement::new(&prefixes, "SELECT ?s ?o { ?s ?p ?o }".into()).unwrap();
+        let connection = MockDataStoreConnection::new("mock")
+            .with_rows(&["s", "o"], vec![vec![
+                Some(Term::iri("https://ekgf.org/a").unwrap()),
+                None,
+            ]])
+            .with_rows(&["s"], vec![vec![Some(Term::string("a, b"))]]);
+        let result = connection.select(&statement, &Parameters::empty().unwrap()).unwrap();
+        assert_eq!(result.variables, vec!["s", "o"]);
+        assert_eq!(result.solutions.len(), 1);
+        assert!(!result.solutions[0].contains_key("o"));
+        assert_eq!(
+            connection.evaluate_to_string(&statement, &TEXT_CSV).unwrap(),
+            "s\r\n\"a, b\"\r\n"
+        );
+        assert!(connection.select(&statement, &Parameters::empty().unwrap()).is_err());
+        assert_eq!(connection.executed_statements().len(), 3);
+    }
+
+    #[test_log::test]
+    fn test_mock_consume_and_stream() {
+        let prefixes = Namespaces::empty().unwrap();
+        let statement = Statement::new(&prefixes, "SELECT ?s { ?s ?p ?o }".into()).unwrap();
+        let rows = vec![vec![Some(Term::string("a"))], vec![None], vec![Some(Term::string("c"))]];
+        let connection = MockDataStoreConnection::new("mock")
+            .with_rows(&["s"], rows.clone())
+            .with_rows(&["s"], rows.clone())
+            .with_rows(&["s"], rows);
+        let parameters = Parameters::empty().unwrap();
+
+        let mut consumed = Vec::new();
+        let mut collect = |row: &[Option<Term>], multiplicity: usize| {
+            consumed.push((row.to_vec(), multiplicity));
+            Ok::<(), ekg_error::Error>(())
+        };
+        assert_eq!(connection.consume(&statement, &parameters, 2, &mut collect).unwrap(), 2);
+        assert_eq!(consumed, vec![
+            (vec![Some(Term::string("a"))], 1),
+            (vec![None], 1)
+        ]);
+        assert_eq!(connection.count(&statement, &parameters).unwrap(), 3);
+
+        let mut csv = Vec::new();
+        connection.evaluate_to_stream(&mut csv, &statement, &TEXT_CSV).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "s\r\na\r\n\r\nc\r\n");
+        assert!(statement.cursor(&connection, &parameters).is_err());
+    }
+
+    #[test_log::test]
+    fn test_mock_import() {
+        let graph = Graph::declare(
+            Namespace::declare_iref_iri("graph:", Iri::new("https://ekgf.org/graph/").unwrap())
+                .unwrap(),
+            "mock",
+        );
+        let connection = MockDataStoreConnection::new("mock");
+        connection
+            .import_data_from_buffer(b"<a> <b> <c> .", &TEXT_TURTLE, &graph)
+            .unwrap();
+        connection
+            .import_data_from_file(Path::new("tests/formats/triples.ttl"), &graph)
+            .unwrap();
+        let imports = connection.imports();
+        assert_eq!(imports.len(), 2);
+        assert_eq!(imports[0].1, "<a> <b> <c> .");
+        assert!(connection
+            .import_data_from_buffer(&[0xff, 0xfe], &TEXT_TURTLE, &graph)
+            .is_err());
+    }
+}
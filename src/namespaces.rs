@@ -11,6 +11,7 @@ use {
             CPrefixes_destroy,
             CPrefixes_newDefaultPrefixes,
         },
+        sparql_text::prefix_declarations,
     },
     ekg_namespace::{
         Class,
@@ -18,7 +19,7 @@ use {
         Namespace,
         Predicate,
     },
-    iref::iri::Iri,
+    iref::{iri::Iri, IriBuf},
     std::{
         collections::HashMap,
         ffi::CString,
@@ -28,6 +29,9 @@ use {
     },
 };
 
+/// The namespaces (prefixes) of statements. Its `CPrefixes` belongs to the
+/// RDFox library rather than to a [`Server`](crate::Server), so it stays
+/// valid after the server has been stopped.
 #[derive(Debug)]
 pub struct Namespaces {
     inner: *mut CPrefixes,
@@ -89,6 +93,42 @@ impl Namespaces {
             .add_namespace(PREFIX_XSD.deref())
     }
 
+    /// The namespaces declared with `@prefix` (or `PREFIX`) in the given
+    /// Turtle text, like the prelude of a `prefixes.ttl` file
+    pub fn from_turtle_prelude(turtle: &str) -> Result<Arc<Self>, ekg_error::Error> {
+        Self::from_declarations(prefix_declarations(turtle, true))
+    }
+
+    /// The namespaces declared with `PREFIX` in the given SPARQL text
+    pub fn from_sparql(sparql: &str) -> Result<Arc<Self>, ekg_error::Error> {
+        Self::from_declarations(prefix_declarations(sparql, false))
+    }
+
+    /// Fails if the same prefix name is declared twice with different IRIs
+    fn from_declarations(declarations: Vec<(&str, &str)>) -> Result<Arc<Self>, ekg_error::Error> {
+        let namespaces = Self::empty()?;
+        for (name, iri) in declarations {
+            let error = |message: String| {
+                ekg_error::Error::Exception {
+                    action: format!("declaring prefix {name} for namespace <{iri}>"),
+                    message,
+                }
+            };
+            if let Some(namespace) = namespaces.get(name) {
+                if namespace.iri.as_str() != iri {
+                    return Err(error(format!(
+                        "{name} has been declared for namespace <{}> already",
+                        namespace.iri.as_str()
+                    )));
+                }
+                continue;
+            }
+            let iri = Iri::new(iri).map_err(|_| error("not a valid IRI".to_string()))?;
+            namespaces.declare(name, iri)?;
+        }
+        Ok(namespaces)
+    }
+
     pub fn declare_namespace(
         self: &Arc<Self>,
         namespace: &Namespace,
@@ -185,6 +225,84 @@ impl Namespaces {
         Ok(())
     }
 
+    /// The namespace with the given prefix name, with or without the
+    /// trailing colon, like `skos:` or `skos`
+    pub fn get(&self, name: &str) -> Option<Namespace> {
+        let name = name.trim_end_matches(':');
+        let map = self.map.lock().unwrap();
+        map.get(format!("{name}:").as_str())
+            .or_else(|| map.get(name))
+            .cloned()
+    }
+
+    /// Expand the given CURIE, like `skos:Concept`, into a full IRI
+    pub fn expand(&self, curie: &str) -> Result<IriBuf, ekg_error::Error> {
+        let error = |message: String| {
+            ekg_error::Error::Exception { action: format!("expanding {curie}"), message }
+        };
+        let (name, local_name) = curie
+            .split_once(':')
+            .ok_or_else(|| error("not a CURIE, there is no colon".to_string()))?;
+        let namespace = self
+            .get(name)
+            .ok_or_else(|| error(format!("unknown prefix {name}:")))?;
+        IriBuf::new(format!("{}{local_name}", namespace.iri.as_str()))
+            .map_err(|_| error("the result is not a valid IRI".to_string()))
+    }
+
+    /// Shorten the given IRI to a CURIE using the namespace with the longest
+    /// IRI that it starts with and that leaves a valid local name, or `None`
+    /// if there is no such namespace.
+    pub fn shorten(&self, iri: &Iri) -> Option<String> {
+        let iri = iri.as_str();
+        let map = self.map.lock().unwrap();
+        let mut candidates = map
+            .values()
+            .filter_map(|namespace| {
+                iri.strip_prefix(namespace.iri.as_str())
+                    .filter(|local_name| Self::is_local_name(local_name))
+                    .map(|local_name| (namespace, local_name))
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(namespace, _)| std::cmp::Reverse(namespace.iri.as_str().len()));
+        candidates.first().map(|(namespace, local_name)| {
+            format!("{}:{local_name}", namespace.name.trim_end_matches(':'))
+        })
+    }
+
+    /// The name (without colon) of the namespace with exactly the given IRI
+    pub(crate) fn name_of(&self, namespace_iri: &str) -> Option<String> {
+        self.map
+            .lock()
+            .unwrap()
+            .values()
+            .find(|namespace| namespace.iri.as_str() == namespace_iri)
+            .map(|namespace| namespace.name.trim_end_matches(':').to_string())
+    }
+
+    /// Whether the given text can be the local name of a CURIE (a
+    /// conservative subset of what Turtle and SPARQL allow)
+    pub(crate) fn is_local_name(local_name: &str) -> bool {
+        !local_name.ends_with('.') &&
+            !local_name.starts_with(['.', '-']) &&
+            local_name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    }
+
+    /// The `PREFIX` declarations of all namespaces (in SPARQL format) except
+    /// the ones with the given names
+    pub(crate) fn sparql_prelude_except(&self, names: &[&str]) -> String {
+        let mut prelude = String::new();
+        for namespace in self.map.lock().unwrap().values() {
+            let name = namespace.name.trim_end_matches(':');
+            if !names.iter().any(|other| other.trim_end_matches(':') == name) {
+                prelude.push_str(format!("PREFIX {namespace}\n").as_str());
+            }
+        }
+        prelude
+    }
+
     pub fn c_ptr(&self) -> *const CPrefixes { self.inner }
 
     pub fn c_mut_ptr(&self) -> *mut CPrefixes { self.inner }
@@ -216,3 +334,113 @@ impl<'a> NamespacesBuilder {
         Ok(to_build)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::Namespaces,
+        ekg_namespace::Namespace,
+        iref::Iri,
+    };
+
+    fn namespaces() -> std::sync::Arc<Namespaces> {
+        let namespace = |name: &str, iri: &str| {
+            Namespace::declare_iref_iri(name, Iri::new(iri).unwrap()).unwrap()
+        };
+        Namespaces::empty()
+            .unwrap()
+            .add_namespace(&namespace("skos:", "http://www.w3.org/2004/02/skos/core#"))
+            .unwrap()
+            .add_namespace(&namespace("ekgf:", "https://ekgf.org/"))
+            .unwrap()
+            .add_namespace(&namespace("story:", "https://ekgf.org/ontology/user-story/"))
+            .unwrap()
+    }
+
+    #[test_log::test]
+    fn test_get() {
+        let namespaces = namespaces();
+        let skos = namespaces.get("skos:").unwrap();
+        assert_eq!(skos.iri.as_str(), "http://www.w3.org/2004/02/skos/core#");
+        assert_eq!(namespaces.get("skos").unwrap().iri.as_str(), skos.iri.as_str());
+        assert!(namespaces.get("owl:").is_none());
+    }
+
+    #[test_log::test]
+    fn test_expand() {
+        let namespaces = namespaces();
+        assert_eq!(
+            namespaces.expand("skos:prefLabel").unwrap().as_str(),
+            "http://www.w3.org/2004/02/skos/core#prefLabel"
+        );
+        assert_eq!(
+            namespaces.expand("story:UserStory").unwrap().as_str(),
+            "https://ekgf.org/ontology/user-story/UserStory"
+        );
+        assert!(namespaces.expand("owl:Thing").is_err());
+        assert!(namespaces.expand("no-colon").is_err());
+    }
+
+    #[test_log::test]
+    fn test_shorten() {
+        let namespaces = namespaces();
+        let shorten = |iri: &str| namespaces.shorten(Iri::new(iri).unwrap());
+        // `#` and `/` terminated namespaces
+        assert_eq!(
+            shorten("http://www.w3.org/2004/02/skos/core#Concept").as_deref(),
+            Some("skos:Concept")
+        );
+        // The longest namespace wins over the ones that are a prefix of it
+        assert_eq!(
+            shorten("https://ekgf.org/ontology/user-story/UserStory").as_deref(),
+            Some("story:UserStory")
+        );
+        assert_eq!(shorten("https://ekgf.org/thing").as_deref(), Some("ekgf:thing"));
+        // A local name with a slash is not a valid one
+        assert_eq!(shorten("https://ekgf.org/ontology/other/Thing"), None);
+        // Not covered by any namespace
+        assert_eq!(shorten("http://example.com/thing"), None);
+    }
+
+    #[test_log::test]
+    fn test_from_turtle_prelude() {
+        let namespaces = Namespaces::from_turtle_prelude(concat!(
+            "@prefix skos: <http://www.w3.org/2004/02/skos/core#> .\n",
+            "# @prefix skos: <https://ekgf.org/not-skos/> .\n",
+            "PREFIX ekgf: <https://ekgf.org/>\n",
+            "@prefix skos: <http://www.w3.org/2004/02/skos/core#> .\n",
+            "ekgf:thing skos:prefLabel \"@prefix no: <https://ekgf.org/no/> .\" .\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            namespaces.expand("skos:Concept").unwrap().as_str(),
+            "http://www.w3.org/2004/02/skos/core#Concept"
+        );
+        assert!(namespaces.get("ekgf:").is_some());
+        assert!(namespaces.get("no:").is_none());
+
+        let conflict = Namespaces::from_turtle_prelude(concat!(
+            "@prefix ekgf: <https://ekgf.org/> .\n",
+            "@prefix ekgf: <https://ekgf.org/ontology/> .\n",
+        ));
+        assert!(conflict.is_err());
+    }
+
+    #[test_log::test]
+    fn test_from_sparql() {
+        let namespaces = Namespaces::from_sparql(
+            "PREFIX story: <https://ekgf.org/ontology/user-story/>\nSELECT * WHERE { ?s a \
+             story:UserStory }",
+        )
+        .unwrap();
+        assert_eq!(
+            namespaces.shorten(Iri::new("https://ekgf.org/ontology/user-story/UserStory").unwrap()),
+            Some("story:UserStory".to_string())
+        );
+        // Turtle's @prefix is not SPARQL
+        assert!(Namespaces::from_sparql("@prefix ekgf: <https://ekgf.org/> .")
+            .unwrap()
+            .get("ekgf:")
+            .is_none());
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::{
+        ffi::CString,
+        fmt::{Display, Formatter},
+        path::{Path, PathBuf},
+    },
+};
+
+/// A path can't be given to RDFox because it's not valid UTF-8, which RDFox
+/// needs on platforms other than Unix.
+/// Get it back from an `ekg_error::Error` with [`NonUtf8Path::from_error`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonUtf8Path {
+    pub path: PathBuf,
+}
+
+impl Display for NonUtf8Path {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NonUtf8Path: {} is not valid UTF-8", self.path.display())
+    }
+}
+
+impl std::error::Error for NonUtf8Path {}
+
+impl TypedError for NonUtf8Path {
+    const NAME: &'static str = "NonUtf8Path";
+
+    fn action(&self) -> String { "passing a path to RDFox".to_string() }
+}
+
+impl From<NonUtf8Path> for ekg_error::Error {
+    fn from(value: NonUtf8Path) -> Self { value.to_error() }
+}
+
+impl NonUtf8Path {
+    /// The [`NonUtf8Path`] error that the given error was made from, if
+    /// any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+
+    /// The given path as a C string for RDFox: its bytes as they are on
+    /// Unix, elsewhere its UTF-8 text (failing with [`NonUtf8Path`])
+    pub(crate) fn c_string(path: &Path) -> Result<CString, ekg_error::Error> {
+        #[cfg(unix)]
+        let c_string = {
+            use std::os::unix::ffi::OsStrExt;
+            CString::new(path.as_os_str().as_bytes())?
+        };
+        #[cfg(not(unix))]
+        let c_string = Self::utf8_c_string(path)?;
+        Ok(c_string)
+    }
+
+    #[cfg_attr(unix, allow(dead_code))]
+    fn utf8_c_string(path: &Path) -> Result<CString, ekg_error::Error> {
+        let text = path
+            .to_str()
+            .ok_or_else(|| NonUtf8Path { path: path.to_path_buf() })?;
+        Ok(CString::new(text)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::NonUtf8Path, std::path::Path};
+
+    #[test_log::test]
+    fn test_c_string() {
+        let path = Path::new("tests/formats/triples.ttl");
+        assert_eq!(
+            NonUtf8Path::c_string(path).unwrap().to_str().unwrap(),
+            "tests/formats/triples.ttl"
+        );
+        assert_eq!(
+            NonUtf8Path::utf8_c_string(path).unwrap(),
+            NonUtf8Path::c_string(path).unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test_log::test]
+    fn test_non_utf8_path() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        let path = Path::new(OsStr::from_bytes(b"tests/caf\xe9.ttl"));
+        assert_eq!(NonUtf8Path::c_string(path).unwrap().as_bytes(), b"tests/caf\xe9.ttl");
+        let err = NonUtf8Path::utf8_c_string(path).unwrap_err();
+        assert_eq!(NonUtf8Path::from_error(&err), Some(NonUtf8Path { path: path.to_path_buf() }));
+    }
+}
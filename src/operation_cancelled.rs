@@ -0,0 +1,46 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{typed_error::TypedError, OperationKind},
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// The operation was stopped because it was cancelled with
+/// [`DataStoreConnection::cancel_operation`](crate::DataStoreConnection::cancel_operation).
+/// Get it back from an `ekg_error::Error` with
+/// [`OperationCancelled::from_error`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationCancelled {
+    /// The identifier of the operation, see
+    /// [`OperationInfo::id`](crate::OperationInfo::id)
+    pub id:   u64,
+    pub kind: OperationKind,
+}
+
+impl Display for OperationCancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OperationCancelled: {} operation #{} was cancelled", self.kind, self.id)
+    }
+}
+
+impl std::error::Error for OperationCancelled {}
+
+impl TypedError for OperationCancelled {
+    const NAME: &'static str = "OperationCancelled";
+
+    fn action(&self) -> String { format!("running {} operation #{}", self.kind, self.id) }
+}
+
+impl From<OperationCancelled> for ekg_error::Error {
+    fn from(value: OperationCancelled) -> Self { value.to_error() }
+}
+
+impl OperationCancelled {
+    /// The [`OperationCancelled`] error that the given error was made from,
+    /// if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
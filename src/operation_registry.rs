@@ -0,0 +1,249 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{
+        prepared_cursors::PreparedCursors,
+        sparql_text::abbreviate,
+        statement::MAX_TOKEN_SIZE_IN_LOG,
+        OperationCancelled,
+        Statement,
+    },
+    ekg_namespace::consts::LOG_TARGET_DATABASE,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::HashMap,
+        fmt::{Display, Formatter},
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc,
+            Mutex,
+            MutexGuard,
+        },
+        time::SystemTime,
+    },
+};
+
+/// The number of separately locked parts of an [`OperationRegistry`], so
+/// that operations starting and ending at the same time hardly ever wait
+/// for each other
+const SHARDS: usize = 8;
+
+/// What kind of statement execution an in-flight operation is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OperationKind {
+    /// Reading the rows of a cursor
+    Query,
+    /// Evaluating an update statement
+    Update,
+    /// Streaming the results of a statement to a writer
+    Stream,
+}
+
+impl OperationKind {
+    /// Whether an operation of this kind can be cancelled: queries and
+    /// streams stop at their next row or chunk of data, but an update runs
+    /// in one call into RDFox that can't be interrupted
+    pub fn is_cancellable(self) -> bool {
+        match self {
+            OperationKind::Query | OperationKind::Stream => true,
+            OperationKind::Update => false,
+        }
+    }
+}
+
+impl Display for OperationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OperationKind::Query => write!(f, "query"),
+            OperationKind::Update => write!(f, "update"),
+            OperationKind::Stream => write!(f, "stream"),
+        }
+    }
+}
+
+/// An operation that is running on a
+/// [`DataStoreConnection`](crate::DataStoreConnection), see
+/// [`DataStoreConnection::active_operations`](crate::DataStoreConnection::active_operations).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationInfo {
+    /// The identifier to pass to
+    /// [`DataStoreConnection::cancel_operation`](crate::DataStoreConnection::cancel_operation)
+    pub id:          u64,
+    pub kind:        OperationKind,
+    /// The fingerprint (hash) of the text of the statement
+    pub fingerprint: u64,
+    /// The text of the statement, with overly long tokens abbreviated
+    pub statement:   String,
+    pub started_at:  SystemTime,
+    /// Whether it has been asked to stop but did not get round to it yet
+    pub cancelled:   bool,
+}
+
+#[derive(Debug)]
+struct Operation {
+    info:      OperationInfo,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// The operations in flight on a connection, each one registered for as
+/// long as its [`OperationGuard`] lives.
+#[derive(Debug, Default)]
+pub(crate) struct OperationRegistry {
+    last_id: AtomicU64,
+    shards:  [Mutex<HashMap<u64, Operation>>; SHARDS],
+}
+
+impl OperationRegistry {
+    fn shard(&self, id: u64) -> MutexGuard<'_, HashMap<u64, Operation>> {
+        self.shards[id as usize % SHARDS]
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Register the execution of the given statement until the returned
+    /// guard is dropped, which also happens when unwinding from a panic.
+    pub(crate) fn register(
+        self: &Arc<Self>,
+        kind: OperationKind,
+        statement: &Statement,
+    ) -> OperationGuard {
+        let id = self.last_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let info = OperationInfo {
+            id,
+            kind,
+            fingerprint: PreparedCursors::fingerprint(statement.as_str()),
+            statement: abbreviate(statement.as_str(), MAX_TOKEN_SIZE_IN_LOG).into_owned(),
+            started_at: SystemTime::now(),
+            cancelled: false,
+        };
+        self.shard(id).insert(id, Operation { info, cancelled: cancelled.clone() });
+        OperationGuard { registry: self.clone(), id, kind, cancelled }
+    }
+
+    /// All operations in flight, in the order in which they started
+    pub(crate) fn active(&self) -> Vec<OperationInfo> {
+        let mut active = (0..SHARDS as u64)
+            .flat_map(|shard| {
+                self.shard(shard)
+                    .values()
+                    .map(|operation| {
+                        OperationInfo {
+                            cancelled: operation.cancelled.load(Ordering::Relaxed),
+                            ..operation.info.clone()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        active.sort_by_key(|info| info.id);
+        active
+    }
+
+    /// Ask the operation with the given identifier to stop, returns false
+    /// if there is no such operation (anymore) or if it can't be cancelled
+    /// (see [`OperationKind::is_cancellable`])
+    pub(crate) fn cancel(&self, id: u64) -> bool {
+        match self.shard(id).get(&id) {
+            Some(operation) if !operation.info.kind.is_cancellable() => {
+                tracing::warn!(
+                    target: LOG_TARGET_DATABASE,
+                    "Cannot cancel {} operation #{id}",
+                    operation.info.kind
+                );
+                false
+            },
+            Some(operation) => {
+                tracing::warn!(
+                    target: LOG_TARGET_DATABASE,
+                    "Cancelling {} operation #{id}",
+                    operation.info.kind
+                );
+                operation.cancelled.store(true, Ordering::Relaxed);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+/// Keeps an operation registered in its [`OperationRegistry`] until it's
+/// dropped.
+#[derive(Debug)]
+pub(crate) struct OperationGuard {
+    registry:  Arc<OperationRegistry>,
+    id:        u64,
+    kind:      OperationKind,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) { self.registry.shard(self.id).remove(&self.id); }
+}
+
+impl OperationGuard {
+    /// The flag that is raised when the operation is cancelled
+    pub(crate) fn cancelled(&self) -> Arc<AtomicBool> { self.cancelled.clone() }
+
+    /// Fail if the operation has been cancelled, to be called regularly by
+    /// the operation itself
+    pub(crate) fn check(&self) -> Result<(), ekg_error::Error> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(OperationCancelled { id: self.id, kind: self.kind }.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{OperationKind, OperationRegistry},
+        crate::{Namespaces, OperationCancelled, Statement},
+        std::sync::Arc,
+    };
+
+    #[test_log::test]
+    fn test_operation_registry() {
+        let registry = Arc::new(OperationRegistry::default());
+        let statement =
+            Statement::new(&Namespaces::empty().unwrap(), "SELECT * WHERE { ?s ?p ?o }".into())
+                .unwrap();
+        let query = registry.register(OperationKind::Query, &statement);
+        let update = registry.register(OperationKind::Update, &statement);
+        let active = registry.active();
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[0].kind, OperationKind::Query);
+        assert_eq!(active[1].kind, OperationKind::Update);
+        assert_eq!(active[0].fingerprint, active[1].fingerprint);
+        assert!(active[0].statement.contains("SELECT * WHERE"));
+
+        assert!(query.check().is_ok());
+        assert!(registry.cancel(active[0].id));
+        assert!(registry.active()[0].cancelled);
+        assert_eq!(
+            OperationCancelled::from_error(&query.check().unwrap_err()),
+            Some(OperationCancelled { id: active[0].id, kind: OperationKind::Query })
+        );
+        // An update can't be cancelled
+        assert!(!registry.cancel(active[1].id));
+        assert!(!registry.active()[1].cancelled);
+        assert!(update.check().is_ok());
+
+        drop(query);
+        assert_eq!(registry.active().len(), 1);
+        assert!(!registry.cancel(active[0].id));
+
+        // Also deregistered when the operation panics
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _stream = registry.register(OperationKind::Stream, &statement);
+            assert_eq!(registry.active().len(), 2);
+            panic!("operation failed");
+        }));
+        assert!(result.is_err());
+        assert_eq!(registry.active().len(), 1);
+        drop(update);
+        assert!(registry.active().is_empty());
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+//! Conversions (feature `oxrdf-interop`) between [`Term`] and the terms of
+//! the [`oxrdf`] crate. Literals typed `xsd:string` are plain literals on
+//! both sides.
+
+use {
+    crate::Term,
+    iref::{Iri, IriBuf},
+};
+
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+fn conversion_error(what: &str, message: String) -> ekg_error::Error {
+    ekg_error::Error::Exception { action: format!("converting {what}"), message }
+}
+
+fn iri_buf(iri: &str) -> Result<IriBuf, ekg_error::Error> {
+    IriBuf::new(iri.to_string()).map_err(|_| {
+        conversion_error("an oxrdf term", format!("<{iri}> is not a valid IRI"))
+    })
+}
+
+impl TryFrom<&Term> for oxrdf::Term {
+    type Error = ekg_error::Error;
+
+    fn try_from(term: &Term) -> Result<Self, Self::Error> {
+        let error = |err: &dyn std::fmt::Display| {
+            conversion_error(format!("{term}").as_str(), err.to_string())
+        };
+        match term {
+            Term::Iri(iri) => {
+                Ok(oxrdf::NamedNode::new(iri.as_str())
+                    .map_err(|err| error(&err))?
+                    .into())
+            }
+            Term::BlankNode(label) => {
+                Ok(oxrdf::BlankNode::new(label.as_str())
+                    .map_err(|err| error(&err))?
+                    .into())
+            }
+            Term::Literal { lexical_form, datatype, language } => {
+                let literal = match (language, datatype) {
+                    (Some(language), _) => {
+                        oxrdf::Literal::new_language_tagged_literal(
+                            lexical_form.as_str(),
+                            language.as_str(),
+                        )
+                        .map_err(|err| error(&err))?
+                    }
+                    (None, Some(datatype)) if datatype.as_str() != XSD_STRING => {
+                        oxrdf::Literal::new_typed_literal(
+                            lexical_form.as_str(),
+                            oxrdf::NamedNode::new(datatype.as_str()).map_err(|err| error(&err))?,
+                        )
+                    }
+                    _ => oxrdf::Literal::new_simple_literal(lexical_form.as_str()),
+                };
+                Ok(literal.into())
+            }
+        }
+    }
+}
+
+impl TryFrom<Term> for oxrdf::Term {
+    type Error = ekg_error::Error;
+
+    fn try_from(term: Term) -> Result<Self, Self::Error> { oxrdf::Term::try_from(&term) }
+}
+
+impl From<&oxrdf::NamedNode> for Term {
+    fn from(node: &oxrdf::NamedNode) -> Self {
+        // oxrdf has validated the IRI already
+        Term::Iri(Iri::new(node.as_str()).unwrap().to_owned())
+    }
+}
+
+impl From<&oxrdf::BlankNode> for Term {
+    fn from(node: &oxrdf::BlankNode) -> Self { Term::blank_node(node.as_str()) }
+}
+
+impl TryFrom<&oxrdf::Literal> for Term {
+    type Error = ekg_error::Error;
+
+    fn try_from(literal: &oxrdf::Literal) -> Result<Self, Self::Error> {
+        if let Some(language) = literal.language() {
+            return Ok(Term::language_tagged_literal(literal.value(), language));
+        }
+        let datatype = literal.datatype();
+        if datatype.as_str() == XSD_STRING {
+            Ok(Term::string(literal.value()))
+        } else {
+            Ok(Term::typed_literal(
+                literal.value(),
+                iri_buf(datatype.as_str())?.as_iri(),
+            ))
+        }
+    }
+}
+
+/// Fails for the terms that [`Term`] has no equivalent for, such as quoted
+/// triples
+impl TryFrom<&oxrdf::Term> for Term {
+    type Error = ekg_error::Error;
+
+    #[allow(unreachable_patterns)]
+    fn try_from(term: &oxrdf::Term) -> Result<Self, Self::Error> {
+        match term {
+            oxrdf::Term::NamedNode(node) => Ok(node.into()),
+            oxrdf::Term::BlankNode(node) => Ok(node.into()),
+            oxrdf::Term::Literal(literal) => Term::try_from(literal),
+            term => {
+                Err(conversion_error(
+                    "an oxrdf term",
+                    format!("{term} has no equivalent"),
+                ))
+            }
+        }
+    }
+}
+
+impl TryFrom<oxrdf::Term> for Term {
+    type Error = ekg_error::Error;
+
+    fn try_from(term: oxrdf::Term) -> Result<Self, Self::Error> { Term::try_from(&term) }
+}
+
+#[cfg(test)]
+mod tests {
+    use {crate::Term, proptest::prelude::*};
+
+    fn any_term() -> impl Strategy<Value = Term> {
+        prop_oneof![
+            "[a-z]{1,10}".prop_map(|path| {
+                Term::iri(format!("https://ekgf.org/{path}").as_str()).unwrap()
+            }),
+            "[a-z][a-z0-9]{0,8}".prop_map(|label| Term::blank_node(label.as_str())),
+            any::<String>().prop_map(|value| Term::string(value.as_str())),
+            (any::<String>(), "[a-z]{2}(-[a-z]{2})?").prop_map(|(value, language)| {
+                Term::language_tagged_literal(value.as_str(), language.as_str())
+            }),
+            any::<i64>().prop_map(|value| {
+                Term::typed_literal(
+                    value.to_string().as_str(),
+                    iref::Iri::new("http://www.w3.org/2001/XMLSchema#integer").unwrap(),
+                )
+            }),
+        ]
+    }
+
+    #[test_log::test]
+    fn test_conversions() {
+        let literal = oxrdf::Literal::new_typed_literal("42", oxrdf::vocab::xsd::INTEGER);
+        let term = Term::try_from(oxrdf::Term::from(literal.clone())).unwrap();
+        assert_eq!(term.to_string(), r#""42"^^<http://www.w3.org/2001/XMLSchema#integer>"#);
+        assert_eq!(oxrdf::Term::try_from(&term).unwrap(), literal.into());
+
+        let string = oxrdf::Literal::new_typed_literal("a", oxrdf::vocab::xsd::STRING);
+        assert_eq!(Term::try_from(&string).unwrap(), Term::string("a"));
+
+        assert!(oxrdf::Term::try_from(Term::blank_node("not a label")).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_round_trip(term in any_term()) {
+            let converted = oxrdf::Term::try_from(&term).unwrap();
+            prop_assert_eq!(Term::try_from(&converted).unwrap(), term);
+        }
+    }
+}
@@ -7,6 +7,7 @@ use {
     alloc::ffi::CString,
     crate::{
         database_call,
+        parameters_builder::{Parameter, ParametersBuilder},
         rdfox_api::{
             CParameters,
             CParameters_destroy,
@@ -14,24 +15,33 @@ use {
             CParameters_newEmptyParameters,
             CParameters_setString,
         },
-    }
-    ,
+    },
+    ekg_namespace::consts::LOG_TARGET_DATABASE,
     std::{
+        collections::HashMap,
         ffi::CStr,
-        fmt::{Display, Formatter},
+        fmt::{Debug, Display, Formatter},
         os::raw::c_char,
-        path::Path,
+        path::{Path, PathBuf},
         ptr,
-        sync::Arc,
+        sync::{Arc, Mutex},
+        time::Duration,
     },
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FactDomain {
+    #[cfg_attr(feature = "serde", serde(rename = "explicit"))]
     ASSERTED,
+    #[cfg_attr(feature = "serde", serde(rename = "derived"))]
     INFERRED,
+    #[cfg_attr(feature = "serde", serde(rename = "all"))]
     ALL,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum PersistenceMode {
     File,
     FileSequence,
@@ -48,25 +58,88 @@ impl Display for PersistenceMode {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DataStoreType {
+    #[cfg_attr(feature = "serde", serde(rename = "parallel-nn"))]
     ParallelNN,
+    #[cfg_attr(feature = "serde", serde(rename = "parallel-nw"))]
     ParallelNW,
+    #[cfg_attr(feature = "serde", serde(rename = "parallel-ww"))]
     ParallelWW,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+impl Display for DataStoreType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataStoreType::ParallelNN => write!(f, "parallel-nn"),
+            DataStoreType::ParallelNW => write!(f, "parallel-nw"),
+            DataStoreType::ParallelWW => write!(f, "parallel-ww"),
+        }
+    }
+}
+
+/// Like [`Namespaces`](crate::Namespaces), parameters belong to the RDFox
+/// library rather than to a [`Server`](crate::Server), so they stay valid
+/// after the server has been stopped.
 pub struct Parameters {
     pub(crate) inner: Arc<*mut CParameters>,
+    /// What has been set with [`Parameters::set_string`], since RDFox
+    /// offers no way to list the keys of a `CParameters` object
+    values:           Mutex<HashMap<String, String>>,
 }
 
 unsafe impl Sync for Parameters {}
 
 unsafe impl Send for Parameters {}
 
+/// Parameters are equal when they have the same keys and values
+impl PartialEq for Parameters {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self, other) || self.get_all().eq(other.get_all())
+    }
+}
+
+/// A deep copy: a new `CParameters` object with the same keys and values,
+/// so that each copy destroys its own object when it's dropped
+impl Clone for Parameters {
+    fn clone(&self) -> Self {
+        let clone = Self::empty().expect("Could not allocate parameters");
+        for (key, value) in self.get_all() {
+            clone
+                .set_string(key.as_str(), value.as_str())
+                .expect("Could not copy parameter");
+        }
+        clone
+    }
+}
+
+impl Eq for Parameters {}
+
+/// Show the keys and values (sorted by key), with the values of sensitive
+/// keys such as `license-content` redacted
 impl Display for Parameters {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Parameters[]") // TODO: show keys and values (currently not
-        // possible)
+        write!(f, "Parameters[")?;
+        for (index, (key, value)) in self.get_all().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{key}={}", redact(key.as_str(), value.as_str()))?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl Debug for Parameters {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Parameters ")?;
+        f.debug_map()
+            .entries(self.get_all().map(|(key, value)| {
+                let value = redact(key.as_str(), value.as_str()).to_string();
+                (key, value)
+            }))
+            .finish()
     }
 }
 
@@ -83,37 +156,68 @@ impl Drop for Parameters {
     }
 }
 
-const SENSITIVE_PARAMETERS: [&str; 1] = ["license-content"];
+pub(crate) const SENSITIVE_PARAMETERS: [&str; 1] = ["license-content"];
+
+/// The given value, or `***` if it's the value of a sensitive key
+fn redact<'a>(key: &str, value: &'a str) -> &'a str {
+    if SENSITIVE_PARAMETERS.contains(&key) { "***" } else { value }
+}
 
 impl Parameters {
+    /// Build parameters from typed values, see [`ParametersBuilder`]
+    pub fn builder() -> ParametersBuilder { ParametersBuilder::default() }
+
     pub fn empty() -> Result<Self, ekg_error::Error> {
         let mut parameters: *mut CParameters = ptr::null_mut();
         database_call!(
             "Allocating parameters",
             CParameters_newEmptyParameters(&mut parameters)
         )?;
-        Ok(Parameters { inner: Arc::new(parameters) })
+        Ok(Parameters {
+            inner:  Arc::new(parameters),
+            values: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Parameters with the given keys and values, for instance as read from
+    /// a config file
+    pub fn from_map(map: HashMap<String, String>) -> Result<Self, ekg_error::Error> {
+        let parameters = Self::empty()?;
+        for (key, value) in map.iter() {
+            parameters.set_string(key.as_str(), value.as_str())?;
+        }
+        Ok(parameters)
+    }
+
+    fn values(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>> {
+        self.values.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 
+    /// All keys and values that have been set, sorted by key. Unlike
+    /// `Display`, `Debug` and `Serialize` this does not hide sensitive values.
+    pub fn get_all(&self) -> impl Iterator<Item = (String, String)> {
+        let mut all = self
+            .values()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect::<Vec<_>>();
+        all.sort();
+        all.into_iter()
+    }
+
+    /// Whether the given key has been set
+    pub fn contains_key(&self, key: &str) -> bool { self.values().contains_key(key) }
+
     pub fn set_string(&self, key: &str, value: &str) -> Result<(), ekg_error::Error> {
         let c_key = CString::new(key).unwrap();
         let c_value = CString::new(value).unwrap();
-        let msg = if SENSITIVE_PARAMETERS.contains(&c_key.to_str().unwrap()) {
-            format!(
-                "Setting parameter {}=[***]",
-                c_key.to_str().unwrap(),
-            )
-        } else {
-            format!(
-                "Setting parameter {}=[{}]",
-                c_key.to_str().unwrap(),
-                c_value.to_str().unwrap()
-            )
-        };
+        let msg = format!("Setting parameter {key}=[{}]", redact(key, value));
         database_call!(
             msg.as_str(),
             CParameters_setString(*self.inner, c_key.as_ptr(), c_value.as_ptr())
-        )
+        )?;
+        self.values().insert(key.to_string(), value.to_string());
+        Ok(())
     }
 
     pub fn get_string(&self, key: &str, default: &str) -> Result<String, ekg_error::Error> {
@@ -147,40 +251,55 @@ impl Parameters {
         Ok(self)
     }
 
+    /// Stop the evaluation of a query after the given time, which makes it
+    /// fail with a [`QueryTimedOut`](crate::QueryTimedOut) error
+    pub fn query_timeout(self, timeout: Duration) -> Result<Self, ekg_error::Error> {
+        self.set_string(
+            Parameter::QueryTimeout.name()?,
+            timeout.as_millis().max(1).to_string().as_str(),
+        )?;
+        Ok(self)
+    }
+
     pub fn switch_off_file_access_sandboxing(self) -> Result<Self, ekg_error::Error> {
         self.set_string("sandbox-directory", "")?;
         Ok(self)
     }
 
     pub fn persist_datastore(self, mode: PersistenceMode) -> Result<Self, ekg_error::Error> {
-        #[cfg(feature = "rdfox-7-0")]
-        match mode {
-            PersistenceMode::File => self.set_string("persistence", "file")?,
-            PersistenceMode::FileSequence => self.set_string("persistence", "file-sequence")?,
-            PersistenceMode::Off => self.set_string("persistence", "off")?,
-        };
-        #[cfg(not(feature = "rdfox-7-0"))]
-        match mode {
-            PersistenceMode::File => self.set_string("persist-ds", "file")?,
-            PersistenceMode::FileSequence => self.set_string("persist-ds", "file-sequence")?,
-            PersistenceMode::Off => self.set_string("persist-ds", "off")?,
-        };
+        self.set_string(Parameter::Persistence.name()?, &mode.to_string())?;
         Ok(self)
     }
 
+    /// The persistence that these (server or data store) parameters
+    /// declare, `None` if they don't
+    pub(crate) fn persistence(&self) -> Option<PersistenceMode> {
+        let value = self.get_string(Parameter::Persistence.name().ok()?, "").ok()?;
+        match value.as_str() {
+            "file" => Some(PersistenceMode::File),
+            "file-sequence" => Some(PersistenceMode::FileSequence),
+            "off" => Some(PersistenceMode::Off),
+            _ => None,
+        }
+    }
+
     #[cfg(not(feature = "rdfox-7-0"))]
     pub fn persist_roles(self, mode: PersistenceMode) -> Result<Self, ekg_error::Error> {
-        self.set_string("persist-roles", &mode.to_string())?;
+        self.set_string(Parameter::PersistRoles.name()?, &mode.to_string())?;
         Ok(self)
     }
 
+    /// The directory that the server persists in, see
+    /// [`Server::server_directory`](crate::Server::server_directory)
     pub fn server_directory(self, dir: &Path) -> Result<Self, ekg_error::Error> {
-        if dir.is_dir() {
-            self.set_string("server-directory", dir.to_str().unwrap())?;
-            Ok(self)
-        } else {
-            panic!("{dir:?} is not a directory")
-        }
+        let Some(path) = dir.to_str().filter(|_| dir.is_dir()) else {
+            return Err(ekg_error::Error::Exception {
+                action:  format!("setting parameter {}", Parameter::ServerDirectory),
+                message: format!("{dir:?} is not a directory"),
+            });
+        };
+        self.set_string(Parameter::ServerDirectory.name()?, path)?;
+        Ok(self)
     }
 
     pub fn license_file(self, file: &Path) -> Result<Self, ekg_error::Error> {
@@ -202,15 +321,36 @@ impl Parameters {
         Ok(self)
     }
 
+    /// Use the license found by [`find_license`](crate::find_license) in
+    /// the given directory (or the usual places), fails like it if there is
+    /// none
     pub fn set_license(self, database_dir: Option<&Path>) -> Result<Self, ekg_error::Error> {
-        match super::license::find_license(database_dir)? {
-            (Some(license_file_name), None) => {
-                return self.license_file(license_file_name.as_path());
-            }
-            (None, Some(license_content)) => return self.license_content(license_content.as_str()),
-            _ => {}
-        };
-        Ok(self)
+        let license = super::license::try_find_license(database_dir)?;
+        self.use_license(license)
+    }
+
+    /// Like [`Parameters::set_license`], but without a license only log a
+    /// warning, for an RDFox that doesn't need one (like an evaluation
+    /// build), otherwise the server won't start
+    pub fn allow_unlicensed(self, database_dir: Option<&Path>) -> Result<Self, ekg_error::Error> {
+        match super::license::try_find_license(database_dir) {
+            Ok(license) => self.use_license(license),
+            Err(not_found) => {
+                tracing::warn!(target: LOG_TARGET_DATABASE, "Running unlicensed: {not_found}");
+                Ok(self)
+            },
+        }
+    }
+
+    fn use_license(
+        self,
+        license: (Option<PathBuf>, Option<String>),
+    ) -> Result<Self, ekg_error::Error> {
+        match license {
+            (_, Some(license_content)) => self.license_content(license_content.as_str()),
+            (Some(license_file_name), None) => self.license_file(license_file_name.as_path()),
+            (None, None) => Ok(self),
+        }
     }
 
     pub fn import_rename_user_blank_nodes(self, setting: bool) -> Result<Self, ekg_error::Error> {
@@ -250,11 +390,7 @@ impl Parameters {
     }
 
     pub fn data_store_type(self, data_store_type: DataStoreType) -> Result<Self, ekg_error::Error> {
-        match data_store_type {
-            DataStoreType::ParallelNN => self.set_string("type", "parallel-nn")?,
-            DataStoreType::ParallelNW => self.set_string("type", "parallel-nw")?,
-            DataStoreType::ParallelWW => self.set_string("type", "parallel-ww")?,
-        }
+        self.set_string(Parameter::DataStoreType.name()?, &data_store_type.to_string())?;
         Ok(self)
     }
 }
@@ -268,4 +404,53 @@ mod tests {
         let value = params.get_string("key1", "whatever").unwrap();
         assert_eq!(value, "value1");
     }
+
+    #[test_log::test]
+    fn test_introspection() {
+        let params = crate::Parameters::empty().unwrap();
+        params.set_string("key2", "value2").unwrap();
+        params.set_string("key1", "value1").unwrap();
+        params.set_string("license-content", "secret").unwrap();
+        assert!(params.contains_key("key1"));
+        assert!(!params.contains_key("key3"));
+        assert_eq!(
+            params.get_all().collect::<Vec<_>>(),
+            vec![
+                ("key1".to_string(), "value1".to_string()),
+                ("key2".to_string(), "value2".to_string()),
+                ("license-content".to_string(), "secret".to_string()),
+            ]
+        );
+        assert_eq!(
+            params.to_string(),
+            "Parameters[key1=value1, key2=value2, license-content=***]"
+        );
+        let debug = format!("{params:?}");
+        assert!(debug.contains("\"key1\": \"value1\""), "{debug}");
+        assert!(!debug.contains("secret"), "{debug}");
+
+        let other = crate::Parameters::empty().unwrap();
+        assert_ne!(params, other);
+        other.set_string("license-content", "secret").unwrap();
+        other.set_string("key1", "value1").unwrap();
+        other.set_string("key2", "value2").unwrap();
+        assert_eq!(params, other);
+    }
+
+    #[test_log::test]
+    fn test_clone() {
+        let params = crate::Parameters::empty().unwrap();
+        params.set_string("key1", "value1").unwrap();
+        let clone = params.clone();
+        assert_ne!(*params.inner, *clone.inner);
+        assert_eq!(params, clone);
+
+        // Changing (or dropping) one does not affect the other
+        clone.set_string("key2", "value2").unwrap();
+        assert!(!params.contains_key("key2"));
+        drop(params);
+        assert_eq!(clone.get_string("key1", "").unwrap(), "value1");
+        assert_eq!(clone.get_string("key2", "").unwrap(), "value2");
+        drop(clone);
+    }
 }
@@ -0,0 +1,325 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{
+        parameters::{DataStoreType, PersistenceMode},
+        typed_error::TypedError,
+        Parameters,
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::{Display, Formatter},
+        num::{NonZeroU64, NonZeroUsize},
+        path::Path,
+    },
+};
+
+/// The RDFox version that has been selected with one of the `rdfox-X-Y`
+/// features
+#[cfg(feature = "rdfox-7-0")]
+pub const RDFOX_VERSION: &str = "7.0";
+#[cfg(all(feature = "rdfox-6-3b", not(feature = "rdfox-7-0")))]
+pub const RDFOX_VERSION: &str = "6.3b";
+#[cfg(all(
+    feature = "rdfox-6-3a",
+    not(any(feature = "rdfox-6-3b", feature = "rdfox-7-0"))
+))]
+pub const RDFOX_VERSION: &str = "6.3a";
+#[cfg(not(any(feature = "rdfox-6-3a", feature = "rdfox-6-3b", feature = "rdfox-7-0")))]
+pub const RDFOX_VERSION: &str = "6.2";
+
+/// The server and data store parameters that [`ParametersBuilder`] knows
+/// about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Parameter {
+    /// The maximum amount of memory (in megabytes) that the server may use
+    MaxMemory,
+    /// The number of threads that the server uses, for instance to import
+    NumThreads,
+    /// How `owl:sameAs` is dealt with
+    Equality,
+    /// The base IRI for relative IRIs in imported content
+    BaseIri,
+    /// The file with RDFox shell commands that initializes a data store
+    InitFile,
+    /// The directory that the server may access files in
+    SandboxDirectory,
+    /// The directory that a server persists its data stores (and roles) in
+    ServerDirectory,
+    /// The directory for the files that the server swaps memory to
+    SwapFileDirectory,
+    /// How a data store is persisted
+    Persistence,
+    /// The type of a data store, see [`DataStoreType`]
+    DataStoreType,
+    /// How roles are persisted
+    PersistRoles,
+    /// How long (in milliseconds) the evaluation of a query may take
+    QueryTimeout,
+}
+
+impl Display for Parameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { write!(f, "{self:?}") }
+}
+
+impl Parameter {
+    /// The name of this parameter in the selected [`RDFOX_VERSION`], this is
+    /// the one place that maps parameters to their names
+    pub fn name(self) -> Result<&'static str, UnsupportedParameterForVersion> {
+        let name = match self {
+            Parameter::MaxMemory => Some("max-memory"),
+            Parameter::NumThreads => Some("num-threads"),
+            Parameter::Equality => Some("equality"),
+            Parameter::BaseIri => Some("base-iri"),
+            Parameter::InitFile => Some("init-file"),
+            Parameter::SandboxDirectory => Some("sandbox-directory"),
+            Parameter::ServerDirectory => Some("server-directory"),
+            Parameter::SwapFileDirectory => Some("swap-file-directory"),
+            Parameter::DataStoreType => Some("type"),
+            #[cfg(feature = "rdfox-7-0")]
+            Parameter::Persistence => Some("persistence"),
+            #[cfg(not(feature = "rdfox-7-0"))]
+            Parameter::Persistence => Some("persist-ds"),
+            #[cfg(feature = "rdfox-7-0")]
+            Parameter::PersistRoles => None,
+            #[cfg(not(feature = "rdfox-7-0"))]
+            Parameter::PersistRoles => Some("persist-roles"),
+            #[cfg(feature = "rdfox-7-0")]
+            Parameter::QueryTimeout => Some("query.time-limit"),
+            #[cfg(not(feature = "rdfox-7-0"))]
+            Parameter::QueryTimeout => Some("query-time-limit"),
+        };
+        name.ok_or_else(|| {
+            UnsupportedParameterForVersion { parameter: self, version: RDFOX_VERSION.to_string() }
+        })
+    }
+}
+
+/// The parameter does not exist in the RDFox version that has been selected
+/// with one of the `rdfox-X-Y` features. Get it back from an
+/// `ekg_error::Error` with [`UnsupportedParameterForVersion::from_error`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsupportedParameterForVersion {
+    pub parameter: Parameter,
+    pub version:   String,
+}
+
+impl Display for UnsupportedParameterForVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "UnsupportedParameterForVersion: RDFox {} does not support parameter {}",
+            self.version, self.parameter
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedParameterForVersion {}
+
+impl TypedError for UnsupportedParameterForVersion {
+    const NAME: &'static str = "UnsupportedParameterForVersion";
+
+    fn action(&self) -> String { format!("setting parameter {}", self.parameter) }
+}
+
+impl From<UnsupportedParameterForVersion> for ekg_error::Error {
+    fn from(value: UnsupportedParameterForVersion) -> Self { value.to_error() }
+}
+
+impl UnsupportedParameterForVersion {
+    /// The [`UnsupportedParameterForVersion`] error that the given error was
+    /// made from, if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
+
+/// How a data store deals with `owl:sameAs`, the value of
+/// [`Parameter::Equality`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqualityMode {
+    /// `owl:sameAs` has no special meaning
+    Off,
+    /// `owl:sameAs` is an equality, without the unique name assumption
+    NoUna,
+    /// `owl:sameAs` is an equality, with the unique name assumption
+    Una,
+}
+
+impl Display for EqualityMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EqualityMode::Off => write!(f, "off"),
+            EqualityMode::NoUna => write!(f, "noUNA"),
+            EqualityMode::Una => write!(f, "UNA"),
+        }
+    }
+}
+
+/// Builds [`Parameters`] from typed values, for instance:
+///
+/// ```no_run
+/// use rdfox_rs::{EqualityMode, Parameters};
+///
+/// let parameters = Parameters::builder()
+///     .equality(EqualityMode::Off)
+///     .base_iri("https://ekgf.org/")
+///     .build()?;
+/// # Ok::<(), ekg_error::Error>(())
+/// ```
+///
+/// Invalid values and parameters that the selected RDFox version does not
+/// support are reported by [`ParametersBuilder::build`].
+#[derive(Debug, Default)]
+pub struct ParametersBuilder {
+    values: Vec<Result<(Parameter, String), ekg_error::Error>>,
+}
+
+impl ParametersBuilder {
+    fn set(mut self, parameter: Parameter, value: impl ToString) -> Self {
+        self.values.push(Ok((parameter, value.to_string())));
+        self
+    }
+
+    fn invalid(mut self, parameter: Parameter, message: String) -> Self {
+        self.values.push(Err(ekg_error::Error::Exception {
+            action: format!("setting parameter {parameter}"),
+            message,
+        }));
+        self
+    }
+
+    fn directory(self, parameter: Parameter, dir: &Path) -> Self {
+        if dir.is_dir() {
+            self.set(parameter, dir.display())
+        } else {
+            self.invalid(parameter, format!("{dir:?} is not a directory"))
+        }
+    }
+
+    pub fn max_memory_mb(self, megabytes: NonZeroU64) -> Self {
+        self.set(Parameter::MaxMemory, megabytes)
+    }
+
+    pub fn num_threads(self, threads: NonZeroUsize) -> Self {
+        self.set(Parameter::NumThreads, threads)
+    }
+
+    pub fn equality(self, mode: EqualityMode) -> Self { self.set(Parameter::Equality, mode) }
+
+    pub fn base_iri(self, iri: &str) -> Self {
+        match iref::Iri::new(iri) {
+            Ok(_) => self.set(Parameter::BaseIri, iri),
+            Err(_) => self.invalid(Parameter::BaseIri, format!("{iri:?} is not an IRI")),
+        }
+    }
+
+    pub fn init_file(self, file: &Path) -> Self {
+        if file.is_file() {
+            self.set(Parameter::InitFile, file.display())
+        } else {
+            self.invalid(Parameter::InitFile, format!("{file:?} does not exist"))
+        }
+    }
+
+    /// An empty path switches off file access sandboxing
+    pub fn sandbox_directory(self, dir: &Path) -> Self {
+        if dir.as_os_str().is_empty() {
+            self.set(Parameter::SandboxDirectory, "")
+        } else {
+            self.directory(Parameter::SandboxDirectory, dir)
+        }
+    }
+
+    pub fn server_directory(self, dir: &Path) -> Self {
+        self.directory(Parameter::ServerDirectory, dir)
+    }
+
+    pub fn swap_file_directory(self, dir: &Path) -> Self {
+        self.directory(Parameter::SwapFileDirectory, dir)
+    }
+
+    pub fn persistence(self, mode: PersistenceMode) -> Self {
+        self.set(Parameter::Persistence, mode)
+    }
+
+    pub fn data_store_type(self, data_store_type: DataStoreType) -> Self {
+        self.set(Parameter::DataStoreType, data_store_type)
+    }
+
+    /// Not supported by RDFox 7.0 and later
+    pub fn persist_roles(self, mode: PersistenceMode) -> Self {
+        self.set(Parameter::PersistRoles, mode)
+    }
+
+    /// The parameters, or the first invalid value or unsupported parameter
+    pub fn build(self) -> Result<Parameters, ekg_error::Error> {
+        let parameters = Parameters::empty()?;
+        for value in self.values {
+            let (parameter, value) = value?;
+            parameters.set_string(parameter.name()?, value.as_str())?;
+        }
+        Ok(parameters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{EqualityMode, Parameter, ParametersBuilder, UnsupportedParameterForVersion},
+        crate::{Parameters, PersistenceMode},
+        std::{num::NonZeroUsize, path::Path},
+    };
+
+    #[test_log::test]
+    fn test_builder() {
+        let parameters = Parameters::builder()
+            .equality(EqualityMode::NoUna)
+            .num_threads(NonZeroUsize::new(4).unwrap())
+            .base_iri("https://ekgf.org/")
+            .sandbox_directory(Path::new(""))
+            .persistence(PersistenceMode::Off)
+            .build()
+            .unwrap();
+        assert_eq!(parameters.get_string("equality", "").unwrap(), "noUNA");
+        assert_eq!(parameters.get_string("num-threads", "").unwrap(), "4");
+        assert_eq!(parameters.get_string("base-iri", "").unwrap(), "https://ekgf.org/");
+        assert!(parameters.contains_key("sandbox-directory"));
+        assert_eq!(
+            parameters
+                .get_string(Parameter::Persistence.name().unwrap(), "")
+                .unwrap(),
+            "off"
+        );
+    }
+
+    #[test_log::test]
+    fn test_invalid_values() {
+        assert!(ParametersBuilder::default().base_iri("not an iri").build().is_err());
+        assert!(ParametersBuilder::default()
+            .swap_file_directory(Path::new("does/not/exist"))
+            .build()
+            .is_err());
+        assert!(ParametersBuilder::default()
+            .server_directory(Path::new("does/not/exist"))
+            .build()
+            .is_err());
+    }
+
+    #[cfg(feature = "rdfox-7-0")]
+    #[test_log::test]
+    fn test_unsupported_parameter() {
+        let err = ParametersBuilder::default()
+            .persist_roles(PersistenceMode::Off)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            UnsupportedParameterForVersion::from_error(&err),
+            Some(UnsupportedParameterForVersion {
+                parameter: Parameter::PersistRoles,
+                version:   "7.0".to_string(),
+            })
+        );
+    }
+}
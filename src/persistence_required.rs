@@ -0,0 +1,48 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// A maintenance operation like
+/// [`DataStoreConnection::compact`](crate::DataStoreConnection::compact)
+/// was used on a data store that is declared with
+/// [`PersistenceMode::Off`](crate::PersistenceMode::Off).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistenceRequired {
+    pub data_store: String,
+    /// What the data store was used for
+    pub action:     String,
+}
+
+impl Display for PersistenceRequired {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PersistenceRequired: data store {} is not persisted, refused {}",
+            self.data_store, self.action
+        )
+    }
+}
+
+impl std::error::Error for PersistenceRequired {}
+
+impl TypedError for PersistenceRequired {
+    const NAME: &'static str = "PersistenceRequired";
+
+    fn action(&self) -> String { self.action.clone() }
+}
+
+impl From<PersistenceRequired> for ekg_error::Error {
+    fn from(value: PersistenceRequired) -> Self { value.to_error() }
+}
+
+impl PersistenceRequired {
+    /// The [`PersistenceRequired`] error that the given error was made from, if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
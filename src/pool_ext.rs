@@ -0,0 +1,249 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{ConnectableDataStore, PoolShutdownTimedOut},
+    ekg_namespace::consts::LOG_TARGET_DATABASE,
+    r2d2::{Pool, PooledConnection},
+    std::{
+        collections::{hash_map::RandomState, HashMap, VecDeque},
+        hash::{BuildHasher, Hasher},
+        sync::{Arc, Condvar, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// How [`PoolExt::get_with_retry`] retries when all connections of a pool are
+/// in use, and how
+/// [`Transaction::begin_read_write_with_retry`](crate::Transaction::begin_read_write_with_retry)
+/// retries conflicting transactions.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts (including the first one)
+    pub max_attempts:    u32,
+    /// The (un-jittered) time to wait after the first failed attempt,
+    /// doubled after every subsequent failed attempt
+    pub initial_backoff: Duration,
+    /// The maximum (un-jittered) time to wait between two attempts
+    pub max_backoff:     Duration,
+    /// The maximum total time to spend on all attempts
+    pub deadline:        Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts:    10,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff:     Duration::from_millis(200),
+            deadline:        Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The backoff after the given (1-based) failed attempt, with up to 50%
+    /// random jitter added to avoid retrying in lock-step with other callers.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff);
+        let random = RandomState::new().build_hasher().finish();
+        backoff + backoff.mul_f64((random % 1000) as f64 / 2000.0)
+    }
+}
+
+/// Extra ways to check out a connection from a pool of
+/// [`DataStoreConnection`](crate::DataStoreConnection)s when it's exhausted.
+pub trait PoolExt {
+    /// Try to check out a connection, retrying with jittered exponential
+    /// backoff according to the given policy while all connections are
+    /// in use.
+    fn get_with_retry(
+        &self,
+        policy: &RetryPolicy,
+    ) -> Result<PooledConnection<ConnectableDataStore>, ekg_error::Error>;
+
+    /// Check out a connection, waiting in line until the given deadline if
+    /// all connections are in use. Waiting callers get their connection in
+    /// order of arrival.
+    ///
+    /// The queue belongs to the pool (see [`ConnectableDataStore::id`]), so
+    /// callers wait in the same line whether they share a `Pool` handle or
+    /// use clones of it.
+    fn try_get_or_queue(
+        &self,
+        deadline: Instant,
+    ) -> Result<PooledConnection<ConnectableDataStore>, ekg_error::Error>;
+
+    /// Shut the pool down (see [`ConnectableDataStore::initiate_shutdown`]),
+    /// destroy its idle connections and wait until the ones that are still
+    /// in use have been dropped too, or fail when that takes longer than
+    /// the given timeout.
+    ///
+    /// Afterwards the data store can be deleted. Fails with
+    /// [`PoolShutdownTimedOut`] if connections are still in use.
+    fn shutdown(&self, timeout: Duration) -> Result<(), ekg_error::Error>;
+}
+
+impl PoolExt for Pool<ConnectableDataStore> {
+    fn get_with_retry(
+        &self,
+        policy: &RetryPolicy,
+    ) -> Result<PooledConnection<ConnectableDataStore>, ekg_error::Error> {
+        let started_at = Instant::now();
+        let deadline = started_at + policy.deadline;
+        let mut attempt = 0_u32;
+        loop {
+            attempt += 1;
+            if let Some(connection) = self.try_get() {
+                tracing::debug!(
+                    target: LOG_TARGET_DATABASE,
+                    attempts = attempt,
+                    waited = ?started_at.elapsed(),
+                    "Checked out pooled connection"
+                );
+                return Ok(connection);
+            }
+            let now = Instant::now();
+            if attempt >= policy.max_attempts || now >= deadline {
+                break;
+            }
+            std::thread::sleep(policy.backoff(attempt).min(deadline - now));
+        }
+        tracing::warn!(
+            target: LOG_TARGET_DATABASE,
+            attempts = attempt,
+            waited = ?started_at.elapsed(),
+            "Could not check out a pooled connection"
+        );
+        Err(ekg_error::Error::Exception {
+            action:  "checking out a pooled connection".to_string(),
+            message: format!(
+                "timed out after {attempt} attempts in {:?}",
+                started_at.elapsed()
+            ),
+        })
+    }
+
+    fn try_get_or_queue(
+        &self,
+        deadline: Instant,
+    ) -> Result<PooledConnection<ConnectableDataStore>, ekg_error::Error> {
+        let started_at = Instant::now();
+        let key = self.manage_connection().id();
+        let queue = FairQueue::join(key);
+        let ticket = queue.take_ticket();
+
+        let result = queue.wait_for_turn(ticket, deadline).and_then(|_| {
+            // We're first in line now so we're the only one waiting for r2d2
+            self.get_timeout(deadline.saturating_duration_since(Instant::now()))
+                .map_err(|_| ())
+        });
+        queue.leave(ticket);
+        FairQueue::release(key, queue);
+
+        match result {
+            Ok(connection) => {
+                tracing::debug!(
+                    target: LOG_TARGET_DATABASE,
+                    ticket,
+                    waited = ?started_at.elapsed(),
+                    "Checked out pooled connection"
+                );
+                Ok(connection)
+            }
+            Err(_) => {
+                Err(ekg_error::Error::Exception {
+                    action:  "waiting in line for a pooled connection".to_string(),
+                    message: format!("timed out after {:?}", started_at.elapsed()),
+                })
+            }
+        }
+    }
+
+    fn shutdown(&self, timeout: Duration) -> Result<(), ekg_error::Error> {
+        let started_at = Instant::now();
+        let manager = self.manage_connection();
+        manager.initiate_shutdown();
+        // Checking out the idle connections and giving them back destroys
+        // them, as they're all marked broken now
+        let idle = std::iter::from_fn(|| self.try_get()).collect::<Vec<_>>();
+        drop(idle);
+        loop {
+            let live = manager.live_connections();
+            if live == 0 {
+                tracing::info!(
+                    target: LOG_TARGET_DATABASE,
+                    waited = ?started_at.elapsed(),
+                    "Shut down connection pool"
+                );
+                return Ok(());
+            }
+            if started_at.elapsed() >= timeout {
+                return Err(PoolShutdownTimedOut { live, waited: started_at.elapsed() }.into());
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+/// A line of callers waiting for a connection of a given pool, in order of
+/// arrival.
+#[derive(Default)]
+struct FairQueue {
+    tickets: Mutex<(u64, VecDeque<u64>)>,
+    turn:    Condvar,
+}
+
+lazy_static::lazy_static! {
+    /// The queues of the pools that currently have callers waiting in line
+    static ref QUEUES: Mutex<HashMap<u64, Arc<FairQueue>>> = Mutex::new(HashMap::new());
+}
+
+impl FairQueue {
+    fn join(key: u64) -> Arc<Self> {
+        QUEUES.lock().unwrap().entry(key).or_default().clone()
+    }
+
+    /// Let go of the queue, removing it when nobody else is in it anymore.
+    fn release(key: u64, queue: Arc<Self>) {
+        let mut queues = QUEUES.lock().unwrap();
+        drop(queue);
+        if let Some(queue) = queues.get(&key) {
+            if Arc::strong_count(queue) == 1 {
+                queues.remove(&key);
+            }
+        }
+    }
+
+    fn take_ticket(&self) -> u64 {
+        let mut tickets = self.tickets.lock().unwrap();
+        tickets.0 += 1;
+        let ticket = tickets.0;
+        tickets.1.push_back(ticket);
+        ticket
+    }
+
+    fn wait_for_turn(&self, ticket: u64, deadline: Instant) -> Result<(), ()> {
+        let mut tickets = self.tickets.lock().unwrap();
+        while tickets.1.front() != Some(&ticket) {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(());
+            }
+            tickets = self.turn.wait_timeout(tickets, deadline - now).unwrap().0;
+        }
+        Ok(())
+    }
+
+    fn leave(&self, ticket: u64) {
+        self.tickets
+            .lock()
+            .unwrap()
+            .1
+            .retain(|waiting| *waiting != ticket);
+        self.turn.notify_all();
+    }
+}
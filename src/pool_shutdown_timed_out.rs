@@ -0,0 +1,54 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::{Display, Formatter},
+        time::Duration,
+    },
+};
+
+/// Connections of a pool were still in use when
+/// [`PoolExt::shutdown`](crate::PoolExt::shutdown) gave up waiting for
+/// them. Get it back from an `ekg_error::Error` with
+/// [`PoolShutdownTimedOut::from_error`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolShutdownTimedOut {
+    /// The number of connections that were still around, see
+    /// [`ConnectableDataStore::live_connections`](crate::ConnectableDataStore::live_connections)
+    pub live:   usize,
+    /// How long the shutdown waited for them
+    pub waited: Duration,
+}
+
+impl Display for PoolShutdownTimedOut {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PoolShutdownTimedOut: {} connection(s) still in use after {:?}",
+            self.live, self.waited
+        )
+    }
+}
+
+impl std::error::Error for PoolShutdownTimedOut {}
+
+impl TypedError for PoolShutdownTimedOut {
+    const NAME: &'static str = "PoolShutdownTimedOut";
+
+    fn action(&self) -> String { "shutting down a connection pool".to_string() }
+}
+
+impl From<PoolShutdownTimedOut> for ekg_error::Error {
+    fn from(value: PoolShutdownTimedOut) -> Self { value.to_error() }
+}
+
+impl PoolShutdownTimedOut {
+    /// The [`PoolShutdownTimedOut`] error that the given error was made
+    /// from, if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
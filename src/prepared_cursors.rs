@@ -0,0 +1,141 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{
+        database_call,
+        rdfox_api::{
+            CCursor,
+            CCursor_destroy,
+            CCursor_open,
+            CDataStoreConnection,
+            CDataStoreConnection_createCursor,
+            CParameters,
+        },
+    },
+    ekg_namespace::consts::LOG_TARGET_DATABASE,
+    std::{
+        collections::{hash_map::DefaultHasher, VecDeque},
+        ffi::CString,
+        hash::{Hash, Hasher},
+        ptr,
+    },
+};
+
+/// The maximum number of cursors kept per connection
+pub(crate) const MAX_PREPARED_CURSORS: usize = 64;
+
+/// A small LRU cache of (already parsed) cursors of a
+/// [`DataStoreConnection`](crate::DataStoreConnection), keyed by a
+/// fingerprint of their statement text, that are simply re-opened to
+/// re-evaluate their statement.
+///
+/// Since every re-open evaluates the statement again there's no need to
+/// invalidate a cursor when the data changes.
+#[derive(Debug, Default)]
+pub(crate) struct PreparedCursors {
+    /// Most recently used first
+    cursors:      VecDeque<PreparedCursor>,
+    /// The number of cursors that have been created (i.e. the number of
+    /// cache misses)
+    preparations: usize,
+}
+
+#[derive(Debug)]
+struct PreparedCursor {
+    fingerprint: u64,
+    text:        String,
+    inner:       *mut CCursor,
+}
+
+impl Drop for PreparedCursor {
+    fn drop(&mut self) {
+        if !self.inner.is_null() {
+            unsafe {
+                CCursor_destroy(self.inner);
+            }
+            self.inner = ptr::null_mut();
+        }
+    }
+}
+
+impl PreparedCursors {
+    /// Open the cursor for the given statement text (creating it first if
+    /// it's not in the cache) and return the multiplicity of its first row.
+    pub(crate) fn open(
+        &mut self,
+        connection: *mut CDataStoreConnection,
+        parameters: *const CParameters,
+        text: &str,
+    ) -> Result<usize, ekg_error::Error> {
+        let fingerprint = Self::fingerprint(text);
+        let position = self
+            .cursors
+            .iter()
+            .position(|cursor| cursor.fingerprint == fingerprint && cursor.text == text);
+        let prepared = match position {
+            Some(position) => self.cursors.remove(position).unwrap(),
+            None => Self::prepare(connection, parameters, fingerprint, text)?,
+        };
+        if position.is_none() {
+            self.preparations += 1;
+        }
+        let c_cursor = prepared.inner;
+        self.cursors.push_front(prepared);
+        self.cursors.truncate(MAX_PREPARED_CURSORS);
+
+        let mut multiplicity = 0_usize;
+        database_call!(
+            "opening a prepared cursor",
+            CCursor_open(c_cursor, 0, &mut multiplicity)
+        )?;
+        Ok(multiplicity)
+    }
+
+    fn prepare(
+        connection: *mut CDataStoreConnection,
+        parameters: *const CParameters,
+        fingerprint: u64,
+        text: &str,
+    ) -> Result<PreparedCursor, ekg_error::Error> {
+        let c_query = CString::new(text)?;
+        let mut c_cursor: *mut CCursor = ptr::null_mut();
+        database_call!(
+            "preparing a cursor",
+            CDataStoreConnection_createCursor(
+                connection,
+                c_query.as_ptr(),
+                c_query.as_bytes().len(),
+                parameters,
+                &mut c_cursor,
+            )
+        )?;
+        tracing::debug!(
+            target: LOG_TARGET_DATABASE,
+            fingerprint,
+            "Prepared cursor"
+        );
+        Ok(PreparedCursor { fingerprint, text: text.to_string(), inner: c_cursor })
+    }
+
+    pub(crate) fn fingerprint(text: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub(crate) fn preparations(&self) -> usize { self.preparations }
+
+    /// Destroy all cursors, which has to happen before their connection
+    /// is destroyed
+    pub(crate) fn clear(&mut self) { self.cursors.clear(); }
+
+    /// Forget all cursors without destroying them, for when RDFox has
+    /// already destroyed them along with the stopped server
+    pub(crate) fn leak(&mut self) {
+        for cursor in self.cursors.iter_mut() {
+            cursor.inner = ptr::null_mut();
+        }
+        self.cursors.clear();
+    }
+}
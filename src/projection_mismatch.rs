@@ -0,0 +1,97 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// The answer variables of a query are not the ones that were expected,
+/// see [`Statement::with_expected_variables`](crate::Statement::with_expected_variables).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectionMismatch {
+    /// The expected variable names (without `?`), empty if there were no
+    /// expectations other than having at least one column
+    pub expected: Vec<String>,
+    /// The variable names (without `?`) that the query projects
+    pub actual:   Vec<String>,
+}
+
+impl Display for ProjectionMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let show = |variables: &[String]| {
+            if variables.is_empty() {
+                "no columns".to_string()
+            } else {
+                variables
+                    .iter()
+                    .map(|variable| format!("?{variable}"))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+        };
+        write!(f, "ProjectionMismatch: ")?;
+        if !self.expected.is_empty() {
+            write!(f, "expected {}, ", show(&self.expected))?;
+        }
+        write!(f, "the query has {}", show(&self.actual))
+    }
+}
+
+impl std::error::Error for ProjectionMismatch {}
+
+impl TypedError for ProjectionMismatch {
+    const NAME: &'static str = "ProjectionMismatch";
+
+    fn action(&self) -> String { "checking the projection of a query".to_string() }
+}
+
+impl From<ProjectionMismatch> for ekg_error::Error {
+    fn from(value: ProjectionMismatch) -> Self { value.to_error() }
+}
+
+impl ProjectionMismatch {
+    /// The [`ProjectionMismatch`] error that the given error was made from, if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+
+    /// Check that the actual variables are the expected ones, in the same
+    /// order
+    pub(crate) fn check(expected: &[String], actual: &[String]) -> Result<(), Self> {
+        if expected == actual {
+            return Ok(());
+        }
+        Err(ProjectionMismatch { expected: expected.to_vec(), actual: actual.to_vec() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProjectionMismatch;
+
+    fn names(names: &[&str]) -> Vec<String> { names.iter().map(|name| name.to_string()).collect() }
+
+    #[test_log::test]
+    fn test_check() {
+        let expected = names(&["s", "p", "o"]);
+        assert!(ProjectionMismatch::check(&expected, &names(&["s", "p", "o"])).is_ok());
+
+        let extra = ProjectionMismatch::check(&expected, &names(&["s", "p", "o", "g"])).unwrap_err();
+        assert_eq!(
+            extra.to_string(),
+            "ProjectionMismatch: expected ?s ?p ?o, the query has ?s ?p ?o ?g"
+        );
+        let missing = ProjectionMismatch::check(&expected, &names(&["s", "o"])).unwrap_err();
+        assert_eq!(missing.actual, names(&["s", "o"]));
+        assert!(ProjectionMismatch::check(&expected, &names(&["p", "s", "o"])).is_err());
+
+        let none = ProjectionMismatch::check(&expected, &[]).unwrap_err();
+        assert_eq!(
+            none.to_string(),
+            "ProjectionMismatch: expected ?s ?p ?o, the query has no columns"
+        );
+        assert_eq!(ProjectionMismatch::from_error(&none.clone().into()), Some(none));
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{SimpleBinding, Term},
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    std::fmt::{Display, Formatter},
+};
+
+/// A fact (a triple, in the default graph unless `graph` is given) in a
+/// [`ProofTree`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofFact {
+    #[serde(with = "term_as_binding")]
+    pub subject:   Term,
+    #[serde(with = "term_as_binding")]
+    pub predicate: Term,
+    #[serde(with = "term_as_binding")]
+    pub object:    Term,
+    #[serde(default, with = "optional_term_as_binding", skip_serializing_if = "Option::is_none")]
+    pub graph:     Option<Term>,
+}
+
+impl Display for ProofFact {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.subject, self.predicate, self.object)?;
+        if let Some(graph) = &self.graph {
+            write!(f, " {graph}")?;
+        }
+        f.write_str(" .")
+    }
+}
+
+/// Why a fact holds: either it was explicitly asserted (`rule` is `None`
+/// and there are no premises) or it was derived by the given rule from the
+/// given premises, each of which has a proof of its own.
+///
+/// See [`DataStoreConnection::explain_fact`](crate::DataStoreConnection::explain_fact).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofTree {
+    pub fact:     ProofFact,
+    /// The identifier (or the text) of the rule that derived the fact
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule:     Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub premises: Vec<ProofTree>,
+}
+
+impl ProofTree {
+    fn fmt_indented(&self, f: &mut Formatter<'_>, depth: usize) -> std::fmt::Result {
+        write!(f, "{:indent$}{}", "", self.fact, indent = depth * 4)?;
+        match &self.rule {
+            Some(rule) => writeln!(f, "  [rule: {rule}]")?,
+            None => writeln!(f, "  [asserted]")?,
+        }
+        for premise in self.premises.iter() {
+            premise.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Shows the proof with every premise indented below the fact it supports
+impl Display for ProofTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { self.fmt_indented(f, 0) }
+}
+
+/// (De)serialize a [`Term`] as a [`SimpleBinding`]
+mod term_as_binding {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(term: &Term, serializer: S) -> Result<S::Ok, S::Error> {
+        SimpleBinding::from(term).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Term, D::Error> {
+        SimpleBinding::deserialize(deserializer)?
+            .as_typed()
+            .map_err(|err| serde::de::Error::custom(format!("{err:?}")))
+    }
+}
+
+mod optional_term_as_binding {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(term: &Option<Term>, serializer: S) -> Result<S::Ok, S::Error> {
+        term.as_ref().map(SimpleBinding::from).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Term>, D::Error> {
+        Option::<SimpleBinding>::deserialize(deserializer)?
+            .map(|binding| binding.as_typed())
+            .transpose()
+            .map_err(|err| serde::de::Error::custom(format!("{err:?}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{ProofFact, ProofTree},
+        crate::Term,
+    };
+
+    fn fact(s: &str, p: &str, o: &str) -> ProofFact {
+        let term = |name: &str| Term::iri(format!("https://ekgf.org/family/{name}").as_str()).unwrap();
+        ProofFact { subject: term(s), predicate: term(p), object: term(o), graph: None }
+    }
+
+    fn grandparent_proof() -> ProofTree {
+        ProofTree {
+            fact:     fact("alice", "grandparentOf", "carol"),
+            rule:     Some("grandparent".to_string()),
+            premises: vec![
+                ProofTree { fact: fact("alice", "parentOf", "bob"), rule: None, premises: vec![] },
+                ProofTree { fact: fact("bob", "parentOf", "carol"), rule: None, premises: vec![] },
+            ],
+        }
+    }
+
+    #[test_log::test]
+    fn test_display() {
+        assert_eq!(
+            grandparent_proof().to_string(),
+            "<https://ekgf.org/family/alice> <https://ekgf.org/family/grandparentOf> \
+             <https://ekgf.org/family/carol> .  [rule: grandparent]\n    \
+             <https://ekgf.org/family/alice> <https://ekgf.org/family/parentOf> \
+             <https://ekgf.org/family/bob> .  [asserted]\n    \
+             <https://ekgf.org/family/bob> <https://ekgf.org/family/parentOf> \
+             <https://ekgf.org/family/carol> .  [asserted]\n"
+        );
+    }
+
+    #[test_log::test]
+    fn test_serde() {
+        let mut proof = grandparent_proof();
+        proof.premises[0].fact.graph = Some(Term::iri("https://ekgf.org/graph/family").unwrap());
+        proof.premises[1].fact.object = Term::string("Carol");
+        let json = serde_json::to_string(&proof).unwrap();
+        assert!(json.contains(r#""rule":"grandparent""#), "{json}");
+        assert_eq!(serde_json::from_str::<ProofTree>(&json).unwrap(), proof);
+    }
+}
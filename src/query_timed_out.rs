@@ -0,0 +1,121 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{typed_error::TypedError, RDFoxException},
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::{Display, Formatter},
+        time::Duration,
+    },
+};
+
+/// The names of the exceptions that RDFox throws when a query runs out of
+/// time
+const TIMEOUT_EXCEPTIONS: &[&str] = &["QueryTimeLimitExceededException", "TimeoutException"];
+
+/// The evaluation of a query took longer than allowed, see
+/// [`Parameters::query_timeout`](crate::Parameters::query_timeout).
+/// Get it back from an `ekg_error::Error` with [`QueryTimedOut::from_error`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryTimedOut {
+    /// How long the query ran before it was stopped
+    pub elapsed: Duration,
+    /// The text of the query, with overly long tokens abbreviated
+    pub query:   String,
+}
+
+impl Display for QueryTimedOut {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "QueryTimedOut: query timed out after {:?}: {}",
+            self.elapsed, self.query
+        )
+    }
+}
+
+impl std::error::Error for QueryTimedOut {}
+
+impl TypedError for QueryTimedOut {
+    const NAME: &'static str = "QueryTimedOut";
+
+    fn action(&self) -> String { "evaluating a query".to_string() }
+}
+
+impl From<QueryTimedOut> for ekg_error::Error {
+    fn from(value: QueryTimedOut) -> Self { value.to_error() }
+}
+
+impl QueryTimedOut {
+    /// The [`QueryTimedOut`] error that the given error was made from, if
+    /// any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+
+    /// Turn the given error into a [`QueryTimedOut`] error if it's the
+    /// exception that RDFox throws when a query runs out of time
+    pub(crate) fn map(err: ekg_error::Error, elapsed: Duration, query: &str) -> ekg_error::Error {
+        match RDFoxException::from_error(&err) {
+            Some(exception) if Self::is_timeout(&exception) => {
+                QueryTimedOut { elapsed, query: query.to_string() }.into()
+            },
+            _ => err,
+        }
+    }
+
+    fn is_timeout(exception: &RDFoxException) -> bool {
+        TIMEOUT_EXCEPTIONS.contains(&exception.name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::QueryTimedOut,
+        crate::{ExceptionKind, RDFoxException},
+        std::time::Duration,
+    };
+
+    fn exception(name: &str, message: &str) -> ekg_error::Error {
+        RDFoxException {
+            action:  "advancing a cursor".to_string(),
+            name:    name.to_string(),
+            message: message.to_string(),
+            kind:    ExceptionKind::Other,
+        }
+        .into()
+    }
+
+    #[test_log::test]
+    fn test_map() {
+        let timeout = exception(
+            "QueryTimeLimitExceededException",
+            "the time limit was exceeded",
+        );
+        let mapped = QueryTimedOut::map(timeout, Duration::from_millis(5), "SELECT * {}");
+        assert_eq!(
+            QueryTimedOut::from_error(&mapped),
+            Some(QueryTimedOut { elapsed: Duration::from_millis(5), query: "SELECT * {}".to_string() })
+        );
+
+        let other = exception("ParsingException", "unexpected token");
+        let mapped = QueryTimedOut::map(other, Duration::ZERO, "SELECT * {}");
+        assert_eq!(QueryTimedOut::from_error(&mapped), None);
+        assert_eq!(RDFoxException::from_error(&mapped).unwrap().name, "ParsingException");
+
+        // Nor is any other exception that mentions a timeout
+        let other = exception("ParsingException", "unexpected token timeout");
+        let mapped = QueryTimedOut::map(other, Duration::ZERO, "SELECT * {}");
+        assert_eq!(QueryTimedOut::from_error(&mapped), None);
+
+        // Only exceptions of RDFox are mapped, not errors that mention a
+        // timeout
+        let text = ekg_error::Error::Exception {
+            action:  "testing".to_string(),
+            message: "timeout".to_string(),
+        };
+        assert_eq!(QueryTimedOut::from_error(&QueryTimedOut::map(text, Duration::ZERO, "")), None);
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use std::{
+    fmt::{Display, Formatter},
+    path::Path,
+    sync::OnceLock,
+};
+
+/// The file extensions (without the dot, in lower case) of each format,
+/// which both [`RdfFormat::from_extension`] and the files that a directory
+/// import picks up are based on
+const EXTENSIONS: &[(&str, RdfFormat)] = &[
+    ("ttl", RdfFormat::Turtle),
+    ("turtle", RdfFormat::Turtle),
+    ("nt", RdfFormat::NTriples),
+    ("ntriples", RdfFormat::NTriples),
+    ("nq", RdfFormat::NQuads),
+    ("nquads", RdfFormat::NQuads),
+    ("trig", RdfFormat::TriG),
+    ("rdf", RdfFormat::RdfXml),
+    ("owl", RdfFormat::RdfXml),
+    ("xml", RdfFormat::RdfXml),
+    ("n3", RdfFormat::N3),
+    ("dlog", RdfFormat::Datalog),
+    ("datalog", RdfFormat::Datalog),
+];
+
+/// The RDF serialization formats that can be imported into RDFox, plus its
+/// own Datalog format for rules (and facts).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RdfFormat {
+    Turtle,
+    NTriples,
+    NQuads,
+    TriG,
+    RdfXml,
+    N3,
+    /// RDFox Datalog rules, see
+    /// [`DataStoreConnection::import_rules_from_file`](crate::DataStoreConnection::import_rules_from_file)
+    Datalog,
+}
+
+impl Display for RdfFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { f.write_str(self.as_str()) }
+}
+
+impl RdfFormat {
+    /// The format name as understood by RDFox (i.e. its media type)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RdfFormat::Turtle => "text/turtle",
+            RdfFormat::NTriples => "application/n-triples",
+            RdfFormat::NQuads => "application/n-quads",
+            RdfFormat::TriG => "application/trig",
+            RdfFormat::RdfXml => "application/rdf+xml",
+            RdfFormat::N3 => "text/n3",
+            RdfFormat::Datalog => "application/x.datalog",
+        }
+    }
+
+    /// The format that goes with the given file extension (without the dot)
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        EXTENSIONS
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+            .map(|(_, format)| *format)
+    }
+
+    /// The format of the given file based on its extension (ignoring a
+    /// trailing `.gz`), if known
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let path = if Self::is_gzipped(path) {
+            Path::new(path.file_stem()?)
+        } else {
+            path
+        };
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_extension)
+    }
+
+    /// Whether the given file is gzip-compressed, based on its extension
+    pub fn is_gzipped(path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("gz"))
+    }
+
+    /// The format of the given file based on its extension, Turtle if not
+    /// known
+    pub fn from_path_or_turtle(path: &Path) -> Self {
+        Self::from_path(path).unwrap_or(RdfFormat::Turtle)
+    }
+
+    /// The file name patterns of all supported formats, plain and
+    /// gzip-compressed, except Datalog: rules are imported explicitly
+    pub(crate) fn glob_patterns() -> &'static [String] {
+        static PATTERNS: OnceLock<Vec<String>> = OnceLock::new();
+        PATTERNS.get_or_init(|| {
+            EXTENSIONS
+                .iter()
+                .filter(|(_, format)| *format != RdfFormat::Datalog)
+                .flat_map(|(extension, _)| [format!("*.{extension}"), format!("*.{extension}.gz")])
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::RdfFormat, std::path::Path};
+
+    #[test_log::test]
+    fn test_from_path() {
+        assert_eq!(
+            RdfFormat::from_path(Path::new("a/b.nt")),
+            Some(RdfFormat::NTriples)
+        );
+        assert_eq!(
+            RdfFormat::from_path(Path::new("b.TTL")),
+            Some(RdfFormat::Turtle)
+        );
+        assert_eq!(
+            RdfFormat::from_path(Path::new("b.rdf")),
+            Some(RdfFormat::RdfXml)
+        );
+        assert_eq!(
+            RdfFormat::from_path(Path::new("b.nt.gz")),
+            Some(RdfFormat::NTriples)
+        );
+        assert!(RdfFormat::is_gzipped(Path::new("b.ttl.GZ")));
+        assert!(!RdfFormat::is_gzipped(Path::new("b.ttl")));
+        assert_eq!(RdfFormat::from_path(Path::new("README")), None);
+        assert_eq!(RdfFormat::from_path(Path::new("b.gz")), None);
+        assert_eq!(
+            RdfFormat::from_path_or_turtle(Path::new("b.txt")),
+            RdfFormat::Turtle
+        );
+        assert_eq!(RdfFormat::NQuads.to_string(), "application/n-quads");
+        assert_eq!(
+            RdfFormat::from_path(Path::new("rules/family.dlog")),
+            Some(RdfFormat::Datalog)
+        );
+    }
+
+    #[test_log::test]
+    fn test_glob_patterns() {
+        let patterns = RdfFormat::glob_patterns();
+        for pattern in patterns {
+            let extension = pattern.trim_start_matches("*.").trim_end_matches(".gz");
+            assert!(RdfFormat::from_extension(extension).is_some(), "{pattern}");
+        }
+        assert!(patterns.contains(&"*.nq.gz".to_string()));
+        assert!(patterns.contains(&"*.turtle".to_string()));
+        assert!(!patterns.iter().any(|pattern| pattern.contains("dlog")));
+    }
+}
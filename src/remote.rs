@@ -0,0 +1,343 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+//! Connections (feature `remote`) to an RDFox server over its REST API
+//! rather than to the embedded engine, see [`RemoteServerConnection`].
+
+use {
+    crate::{
+        DataStore,
+        DataStoreConnectionLike,
+        Parameters,
+        RoleCreds,
+        SelectResult,
+        SimpleBinding,
+        Statement,
+    },
+    base64::Engine,
+    ekg_namespace::{consts::DEFAULT_GRAPH_RDFOX, Graph},
+    mime::Mime,
+    percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC},
+    serde::Deserialize,
+    std::{collections::HashMap, ops::Deref, sync::Arc},
+};
+
+/// The environment variable with the base URL of the RDFox endpoint, like
+/// `http://localhost:12110`, see [`RemoteServerConnection::from_env`]
+pub const RDFOX_ENDPOINT: &str = "RDFOX_ENDPOINT";
+
+const SPARQL_RESULTS_JSON: &str = "application/sparql-results+json";
+
+/// Everything but the unreserved characters of RFC 3986 is percent-encoded
+/// in a segment of a path, like the name of a data store
+const PATH_SEGMENT: &AsciiSet =
+    &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// The path of the given data store in the REST API
+fn data_store_path(data_store: &DataStore) -> String {
+    format!("/datastores/{}", utf8_percent_encode(data_store.name.as_str(), PATH_SEGMENT))
+}
+
+/// The names of the data stores in the SPARQL 1.1 Query Results JSON that
+/// the server returns for `GET /datastores`, from its `Name` column (or the
+/// first one, if there's none)
+fn data_store_names(body: &str) -> Result<Vec<String>, serde_json::Error> {
+    let results: SparqlResults = serde_json::from_str(body)?;
+    let Some(column) = results
+        .head
+        .vars
+        .iter()
+        .find(|var| var.as_str() == "Name")
+        .or_else(|| results.head.vars.first())
+    else {
+        return Ok(Vec::new());
+    };
+    Ok(results
+        .results
+        .bindings
+        .iter()
+        .filter_map(|binding| binding.get(column))
+        .map(|binding| binding.value.clone())
+        .collect())
+}
+
+/// A connection to an RDFox server over its REST API, with the same data
+/// store management as [`ServerConnection`](crate::ServerConnection).
+///
+/// Statements are still created with [`Statement::new`], which needs the
+/// RDFox library, but they are evaluated by the remote server.
+#[derive(Debug)]
+pub struct RemoteServerConnection {
+    base_url:      String,
+    role_name:     String,
+    authorization: String,
+    agent:         ureq::Agent,
+}
+
+/// The shape of SPARQL 1.1 Query Results JSON
+#[derive(Deserialize)]
+struct SparqlResults {
+    head:    SparqlHead,
+    results: SparqlBindings,
+}
+
+#[derive(Deserialize)]
+struct SparqlHead {
+    #[serde(default)]
+    vars: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SparqlBindings {
+    bindings: Vec<HashMap<String, SimpleBinding>>,
+}
+
+impl RemoteServerConnection {
+    /// Connect to the RDFox endpoint at the given base URL (like
+    /// `http://localhost:12110`) as the given role, which is not checked
+    /// until the first request
+    pub fn connect(base_url: &str, role_creds: &RoleCreds) -> Arc<Self> {
+        let credentials = format!("{}:{}", role_creds.role_name, role_creds.password);
+        Arc::new(Self {
+            base_url:      base_url.trim_end_matches('/').to_string(),
+            role_name:     role_creds.role_name.clone(),
+            authorization: format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            ),
+            agent:         ureq::Agent::new(),
+        })
+    }
+
+    /// Connect to the endpoint in the [`RDFOX_ENDPOINT`] environment
+    /// variable with the credentials of [`RoleCreds::from_env`], `None` if
+    /// that variable is not set
+    pub fn from_env() -> Result<Option<Arc<Self>>, ekg_error::Error> {
+        match std::env::var(RDFOX_ENDPOINT) {
+            Ok(base_url) => Ok(Some(Self::connect(base_url.as_str(), &RoleCreds::from_env()?))),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn base_url(&self) -> &str { self.base_url.as_str() }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        self.agent
+            .request(method, format!("{}{path}", self.base_url).as_str())
+            .set("Authorization", self.authorization.as_str())
+    }
+
+    /// Turn a failed request into an exception with the HTTP status and
+    /// the message in the body of the response
+    fn error(action: &str, err: ureq::Error) -> ekg_error::Error {
+        let message = match err {
+            ureq::Error::Status(status, response) => {
+                let body = response.into_string().unwrap_or_default();
+                format!("HTTP {status}: {}", body.trim())
+            },
+            ureq::Error::Transport(transport) => transport.to_string(),
+        };
+        ekg_error::Error::Exception { action: action.to_string(), message }
+    }
+
+    fn body(action: &str, response: ureq::Response) -> Result<String, ekg_error::Error> {
+        response.into_string().map_err(|err| {
+            ekg_error::Error::Exception { action: action.to_string(), message: err.to_string() }
+        })
+    }
+
+    /// Return the names of all data stores in the server
+    pub fn list_data_stores(&self) -> Result<Vec<String>, ekg_error::Error> {
+        let action = "Listing the data stores";
+        let response = self
+            .request("GET", "/datastores")
+            .set("Accept", SPARQL_RESULTS_JSON)
+            .call()
+            .map_err(|err| Self::error(action, err))?;
+        data_store_names(Self::body(action, response)?.as_str()).map_err(|err| {
+            ekg_error::Error::Exception { action: action.to_string(), message: err.to_string() }
+        })
+    }
+
+    pub fn data_store_exists(&self, name: &str) -> Result<bool, ekg_error::Error> {
+        Ok(self.list_data_stores()?.iter().any(|existing| existing == name))
+    }
+
+    /// Create the given data store with its parameters
+    pub fn create_data_store(&self, data_store: &DataStore) -> Result<(), ekg_error::Error> {
+        let action = format!("Creating {data_store} remotely as role [{}]", self.role_name);
+        let mut request = self.request("POST", data_store_path(data_store).as_str());
+        for (key, value) in data_store.parameters.get_all() {
+            request = request.query(key.as_str(), value.as_str());
+        }
+        request.call().map_err(|err| Self::error(action.as_str(), err))?;
+        Ok(())
+    }
+
+    pub fn delete_data_store(&self, data_store: &DataStore) -> Result<(), ekg_error::Error> {
+        let action = format!("Deleting {data_store} remotely");
+        self.request("DELETE", data_store_path(data_store).as_str())
+            .call()
+            .map_err(|err| Self::error(action.as_str(), err))?;
+        Ok(())
+    }
+
+    pub fn connect_to_data_store(
+        self: &Arc<Self>,
+        data_store: &Arc<DataStore>,
+    ) -> Arc<RemoteDataStoreConnection> {
+        Arc::new(RemoteDataStoreConnection {
+            server:     self.clone(),
+            data_store: data_store.clone(),
+        })
+    }
+}
+
+/// A connection to a data store of a [`RemoteServerConnection`], which
+/// implements [`DataStoreConnectionLike`].
+///
+/// Every request is a transaction of its own, there are no transactions
+/// that span several of them.
+#[derive(Debug)]
+pub struct RemoteDataStoreConnection {
+    pub server:     Arc<RemoteServerConnection>,
+    pub data_store: Arc<DataStore>,
+}
+
+impl RemoteDataStoreConnection {
+    fn path(&self, resource: &str) -> String {
+        format!("{}/{resource}", data_store_path(&self.data_store))
+    }
+
+    fn query(
+        &self,
+        statement: &Statement,
+        parameters: Option<&Parameters>,
+        accept: &str,
+    ) -> Result<String, ekg_error::Error> {
+        let action = format!("Evaluating a query in {} remotely", self.data_store);
+        let mut request = self
+            .server
+            .request("POST", self.path("sparql").as_str())
+            .set("Accept", accept);
+        for (key, value) in parameters.into_iter().flat_map(|parameters| parameters.get_all()) {
+            request = request.query(key.as_str(), value.as_str());
+        }
+        let response = request
+            .send_form(&[("query", statement.as_str())])
+            .map_err(|err| RemoteServerConnection::error(action.as_str(), err))?;
+        RemoteServerConnection::body(action.as_str(), response)
+    }
+
+    /// The IRI of the given graph without the angle brackets
+    fn graph_iri(graph: &Graph) -> String {
+        graph
+            .as_display_iri()
+            .to_string()
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .to_string()
+    }
+}
+
+impl DataStoreConnectionLike for RemoteDataStoreConnection {
+    fn data_store_name(&self) -> &str { self.data_store.name.as_str() }
+
+    /// Parses the SPARQL 1.1 Query Results JSON that the server returns
+    /// into a [`SelectResult`]
+    fn select(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<SelectResult, ekg_error::Error> {
+        let body = self.query(statement, Some(parameters), SPARQL_RESULTS_JSON)?;
+        let results: SparqlResults = serde_json::from_str(body.as_str()).map_err(|err| {
+            ekg_error::Error::Exception {
+                action:  format!("Reading the results of a query in {}", self.data_store),
+                message: err.to_string(),
+            }
+        })?;
+        SelectResult::from_simple_bindings(results.head.vars, &results.results.bindings)
+    }
+
+    fn update(
+        &self,
+        statement: &Statement,
+        parameters: &Parameters,
+    ) -> Result<(), ekg_error::Error> {
+        let action = format!("Evaluating an update in {} remotely", self.data_store);
+        let mut request = self.server.request("POST", self.path("sparql").as_str());
+        for (key, value) in parameters.get_all() {
+            request = request.query(key.as_str(), value.as_str());
+        }
+        request
+            .send_form(&[("update", statement.as_str())])
+            .map_err(|err| RemoteServerConnection::error(action.as_str(), err))?;
+        Ok(())
+    }
+
+    fn import_data_from_str(
+        &self,
+        content: &str,
+        format: &Mime,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        let action = format!("Importing {format} into {} remotely", self.data_store);
+        let graph_iri = Self::graph_iri(graph);
+        let request = self.server.request("POST", self.path("content").as_str());
+        let request = if graph_iri == Self::graph_iri(DEFAULT_GRAPH_RDFOX.deref()) {
+            request.query("default", "")
+        } else {
+            request.query("graph", graph_iri.as_str())
+        };
+        request
+            .set("Content-Type", format.as_ref())
+            .send_string(content)
+            .map_err(|err| RemoteServerConnection::error(action.as_str(), err))?;
+        Ok(())
+    }
+
+    fn evaluate_to_string(
+        &self,
+        statement: &Statement,
+        mime_type: &'static Mime,
+    ) -> Result<String, ekg_error::Error> {
+        self.query(statement, None, mime_type.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{data_store_names, data_store_path},
+        crate::{DataStore, Parameters},
+    };
+
+    #[test_log::test]
+    fn test_data_store_path() -> Result<(), ekg_error::Error> {
+        let path = |name: &str| -> Result<String, ekg_error::Error> {
+            Ok(data_store_path(&DataStore::declare_with_parameters(name, Parameters::empty()?)?))
+        };
+        assert_eq!(path("a b/c?d#e%")?, "/datastores/a%20b%2Fc%3Fd%23e%25");
+        assert_eq!(path("plain-name_1.0~")?, "/datastores/plain-name_1.0~");
+        Ok(())
+    }
+
+    #[test_log::test]
+    fn test_data_store_names() {
+        let body = r#"{
+            "head": { "vars": [ "Name", "UniqueID", "Persistent", "Online" ] },
+            "results": { "bindings": [
+                { "Name": { "type": "literal", "value": "one, two" },
+                  "Online": { "type": "literal", "value": "true" } },
+                { "Name": { "type": "literal", "value": "\"quoted\"" } }
+            ] }
+        }"#;
+        assert_eq!(
+            data_store_names(body).unwrap(),
+            vec!["one, two".to_string(), "\"quoted\"".to_string()]
+        );
+        let empty = r#"{ "head": { "vars": [] }, "results": { "bindings": [] } }"#;
+        assert_eq!(data_store_names(empty).unwrap(), Vec::<String>::new());
+        assert!(data_store_names("Name\nfirst\n").is_err());
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use std::fmt::{Display, Formatter};
+
+/// How the multiplicities of the solutions of a query end up in a
+/// materialised result like a [`SelectResult`](crate::SelectResult) and
+/// everything exported from it (CSV, simple bindings, `VALUES` blocks and
+/// test fixtures).
+///
+/// The default is [`ResultSemantics::Bag`], which is what SPARQL itself
+/// does.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum ResultSemantics {
+    /// Every solution occurs as many times as its multiplicity
+    #[default]
+    Bag,
+    /// Every distinct solution occurs once
+    Set,
+    /// Every distinct solution occurs once with its multiplicity in an
+    /// extra `xsd:integer` column (i.e. answer variable) with the given
+    /// name, so no information is lost
+    KeepMultiplicityColumn(String),
+}
+
+impl Display for ResultSemantics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResultSemantics::Bag => write!(f, "bag"),
+            ResultSemantics::Set => write!(f, "set"),
+            ResultSemantics::KeepMultiplicityColumn(name) => write!(f, "set with ?{name}"),
+        }
+    }
+}
@@ -0,0 +1,352 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{
+        select_result::{csv_line, csv_value, XSD_INTEGER},
+        Cursor,
+        CursorRow,
+        OpenedCursor,
+        ResultSemantics,
+        SimpleBinding,
+        Term,
+        Transaction,
+    },
+    iref::Iri,
+    std::{
+        collections::{HashMap, HashSet},
+        io::Write,
+        sync::Arc,
+    },
+};
+
+/// Writes the solutions of a `SELECT` query in a standard results format,
+/// one row at a time, so rows can be filtered (or made up) in Rust first.
+///
+/// Call [`ResultSerializer::start`] once, [`ResultSerializer::row`] for each
+/// row and [`ResultSerializer::finish`] at the end, or let
+/// [`ResultSerializer::write_cursor`] do all of that.
+pub trait ResultSerializer {
+    /// Write the header with the given answer variables (without `?`)
+    fn start(&mut self, variables: &[String]) -> Result<(), ekg_error::Error>;
+
+    /// Write a row with the values of the variables (in the order given to
+    /// `start`, `None` when unbound), repeated `multiplicity` times
+    fn row(
+        &mut self,
+        values: &[Option<Term>],
+        multiplicity: usize,
+    ) -> Result<(), ekg_error::Error>;
+
+    fn finish(&mut self) -> Result<(), ekg_error::Error>;
+
+    /// Write all rows of the given cursor for which `keep` returns true,
+    /// with bag semantics, returns the number of solutions written
+    fn write_cursor<F>(
+        &mut self,
+        cursor: &mut Cursor,
+        tx: &Arc<Transaction>,
+        keep: F,
+    ) -> Result<usize, ekg_error::Error>
+        where
+            Self: Sized,
+            F: FnMut(&CursorRow) -> Result<bool, ekg_error::Error>,
+    {
+        self.write_cursor_with_semantics(cursor, tx, &ResultSemantics::Bag, keep)
+    }
+
+    /// Write all rows of the given cursor for which `keep` returns true,
+    /// expanding or merging their multiplicities according to the given
+    /// [`ResultSemantics`] like [`Cursor::select_result_with_semantics`]
+    /// does, returns the number of solutions written.
+    ///
+    /// With [`ResultSemantics::KeepMultiplicityColumn`] the distinct rows
+    /// are only written at the end, once their multiplicities are known.
+    fn write_cursor_with_semantics<F>(
+        &mut self,
+        cursor: &mut Cursor,
+        tx: &Arc<Transaction>,
+        semantics: &ResultSemantics,
+        mut keep: F,
+    ) -> Result<usize, ekg_error::Error>
+        where
+            Self: Sized,
+            F: FnMut(&CursorRow) -> Result<bool, ekg_error::Error>,
+    {
+        let (mut opened, mut multiplicity) = OpenedCursor::new(cursor, tx.clone())?;
+        let mut variables = opened.variable_names().to_vec();
+        if let ResultSemantics::KeepMultiplicityColumn(name) = semantics {
+            let name = name.trim_start_matches(['?', '$']).to_string();
+            if variables.contains(&name) {
+                return Err(ekg_error::Error::Exception {
+                    action:  format!("adding the multiplicity column ?{name}"),
+                    message: "there already is an answer variable with that name".to_string(),
+                });
+            }
+            variables.push(name);
+        }
+        self.start(variables.as_slice())?;
+        // The distinct rows seen so far, for set semantics, and their
+        // multiplicities (in the order in which they first occur) for a
+        // multiplicity column
+        let mut seen = HashSet::new();
+        let mut distinct: Vec<(Vec<Option<Term>>, usize)> = Vec::new();
+        let mut positions: HashMap<Vec<Option<Term>>, usize> = HashMap::new();
+        let mut rowid = 0_usize;
+        let mut count = 0_usize;
+        let mut written = 0_usize;
+        while multiplicity > 0 {
+            rowid += 1;
+            count += multiplicity;
+            let row = CursorRow {
+                opened:       &opened,
+                multiplicity: &multiplicity,
+                count:        &count,
+                rowid:        &rowid,
+            };
+            if keep(&row)? {
+                let values = (0..opened.arity)
+                    .map(|term_index| row.term(term_index))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match semantics {
+                    ResultSemantics::Bag => {
+                        self.row(values.as_slice(), multiplicity)?;
+                        written += multiplicity;
+                    },
+                    ResultSemantics::Set => {
+                        if !seen.contains(&values) {
+                            self.row(values.as_slice(), 1)?;
+                            seen.insert(values);
+                            written += 1;
+                        }
+                    },
+                    ResultSemantics::KeepMultiplicityColumn(_) => {
+                        match positions.get(&values) {
+                            Some(position) => distinct[*position].1 += multiplicity,
+                            None => {
+                                positions.insert(values.clone(), distinct.len());
+                                distinct.push((values, multiplicity));
+                            },
+                        }
+                    },
+                }
+            }
+            multiplicity = opened.advance()?;
+        }
+        let datatype = Iri::new(XSD_INTEGER).unwrap();
+        for (mut values, multiplicity) in distinct {
+            values.push(Some(Term::typed_literal(multiplicity.to_string().as_str(), datatype)));
+            self.row(values.as_slice(), 1)?;
+            written += 1;
+        }
+        self.finish()?;
+        Ok(written)
+    }
+}
+
+/// Writes the SPARQL 1.1 Query Results JSON format
+pub struct SparqlJsonSerializer<W: Write> {
+    writer:    W,
+    variables: Vec<String>,
+    first_row: bool,
+}
+
+impl<W: Write> SparqlJsonSerializer<W> {
+    pub fn new(writer: W) -> Self { Self { writer, variables: Vec::new(), first_row: true } }
+
+    pub fn into_inner(self) -> W { self.writer }
+
+    fn json<T: serde::Serialize + ?Sized>(value: &T) -> Result<String, ekg_error::Error> {
+        serde_json::to_string(value).map_err(|err| {
+            ekg_error::Error::Exception {
+                action:  "writing SPARQL results JSON".to_string(),
+                message: err.to_string(),
+            }
+        })
+    }
+}
+
+impl<W: Write> ResultSerializer for SparqlJsonSerializer<W> {
+    fn start(&mut self, variables: &[String]) -> Result<(), ekg_error::Error> {
+        self.variables = variables.to_vec();
+        self.first_row = true;
+        write!(
+            self.writer,
+            "{{\"head\":{{\"vars\":{}}},\"results\":{{\"bindings\":[",
+            Self::json(variables)?
+        )?;
+        Ok(())
+    }
+
+    fn row(
+        &mut self,
+        values: &[Option<Term>],
+        multiplicity: usize,
+    ) -> Result<(), ekg_error::Error> {
+        let mut solution = String::from("{");
+        for (variable, value) in self.variables.iter().zip(values.iter()) {
+            let Some(term) = value else {
+                continue;
+            };
+            if solution.len() > 1 {
+                solution.push(',');
+            }
+            solution.push_str(Self::json(variable)?.as_str());
+            solution.push(':');
+            solution.push_str(Self::json(&SimpleBinding::from(term))?.as_str());
+        }
+        solution.push('}');
+        for _ in 0..multiplicity {
+            if !self.first_row {
+                self.writer.write_all(b",")?;
+            }
+            self.first_row = false;
+            self.writer.write_all(solution.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ekg_error::Error> {
+        self.writer.write_all(b"]}}")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes the SPARQL 1.1 Query Results CSV format, i.e. the lexical form of
+/// each value without its datatype or language tag
+pub struct CsvSerializer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> CsvSerializer<W> {
+    pub fn new(writer: W) -> Self { Self { writer } }
+
+    pub fn into_inner(self) -> W { self.writer }
+}
+
+impl<W: Write> ResultSerializer for CsvSerializer<W> {
+    fn start(&mut self, variables: &[String]) -> Result<(), ekg_error::Error> {
+        let mut line = String::new();
+        csv_line(&mut line, variables.iter().map(|variable| Some(variable.as_str())));
+        self.writer.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    fn row(
+        &mut self,
+        values: &[Option<Term>],
+        multiplicity: usize,
+    ) -> Result<(), ekg_error::Error> {
+        let values = values
+            .iter()
+            .map(|value| value.as_ref().map(csv_value))
+            .collect::<Vec<_>>();
+        let mut line = String::new();
+        csv_line(&mut line, values.iter().map(Option::as_deref));
+        for _ in 0..multiplicity {
+            self.writer.write_all(line.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), ekg_error::Error> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{CsvSerializer, ResultSerializer, SparqlJsonSerializer},
+        crate::Term,
+        iref::Iri,
+    };
+
+    fn write<S: ResultSerializer>(serializer: &mut S) {
+        let integer = Iri::new("http://www.w3.org/2001/XMLSchema#integer").unwrap();
+        serializer
+            .start(&["s".to_string(), "age".to_string(), "label".to_string()])
+            .unwrap();
+        serializer
+            .row(
+                &[
+                    Some(Term::iri("https://ekgf.org/a").unwrap()),
+                    Some(Term::typed_literal("42", integer)),
+                    None,
+                ],
+                1,
+            )
+            .unwrap();
+        serializer
+            .row(
+                &[
+                    Some(Term::blank_node("b0")),
+                    None,
+                    Some(Term::language_tagged_literal("chat, \"le\"", "fr")),
+                ],
+                2,
+            )
+            .unwrap();
+        serializer.finish().unwrap();
+    }
+
+    #[test_log::test]
+    fn test_sparql_json() {
+        let mut serializer = SparqlJsonSerializer::new(Vec::new());
+        write(&mut serializer);
+        let actual: serde_json::Value = serde_json::from_slice(&serializer.into_inner()).unwrap();
+        let bnode = serde_json::json!({
+            "s": {"type": "bnode", "value": "b0"},
+            "label": {"type": "literal", "value": "chat, \"le\"", "xml:lang": "fr"}
+        });
+        let expected = serde_json::json!({
+            "head": {"vars": ["s", "age", "label"]},
+            "results": {"bindings": [
+                {
+                    "s": {"type": "uri", "value": "https://ekgf.org/a"},
+                    "age": {
+                        "type": "literal",
+                        "value": "42",
+                        "datatype": "http://www.w3.org/2001/XMLSchema#integer"
+                    }
+                },
+                bnode.clone(),
+                bnode
+            ]}
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test_log::test]
+    fn test_csv() {
+        let mut serializer = CsvSerializer::new(Vec::new());
+        write(&mut serializer);
+        assert_eq!(
+            String::from_utf8(serializer.into_inner()).unwrap(),
+            "s,age,label\r\nhttps://ekgf.org/a,42,\r\n_:b0,,\"chat, \"\"le\"\"\"\r\n_:b0,,\"chat, \
+             \"\"le\"\"\"\r\n"
+        );
+    }
+
+    #[test_log::test]
+    fn test_multiplicity() {
+        let values = [Some(Term::iri("https://ekgf.org/a").unwrap())];
+        let mut json = SparqlJsonSerializer::new(Vec::new());
+        let mut csv = CsvSerializer::new(Vec::new());
+        for serializer in [&mut json as &mut dyn ResultSerializer, &mut csv] {
+            serializer.start(&["s".to_string()]).unwrap();
+            serializer.row(&values, 3).unwrap();
+            serializer.row(&values, 0).unwrap();
+            serializer.finish().unwrap();
+        }
+        let json: serde_json::Value = serde_json::from_slice(&json.into_inner()).unwrap();
+        let binding = serde_json::json!({"s": {"type": "uri", "value": "https://ekgf.org/a"}});
+        assert_eq!(json["results"]["bindings"], serde_json::json!([binding, binding, binding]));
+        assert_eq!(
+            String::from_utf8(csv.into_inner()).unwrap(),
+            "s\r\nhttps://ekgf.org/a\r\nhttps://ekgf.org/a\r\nhttps://ekgf.org/a\r\n"
+        );
+    }
+}
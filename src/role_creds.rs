@@ -1,30 +1,115 @@
 // Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
 //---------------------------------------------------------------
 
+use std::fmt::{Debug, Display, Formatter};
+
 const RDFOX_DEFAULT_ROLE_USERID: &str = "admin";
 const RDFOX_DEFAULT_ROLE_PASSWD: &str = "admin";
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The environment variable with the role name for [`RoleCreds::from_env`]
+pub const RDFOX_ROLE: &str = "RDFOX_ROLE";
+/// The environment variable with the password for [`RoleCreds::from_env`]
+pub const RDFOX_PASSWORD: &str = "RDFOX_PASSWORD";
+
+/// The name and password of a role. The password is never shown by `Debug`
+/// or `Display`, nor serialized (with feature `serde`).
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RoleCreds {
     pub(crate) role_name: String,
+    #[cfg_attr(feature = "serde", serde(skip_serializing, default))]
     pub(crate) password:  String,
 }
 
+/// The well-known `admin`/`admin` credentials, see
+/// [`RoleCreds::insecure_default`]
 impl Default for RoleCreds {
-    fn default() -> Self {
-        Self {
-            role_name: RDFOX_DEFAULT_ROLE_USERID.to_string(),
-            password:  RDFOX_DEFAULT_ROLE_PASSWD.to_string(),
-        }
+    fn default() -> Self { Self::insecure_default() }
+}
+
+impl Debug for RoleCreds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RoleCreds")
+            .field("role_name", &self.role_name)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+impl Display for RoleCreds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "role [{}]", self.role_name)
     }
 }
 
 impl RoleCreds {
-    #[allow(dead_code)]
     pub fn new(role_name: &str, password: &str) -> Self {
         Self {
             role_name: role_name.to_string(),
             password:  password.to_string(),
         }
     }
+
+    /// The credentials in the environment variables [`RDFOX_ROLE`] and
+    /// [`RDFOX_PASSWORD`]
+    pub fn from_env() -> Result<Self, ekg_error::Error> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|err| {
+                ekg_error::Error::Exception {
+                    action:  format!("reading role credentials from {name}"),
+                    message: err.to_string(),
+                }
+            })
+        };
+        let role_creds = Self::new(var(RDFOX_ROLE)?.as_str(), var(RDFOX_PASSWORD)?.as_str());
+        role_creds.validate()?;
+        Ok(role_creds)
+    }
+
+    /// The well-known `admin`/`admin` credentials, only meant for tests and
+    /// local experiments
+    pub fn insecure_default() -> Self {
+        Self::new(RDFOX_DEFAULT_ROLE_USERID, RDFOX_DEFAULT_ROLE_PASSWD)
+    }
+
+    /// Shadows [`Default::default`] so that `RoleCreds::default()` warns
+    #[deprecated(note = "use RoleCreds::new, RoleCreds::from_env or RoleCreds::insecure_default")]
+    #[allow(clippy::should_implement_trait)]
+    pub fn default() -> Self { Self::insecure_default() }
+
+    pub fn role_name(&self) -> &str { self.role_name.as_str() }
+
+    /// Fail if the role name or the password is empty
+    pub fn validate(&self) -> Result<(), ekg_error::Error> {
+        let missing = if self.role_name.is_empty() {
+            "role name"
+        } else if self.password.is_empty() {
+            "password"
+        } else {
+            return Ok(());
+        };
+        Err(ekg_error::Error::Exception {
+            action:  format!("validating the credentials of {self}"),
+            message: format!("the {missing} is empty"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RoleCreds;
+
+    #[test_log::test]
+    fn test_role_creds() {
+        let role_creds = RoleCreds::new("reader", "secret");
+        assert!(role_creds.validate().is_ok());
+        assert_eq!(role_creds.to_string(), "role [reader]");
+        let debug = format!("{role_creds:?}");
+        assert!(debug.contains("reader"), "{debug}");
+        assert!(!debug.contains("secret"), "{debug}");
+
+        assert!(RoleCreds::new("", "secret").validate().is_err());
+        assert!(RoleCreds::new("reader", "").validate().is_err());
+        assert_eq!(<RoleCreds as Default>::default(), RoleCreds::insecure_default());
+    }
 }
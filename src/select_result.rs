@@ -0,0 +1,525 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{Cursor, CursorRow, OpenedCursor, ResultSemantics, Statement, Term, Transaction},
+    iref::{Iri, IriBuf},
+    serde::{Deserialize, Serialize},
+    std::{borrow::Cow, collections::HashMap, fmt::Write, sync::Arc},
+};
+
+/// The (fully materialised) solutions of a SPARQL SELECT query, see
+/// [`Cursor::select_result`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectResult {
+    /// The answer variables (without the `?`) in the order of the
+    /// projection
+    pub variables: Vec<String>,
+    /// The bound variables of each solution, unbound variables are absent,
+    /// solutions with a multiplicity greater than one are repeated unless
+    /// the result was read with another [`ResultSemantics`] than `Bag`
+    pub solutions: Vec<HashMap<String, Term>>,
+}
+
+pub(crate) const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+
+/// The kind of a [`SimpleBinding`], named as in the SPARQL 1.1 Query
+/// Results JSON format (and `typed-literal` for older endpoints).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BindingKind {
+    Uri,
+    Literal,
+    TypedLiteral,
+    Bnode,
+}
+
+/// The value bound to a variable in the shape that Python's SPARQLWrapper
+/// returns in `results["bindings"]`, i.e.
+/// `{"type": "literal", "value": "...", "datatype": "...", "xml:lang": "..."}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimpleBinding {
+    #[serde(rename = "type")]
+    pub kind:     BindingKind,
+    pub value:    String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datatype: Option<String>,
+    #[serde(default, rename = "xml:lang", skip_serializing_if = "Option::is_none")]
+    pub lang:     Option<String>,
+}
+
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+impl From<&Term> for SimpleBinding {
+    fn from(term: &Term) -> Self {
+        match term {
+            Term::Iri(iri) => {
+                Self { kind: BindingKind::Uri, value: iri.as_str().to_string(), datatype: None, lang: None }
+            }
+            Term::BlankNode(label) => {
+                Self { kind: BindingKind::Bnode, value: label.clone(), datatype: None, lang: None }
+            }
+            Term::Literal { lexical_form, datatype, language } => {
+                Self {
+                    kind:     BindingKind::Literal,
+                    value:    lexical_form.clone(),
+                    datatype: datatype
+                        .as_ref()
+                        .map(|datatype| datatype.as_str().to_string())
+                        .filter(|datatype| datatype != XSD_STRING),
+                    lang:     language.clone(),
+                }
+            }
+        }
+    }
+}
+
+impl SimpleBinding {
+    /// Upgrade this binding back to a typed [`Term`].
+    pub fn as_typed(&self) -> Result<Term, ekg_error::Error> {
+        match self.kind {
+            BindingKind::Uri => Term::iri(self.value.as_str()),
+            BindingKind::Bnode => Ok(Term::blank_node(self.value.as_str())),
+            BindingKind::Literal | BindingKind::TypedLiteral => {
+                match (&self.lang, &self.datatype) {
+                    (Some(lang), _) => {
+                        Ok(Term::language_tagged_literal(
+                            self.value.as_str(),
+                            lang.as_str(),
+                        ))
+                    }
+                    (None, Some(datatype)) if datatype != XSD_STRING => {
+                        let datatype = IriBuf::new(datatype.clone()).map_err(|_| {
+                            ekg_error::Error::Exception {
+                                action:  "upgrading a simple binding".to_string(),
+                                message: format!("<{datatype}> is not a valid datatype IRI"),
+                            }
+                        })?;
+                        Ok(Term::typed_literal(self.value.as_str(), &datatype))
+                    }
+                    _ => Ok(Term::string(self.value.as_str())),
+                }
+            }
+        }
+    }
+}
+
+/// The value of the given term in the SPARQL 1.1 Query Results CSV format
+pub(crate) fn csv_value(term: &Term) -> Cow<'_, str> {
+    match term {
+        Term::Iri(iri) => Cow::Borrowed(iri.as_str()),
+        Term::BlankNode(label) => Cow::Owned(format!("_:{label}")),
+        Term::Literal { lexical_form, .. } => Cow::Borrowed(lexical_form.as_str()),
+    }
+}
+
+/// Append a line with the given fields (empty when `None`), quoted where
+/// needed, in the SPARQL 1.1 Query Results CSV format
+pub(crate) fn csv_line<'a>(csv: &mut String, fields: impl Iterator<Item = Option<&'a str>>) {
+    for (index, value) in fields.enumerate() {
+        if index > 0 {
+            csv.push(',');
+        }
+        match value {
+            Some(value) if value.contains(['"', ',', '\r', '\n']) => {
+                csv.push('"');
+                csv.push_str(value.replace('"', "\"\"").as_str());
+                csv.push('"');
+            },
+            Some(value) => csv.push_str(value),
+            None => {},
+        }
+    }
+    csv.push_str("\r\n");
+}
+
+impl SelectResult {
+    /// Read all solutions of the given cursor, with bag semantics.
+    pub fn from_cursor(cursor: &mut Cursor, tx: &Arc<Transaction>) -> Result<Self, ekg_error::Error> {
+        Self::from_cursor_with_semantics(cursor, tx, &ResultSemantics::Bag)
+    }
+
+    /// Read all solutions of the given cursor, expanding or merging their
+    /// multiplicities according to the given [`ResultSemantics`].
+    pub fn from_cursor_with_semantics(
+        cursor: &mut Cursor,
+        tx: &Arc<Transaction>,
+        semantics: &ResultSemantics,
+    ) -> Result<Self, ekg_error::Error> {
+        let (mut opened, mut multiplicity) = OpenedCursor::new(cursor, tx.clone())?;
+        let variables = opened.variable_names().to_vec();
+        let mut rows = Vec::new();
+        let mut rowid = 0_usize;
+        let mut count = 0_usize;
+        while multiplicity > 0 {
+            rowid += 1;
+            count += multiplicity;
+            let row = CursorRow {
+                opened:       &opened,
+                multiplicity: &multiplicity,
+                count:        &count,
+                rowid:        &rowid,
+            };
+            let mut solution = HashMap::new();
+            for (term_index, variable) in variables.iter().enumerate() {
+                if let Some(term) = row.term(term_index)? {
+                    solution.insert(variable.clone(), term);
+                }
+            }
+            rows.push((solution, multiplicity));
+            multiplicity = opened.advance()?;
+        }
+        Self::materialize(variables, rows, semantics)
+    }
+
+    /// A copy of this result with the given [`ResultSemantics`] applied to
+    /// its solutions, where identical solutions count as one solution with
+    /// a multiplicity.
+    ///
+    /// Since a result with set semantics has lost its multiplicities, turning
+    /// it back into a bag does not bring them back.
+    pub fn with_semantics(&self, semantics: &ResultSemantics) -> Result<Self, ekg_error::Error> {
+        Self::materialize(
+            self.variables.clone(),
+            self.solutions
+                .iter()
+                .map(|solution| (solution.clone(), 1))
+                .collect(),
+            semantics,
+        )
+    }
+
+    /// Turn the given solutions and their multiplicities into a result with
+    /// the given semantics
+    fn materialize(
+        mut variables: Vec<String>,
+        rows: Vec<(HashMap<String, Term>, usize)>,
+        semantics: &ResultSemantics,
+    ) -> Result<Self, ekg_error::Error> {
+        if *semantics == ResultSemantics::Bag {
+            let solutions = rows
+                .into_iter()
+                .flat_map(|(solution, multiplicity)| std::iter::repeat(solution).take(multiplicity))
+                .collect();
+            return Ok(Self { variables, solutions });
+        }
+        // Merge identical solutions (keeping the order in which they first
+        // occur), using the values in the order of the variables as the key
+        let mut distinct: Vec<(HashMap<String, Term>, usize)> = Vec::new();
+        let mut positions: HashMap<Vec<Option<Term>>, usize> = HashMap::new();
+        for (solution, multiplicity) in rows {
+            let key = variables
+                .iter()
+                .map(|variable| solution.get(variable).cloned())
+                .collect::<Vec<_>>();
+            match positions.get(&key) {
+                Some(position) => distinct[*position].1 += multiplicity,
+                None => {
+                    positions.insert(key, distinct.len());
+                    distinct.push((solution, multiplicity));
+                }
+            }
+        }
+        let solutions = match semantics {
+            ResultSemantics::KeepMultiplicityColumn(name) => {
+                let name = name.trim_start_matches(['?', '$']).to_string();
+                if variables.contains(&name) {
+                    return Err(ekg_error::Error::Exception {
+                        action:  format!("adding the multiplicity column ?{name}"),
+                        message: "there already is an answer variable with that name".to_string(),
+                    });
+                }
+                let datatype = Iri::new(XSD_INTEGER).unwrap();
+                let solutions = distinct
+                    .into_iter()
+                    .map(|(mut solution, multiplicity)| {
+                        solution.insert(
+                            name.clone(),
+                            Term::typed_literal(multiplicity.to_string().as_str(), datatype),
+                        );
+                        solution
+                    })
+                    .collect();
+                variables.push(name);
+                solutions
+            }
+            _ => distinct.into_iter().map(|(solution, _)| solution).collect(),
+        };
+        Ok(Self { variables, solutions })
+    }
+
+    /// The solutions shaped like SPARQLWrapper's `results["bindings"]`
+    pub fn to_simple_bindings(&self) -> Vec<HashMap<String, SimpleBinding>> {
+        self.solutions
+            .iter()
+            .map(|solution| {
+                solution
+                    .iter()
+                    .map(|(variable, term)| (variable.clone(), SimpleBinding::from(term)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Render the values of the given variables (with or without the `?`)
+    /// as a SPARQL `VALUES (?a ?b) { ... }` block, with `UNDEF` for unbound
+    /// values, to freeze the current results of a query.
+    ///
+    /// Fails for blank nodes (which are not allowed in a `VALUES` block) and
+    /// when the block would be larger than the maximum statement size (see
+    /// [`Statement::max_size`]).
+    pub fn to_values_block(&self, vars: &[&str]) -> Result<String, ekg_error::Error> {
+        let vars = vars
+            .iter()
+            .map(|var| var.trim_start_matches(['?', '$']))
+            .collect::<Vec<_>>();
+        let mut block = format!(
+            "VALUES ({}) {{\n",
+            vars.iter()
+                .map(|var| format!("?{var}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        for solution in self.solutions.iter() {
+            let values = vars
+                .iter()
+                .map(|var| {
+                    match solution.get(*var) {
+                        None => Ok("UNDEF".to_string()),
+                        Some(Term::BlankNode(label)) => {
+                            Err(ekg_error::Error::Exception {
+                                action:  "rendering a VALUES block".to_string(),
+                                message: format!("?{var} is bound to blank node _:{label}"),
+                            })
+                        }
+                        Some(term) => Ok(term.to_string()),
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            writeln!(block, "    ({})", values.join(" ")).unwrap();
+            Self::check_size("rendering a VALUES block", block.len())?;
+        }
+        block.push('}');
+        Ok(block)
+    }
+
+    /// Render all solutions as a Rust `const` with an array of tuples of
+    /// strings (holding the values in SPARQL syntax, or `UNDEF`), in the
+    /// order of [`SelectResult::variables`], to paste into a test.
+    ///
+    /// Fails when the snippet would be larger than the maximum statement
+    /// size (see [`Statement::max_size`]).
+    pub fn to_test_fixture(&self, name: &str) -> Result<String, ekg_error::Error> {
+        let name = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect::<String>();
+        let tuple_type = match self.variables.len() {
+            1 => "(&str,)".to_string(),
+            arity => format!("({})", vec!["&str"; arity].join(", ")),
+        };
+        let mut fixture = format!(
+            "/// ?{}\npub const {name}: [{tuple_type}; {}] = [\n",
+            self.variables.join(" ?"),
+            self.solutions.len()
+        );
+        for solution in self.solutions.iter() {
+            let values = self
+                .variables
+                .iter()
+                .map(|var| {
+                    let value = solution
+                        .get(var)
+                        .map_or_else(|| "UNDEF".to_string(), |term| term.to_string());
+                    format!("{value:?}")
+                })
+                .collect::<Vec<_>>();
+            if values.len() == 1 {
+                writeln!(fixture, "    ({},),", values[0]).unwrap();
+            } else {
+                writeln!(fixture, "    ({}),", values.join(", ")).unwrap();
+            }
+            Self::check_size("generating a test fixture", fixture.len())?;
+        }
+        fixture.push_str("];\n");
+        Ok(fixture)
+    }
+
+    fn check_size(action: &str, size: usize) -> Result<(), ekg_error::Error> {
+        if size > Statement::max_size() {
+            return Err(ekg_error::Error::Exception {
+                action:  action.to_string(),
+                message: format!(
+                    "output exceeds the maximum of {} bytes",
+                    Statement::max_size()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Render all solutions in the SPARQL 1.1 Query Results CSV format, i.e.
+    /// a header with the variables and a line per solution with the lexical
+    /// form of each value (empty when unbound).
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        csv_line(&mut csv, self.variables.iter().map(|variable| Some(variable.as_str())));
+        for solution in self.solutions.iter() {
+            let values = self
+                .variables
+                .iter()
+                .map(|variable| solution.get(variable).map(csv_value))
+                .collect::<Vec<_>>();
+            csv_line(&mut csv, values.iter().map(Option::as_deref));
+        }
+        csv
+    }
+
+    /// The inverse of [`SelectResult::to_simple_bindings`]
+    pub fn from_simple_bindings(
+        variables: Vec<String>,
+        bindings: &[HashMap<String, SimpleBinding>],
+    ) -> Result<Self, ekg_error::Error> {
+        let solutions = bindings
+            .iter()
+            .map(|solution| {
+                solution
+                    .iter()
+                    .map(|(variable, binding)| Ok((variable.clone(), binding.as_typed()?)))
+                    .collect::<Result<HashMap<_, _>, ekg_error::Error>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { variables, solutions })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{BindingKind, SelectResult, SimpleBinding},
+        crate::{ResultSemantics, Term},
+        std::collections::HashMap,
+    };
+
+    fn sample() -> SelectResult {
+        SelectResult {
+            variables: vec!["s".to_string(), "label".to_string()],
+            solutions: vec![
+                HashMap::from([
+                    ("s".to_string(), Term::iri("https://ekgf.org/a").unwrap()),
+                    ("label".to_string(), Term::string("say \"hi\"")),
+                ]),
+                HashMap::from([("s".to_string(), Term::iri("https://ekgf.org/b").unwrap())]),
+            ],
+        }
+    }
+
+    #[test_log::test]
+    fn test_values_block() {
+        assert_eq!(
+            sample().to_values_block(&["?s", "label"]).unwrap(),
+            "VALUES (?s ?label) {\n    (<https://ekgf.org/a> \"say \\\"hi\\\"\")\n    \
+             (<https://ekgf.org/b> UNDEF)\n}"
+        );
+        let mut with_blank_node = sample();
+        with_blank_node.solutions[1].insert("label".to_string(), Term::blank_node("b0"));
+        assert!(with_blank_node.to_values_block(&["s", "label"]).is_err());
+    }
+
+    #[test_log::test]
+    fn test_test_fixture() {
+        assert_eq!(
+            sample().to_test_fixture("my fixture").unwrap(),
+            "/// ?s ?label\npub const MY_FIXTURE: [(&str, &str); 2] = [\n    \
+             (\"<https://ekgf.org/a>\", \"\\\"say \\\\\\\"hi\\\\\\\"\\\"\"),\n    \
+             (\"<https://ekgf.org/b>\", \"UNDEF\"),\n];\n"
+        );
+    }
+
+    /// Three solutions of which two are the same
+    fn sample_with_duplicates() -> SelectResult {
+        let mut result = sample();
+        result.solutions.push(result.solutions[0].clone());
+        result.solutions.swap(1, 2);
+        result
+    }
+
+    #[test_log::test]
+    fn test_semantics() {
+        let bag = sample_with_duplicates();
+        assert_eq!(bag.with_semantics(&ResultSemantics::Bag).unwrap(), bag);
+        assert_eq!(ResultSemantics::default(), ResultSemantics::Bag);
+
+        let set = bag.with_semantics(&ResultSemantics::Set).unwrap();
+        assert_eq!(set.variables, bag.variables);
+        assert_eq!(set, sample());
+
+        let counted = bag
+            .with_semantics(&ResultSemantics::KeepMultiplicityColumn("?n".to_string()))
+            .unwrap();
+        assert_eq!(counted.variables, vec!["s", "label", "n"]);
+        assert_eq!(counted.solutions.len(), 2);
+        let multiplicities = counted
+            .solutions
+            .iter()
+            .map(|solution| solution["n"].to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            multiplicities,
+            vec![
+                "\"2\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+                "\"1\"^^<http://www.w3.org/2001/XMLSchema#integer>"
+            ]
+        );
+        assert!(bag
+            .with_semantics(&ResultSemantics::KeepMultiplicityColumn("label".to_string()))
+            .is_err());
+
+        assert_eq!(
+            bag.to_csv(),
+            "s,label\r\nhttps://ekgf.org/a,\"say \"\"hi\"\"\"\r\nhttps://ekgf.org/a,\"say \
+             \"\"hi\"\"\"\r\nhttps://ekgf.org/b,\r\n"
+        );
+        assert_eq!(
+            set.to_csv(),
+            "s,label\r\nhttps://ekgf.org/a,\"say \"\"hi\"\"\"\r\nhttps://ekgf.org/b,\r\n"
+        );
+        assert_eq!(
+            counted.to_csv(),
+            "s,label,n\r\nhttps://ekgf.org/a,\"say \"\"hi\"\"\",2\r\nhttps://ekgf.org/b,,1\r\n"
+        );
+    }
+
+    #[test_log::test]
+    fn test_round_trip() {
+        let json = r#"[
+            {
+                "s": {"type": "uri", "value": "https://ekgf.org/a"},
+                "label": {"type": "literal", "value": "chat", "xml:lang": "fr"},
+                "age": {"type": "typed-literal", "value": "42", "datatype": "http://www.w3.org/2001/XMLSchema#integer"}
+            },
+            {
+                "s": {"type": "bnode", "value": "b0"},
+                "name": {"type": "literal", "value": "Alice"}
+            }
+        ]"#;
+        let bindings: Vec<HashMap<String, SimpleBinding>> = serde_json::from_str(json).unwrap();
+        let variables = vec!["s".to_string(), "label".to_string(), "age".to_string(), "name".to_string()];
+        let result = SelectResult::from_simple_bindings(variables.clone(), &bindings).unwrap();
+        assert_eq!(
+            result.solutions[0]["age"].to_string(),
+            r#""42"^^<http://www.w3.org/2001/XMLSchema#integer>"#
+        );
+        assert!(!result.solutions[1].contains_key("label"));
+
+        let round_tripped = result.to_simple_bindings();
+        // Only the (legacy) typed-literal kind is normalised to literal
+        assert_eq!(round_tripped[0]["age"].kind, BindingKind::Literal);
+        assert_eq!(round_tripped[0]["label"], bindings[0]["label"]);
+        assert_eq!(round_tripped[1], bindings[1]);
+        assert_eq!(
+            SelectResult::from_simple_bindings(variables, &round_tripped).unwrap(),
+            result
+        );
+    }
+}
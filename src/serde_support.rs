@@ -0,0 +1,142 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+//! `Serialize` and `Deserialize` (feature `serde`) of the types that
+//! wrap an RDFox object, the other types derive them where they're defined.
+
+use {
+    crate::{parameters::SENSITIVE_PARAMETERS, Namespaces, Parameters, Statement},
+    ekg_namespace::Namespace,
+    serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer},
+    std::collections::{BTreeMap, HashMap},
+};
+
+/// The keys and values, without sensitive ones like `license-content` so that
+/// a serialized config can't leak them; set those after deserializing
+impl Serialize for Parameters {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            self.get_all()
+                .filter(|(key, _)| !SENSITIVE_PARAMETERS.contains(&key.as_str())),
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for Parameters {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map = HashMap::<String, String>::deserialize(deserializer)?;
+        Parameters::from_map(map).map_err(D::Error::custom)
+    }
+}
+
+/// A statement as its namespaces (by name, like `skos:`) and its text
+#[derive(Serialize, Deserialize)]
+struct SerializedStatement {
+    prefixes: BTreeMap<String, String>,
+    text:     String,
+}
+
+impl Serialize for Statement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut prefixes = BTreeMap::new();
+        self.prefixes.for_each_namespace_do(|name, namespace| {
+            prefixes.insert(name.to_string(), namespace.iri.as_str().to_string());
+            Ok::<(), S::Error>(())
+        })?;
+        SerializedStatement { prefixes, text: self.text.clone() }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Statement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let serialized = SerializedStatement::deserialize(deserializer)?;
+        let statement = || -> Result<Statement, ekg_error::Error> {
+            let prefixes = Namespaces::empty()?;
+            for (name, iri) in serialized.prefixes.iter() {
+                let iri = iref::Iri::new(iri.as_str()).map_err(|_| {
+                    ekg_error::Error::Exception {
+                        action:  format!("deserializing namespace {name}"),
+                        message: format!("{iri:?} is not an IRI"),
+                    }
+                })?;
+                prefixes.declare_namespace(&Namespace::declare_iref_iri(name, iri)?)?;
+            }
+            // The text has the PREFIX declarations already, which `new`
+            // recognizes, so they're not added again
+            Statement::new(&prefixes, serialized.text.as_str().into())
+        };
+        statement().map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            DataStore,
+            DataStoreType,
+            FactDomain,
+            Namespaces,
+            Parameters,
+            PersistenceMode,
+            RoleCreds,
+            Statement,
+        },
+        ekg_namespace::consts::PREFIX_SKOS,
+    };
+
+    #[test_log::test]
+    fn test_data_store() {
+        let parameters = Parameters::empty()
+            .unwrap()
+            .persist_datastore(PersistenceMode::Off)
+            .unwrap()
+            .data_store_type(DataStoreType::ParallelNW)
+            .unwrap();
+        let data_store = DataStore::declare_with_parameters("config", parameters).unwrap();
+        let json = serde_json::to_string(data_store.as_ref()).unwrap();
+        assert!(json.contains(r#""type":"parallel-nw""#), "{json}");
+        let deserialized: DataStore = serde_json::from_str(json.as_str()).unwrap();
+        assert_eq!(&deserialized, data_store.as_ref());
+        assert_eq!(deserialized.persistence(), Some(PersistenceMode::Off));
+    }
+
+    #[test_log::test]
+    fn test_parameters_skip_sensitive() {
+        let parameters = Parameters::empty().unwrap();
+        parameters.set_string("license-content", "secret").unwrap();
+        parameters.set_string("persistence", "off").unwrap();
+        let json = serde_json::to_string(&parameters).unwrap();
+        assert_eq!(json, r#"{"persistence":"off"}"#);
+    }
+
+    #[test_log::test]
+    fn test_role_creds() {
+        let json = serde_json::to_string(&RoleCreds::new("reader", "secret")).unwrap();
+        assert_eq!(json, r#"{"role_name":"reader"}"#);
+        let role_creds: RoleCreds =
+            serde_json::from_str(r#"{"role_name":"reader","password":"secret"}"#).unwrap();
+        assert_eq!(role_creds, RoleCreds::new("reader", "secret"));
+    }
+
+    #[test_log::test]
+    fn test_statement() {
+        let prefixes = Namespaces::empty().unwrap().add_namespace(&PREFIX_SKOS).unwrap();
+        let statement =
+            Statement::new(&prefixes, "SELECT ?label { ?s skos:prefLabel ?label }".into()).unwrap();
+        let json = serde_json::to_string(&statement).unwrap();
+        let deserialized: Statement = serde_json::from_str(json.as_str()).unwrap();
+        assert_eq!(deserialized.as_str().trim(), statement.as_str().trim());
+        assert!(deserialized.prefixes.get("skos:").is_some());
+    }
+
+    #[test_log::test]
+    fn test_enums() {
+        assert_eq!(serde_json::to_string(&FactDomain::ASSERTED).unwrap(), r#""explicit""#);
+        assert_eq!(
+            serde_json::to_string(&PersistenceMode::FileSequence).unwrap(),
+            r#""file-sequence""#
+        );
+        let mode: PersistenceMode = serde_json::from_str(r#""off""#).unwrap();
+        assert_eq!(mode, PersistenceMode::Off);
+    }
+}
@@ -4,7 +4,9 @@
 use {
     crate::{
         database_call,
+        Parameter,
         Parameters,
+        PersistenceMode,
         rdfox_api::{
             CServer_createFirstLocalServerRole,
             CServer_getNumberOfLocalServerRoles,
@@ -14,23 +16,73 @@ use {
             CServerConnection_newServerConnection,
         },
         RoleCreds,
+        ServerAlreadyRunning,
+        ServerInUse,
         server_connection::ServerConnection,
+        server_state::{ServerLifecycle, ServerState},
     },
     ekg_namespace::consts::LOG_TARGET_DATABASE,
     std::{
         ffi::CString,
+        path::{Path, PathBuf},
         ptr,
         sync::{
-            Arc,
             atomic::{AtomicBool, Ordering},
+            Arc,
         },
     },
 };
 
+/// Whether a local server has been started in this process and not been
+/// stopped yet, RDFox has only one
+static LOCAL_SERVER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// A local RDFox server, see [`ServerState`] for its lifecycle.
+///
+/// There can be only one at a time in a process: starting another one
+/// while it runs fails with [`ServerAlreadyRunning`], use
+/// [`Server::restart`] to replace it, for instance with another server
+/// directory.
 #[derive(Debug)]
 pub struct Server {
     default_role_creds: RoleCreds,
-    running: AtomicBool,
+    lifecycle: ServerLifecycle,
+    server_directory: Option<PathBuf>,
+    persistence: PersistenceMode,
+}
+
+/// Registration of a server connection, datastore connection, transaction or
+/// cursor as a dependent of its [`Server`], which keeps a draining server
+/// from stopping until it is dropped.
+#[derive(Debug)]
+pub(crate) struct ServerDependent {
+    server: Arc<Server>,
+}
+
+impl ServerDependent {
+    pub(crate) fn server(&self) -> &Arc<Server> { &self.server }
+
+    /// Fail with [`ServerShutDown`](crate::ServerShutDown) if the server has
+    /// been stopped with [`Server::stop`], after which the RDFox objects of
+    /// this dependent are no longer valid.
+    ///
+    /// This is a check, not a lock: a call that passed it can still be in
+    /// RDFox when another thread stops the server, see [`Server::stop`].
+    pub(crate) fn check_not_stopped(&self, action: &str) -> Result<(), ekg_error::Error> {
+        Ok(self.server.lifecycle.check_not_stopped(action)?)
+    }
+
+    /// Whether the server has been stopped, after which the RDFox objects of
+    /// this dependent must be leaked rather than destroyed when it is dropped
+    pub(crate) fn is_stopped(&self) -> bool { self.server.state() == ServerState::Stopped }
+}
+
+impl Drop for ServerDependent {
+    fn drop(&mut self) {
+        if self.server.lifecycle.release() {
+            self.server.stop_local_server();
+        }
+    }
 }
 
 impl Drop for Server {
@@ -45,7 +97,27 @@ impl std::fmt::Display for Server {
 }
 
 impl Server {
-    pub fn is_running(&self) -> bool { self.running.load(Ordering::Relaxed) }
+    pub fn is_running(&self) -> bool { self.state() == ServerState::Running }
+
+    pub fn state(&self) -> ServerState { self.lifecycle.state() }
+
+    /// The number of server connections, datastore connections,
+    /// transactions and cursors that currently use this server.
+    pub fn number_of_dependents(&self) -> usize { self.lifecycle.dependents() }
+
+    /// Register a new dependent of this server, which is refused with an
+    /// error when the server is not running (anymore).
+    pub(crate) fn dependent(self: &Arc<Self>, action: &str) -> Result<ServerDependent, ekg_error::Error> {
+        self.lifecycle.acquire(action).map_err(|err| {
+            tracing::warn!(
+                target: LOG_TARGET_DATABASE,
+                server = format!("{self:p}"),
+                "Refused {action}: {err}"
+            );
+            err
+        })?;
+        Ok(ServerDependent { server: self.clone() })
+    }
 
     pub fn start(role_creds: RoleCreds) -> Result<Arc<Self>, ekg_error::Error> {
         Self::start_with_parameters(role_creds, None)
@@ -55,6 +127,68 @@ impl Server {
         role_creds: RoleCreds,
         params: Option<Parameters>,
     ) -> Result<Arc<Self>, ekg_error::Error> {
+        role_creds.validate()?;
+        // With a dynamically linked RDFox, check that it has everything we
+        // need before calling into it
+        #[cfg(feature = "rdfox-dylib")]
+        crate::diagnostics::verify_symbols()?;
+        if LOCAL_SERVER_RUNNING
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(ServerAlreadyRunning.into());
+        }
+        Self::start_local_server(role_creds, params).map_err(|err| {
+            // A server that got started has been stopped again when it was
+            // dropped, a server that did not start is not running either
+            LOCAL_SERVER_RUNNING.store(false, Ordering::SeqCst);
+            err
+        })
+    }
+
+    /// Stop this server and start a new one with the given parameters and
+    /// the same default role. RDFox has stopped when [`Server::stop`]
+    /// returns, so the new server can use the same or another server
+    /// directory.
+    ///
+    /// Fails with [`ServerInUse`] while the server still has dependents
+    /// (see [`Server::number_of_dependents`]), which would otherwise keep
+    /// using RDFox objects of the stopped server.
+    pub fn restart(&self, params: Option<Parameters>) -> Result<Arc<Self>, ekg_error::Error> {
+        let dependents = self.number_of_dependents();
+        if dependents > 0 {
+            return Err(ServerInUse { dependents }.into());
+        }
+        tracing::debug!(
+            target: LOG_TARGET_DATABASE,
+            server = format!("{self:p}"),
+            "Restarting local RDFox server"
+        );
+        self.stop();
+        Self::start_with_parameters(self.default_role_creds.clone(), params)
+    }
+
+    /// The `server-directory` parameter that the server was started with,
+    /// where it persists its data stores
+    pub fn server_directory(&self) -> Option<&Path> { self.server_directory.as_deref() }
+
+    /// The `persistence` parameter that the server was started with, the
+    /// default for data stores that don't declare their own. RDFox keeps
+    /// everything in memory when it isn't set.
+    pub fn persistence(&self) -> PersistenceMode { self.persistence }
+
+    fn start_local_server(
+        role_creds: RoleCreds,
+        params: Option<Parameters>,
+    ) -> Result<Arc<Self>, ekg_error::Error> {
+        let server_directory = match params.as_ref() {
+            Some(params) => params.get_string(Parameter::ServerDirectory.name()?, "")?,
+            None => String::new(),
+        };
+        let persistence = params
+            .as_ref()
+            .and_then(Parameters::persistence)
+            .unwrap_or(PersistenceMode::Off);
         if let Some(params) = params {
             #[cfg(feature = "rdfox-7-0")]
             {
@@ -87,12 +221,16 @@ impl Server {
         };
         let server = Server {
             default_role_creds: role_creds,
-            running: AtomicBool::new(true),
+            lifecycle: ServerLifecycle::default(),
+            server_directory: (!server_directory.is_empty())
+                .then(|| PathBuf::from(server_directory)),
+            persistence,
         };
 
         if server.get_number_of_local_server_roles()? == 0 {
             server.create_role(&server.default_role_creds)?;
         }
+        server.lifecycle.started();
 
         tracing::debug!(
             target: LOG_TARGET_DATABASE,
@@ -102,6 +240,7 @@ impl Server {
     }
 
     pub fn create_role(&self, role_creds: &RoleCreds) -> Result<(), ekg_error::Error> {
+        role_creds.validate()?;
         let c_role_name = CString::new(role_creds.role_name.as_str()).unwrap();
         let c_password = CString::new(role_creds.password.as_str()).unwrap();
         let msg = format!(
@@ -134,11 +273,13 @@ impl Server {
         self: &Arc<Self>,
         role_creds: RoleCreds,
     ) -> Result<Arc<ServerConnection>, ekg_error::Error> {
+        role_creds.validate()?;
+        let dependent = self.dependent("creating a server connection")?;
         let c_role_name = CString::new(role_creds.role_name.as_str()).unwrap();
         let c_password = CString::new(role_creds.password.as_str()).unwrap();
         let mut server_connection_ptr: *mut CServerConnection = ptr::null_mut();
         database_call!(
-            "Creating a server connection",
+            format!("Creating a server connection for {role_creds}").as_str(),
             CServerConnection_newServerConnection(
                 c_role_name.as_ptr(),
                 c_password.as_ptr(),
@@ -148,19 +289,50 @@ impl Server {
         if server_connection_ptr.is_null() {
             tracing::error!(
                 target: LOG_TARGET_DATABASE,
-                "Could not establish connection to {self}"
+                "Could not establish connection to {self} for {role_creds}"
             );
             return Err(ekg_error::Error::CouldNotConnectToServer);
         }
         Ok(Arc::new(ServerConnection::new(
             role_creds,
-            self.clone(),
+            dependent,
             server_connection_ptr,
         )))
     }
 
-    pub fn stop(&mut self) {
-        *self.running.get_mut() = false;
+    /// Stop accepting new connections, transactions and cursors and stop the
+    /// server as soon as the existing ones are gone (which may be right
+    /// away).
+    pub fn begin_shutdown(&self) {
+        tracing::debug!(
+            target: LOG_TARGET_DATABASE,
+            server = format!("{self:p}"),
+            dependents = self.number_of_dependents(),
+            "Shutting down local RDFox server"
+        );
+        if self.lifecycle.begin_shutdown() {
+            self.stop_local_server();
+        }
+    }
+
+    /// Stop the server right away, regardless of its dependents. Their
+    /// connections, transactions and cursors fail with
+    /// [`ServerShutDown`](crate::ServerShutDown) from then on, and are
+    /// leaked (with a warning) rather than destroyed when they're dropped,
+    /// since RDFox has freed them along with the server.
+    ///
+    /// It does not wait for calls that other threads are making at that
+    /// moment: one that checked the server just before it was stopped goes
+    /// on to use RDFox objects that have been freed. Only stop a server that
+    /// no other thread uses anymore, use [`Server::begin_shutdown`] to wait
+    /// for its dependents to be dropped otherwise.
+    pub fn stop(&self) {
+        if self.lifecycle.stop() {
+            self.stop_local_server();
+        }
+    }
+
+    fn stop_local_server(&self) {
         tracing::trace!(
             target: LOG_TARGET_DATABASE,
             server = format!("{self:p}"),
@@ -169,6 +341,7 @@ impl Server {
         unsafe {
             CServer_stopLocalServer();
         }
+        LOCAL_SERVER_RUNNING.store(false, Ordering::SeqCst);
         tracing::trace!(
             target: LOG_TARGET_DATABASE,
             server = format!("{self:p}"),
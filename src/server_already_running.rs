@@ -0,0 +1,43 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// A local RDFox server was started while another one is still running in
+/// this process (RDFox has only one), see
+/// [`Server::restart`](crate::Server::restart) to replace it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerAlreadyRunning;
+
+impl Display for ServerAlreadyRunning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ServerAlreadyRunning: a local RDFox server is already running in this process, stop \
+             it first"
+        )
+    }
+}
+
+impl std::error::Error for ServerAlreadyRunning {}
+
+impl TypedError for ServerAlreadyRunning {
+    const NAME: &'static str = "ServerAlreadyRunning";
+
+    fn action(&self) -> String { "starting a local RDFox server".to_string() }
+}
+
+impl From<ServerAlreadyRunning> for ekg_error::Error {
+    fn from(value: ServerAlreadyRunning) -> Self { value.to_error() }
+}
+
+impl ServerAlreadyRunning {
+    /// The [`ServerAlreadyRunning`] error that the given error was made from, if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
@@ -1,27 +1,41 @@
 use {
+    super::data_store_cache::DataStoreCache,
     crate::{
+        ConnectableDataStore,
         database_call,
         DataStore,
         DataStoreConnection,
+        data_store_state::state_updates,
+        Instrumentation,
+        NoInstrumentation,
+        Parameters,
+        PoolOptions,
         rdfox_api::{
             CServerConnection,
             CServerConnection_createDataStore,
             CServerConnection_deleteDataStore,
             CServerConnection_destroy,
+            CServerConnection_getComponentInfo,
             CServerConnection_getMemoryUse,
             CServerConnection_getNumberOfThreads,
             CServerConnection_getVersion,
+            CServerConnection_listDataStores,
             CServerConnection_newDataStoreConnection,
             CServerConnection_setNumberOfThreads,
         },
         RoleCreds,
+        server::ServerDependent,
         Server,
+        statistics::{read_component_info, MemoryUse, ServerStatistics},
+        Transaction,
     },
     ekg_namespace::consts::LOG_TARGET_DATABASE,
+    r2d2::Pool,
     std::{
-        ffi::{CStr, CString},
+        ffi::{c_void, CStr, CString},
+        os::raw::c_char,
         ptr,
-        sync::Arc,
+        sync::{Arc, RwLock},
     },
 };
 
@@ -31,7 +45,10 @@ pub struct ServerConnection {
     #[allow(dead_code)]
     role_creds: RoleCreds,
     server: Arc<Server>,
-    inner: *mut CServerConnection,
+    pub(super) inner: *mut CServerConnection,
+    cache: DataStoreCache,
+    instrumentation: RwLock<Arc<dyn Instrumentation>>,
+    dependent: ServerDependent,
 }
 
 unsafe impl Sync for ServerConnection {}
@@ -44,8 +61,17 @@ impl Drop for ServerConnection {
             !self.inner.is_null(),
             "Could not drop ServerConnection"
         );
-        unsafe {
-            CServerConnection_destroy(self.inner);
+        // RDFox has already freed everything of a stopped server, so
+        // destroying our pointer now would be a use-after-free
+        if self.dependent.is_stopped() {
+            tracing::warn!(
+                target: LOG_TARGET_DATABASE,
+                "Leaking {self:} since the server has been stopped"
+            );
+        } else {
+            unsafe {
+                CServerConnection_destroy(self.inner);
+            }
         }
         self.inner = ptr::null_mut();
         tracing::debug!(target: LOG_TARGET_DATABASE, "Dropped {self:}");
@@ -61,15 +87,18 @@ impl std::fmt::Display for ServerConnection {
 impl ServerConnection {
     pub(crate) fn new(
         role_creds: RoleCreds,
-        server: Arc<Server>,
+        dependent: ServerDependent,
         server_connection_ptr: *mut CServerConnection,
     ) -> Self {
         assert!(!server_connection_ptr.is_null());
-        assert!(
-            server.is_running(),
-            "cannot connect to an RDFox server that is not running"
-        );
-        let connection = Self { role_creds, server, inner: server_connection_ptr };
+        let connection = Self {
+            role_creds,
+            server: dependent.server().clone(),
+            inner: server_connection_ptr,
+            cache: DataStoreCache::default(),
+            instrumentation: RwLock::new(Arc::new(NoInstrumentation)),
+            dependent,
+        };
         tracing::debug!(
             target: LOG_TARGET_DATABASE,
             "Established {connection:}"
@@ -77,6 +106,27 @@ impl ServerConnection {
         connection
     }
 
+    pub fn server(&self) -> &Arc<Server> { &self.server }
+
+    /// Fail with [`ServerShutDown`](crate::ServerShutDown) once the server
+    /// has been stopped, after which the RDFox connection is no longer valid
+    pub(crate) fn check_not_stopped(&self, action: &str) -> Result<(), ekg_error::Error> {
+        self.dependent.check_not_stopped(action)
+    }
+
+    /// The [`Instrumentation`] of the data store connections made by this
+    /// connection that have none of their own
+    pub fn instrumentation(&self) -> Arc<dyn Instrumentation> {
+        self.instrumentation.read().unwrap().clone()
+    }
+
+    /// Replace the [`Instrumentation`] (by default [`NoInstrumentation`]) of
+    /// all data store connections of this connection, including the ones
+    /// that already exist, unless they have one of their own
+    pub fn set_instrumentation(&self, instrumentation: Arc<dyn Instrumentation>) {
+        *self.instrumentation.write().unwrap() = instrumentation;
+    }
+
     /// Return the version number of the underlying database engine
     ///
     /// CRDFOX const CException*
@@ -85,6 +135,7 @@ impl ServerConnection {
     ///     const char** version
     /// );
     pub fn get_version(&self) -> Result<String, ekg_error::Error> {
+        self.check_not_stopped("getting the version")?;
         let mut c_buf: *const std::os::raw::c_char = ptr::null();
         database_call!(
             "Getting the version",
@@ -95,6 +146,7 @@ impl ServerConnection {
     }
 
     pub fn get_number_of_threads(&self) -> Result<u32, ekg_error::Error> {
+        self.check_not_stopped("getting the number of server threads")?;
         let mut number_of_threads = 0_usize;
         database_call!(
             format!(
@@ -117,6 +169,7 @@ impl ServerConnection {
         number_of_threads: usize,
     ) -> Result<(), ekg_error::Error> {
         assert!(!self.inner.is_null());
+        self.check_not_stopped("setting the number of server threads")?;
         let msg = format!(
             "Setting the number of threads to {}",
             number_of_threads
@@ -127,7 +180,10 @@ impl ServerConnection {
         )
     }
 
-    pub fn get_memory_use(&self) -> Result<(usize, usize), ekg_error::Error> {
+    /// How much memory the server used at most so far and how much it can
+    /// still use
+    pub fn memory_use(&self) -> Result<MemoryUse, ekg_error::Error> {
+        self.check_not_stopped("getting the memory use of the server")?;
         let mut max_used_bytes = 0_usize;
         let mut available_bytes = 0_usize;
         database_call!(CServerConnection_getMemoryUse(
@@ -135,14 +191,43 @@ impl ServerConnection {
             &mut max_used_bytes,
             &mut available_bytes
         ))?;
-        Ok((max_used_bytes, available_bytes))
+        Ok(MemoryUse { max_used_bytes, available_bytes })
     }
 
+    #[deprecated(note = "use ServerConnection::memory_use")]
+    pub fn get_memory_use(&self) -> Result<(usize, usize), ekg_error::Error> {
+        let memory_use = self.memory_use()?;
+        Ok((memory_use.max_used_bytes, memory_use.available_bytes))
+    }
+
+    /// The version, threads and memory use of the server, its datastores
+    /// and whatever else RDFox reports about it.
+    pub fn server_statistics(&self) -> Result<ServerStatistics, ekg_error::Error> {
+        assert!(!self.inner.is_null());
+        self.check_not_stopped("getting the statistics of the server")?;
+        let MemoryUse { max_used_bytes, available_bytes } = self.memory_use()?;
+        let component_info = read_component_info("getting the statistics of the server", |info| {
+            unsafe { CServerConnection_getComponentInfo(self.inner, true, info) }
+        })?;
+        Ok(ServerStatistics {
+            version: self.get_version()?,
+            number_of_threads: self.get_number_of_threads()?,
+            max_used_bytes,
+            available_bytes,
+            data_stores: self.list_data_stores()?,
+            component_info,
+        })
+    }
+
+    /// Delete the given datastore, evicting it (and its shared pool) from
+    /// the cache of this connection first.
     pub fn delete_data_store(
         &self,
         data_store: &DataStore,
     ) -> Result<(), ekg_error::Error> {
         assert!(!self.inner.is_null());
+        self.check_not_stopped("deleting a datastore")?;
+        self.cache.evict(data_store.name.as_str());
         let msg = format!("Deleting {data_store}");
         let c_name = CString::new(data_store.name.as_str()).unwrap();
         database_call!(
@@ -160,6 +245,8 @@ impl ServerConnection {
             "Creating {data_store:}"
         );
         assert!(!self.inner.is_null());
+        self.check_not_stopped("creating a datastore")?;
+        let _dependent = self.server.dependent("creating a datastore")?;
         let c_name = CString::new(data_store.name.as_str()).unwrap();
         database_call!(
             "creating a datastore",
@@ -179,6 +266,24 @@ impl ServerConnection {
     pub fn connect_to_data_store(
         self: &Arc<Self>,
         data_store: &Arc<DataStore>,
+    ) -> Result<Arc<DataStoreConnection>, ekg_error::Error> {
+        self.connect(data_store, false)
+    }
+
+    /// Connect to the given data store for administrative work that has to
+    /// be done while it is offline, see
+    /// [`ServerConnection::set_data_store_offline`].
+    pub fn connect_to_data_store_for_maintenance(
+        self: &Arc<Self>,
+        data_store: &Arc<DataStore>,
+    ) -> Result<Arc<DataStoreConnection>, ekg_error::Error> {
+        self.connect(data_store, true)
+    }
+
+    fn connect(
+        self: &Arc<Self>,
+        data_store: &Arc<DataStore>,
+        maintenance: bool,
     ) -> Result<Arc<DataStoreConnection>, ekg_error::Error> {
         tracing::debug!(
             target: LOG_TARGET_DATABASE,
@@ -186,7 +291,10 @@ impl ServerConnection {
             data_store
         );
         assert!(!self.inner.is_null());
-        let mut ds_connection = DataStoreConnection::new(self, data_store, ptr::null_mut());
+        self.check_not_stopped("creating a datastore connection")?;
+        let dependent = self.server.dependent("creating a datastore connection")?;
+        let mut ds_connection =
+            DataStoreConnection::new(self, data_store, dependent, ptr::null_mut(), maintenance);
         let c_name = CString::new(data_store.name.as_str()).unwrap();
         tracing::debug!(
             target: LOG_TARGET_DATABASE,
@@ -202,6 +310,9 @@ impl ServerConnection {
                 &mut ds_connection.inner,
             )
         )?;
+        // Remember the unique id of the data store, see
+        // `DataStoreConnection::validate`
+        ds_connection.validate()?;
         tracing::info!(
             target: LOG_TARGET_DATABASE,
             "Connected to {}",
@@ -209,4 +320,200 @@ impl ServerConnection {
         );
         Ok(Arc::new(ds_connection))
     }
+
+    /// Take the given data store offline for maintenance, without touching
+    /// its data: pools stop handing out its connections and connections
+    /// that check its state (see [`DataStoreConnection::set_online_check`])
+    /// refuse new transactions, both with a `DataStoreOffline` error
+    /// carrying the given reason.
+    ///
+    /// The state is recorded in the reserved graph
+    /// [`DATA_STORE_STATE_GRAPH`](crate::DATA_STORE_STATE_GRAPH) of the data
+    /// store itself, so it survives reconnects and is seen by every process
+    /// that uses the data store. It is advisory: connections made with
+    /// [`ServerConnection::connect_to_data_store_for_maintenance`] ignore it.
+    pub fn set_data_store_offline(
+        self: &Arc<Self>,
+        data_store: &Arc<DataStore>,
+        reason: &str,
+    ) -> Result<(), ekg_error::Error> {
+        self.set_data_store_state(data_store, Some(reason))
+    }
+
+    /// Bring the given data store back online, see
+    /// [`ServerConnection::set_data_store_offline`].
+    pub fn set_data_store_online(
+        self: &Arc<Self>,
+        data_store: &Arc<DataStore>,
+    ) -> Result<(), ekg_error::Error> {
+        self.set_data_store_state(data_store, None)
+    }
+
+    fn set_data_store_state(
+        self: &Arc<Self>,
+        data_store: &Arc<DataStore>,
+        reason: Option<&str>,
+    ) -> Result<(), ekg_error::Error> {
+        let connection = self.connect_to_data_store_for_maintenance(data_store)?;
+        let updates = state_updates(reason)?;
+        Transaction::begin_read_write(&connection)?.update_and_commit(|tx| {
+            for update in updates.iter() {
+                tx.evaluate_update(update, &Parameters::empty()?, true)?;
+            }
+            Ok::<(), ekg_error::Error>(())
+        })?;
+        match reason {
+            Some(reason) => {
+                tracing::warn!(
+                    target: LOG_TARGET_DATABASE,
+                    "Took {data_store} offline: {reason}"
+                )
+            },
+            None => {
+                tracing::info!(
+                    target: LOG_TARGET_DATABASE,
+                    "Brought {data_store} online"
+                )
+            },
+        }
+        Ok(())
+    }
+
+    /// Return the names of all datastores in the server.
+    pub fn list_data_stores(&self) -> Result<Vec<String>, ekg_error::Error> {
+        assert!(!self.inner.is_null());
+        self.check_not_stopped("listing the datastores")?;
+        let mut names: Vec<String> = Vec::new();
+        database_call!(
+            "Listing the datastores",
+            CServerConnection_listDataStores(
+                self.inner,
+                &mut names as *mut Vec<String> as *mut c_void,
+                Some(Self::visit_data_store_name),
+            )
+        )?;
+        Ok(names)
+    }
+
+    /// Whether there is a datastore with the given name in the server
+    pub fn data_store_exists(&self, name: &str) -> Result<bool, ekg_error::Error> {
+        Ok(self.list_data_stores()?.iter().any(|n| n == name))
+    }
+
+    /// Create the given datastore unless it already exists, return whether
+    /// it was created.
+    pub fn create_data_store_if_missing(
+        &self,
+        data_store: &Arc<DataStore>,
+    ) -> Result<bool, ekg_error::Error> {
+        if self.data_store_exists(data_store.name.as_str())? {
+            tracing::debug!(
+                target: LOG_TARGET_DATABASE,
+                "{data_store:} exists already"
+            );
+            return Ok(false);
+        }
+        match self.create_data_store(data_store) {
+            Ok(()) => Ok(true),
+            // Somebody else may have created it in the meantime
+            Err(_) if self.data_store_exists(data_store.name.as_str())? => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    extern "C" fn visit_data_store_name(context: *mut c_void, name: *const c_char) -> bool {
+        let names = unsafe { &mut *(context as *mut Vec<String>) };
+        let name = unsafe { CStr::from_ptr(name) };
+        names.push(name.to_string_lossy().into_owned());
+        true
+    }
+
+    /// Return the (cached) [`DataStore`] with the given name.
+    ///
+    /// The first call for a given name checks that the datastore exists in
+    /// the server, subsequent calls return the same `Arc<DataStore>`.
+    pub fn data_store(&self, name: &str) -> Result<Arc<DataStore>, ekg_error::Error> {
+        if let Some(data_store) = self.cache.data_stores.read().unwrap().get(name) {
+            return Ok(data_store.clone());
+        }
+        if !self.data_store_exists(name)? {
+            tracing::error!(
+                target: LOG_TARGET_DATABASE,
+                "Datastore [{name}] does not exist in {self}"
+            );
+            return Err(ekg_error::Error::Exception {
+                action:  format!("Looking up datastore [{name}]"),
+                message: "datastore does not exist".to_string(),
+            });
+        }
+        let mut data_stores = self.cache.data_stores.write().unwrap();
+        if let Some(data_store) = data_stores.get(name) {
+            return Ok(data_store.clone());
+        }
+        let parameters = self.data_store_parameters(name)?;
+        let data_store = DataStore::declare_with_parameters(name, parameters)?;
+        data_stores.insert(name.to_string(), data_store.clone());
+        Ok(data_store)
+    }
+
+    /// The effective parameters of the datastore with the given name, i.e.
+    /// the ones that it was created with plus the defaults of the server,
+    /// as RDFox reports them in its component info
+    fn data_store_parameters(&self, name: &str) -> Result<Parameters, ekg_error::Error> {
+        self.check_not_stopped("getting the parameters of a datastore")?;
+        let component_info = read_component_info(
+            format!("getting the parameters of datastore [{name}]").as_str(),
+            |info| unsafe { CServerConnection_getComponentInfo(self.inner, true, info) },
+        )?;
+        let parameters = component_info.data_store_parameters(name).unwrap_or_else(|| {
+            tracing::warn!(
+                target: LOG_TARGET_DATABASE,
+                "No parameters of datastore [{name}] in the component info of {self}"
+            );
+            Default::default()
+        });
+        Parameters::from_map(parameters)
+    }
+
+    /// Return the connection pool for the datastore with the given name that
+    /// is shared by all users of this server connection, creating it on
+    /// first use with the given options (the options are ignored for
+    /// subsequent calls).
+    ///
+    /// The pool is shared for as long as somebody holds on to it, once the
+    /// last user lets go of it it's closed and the next call creates a new
+    /// one.
+    pub fn shared_pool_for(
+        self: &Arc<Self>,
+        name: &str,
+        options: &PoolOptions,
+    ) -> Result<Arc<Pool<ConnectableDataStore>>, ekg_error::Error> {
+        if let Some(pool) = DataStoreCache::pool(&self.cache.pools.read().unwrap(), name) {
+            return Ok(pool);
+        }
+        let data_store = self.data_store(name)?;
+        let mut pools = self.cache.pools.write().unwrap();
+        if let Some(pool) = DataStoreCache::pool(&pools, name) {
+            return Ok(pool);
+        }
+        let pool = Arc::new(
+            ConnectableDataStore::new(
+                &data_store,
+                self,
+                options.release_on_return_to_pool,
+            )
+            .build_pool_with_options(options)?,
+        );
+        pools.insert(name.to_string(), Arc::downgrade(&pool));
+        tracing::debug!(
+            target: LOG_TARGET_DATABASE,
+            "Created shared pool for {data_store}"
+        );
+        Ok(pool)
+    }
+
+    /// Forget all shared pools (see [`ServerConnection::shared_pool_for`]),
+    /// so that subsequent calls create new ones. The existing ones are
+    /// closed as soon as their last user lets go of them.
+    pub fn release_shared_pools(&self) { self.cache.pools.write().unwrap().clear(); }
 }
@@ -0,0 +1,57 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{ConnectableDataStore, DataStore},
+    r2d2::Pool,
+    std::{
+        collections::HashMap,
+        fmt::{Debug, Formatter},
+        sync::{Arc, RwLock, Weak},
+    },
+};
+
+/// The in-process cache of [`DataStore`]s and their shared connection
+/// pools, keyed by datastore name, of a
+/// [`ServerConnection`](crate::ServerConnection).
+///
+/// The pools hold on to the `ServerConnection`, so they're only cached
+/// as long as somebody uses them, anything else would keep the connection
+/// alive forever.
+#[derive(Default)]
+pub(crate) struct DataStoreCache {
+    pub(crate) data_stores: RwLock<HashMap<String, Arc<DataStore>>>,
+    pub(crate) pools: RwLock<HashMap<String, Weak<Pool<ConnectableDataStore>>>>,
+}
+
+/// Only show the names
+impl Debug for DataStoreCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataStoreCache")
+            .field(
+                "data_stores",
+                &self.data_stores.read().unwrap().keys().collect::<Vec<_>>(),
+            )
+            .field(
+                "pools",
+                &self.pools.read().unwrap().keys().collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl DataStoreCache {
+    /// The shared pool for the given datastore, if somebody still uses it
+    pub(crate) fn pool(
+        pools: &HashMap<String, Weak<Pool<ConnectableDataStore>>>,
+        name: &str,
+    ) -> Option<Arc<Pool<ConnectableDataStore>>> {
+        pools.get(name).and_then(Weak::upgrade)
+    }
+
+    /// Remove the given datastore and its pool from the cache
+    pub(crate) fn evict(&self, name: &str) {
+        self.pools.write().unwrap().remove(name);
+        self.data_stores.write().unwrap().remove(name);
+    }
+}
@@ -1,6 +1,11 @@
 // Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
 //---------------------------------------------------------------
 
-pub use connection::ServerConnection;
+pub use {
+    connection::ServerConnection,
+    roles::{Privilege, Resource},
+};
 
 mod connection;
+mod data_store_cache;
+mod roles;
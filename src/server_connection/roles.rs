@@ -0,0 +1,207 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    super::ServerConnection,
+    crate::{
+        database_call,
+        rdfox_api::{
+            CServerConnection_changeRolePassword,
+            CServerConnection_createRole,
+            CServerConnection_deleteRole,
+            CServerConnection_grantPrivileges,
+            CServerConnection_listRoles,
+            CServerConnection_revokePrivileges,
+        },
+        DataStore,
+        RoleCreds,
+    },
+    std::{
+        ffi::{c_void, CStr, CString},
+        fmt::{Display, Formatter},
+        os::raw::c_char,
+    },
+};
+
+/// What a role may do with a [`Resource`], see
+/// [`ServerConnection::grant_privilege`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Privilege {
+    Read,
+    Write,
+    /// Grant the privileges on the resource to other roles
+    Grant,
+    /// All of the above
+    Full,
+}
+
+impl Privilege {
+    /// The access types as the bit mask that RDFox expects
+    fn access_types(self) -> u8 {
+        match self {
+            Privilege::Read => 1,
+            Privilege::Write => 2,
+            Privilege::Grant => 4,
+            Privilege::Full => 7,
+        }
+    }
+}
+
+impl Display for Privilege {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Privilege::Read => write!(f, "read"),
+            Privilege::Write => write!(f, "write"),
+            Privilege::Grant => write!(f, "grant"),
+            Privilege::Full => write!(f, "full"),
+        }
+    }
+}
+
+/// Something that privileges can be granted on, displayed as the resource
+/// specifier that RDFox expects
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Resource {
+    /// All data stores (and everything in them)
+    AllDataStores,
+    /// The given data store (and everything in it)
+    DataStore(String),
+    /// All roles
+    Roles,
+    /// Any other RDFox resource specifier, such as `>datastores>ekg>tuples`
+    Other(String),
+}
+
+impl Resource {
+    pub fn data_store(data_store: &DataStore) -> Self {
+        Resource::DataStore(data_store.name.clone())
+    }
+}
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Resource::AllDataStores => write!(f, ">datastores"),
+            Resource::DataStore(name) => write!(f, ">datastores>{name}"),
+            Resource::Roles => write!(f, ">roles"),
+            Resource::Other(specifier) => write!(f, "{specifier}"),
+        }
+    }
+}
+
+impl ServerConnection {
+    /// Create a role with the given name and password, which has no
+    /// privileges until they're granted with
+    /// [`ServerConnection::grant_privilege`].
+    pub fn create_role(&self, role_creds: &RoleCreds) -> Result<(), ekg_error::Error> {
+        role_creds.validate()?;
+        self.check_not_stopped("creating a role")?;
+        let c_role_name = CString::new(role_creds.role_name.as_str()).unwrap();
+        let c_password = CString::new(role_creds.password.as_str()).unwrap();
+        database_call!(
+            format!("Creating role [{}]", role_creds.role_name).as_str(),
+            CServerConnection_createRole(self.inner, c_role_name.as_ptr(), c_password.as_ptr())
+        )
+    }
+
+    pub fn delete_role(&self, role_name: &str) -> Result<(), ekg_error::Error> {
+        self.check_not_stopped("deleting a role")?;
+        let c_role_name = CString::new(role_name).unwrap();
+        database_call!(
+            format!("Deleting role [{role_name}]").as_str(),
+            CServerConnection_deleteRole(self.inner, c_role_name.as_ptr())
+        )
+    }
+
+    /// Return the names of all roles in the server
+    pub fn list_roles(&self) -> Result<Vec<String>, ekg_error::Error> {
+        self.check_not_stopped("listing the roles")?;
+        let mut names: Vec<String> = Vec::new();
+        database_call!(
+            "Listing the roles",
+            CServerConnection_listRoles(
+                self.inner,
+                &mut names as *mut Vec<String> as *mut c_void,
+                Some(Self::visit_role_name),
+            )
+        )?;
+        Ok(names)
+    }
+
+    extern "C" fn visit_role_name(context: *mut c_void, name: *const c_char) -> bool {
+        let names = unsafe { &mut *(context as *mut Vec<String>) };
+        let name = unsafe { CStr::from_ptr(name) };
+        names.push(name.to_string_lossy().into_owned());
+        true
+    }
+
+    /// Change the password of the role that this connection uses
+    pub fn change_role_password(
+        &self,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<(), ekg_error::Error> {
+        self.check_not_stopped("changing the password of the role")?;
+        let c_current = CString::new(current_password).unwrap();
+        let c_new = CString::new(new_password).unwrap();
+        database_call!(
+            "Changing the password of the role",
+            CServerConnection_changeRolePassword(self.inner, c_current.as_ptr(), c_new.as_ptr())
+        )
+    }
+
+    pub fn grant_privilege(
+        &self,
+        role_name: &str,
+        resource: &Resource,
+        privilege: Privilege,
+    ) -> Result<(), ekg_error::Error> {
+        self.check_not_stopped("granting a privilege")?;
+        let c_role_name = CString::new(role_name).unwrap();
+        let c_resource = CString::new(resource.to_string()).unwrap();
+        database_call!(
+            format!("Granting {privilege} privilege on {resource} to role [{role_name}]")
+                .as_str(),
+            CServerConnection_grantPrivileges(
+                self.inner,
+                c_role_name.as_ptr(),
+                c_resource.as_ptr(),
+                privilege.access_types()
+            )
+        )
+    }
+
+    pub fn revoke_privilege(
+        &self,
+        role_name: &str,
+        resource: &Resource,
+        privilege: Privilege,
+    ) -> Result<(), ekg_error::Error> {
+        self.check_not_stopped("revoking a privilege")?;
+        let c_role_name = CString::new(role_name).unwrap();
+        let c_resource = CString::new(resource.to_string()).unwrap();
+        database_call!(
+            format!("Revoking {privilege} privilege on {resource} from role [{role_name}]")
+                .as_str(),
+            CServerConnection_revokePrivileges(
+                self.inner,
+                c_role_name.as_ptr(),
+                c_resource.as_ptr(),
+                privilege.access_types()
+            )
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Privilege, Resource};
+
+    #[test_log::test]
+    fn test_resource_specifiers() {
+        assert_eq!(Resource::AllDataStores.to_string(), ">datastores");
+        assert_eq!(Resource::DataStore("ekg".to_string()).to_string(), ">datastores>ekg");
+        assert_eq!(Resource::Roles.to_string(), ">roles");
+        assert_eq!(Privilege::Full.access_types(), 7);
+    }
+}
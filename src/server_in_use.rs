@@ -0,0 +1,48 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// A local RDFox server was restarted with
+/// [`Server::restart`](crate::Server::restart) while it still had
+/// connections, transactions or cursors, which would have ended up using
+/// the new server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerInUse {
+    /// See [`Server::number_of_dependents`](crate::Server::number_of_dependents)
+    pub dependents: usize,
+}
+
+impl Display for ServerInUse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ServerInUse: the local RDFox server still has {} connection(s), transaction(s) or \
+             cursor(s), drop them first",
+            self.dependents
+        )
+    }
+}
+
+impl std::error::Error for ServerInUse {}
+
+impl TypedError for ServerInUse {
+    const NAME: &'static str = "ServerInUse";
+
+    fn action(&self) -> String { "restarting the local RDFox server".to_string() }
+}
+
+impl From<ServerInUse> for ekg_error::Error {
+    fn from(value: ServerInUse) -> Self { value.to_error() }
+}
+
+impl ServerInUse {
+    /// The [`ServerInUse`] error that the given error was made from, if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
@@ -0,0 +1,44 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// The [`Server`](crate::Server) is still starting, so it refuses new
+/// connections, transactions and cursors until it is running.
+/// Get it back from an `ekg_error::Error` with
+/// [`ServerNotStarted::from_error`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerNotStarted {
+    /// What the server was used for
+    pub action: String,
+}
+
+impl Display for ServerNotStarted {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ServerNotStarted: the RDFox server is starting, refused {}", self.action)
+    }
+}
+
+impl std::error::Error for ServerNotStarted {}
+
+impl TypedError for ServerNotStarted {
+    const NAME: &'static str = "ServerNotStarted";
+
+    fn action(&self) -> String { self.action.clone() }
+}
+
+impl From<ServerNotStarted> for ekg_error::Error {
+    fn from(value: ServerNotStarted) -> Self { value.to_error() }
+}
+
+impl ServerNotStarted {
+    /// The [`ServerNotStarted`] error that the given error was made from, if
+    /// any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{typed_error::TypedError, ServerState},
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// The [`Server`](crate::Server) is shutting down or has been stopped, so
+/// it refuses new connections, transactions and cursors, and after
+/// [`Server::stop`](crate::Server::stop) any use of the existing ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerShutDown {
+    pub state:  ServerState,
+    /// What the server was used for
+    pub action: String,
+}
+
+impl Display for ServerShutDown {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ServerShutDown: the RDFox server is {}, refused {}", self.state, self.action)
+    }
+}
+
+impl std::error::Error for ServerShutDown {}
+
+impl TypedError for ServerShutDown {
+    const NAME: &'static str = "ServerShutDown";
+
+    fn action(&self) -> String { self.action.clone() }
+}
+
+impl From<ServerShutDown> for ekg_error::Error {
+    fn from(value: ServerShutDown) -> Self { value.to_error() }
+}
+
+impl ServerShutDown {
+    /// The [`ServerShutDown`] error that the given error was made from, if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{ServerNotStarted, ServerShutDown},
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::{Display, Formatter},
+        sync::Mutex,
+    },
+};
+
+/// The lifecycle of a [`Server`](crate::Server):
+///
+/// ```text
+/// Starting --start--> Running --begin_shutdown--> Draining --no more dependents--> Stopped
+/// ```
+///
+/// Its dependents are the server connections, datastore connections,
+/// transactions and cursors that use it. They can only be created while the
+/// server is `Running`; once it is `Draining` the existing ones may complete
+/// and the server stops as soon as the last one is gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServerState {
+    Starting,
+    Running,
+    Draining,
+    Stopped,
+}
+
+impl Display for ServerState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerState::Starting => write!(f, "starting"),
+            ServerState::Running => write!(f, "running"),
+            ServerState::Draining => write!(f, "shutting down"),
+            ServerState::Stopped => write!(f, "stopped"),
+        }
+    }
+}
+
+/// The state of a server and the number of its dependents, the transitions
+/// return `true` when the server has to be stopped as a result.
+#[derive(Debug)]
+pub(crate) struct ServerLifecycle {
+    inner: Mutex<(ServerState, usize)>,
+}
+
+impl Default for ServerLifecycle {
+    fn default() -> Self { Self { inner: Mutex::new((ServerState::Starting, 0)) } }
+}
+
+impl ServerLifecycle {
+    pub(crate) fn state(&self) -> ServerState { self.inner.lock().unwrap().0 }
+
+    pub(crate) fn dependents(&self) -> usize { self.inner.lock().unwrap().1 }
+
+    pub(crate) fn started(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.0 == ServerState::Starting {
+            inner.0 = ServerState::Running;
+        }
+    }
+
+    /// Register a new dependent, which is refused unless the server is
+    /// running, with [`ServerNotStarted`] while it is starting and with
+    /// [`ServerShutDown`] once it is shutting down.
+    pub(crate) fn acquire(&self, action: &str) -> Result<(), ekg_error::Error> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.0 {
+            ServerState::Running => (),
+            ServerState::Starting => {
+                return Err(ServerNotStarted { action: action.to_string() }.into());
+            },
+            state => return Err(ServerShutDown { state, action: action.to_string() }.into()),
+        }
+        inner.1 += 1;
+        Ok(())
+    }
+
+    /// Fail with [`ServerShutDown`] once the server has been stopped, which
+    /// (unlike draining) also ends the use of existing dependents
+    pub(crate) fn check_not_stopped(&self, action: &str) -> Result<(), ServerShutDown> {
+        match self.state() {
+            ServerState::Stopped => {
+                Err(ServerShutDown { state: ServerState::Stopped, action: action.to_string() })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn release(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        inner.1 = inner.1.saturating_sub(1);
+        Self::stop_when_drained(&mut inner)
+    }
+
+    pub(crate) fn begin_shutdown(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if matches!(inner.0, ServerState::Starting | ServerState::Running) {
+            inner.0 = ServerState::Draining;
+        }
+        Self::stop_when_drained(&mut inner)
+    }
+
+    /// Stop regardless of any remaining dependents
+    pub(crate) fn stop(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let was_stopped = inner.0 == ServerState::Stopped;
+        inner.0 = ServerState::Stopped;
+        !was_stopped
+    }
+
+    fn stop_when_drained(inner: &mut (ServerState, usize)) -> bool {
+        if inner.0 == ServerState::Draining && inner.1 == 0 {
+            inner.0 = ServerState::Stopped;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{ServerLifecycle, ServerState},
+        crate::ServerNotStarted,
+    };
+
+    #[test_log::test]
+    fn test_lifecycle() {
+        let lifecycle = ServerLifecycle::default();
+        assert_eq!(lifecycle.state(), ServerState::Starting);
+        let err = lifecycle.acquire("connecting").unwrap_err();
+        assert_eq!(ServerNotStarted::from_error(&err).unwrap().action, "connecting");
+
+        lifecycle.started();
+        assert_eq!(lifecycle.state(), ServerState::Running);
+        lifecycle.acquire("connecting").unwrap();
+        lifecycle.acquire("beginning a transaction").unwrap();
+        assert_eq!(lifecycle.dependents(), 2);
+
+        // Draining refuses new dependents but lets existing ones complete
+        assert!(!lifecycle.begin_shutdown());
+        assert_eq!(lifecycle.state(), ServerState::Draining);
+        assert!(lifecycle.acquire("creating a cursor").is_err());
+        assert!(!lifecycle.release());
+        assert_eq!(lifecycle.state(), ServerState::Draining);
+
+        // The last dependent to go stops the server, only once
+        assert!(lifecycle.release());
+        assert_eq!(lifecycle.state(), ServerState::Stopped);
+        assert!(!lifecycle.stop());
+        assert!(lifecycle.acquire("connecting").is_err());
+        assert_eq!(
+            lifecycle.check_not_stopped("opening a cursor").unwrap_err().state,
+            ServerState::Stopped
+        );
+    }
+
+    #[test_log::test]
+    fn test_shutdown_without_dependents() {
+        let lifecycle = ServerLifecycle::default();
+        lifecycle.started();
+        assert!(lifecycle.check_not_stopped("opening a cursor").is_ok());
+        assert!(lifecycle.begin_shutdown());
+        assert_eq!(lifecycle.state(), ServerState::Stopped);
+        assert!(!lifecycle.begin_shutdown());
+    }
+}
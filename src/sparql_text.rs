@@ -0,0 +1,736 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+//! A small, forgiving tokenizer for SPARQL text.
+//!
+//! It does not try to parse SPARQL, it only knows enough to tell apart
+//! the parts of a statement that must never be touched by text-level
+//! rewrites (IRIs and string literals) from comments and "everything
+//! else".
+//!
+//! The token model is lossless: every byte of the input belongs to
+//! exactly one token and concatenating the tokens (see [`detokenize`])
+//! always gives back the original text, whatever the input is
+//! (including unterminated strings or IRIs).
+
+use {
+    crate::Term,
+    std::{borrow::Cow, fmt::Write},
+};
+
+/// The kind of a [`SparqlToken`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparqlTokenKind {
+    /// Anything that is not one of the other kinds: keywords, variables,
+    /// prefixed names, punctuation, whitespace, etc.
+    Text,
+    /// A `#`-comment, up to but not including the line-feed.
+    Comment,
+    /// An IRI reference such as `<https://ekgf.org/>`, including the angle
+    /// brackets.
+    Iri,
+    /// A string literal in any of its four quoting styles, including the
+    /// quotes. An unterminated string runs up to the end of the line (short
+    /// strings) or the end of the text (long strings).
+    String,
+}
+
+/// A slice of SPARQL text of a given [`SparqlTokenKind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SparqlToken<'a> {
+    pub kind: SparqlTokenKind,
+    pub text: &'a str,
+}
+
+/// Split the given SPARQL text into tokens.
+pub fn tokenize(text: &str) -> Vec<SparqlToken<'_>> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut text_start = 0_usize;
+    let mut pos = 0_usize;
+
+    while pos < bytes.len() {
+        let special = match bytes[pos] {
+            b'#' => Some((SparqlTokenKind::Comment, end_of_comment(bytes, pos))),
+            b'"' | b'\'' => Some((SparqlTokenKind::String, end_of_string(bytes, pos))),
+            b'<' => end_of_iri(bytes, pos).map(|end| (SparqlTokenKind::Iri, end)),
+            _ => None,
+        };
+        match special {
+            Some((kind, end)) => {
+                if text_start < pos {
+                    tokens.push(SparqlToken {
+                        kind: SparqlTokenKind::Text,
+                        text: &text[text_start..pos],
+                    });
+                }
+                tokens.push(SparqlToken { kind, text: &text[pos..end] });
+                pos = end;
+                text_start = end;
+            }
+            None => pos += 1,
+        }
+    }
+    if text_start < bytes.len() {
+        tokens.push(SparqlToken {
+            kind: SparqlTokenKind::Text,
+            text: &text[text_start..],
+        });
+    }
+    tokens
+}
+
+/// Concatenate the given tokens back into SPARQL text.
+pub fn detokenize(tokens: &[SparqlToken]) -> String {
+    tokens.iter().map(|token| token.text).collect()
+}
+
+/// Remove all `#`-comments from the given SPARQL text, leaving `#` characters
+/// in IRIs and string literals alone.
+///
+/// Trailing whitespace is removed from every line (but never from the inside
+/// of a string literal) and every line ends with a line-feed.
+pub fn no_comments(string: &str) -> String {
+    let mut output = String::with_capacity(string.len() + 1);
+    // Start of the run of trailing whitespace at the end of `output`,
+    // if that whitespace came from a Text token (and can thus be trimmed).
+    let mut trailing_whitespace: Option<usize> = None;
+
+    for token in tokenize(string) {
+        match token.kind {
+            SparqlTokenKind::Comment => {}
+            SparqlTokenKind::Text => {
+                for c in token.text.chars() {
+                    if c == '\n' {
+                        if let Some(start) = trailing_whitespace.take() {
+                            output.truncate(start);
+                        }
+                        output.push(c);
+                    } else if c.is_whitespace() {
+                        trailing_whitespace.get_or_insert(output.len());
+                        output.push(c);
+                    } else {
+                        trailing_whitespace = None;
+                        output.push(c);
+                    }
+                }
+            }
+            SparqlTokenKind::Iri | SparqlTokenKind::String => {
+                trailing_whitespace = None;
+                output.push_str(token.text);
+            }
+        }
+    }
+    if let Some(start) = trailing_whitespace {
+        output.truncate(start);
+    }
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+/// Split the given SPARQL text into its statements, such as the operations
+/// of an update request, at every `;` outside braces, IRIs, string literals
+/// and comments (so not at the `;` between the predicates of a triple
+/// pattern). The statements keep their whitespace and comments, joining them
+/// with `;` always gives back the original text.
+pub fn split_statements(text: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut start = 0_usize;
+    let mut offset = 0_usize;
+    let mut depth = 0_usize;
+    for token in tokenize(text) {
+        if token.kind == SparqlTokenKind::Text {
+            for (index, c) in token.text.char_indices() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth = depth.saturating_sub(1),
+                    ';' if depth == 0 => {
+                        statements.push(&text[start..offset + index]);
+                        start = offset + index + 1;
+                    },
+                    _ => {},
+                }
+            }
+        }
+        offset += token.text.len();
+    }
+    statements.push(&text[start..]);
+    statements
+}
+
+/// Return the given SPARQL text with every IRI or string literal that is
+/// longer than `max_token_len` bytes cut short (showing its original length),
+/// to keep log messages about statements with huge literals readable.
+pub(crate) fn abbreviate(text: &str, max_token_len: usize) -> Cow<'_, str> {
+    let too_long = |token: &SparqlToken| {
+        token.text.len() > max_token_len &&
+            matches!(
+                token.kind,
+                SparqlTokenKind::Iri | SparqlTokenKind::String
+            )
+    };
+    if text.len() <= max_token_len {
+        return Cow::Borrowed(text);
+    }
+    let tokens = tokenize(text);
+    if !tokens.iter().any(too_long) {
+        return Cow::Borrowed(text);
+    }
+    let mut output = String::new();
+    for token in tokens {
+        if !too_long(&token) {
+            output.push_str(token.text);
+        } else {
+            let mut end = max_token_len;
+            while !token.text.is_char_boundary(end) {
+                end -= 1;
+            }
+            write!(
+                output,
+                "{}…[{} bytes]",
+                &token.text[..end],
+                token.text.len()
+            )
+            .unwrap();
+        }
+    }
+    Cow::Owned(output)
+}
+
+/// The keywords that start a SPARQL query or update operation
+const QUERY_FORMS: &[&str] = &[
+    "SELECT", "ASK", "CONSTRUCT", "DESCRIBE", "INSERT", "DELETE", "WITH", "LOAD", "CLEAR", "DROP",
+    "CREATE", "ADD", "MOVE", "COPY",
+];
+
+/// Return the (upper case) keyword that determines the form of the given
+/// SPARQL statement, like `SELECT` or `ASK`, skipping any `PREFIX` or `BASE`
+/// declarations and comments.
+pub(crate) fn query_form(text: &str) -> Option<&'static str> {
+    for token in tokenize(text) {
+        if token.kind != SparqlTokenKind::Text {
+            continue;
+        }
+        let mut rest = token.text;
+        while let Some(start) = rest.find(|c: char| c.is_ascii_alphabetic()) {
+            let word_and_rest = &rest[start..];
+            let end = word_and_rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(word_and_rest.len());
+            let word = &word_and_rest[..end];
+            let preceded_by = rest[..start].chars().last();
+            let followed_by = word_and_rest[end..].chars().next();
+            rest = &word_and_rest[end..];
+            // Skip variables, prefixed names and the like
+            if matches!(preceded_by, Some('?' | '$' | ':' | '@' | '_')) || followed_by == Some(':') {
+                continue;
+            }
+            if let Some(form) = QUERY_FORMS
+                .iter()
+                .find(|form| form.eq_ignore_ascii_case(word))
+            {
+                return Some(form);
+            }
+        }
+    }
+    None
+}
+
+/// Return the names (without `?` or `$`) of the variables projected by the
+/// given `SELECT` query, in order, including the ones bound with `(... AS
+/// ?name)`. Returns `None` for `SELECT *` or for anything that's not a
+/// `SELECT` query, and an empty list for a query without any columns.
+pub(crate) fn projection(text: &str) -> Option<Vec<String>> {
+    if query_form(text) != Some("SELECT") {
+        return None;
+    }
+    // Only keep the parts that can contain keywords, variables or brackets
+    let text = tokenize(text)
+        .iter()
+        .map(|token| {
+            match token.kind {
+                SparqlTokenKind::Text => token.text,
+                SparqlTokenKind::Comment => "",
+                SparqlTokenKind::Iri | SparqlTokenKind::String => " ",
+            }
+        })
+        .collect::<String>();
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_';
+    let end_of_name = |from: usize| {
+        text[from..]
+            .find(|c: char| !is_name_char(c))
+            .map_or(text.len(), |end| from + end)
+    };
+    let mut variables = Vec::new();
+    let mut select_seen = false;
+    let mut depth = 0_usize;
+    let mut after_as = false;
+    let mut pos = 0_usize;
+    while let Some(c) = text[pos..].chars().next() {
+        let start = pos;
+        pos += c.len_utf8();
+        match c {
+            '?' | '$' => {
+                let end = end_of_name(pos);
+                if select_seen && (depth == 0 || after_as) && end > pos {
+                    variables.push(text[pos..end].to_string());
+                    after_as = false;
+                }
+                pos = end;
+            },
+            // The local part of a prefixed name is never a keyword
+            ':' => pos = end_of_name(pos),
+            c if c.is_alphabetic() => {
+                pos = end_of_name(pos);
+                let word = &text[start..pos];
+                if text[pos..].starts_with(':') {
+                    // The prefix of a prefixed name is never a keyword either
+                } else if !select_seen {
+                    select_seen = word.eq_ignore_ascii_case("SELECT");
+                } else if depth == 0 &&
+                    (word.eq_ignore_ascii_case("WHERE") || word.eq_ignore_ascii_case("FROM"))
+                {
+                    break;
+                } else if word.eq_ignore_ascii_case("AS") {
+                    after_as = true;
+                }
+            },
+            '(' if select_seen => depth += 1,
+            ')' if select_seen => depth = depth.saturating_sub(1),
+            '{' if select_seen => break,
+            '*' if select_seen && depth == 0 => return None,
+            _ => {},
+        }
+    }
+    Some(variables)
+}
+
+/// Replace every occurrence of the given variables (`?name` or `$name`,
+/// outside IRIs, string literals and comments) with the SPARQL syntax of the
+/// given term, which escapes whatever the term contains so that it can only
+/// ever be data.
+///
+/// Fails if a variable does not occur in the text, if it's projected by
+/// a `SELECT` query (where a term would not be valid syntax) or if a term
+/// has a blank node label or language tag that's not valid SPARQL.
+pub(crate) fn bind_variables(text: &str, bindings: &[(&str, &Term)]) -> Result<String, String> {
+    let bindings = bindings
+        .iter()
+        .map(|(name, term)| (name.trim_start_matches(['?', '$']), *term))
+        .collect::<Vec<_>>();
+    for (name, term) in bindings.iter() {
+        term.check_syntax().map_err(|message| format!("cannot bind ?{name}: {message}"))?;
+    }
+    if let Some(projected) = projection(text) {
+        let is_projected = |name: &str| projected.iter().any(|variable| variable == name);
+        if let Some((name, _)) = bindings.iter().find(|(name, _)| is_projected(name)) {
+            return Err(format!("variable ?{name} is projected by the query"));
+        }
+    }
+    let mut used = vec![false; bindings.len()];
+    let mut output = String::with_capacity(text.len());
+    for token in tokenize(text) {
+        if token.kind != SparqlTokenKind::Text {
+            output.push_str(token.text);
+            continue;
+        }
+        let mut rest = token.text;
+        while let Some(start) = rest.find(['?', '$']) {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let end = after
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            match bindings.iter().position(|(name, _)| *name == &after[..end]) {
+                Some(index) if end > 0 => {
+                    used[index] = true;
+                    write!(output, "{}", bindings[index].1).unwrap();
+                },
+                _ => output.push_str(&rest[..start + 1 + end]),
+            }
+            rest = &after[end..];
+        }
+        output.push_str(rest);
+    }
+    match bindings.iter().zip(used).find(|(_, used)| !used) {
+        Some(((name, _), _)) => Err(format!("variable ?{name} does not occur in the statement")),
+        None => Ok(output),
+    }
+}
+
+/// Return the prefix declarations (the name with its colon and the IRI
+/// without its angle brackets) in the given text, in order, recognizing
+/// `PREFIX name: <iri>` and, if `turtle` is true, `@prefix name: <iri> .`
+/// as well. Comments, strings and anything else are skipped.
+pub(crate) fn prefix_declarations(text: &str, turtle: bool) -> Vec<(&str, &str)> {
+    let is_keyword =
+        |word: &str| word.eq_ignore_ascii_case("PREFIX") || (turtle && word == "@prefix");
+    let is_name = |word: &str| word.ends_with(':') && !word[..word.len() - 1].contains(':');
+    let mut declarations = Vec::new();
+    // The keyword has been seen (`Some(None)`) or the keyword and the name
+    // (`Some(Some(name))`)
+    let mut declaration: Option<Option<&str>> = None;
+    for token in tokenize(text) {
+        match token.kind {
+            SparqlTokenKind::Comment => {}
+            SparqlTokenKind::Text => {
+                for word in token.text.split_whitespace() {
+                    declaration = match declaration {
+                        Some(None) if is_name(word) => Some(Some(word)),
+                        _ if is_keyword(word) => Some(None),
+                        _ => None,
+                    };
+                }
+            }
+            SparqlTokenKind::Iri => {
+                if let Some(Some(name)) = declaration {
+                    declarations.push((name, &token.text[1..token.text.len() - 1]));
+                }
+                declaration = None;
+            }
+            SparqlTokenKind::String => declaration = None,
+        }
+    }
+    declarations
+}
+
+fn end_of_comment(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map_or(bytes.len(), |offset| start + offset)
+}
+
+fn end_of_string(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let long = bytes.len() >= start + 3 && bytes[start + 1] == quote && bytes[start + 2] == quote;
+    let mut pos = if long { start + 3 } else { start + 1 };
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b if b == quote => {
+                if !long {
+                    return pos + 1;
+                }
+                if bytes.len() >= pos + 3 && bytes[pos + 1] == quote && bytes[pos + 2] == quote {
+                    return pos + 3;
+                }
+                pos += 1;
+            }
+            b'\n' | b'\r' if !long => return pos,
+            _ => pos += 1,
+        }
+    }
+    // An escape at the very end may have pushed us past the end
+    bytes.len()
+}
+
+/// Returns the end (exclusive) of the IRI reference that starts at `start`,
+/// or `None` if the `<` does not start an IRI reference (in which case it's
+/// probably a less-than operator).
+fn end_of_iri(bytes: &[u8], start: usize) -> Option<usize> {
+    for (offset, b) in bytes[start + 1..].iter().enumerate() {
+        match *b {
+            b'>' => return Some(start + 1 + offset + 1),
+            b'<' | b'"' | b'{' | b'}' | b'|' | b'^' | b'`' | b'\\' => return None,
+            b if b <= 0x20 => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            abbreviate,
+            bind_variables,
+            detokenize,
+            no_comments,
+            prefix_declarations,
+            projection,
+            query_form,
+            split_statements,
+            tokenize,
+            SparqlToken,
+            SparqlTokenKind,
+        },
+        crate::Term,
+        proptest::prelude::*,
+    };
+
+    #[test_log::test]
+    fn test_prefix_declarations() {
+        let sparql = concat!(
+            "# PREFIX no: <https://ekgf.org/no/>\n",
+            "PREFIX skos: <http://www.w3.org/2004/02/skos/core#>\n",
+            "prefix : <https://ekgf.org/>\n",
+            "@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .\n",
+            "SELECT ?s WHERE { ?s skos:prefLabel \"PREFIX no: <https://ekgf.org/no/>\" }",
+        );
+        assert_eq!(
+            prefix_declarations(sparql, false),
+            vec![
+                ("skos:", "http://www.w3.org/2004/02/skos/core#"),
+                (":", "https://ekgf.org/"),
+            ]
+        );
+        assert_eq!(
+            prefix_declarations(sparql, true)[2],
+            ("rdfs:", "http://www.w3.org/2000/01/rdf-schema#")
+        );
+        assert!(prefix_declarations("SELECT ?prefix WHERE { ?s ?p <https://ekgf.org/> }", true)
+            .is_empty());
+    }
+
+    #[test_log::test]
+    fn test_bind_variables() {
+        let injection = Term::string("\"} UNION { ?s ?p ?o }");
+        let subject = Term::iri("https://ekgf.org/s").unwrap();
+        let bound = bind_variables(
+            "SELECT ?o WHERE { ?s <https://ekgf.org/p?x> ?o . FILTER(?o = $label) } # ?s",
+            &[("s", &subject), ("?label", &injection)],
+        )
+        .unwrap();
+        assert_eq!(
+            bound,
+            concat!(
+                r#"SELECT ?o WHERE { <https://ekgf.org/s> <https://ekgf.org/p?x> ?o . "#,
+                r#"FILTER(?o = "\"} UNION { ?s ?p ?o }") } # ?s"#
+            )
+        );
+        // The injected text is one string literal, not query syntax
+        let strings = tokenize(bound.as_str())
+            .into_iter()
+            .filter(|token| token.kind == SparqlTokenKind::String)
+            .map(|token| token.text)
+            .collect::<Vec<_>>();
+        assert_eq!(strings, vec![r#""\"} UNION { ?s ?p ?o }""#]);
+
+        // ?s2 is not ?s
+        assert!(bind_variables("ASK { ?s2 ?p ?o }", &[("s", &subject)]).is_err());
+        assert!(bind_variables("SELECT ?s WHERE { ?s ?p ?o }", &[("s", &subject)]).is_err());
+
+        // labels and language tags are written as is, so they're checked
+        let blank_node = Term::blank_node("b0 } ; DROP ALL ; INSERT DATA { _:b1");
+        let language = Term::language_tagged_literal("x", "en . } UNION { ?s ?p ?o");
+        for term in [&blank_node, &language] {
+            assert!(
+                bind_variables("ASK { ?s ?p ?o }", &[("o", term)]).is_err(),
+                "{term}"
+            );
+        }
+    }
+
+    #[test_log::test]
+    fn test_no_comments() {
+        let sparql = indoc::formatdoc! {r##"
+            PREFIX abc: <https://whatever.org#> # focus on this and the next line
+            PREFIX owl: <http://www.w3.org/2002/07/owl#>
+            SELECT DISTINCT ?thing
+            WHERE {{
+                {{ # some comment
+                    GRAPH ?graph {{ # more # and more
+                        ?thing a Whatever#
+                    }}
+                }} UNION {{
+                    ?thing a Whatever .# abc
+                                       # def
+                    BIND(graph:Graph AS ?graph)
+                }}
+            }}
+            "##
+        };
+        let expected = indoc::formatdoc! {r##"
+            PREFIX abc: <https://whatever.org#>
+            PREFIX owl: <http://www.w3.org/2002/07/owl#>
+            SELECT DISTINCT ?thing
+            WHERE {{
+                {{
+                    GRAPH ?graph {{
+                        ?thing a Whatever
+                    }}
+                }} UNION {{
+                    ?thing a Whatever .
+
+                    BIND(graph:Graph AS ?graph)
+                }}
+            }}
+            "##
+        };
+        let actual = no_comments(sparql.as_str());
+        assert_eq!(actual.as_str(), expected.as_str());
+    }
+
+    #[test_log::test]
+    fn test_no_comments_keeps_hash_in_strings() {
+        let sparql = indoc::indoc! {r##"
+            SELECT ?x WHERE { ?x rdfs:label "C# and F#" } # a comment
+            FILTER(?x < 10 && ?y > 2) # less than, not an IRI
+            INSERT DATA { <a> <b> """multi # line
+            literal""" ; <c> 'it''s' }
+            VALUES ?g { <https://example.org/graph#part> <https://example.org/#a#b> } # graphs
+            BIND('# not a comment' AS ?x) BIND('''# nor
+            # this''' AS ?y) # but this is
+            "##
+        };
+        let expected = indoc::indoc! {r##"
+            SELECT ?x WHERE { ?x rdfs:label "C# and F#" }
+            FILTER(?x < 10 && ?y > 2)
+            INSERT DATA { <a> <b> """multi # line
+            literal""" ; <c> 'it''s' }
+            VALUES ?g { <https://example.org/graph#part> <https://example.org/#a#b> }
+            BIND('# not a comment' AS ?x) BIND('''# nor
+            # this''' AS ?y)
+            "##
+        };
+        assert_eq!(no_comments(sparql).as_str(), expected);
+    }
+
+    #[test_log::test]
+    fn test_tokenize() {
+        let tokens = tokenize(r##"?s <https://x.org/#a> "a\"#b" # c"##);
+        assert_eq!(
+            tokens,
+            vec![
+                SparqlToken { kind: SparqlTokenKind::Text, text: "?s " },
+                SparqlToken { kind: SparqlTokenKind::Iri, text: "<https://x.org/#a>" },
+                SparqlToken { kind: SparqlTokenKind::Text, text: " " },
+                SparqlToken { kind: SparqlTokenKind::String, text: r##""a\"#b""## },
+                SparqlToken { kind: SparqlTokenKind::Text, text: " " },
+                SparqlToken { kind: SparqlTokenKind::Comment, text: "# c" },
+            ]
+        );
+    }
+
+    #[test_log::test]
+    fn test_query_form() {
+        let sparql = indoc::indoc! {r##"
+            PREFIX select: <https://x.org/ask#>
+            # SELECT in a comment
+            BASE <https://x.org/>
+            ask { ?s a select:Thing }
+            "##
+        };
+        assert_eq!(query_form(sparql), Some("ASK"));
+        assert_eq!(query_form("SELECT ?ask WHERE { ?s ?p ?o }"), Some("SELECT"));
+        assert_eq!(query_form("DELETE { ?s ?p ?o } WHERE { ?s ?p ?o }"), Some("DELETE"));
+        assert_eq!(query_form("PREFIX ask: <https://x.org/>\n"), None);
+    }
+
+    #[test_log::test]
+    fn test_projection() {
+        let names = |names: &[&str]| Some(names.iter().map(|name| name.to_string()).collect());
+        assert_eq!(projection("SELECT ?a $b WHERE { ?a ?p ?b }"), names(&["a", "b"]));
+        assert_eq!(
+            projection(indoc::indoc! {r##"
+                PREFIX where: <https://x.org/select?x=1#>
+                # SELECT ?commented
+                SELECT DISTINCT ?a (COUNT(?b) AS ?count) (where:f(?a, "?x") AS ?y)
+                FROM <https://x.org/graph?g>
+                { ?a ?p ?b }
+                "##
+            }),
+            names(&["a", "count", "y"])
+        );
+        assert_eq!(projection("select ?s {?s ?p ?o}"), names(&["s"]));
+        assert_eq!(projection("SELECT WHERE { }"), names(&[]));
+        assert_eq!(projection("SELECT * WHERE { ?s ?p ?o }"), None);
+        assert_eq!(projection("ASK { ?s ?p ?o }"), None);
+    }
+
+    #[test_log::test]
+    fn test_abbreviate() {
+        let long_literal = format!("\"{}\"", "x".repeat(1000));
+        let sparql = format!("SELECT * WHERE {{ ?s ?p {long_literal} }}");
+        let abbreviated = abbreviate(sparql.as_str(), 10);
+        assert_eq!(
+            abbreviated,
+            "SELECT * WHERE { ?s ?p \"xxxxxxxxx…[1002 bytes] }"
+        );
+        assert_eq!(abbreviate("SELECT", 10), "SELECT");
+    }
+
+    /// Strings made of the fragments that matter to the tokenizer, so that
+    /// quotes, IRIs and comments actually interact with each other.
+    fn sparql_ish() -> impl Strategy<Value = String> {
+        prop::collection::vec(
+            prop_oneof![
+                Just("#".to_string()),
+                Just("\"".to_string()),
+                Just("'".to_string()),
+                Just("\"\"\"".to_string()),
+                Just("'''".to_string()),
+                Just("<".to_string()),
+                Just(">".to_string()),
+                Just("\\".to_string()),
+                Just("\n".to_string()),
+                Just("\r\n".to_string()),
+                Just(" ".to_string()),
+                Just("\t".to_string()),
+                "[a-z?:{}.;]{1,4}",
+                any::<String>(),
+            ],
+            0..40,
+        )
+        .prop_map(|fragments| fragments.concat())
+    }
+
+    #[test_log::test]
+    fn test_split_statements() {
+        let update = "PREFIX x: <https://ekgf.org/;>\n\
+                      INSERT DATA { x:a x:b \"c;d\" ; x:e x:f } ;\n\
+                      # a comment; not a separator\n\
+                      DELETE WHERE { ?s ?p ?o }";
+        let statements = split_statements(update);
+        assert_eq!(statements.len(), 2, "{statements:?}");
+        assert!(statements[0].ends_with("x:e x:f } "));
+        assert!(statements[1].trim_start().starts_with("# a comment; not a separator"));
+        assert_eq!(statements.join(";"), update);
+        assert_eq!(split_statements(""), vec![""]);
+        assert_eq!(split_statements("a;;b"), vec!["a", "", "b"]);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_split_statements_round_trip(text in sparql_ish()) {
+            let statements = split_statements(text.as_str());
+            prop_assert_eq!(statements.join(";"), text);
+            for statement in statements {
+                prop_assert_eq!(split_statements(statement), vec![statement]);
+            }
+        }
+
+        #[test]
+        fn prop_round_trip(text in sparql_ish()) {
+            prop_assert_eq!(detokenize(&tokenize(text.as_str())), text);
+        }
+
+        #[test]
+        fn prop_round_trip_any_string(text in any::<String>()) {
+            prop_assert_eq!(detokenize(&tokenize(text.as_str())), text);
+        }
+
+        #[test]
+        fn prop_no_comments_keeps_strings_and_iris(text in sparql_ish()) {
+            let output = no_comments(text.as_str());
+            let mut from = 0_usize;
+            for token in tokenize(text.as_str()) {
+                if matches!(token.kind, SparqlTokenKind::Iri | SparqlTokenKind::String) {
+                    let found = output[from..].find(token.text);
+                    prop_assert!(found.is_some(), "{:?} is missing in {:?}", token.text, output);
+                    from += found.unwrap() + token.text.len();
+                }
+            }
+        }
+
+        #[test]
+        fn prop_no_comments_is_idempotent(text in sparql_ish()) {
+            let once = no_comments(text.as_str());
+            prop_assert_eq!(no_comments(once.as_str()), once);
+        }
+    }
+}
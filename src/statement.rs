@@ -3,23 +3,92 @@
 
 use {
     core::fmt::{Display, Formatter},
-    crate::{Cursor, DataStoreConnection, Namespaces, Parameters},
+    crate::{
+        sparql_text::{
+            abbreviate,
+            bind_variables,
+            no_comments,
+            prefix_declarations,
+            projection,
+            query_form,
+        },
+        Cursor,
+        DataStoreConnectionLike,
+        DATA_STORE_STATE_GRAPH,
+        ExceptionKind,
+        InvalidSparql,
+        Namespaces,
+        OpenedCursor,
+        Parameters,
+        RDFoxException,
+        StatementTooLarge,
+        Term,
+        Transaction,
+    },
     ekg_namespace::consts::{DEFAULT_GRAPH_RDFOX, LOG_TARGET_SPARQL},
     indoc::formatdoc,
-    std::{borrow::Cow, ffi::CString, ops::Deref, sync::Arc},
+    std::{
+        borrow::Cow,
+        ffi::{CStr, CString},
+        ops::Deref,
+        path::Path,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+            OnceLock,
+        },
+    },
 };
 
+/// The default maximum size in bytes of the text of a [`Statement`]
+pub const DEFAULT_MAX_STATEMENT_SIZE: usize = 16 * 1024 * 1024;
+
+/// Tokens (like IRIs or literals) longer than this number of bytes are
+/// cut short when a [`Statement`] is shown or logged
+pub const MAX_TOKEN_SIZE_IN_LOG: usize = 256;
+
+static MAX_STATEMENT_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_STATEMENT_SIZE);
+
 /// SPARQL Statement
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, Clone)]
 pub struct Statement {
     pub prefixes: Arc<Namespaces>,
     pub(crate) text: String,
+    /// The text as a C string, created (and checked for NUL characters)
+    /// only once, shared between clones. The text never changes after
+    /// [`Statement::new`] so every other statement, derived from this one
+    /// or not, has to go through `new` and gets its own cache.
+    c_text: Arc<OnceLock<CString>>,
+    /// The answer variables (without `?`) that the caller expects, see
+    /// [`Statement::with_expected_variables`]
+    expected_variables: Option<Arc<[String]>>,
+    /// The file that the statement has been read from, see
+    /// [`Statement::from_file`]
+    source: Option<Arc<Path>>,
+    /// The number of lines in the text before the given statement (the
+    /// prepended prefixes) and the number of empty lines that were trimmed
+    /// from the start of the given statement
+    line_offset: (usize, usize),
 }
 
+impl PartialEq for Statement {
+    fn eq(&self, other: &Self) -> bool { self.prefixes == other.prefixes && self.text == other.text }
+}
+
+impl Eq for Statement {}
+
+/// Shows the numbered lines of the statement, with overly long tokens
+/// abbreviated (see [`MAX_TOKEN_SIZE_IN_LOG`])
 impl Display for Statement {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        writeln!(f, "SPARQL Statement:")?;
-        for (number, line) in self.text.lines().enumerate() {
+        match &self.source {
+            Some(source) => writeln!(f, "SPARQL Statement from {}:", source.display())?,
+            None => writeln!(f, "SPARQL Statement:")?,
+        }
+        for (number, line) in abbreviate(self.text.as_str(), MAX_TOKEN_SIZE_IN_LOG)
+            .lines()
+            .enumerate()
+        {
             writeln!(f, "{:0>4}: {line}", number + 1)?;
         }
         Ok(())
@@ -27,25 +96,255 @@ impl Display for Statement {
 }
 
 impl Statement {
+    /// Create a new statement with the given prefixes prepended to the given
+    /// SPARQL text, except the ones that the text declares itself.
+    ///
+    /// Fails if the text declares one of the given prefixes for a different
+    /// namespace, or with [`StatementTooLarge`] if the resulting text is
+    /// larger than the maximum statement size (see
+    /// [`Statement::set_max_size`]).
     pub fn new(prefixes: &Arc<Namespaces>, statement: Cow<str>) -> Result<Self, ekg_error::Error> {
+        let trimmed_lines = statement[..statement.len() - statement.trim_start().len()]
+            .matches('\n')
+            .count();
+        let statement = statement.trim();
+        let declared = prefix_declarations(statement, false);
+        if let Some((name, iri, namespace)) = declared.iter().find_map(|(name, iri)| {
+            prefixes
+                .get(name)
+                .filter(|namespace| namespace.iri.as_str() != *iri)
+                .map(|namespace| (name, iri, namespace))
+        }) {
+            return Err(ekg_error::Error::Exception {
+                action:  "creating a SPARQL statement".to_string(),
+                message: format!(
+                    "the statement declares prefix {name} for <{iri}> instead of <{}>",
+                    namespace.iri.as_str()
+                ),
+            });
+        }
+        let declared = declared.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+        let prelude = prefixes.sparql_prelude_except(&declared);
+        let line_offset = (prelude.matches('\n').count() + 1, trimmed_lines);
+        let text = format!("{prelude}\n{statement}");
+        let limit = Self::max_size();
+        if text.len() > limit {
+            tracing::error!(
+                target: LOG_TARGET_SPARQL,
+                size = text.len(),
+                limit,
+                "SPARQL statement too large:\n{}",
+                abbreviate(text.as_str(), MAX_TOKEN_SIZE_IN_LOG)
+            );
+            return Err(StatementTooLarge { size: text.len(), limit }.into());
+        }
         let s = Self {
             prefixes: prefixes.clone(),
-            text: format!("{}\n{}", &prefixes.to_string(), statement.trim()),
+            text,
+            c_text: Arc::default(),
+            expected_variables: None,
+            source: None,
+            line_offset,
         };
         tracing::trace!(target: LOG_TARGET_SPARQL, "{:}", s);
         Ok(s)
     }
 
-    pub fn cursor(
+    /// Create a statement from the SPARQL text in the given file (like a
+    /// `.rq` file), see [`Statement::new`]. A UTF-8 byte order mark is
+    /// skipped and the file is remembered so that errors and `Display` can
+    /// show where the statement came from.
+    pub fn from_file(prefixes: &Arc<Namespaces>, path: &Path) -> Result<Self, ekg_error::Error> {
+        Self::read_file(prefixes, path, false)
+    }
+
+    /// Like [`Statement::from_file`] but with the comments removed, see
+    /// [`no_comments`]
+    pub fn from_file_without_comments(
+        prefixes: &Arc<Namespaces>,
+        path: &Path,
+    ) -> Result<Self, ekg_error::Error> {
+        Self::read_file(prefixes, path, true)
+    }
+
+    fn read_file(
+        prefixes: &Arc<Namespaces>,
+        path: &Path,
+        without_comments: bool,
+    ) -> Result<Self, ekg_error::Error> {
+        let action = format!("reading a SPARQL statement from {}", path.display());
+        let text = std::fs::read_to_string(path).map_err(|err| {
+            ekg_error::Error::Exception { action: action.clone(), message: err.to_string() }
+        })?;
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text.as_str());
+        let text = if without_comments { no_comments(text).into() } else { Cow::Borrowed(text) };
+        let mut statement = Self::new(prefixes, text).map_err(|err| {
+            match err {
+                ekg_error::Error::Exception { message, .. } => {
+                    ekg_error::Error::Exception { action, message }
+                },
+                err => err,
+            }
+        })?;
+        statement.source = Some(Arc::from(path));
+        Ok(statement)
+    }
+
+    /// The file that this statement has been read from, if any, see
+    /// [`Statement::from_file`]
+    pub fn source(&self) -> Option<&Path> { self.source.as_deref() }
+
+    /// Create a statement in which the given variables (with or without `?`
+    /// or `$`) are replaced by the given terms, which are escaped so that
+    /// whatever they contain is treated as data rather than SPARQL syntax.
+    ///
+    /// Fails if a variable does not occur in the statement or if it's
+    /// projected by a `SELECT` query.
+    pub fn new_with_bindings(
+        prefixes: &Arc<Namespaces>,
+        statement: Cow<str>,
+        bindings: &[(&str, &Term)],
+    ) -> Result<Self, ekg_error::Error> {
+        let statement = bind_variables(statement.as_ref(), bindings).map_err(|message| {
+            ekg_error::Error::Exception {
+                action: "binding the variables of a SPARQL statement".to_string(),
+                message,
+            }
+        })?;
+        Self::new(prefixes, statement.into())
+    }
+
+    /// Expect the query to project exactly the given answer variables (with
+    /// or without `?` or `$`), in this order, so that a mismatch makes
+    /// reading or streaming its results fail before any of them is
+    /// produced.
+    pub fn with_expected_variables<I, S>(mut self, variables: I) -> Self
+        where
+            I: IntoIterator<Item = S>,
+            S: AsRef<str>,
+    {
+        self.expected_variables = Some(
+            variables
+                .into_iter()
+                .map(|variable| variable.as_ref().trim_start_matches(['?', '$']).to_string())
+                .collect(),
+        );
+        self
+    }
+
+    /// The answer variables (without `?`) that this query is expected to
+    /// project, if any, see [`Statement::with_expected_variables`]
+    pub fn expected_variables(&self) -> Option<&[String]> { self.expected_variables.as_deref() }
+
+    /// The answer variables (without `?`) that this `SELECT` query
+    /// projects, found by scanning its text, or `None` for `SELECT *` or
+    /// any other kind of statement.
+    pub fn projection(&self) -> Option<Vec<String>> { projection(self.text.as_str()) }
+
+    /// The maximum size in bytes of the text of a statement, defaults to
+    /// [`DEFAULT_MAX_STATEMENT_SIZE`]
+    pub fn max_size() -> usize { MAX_STATEMENT_SIZE.load(Ordering::Relaxed) }
+
+    /// Set the maximum size in bytes of the text of any statement created
+    /// after this call
+    pub fn set_max_size(limit: usize) { MAX_STATEMENT_SIZE.store(limit, Ordering::Relaxed) }
+
+    /// Create a cursor for this statement on the given connection, which
+    /// has to be an embedded one (such as an `Arc<DataStoreConnection>` or
+    /// a [`GraphConnection`](crate::GraphConnection)) since cursors are
+    /// RDFox objects, see [`DataStoreConnectionLike::consume`] for any
+    /// connection.
+    pub fn cursor<C: DataStoreConnectionLike + ?Sized>(
         &self,
-        connection: &Arc<DataStoreConnection>,
+        connection: &C,
         parameters: &Parameters,
     ) -> Result<Cursor, ekg_error::Error> {
-        Cursor::create(connection, parameters, self)
+        let Some(embedded) = connection.embedded_connection() else {
+            return Err(ekg_error::Error::Exception {
+                action:  format!("Starting a cursor on {}", connection.data_store_name()),
+                message: "not an embedded connection, use DataStoreConnectionLike::consume"
+                    .to_string(),
+            });
+        };
+        Cursor::create(embedded, parameters, self)
+    }
+
+    /// Evaluate this `ASK` query in the given transaction and return its
+    /// answer.
+    ///
+    /// Fails if this statement is not an `ASK` query.
+    pub fn ask<C: DataStoreConnectionLike + ?Sized>(
+        &self,
+        connection: &C,
+        parameters: &Parameters,
+        tx: &Arc<Transaction>,
+    ) -> Result<bool, ekg_error::Error> {
+        let form = query_form(self.text.as_str());
+        if form != Some("ASK") {
+            return Err(ekg_error::Error::Exception {
+                action:  "evaluating an ASK query".to_string(),
+                message: format!(
+                    "not an ASK query but {}:\n{self}",
+                    form.unwrap_or("an unknown kind of statement")
+                ),
+            });
+        }
+        let mut cursor = self.cursor(connection, parameters)?;
+        let (_, multiplicity) = OpenedCursor::new(&mut cursor, tx.clone())?;
+        Ok(multiplicity > 0)
     }
 
-    pub(crate) fn as_c_string(&self) -> Result<CString, ekg_error::Error> {
-        Ok(CString::new(self.text.as_str())?)
+    /// Let RDFox parse (and plan) this statement without evaluating it.
+    ///
+    /// Fails with [`InvalidSparql`] if it can't be parsed, with the line
+    /// number in the text that was given to this statement.
+    pub fn validate<C: DataStoreConnectionLike + ?Sized>(
+        &self,
+        connection: &C,
+    ) -> Result<(), ekg_error::Error> {
+        self.cursor(connection, &Parameters::empty()?)
+            .map(|_cursor| ())
+            .map_err(|err| self.invalid_sparql(err))
+    }
+
+    /// Turn the given error into an [`InvalidSparql`] error if it's a
+    /// parsing exception, with the line number that RDFox mentions mapped
+    /// back to the text that was given to this statement
+    fn invalid_sparql(&self, err: ekg_error::Error) -> ekg_error::Error {
+        let Some(exception) = RDFoxException::from_error(&err) else {
+            return err;
+        };
+        let ExceptionKind::InvalidSparql { line, column } = exception.kind else {
+            return err;
+        };
+        let (prelude_lines, trimmed_lines) = self.line_offset;
+        let line_in_text = line.filter(|line| *line > prelude_lines);
+        InvalidSparql {
+            line:      line_in_text.map(|line| line - prelude_lines + trimmed_lines),
+            column:    line_in_text.and(column),
+            message:   exception.message.trim_end().to_string(),
+            line_text: line_in_text
+                .and_then(|line| self.text.lines().nth(line - 1))
+                .map(str::to_string),
+        }
+        .into()
+    }
+
+    /// Return the text as a C string, which is only created (and checked
+    /// for NUL characters) the first time, so executing the same statement
+    /// over and over again (creating cursors, evaluating updates or
+    /// streaming) does not copy its text each time.
+    pub fn as_c_string(&self) -> Result<&CStr, ekg_error::Error> {
+        if let Some(c_text) = self.c_text.get() {
+            return Ok(c_text.as_c_str());
+        }
+        let c_text = CString::new(self.text.as_str())?;
+        tracing::trace!(
+            target: LOG_TARGET_SPARQL,
+            size = self.text.len(),
+            "Converted SPARQL statement to a C string"
+        );
+        Ok(self.c_text.get_or_init(|| c_text).as_c_str())
     }
 
     pub fn as_str(&self) -> &str { self.text.as_str() }
@@ -55,103 +354,201 @@ impl Statement {
     /// Return a Statement that can be used to export all data in
     /// `application/nquads` format
     pub fn nquads_query(prefixes: &Arc<Namespaces>) -> Result<Statement, ekg_error::Error> {
-        let default_graph = DEFAULT_GRAPH_RDFOX.deref().as_display_iri();
-        let statement = Statement::new(
+        Statement::new(
             prefixes,
-            formatdoc!(
-                r##"
-                SELECT ?S ?P ?O ?G
-                WHERE {{
-                    {{
-                        GRAPH ?G {{ ?S ?P ?O }}
-                    }} UNION {{
-                        ?S ?P ?P .
-                        BIND({default_graph} AS ?G)
-                    }}
-                }}
-            "##
+            format!(
+                "SELECT ?S ?P ?O ?G\nWHERE {{\n{}\n}}",
+                Self::all_graphs_pattern("?S", "?P", "?O", "?G")
             )
-                .into(),
-        )?;
-        Ok(statement)
+            .into(),
+        )
+    }
+
+    /// The graph pattern that matches the triples in all named graphs and in
+    /// RDFox's default graph, binding the graph of each one to the given
+    /// variable. The state of the data store in
+    /// [`DATA_STORE_STATE_GRAPH`] is not data, so it's left out.
+    pub(crate) fn all_graphs_pattern(s: &str, p: &str, o: &str, graph: &str) -> String {
+        let default_graph = DEFAULT_GRAPH_RDFOX.deref().as_display_iri();
+        formatdoc!(
+            r##"
+                {{
+                    GRAPH {graph} {{ {s} {p} {o} }}
+                    FILTER({graph} != <{DATA_STORE_STATE_GRAPH}>)
+                }} UNION {{
+                    {s} {p} {o} .
+                    BIND({default_graph} AS {graph})
+                }}"##
+        )
     }
 }
 
-pub fn no_comments(string: &str) -> String {
-    use std::fmt::Write;
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{
+            ExceptionKind,
+            InvalidSparql,
+            Namespaces,
+            RDFoxException,
+            Statement,
+            StatementTooLarge,
+            DEFAULT_MAX_STATEMENT_SIZE,
+        },
+        std::path::Path,
+    };
+
+    /// The text of a statement with empty prefixes is a line-feed plus the
+    /// given statement, so a literal of `size - 3` bytes (quotes included)
+    /// makes a statement of `size` bytes.
+    fn statement_of_size(size: usize) -> Result<Statement, ekg_error::Error> {
+        let literal = format!("\"{}\"", "x".repeat(size - 3));
+        Statement::new(&Namespaces::empty()?, literal.into())
+    }
 
-    let re = fancy_regex::Regex::new(r"(.*)(?!#>)#.*$").unwrap();
+    #[test_log::test]
+    fn test_nquads_query() {
+        let text = Statement::nquads_query(&Namespaces::empty().unwrap())
+            .unwrap()
+            .as_str()
+            .to_string();
+        assert!(text.contains("GRAPH ?G { ?S ?P ?O }"), "{text}");
+        assert!(text.contains("?S ?P ?O .\n"), "{text}");
+        assert!(!text.contains("?P ?P"), "{text}");
+    }
 
-    let do_line = |line: &str| -> (bool, String) {
-        let caps = re.captures(line);
-        if let Ok(Some(caps)) = caps {
-            let mat = caps.get(1).unwrap();
-            (
-                true,
-                line[mat.start()..mat.end()].trim_end().to_string(),
+    #[test_log::test]
+    fn test_prefixes_declared_by_the_statement() {
+        let prefixes = Namespaces::default_namespaces().unwrap();
+        let statement = Statement::new(
+            &prefixes,
+            concat!(
+                "PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>\n",
+                "PREFIX ekgf: <https://ekgf.org/ontology/>\n",
+                "SELECT ?s WHERE { ?s a ekgf:Thing ; rdfs:label ?label }"
             )
-        } else {
-            (false, line.trim_end().to_string())
+            .into(),
+        )
+        .unwrap();
+        let text = statement.as_str();
+        assert_eq!(text.matches("PREFIX rdfs:").count(), 1, "{text}");
+        assert_eq!(text.matches("PREFIX ekgf:").count(), 1, "{text}");
+        assert!(text.contains("PREFIX owl:"), "{text}");
+
+        // Declaring one of the given prefixes for another namespace is an error
+        let conflict = Statement::new(
+            &prefixes,
+            "PREFIX rdfs: <https://ekgf.org/not-rdfs#>\nSELECT ?s WHERE { ?s rdfs:label ?o }"
+                .into(),
+        );
+        match conflict {
+            Err(ekg_error::Error::Exception { message, .. }) => {
+                assert!(message.contains("prefix rdfs:"), "{message}")
+            },
+            result => panic!("unexpected {result:?}"),
         }
-    };
+    }
 
-    let mut output = String::new();
-    for line in string.lines() {
-        let mut line = line.to_string();
-        loop {
-            let (again, result) = do_line(line.as_str());
-            if again {
-                // Repeat the call to do_line again to make sure that all #-comments are removed
-                // (there could be multiple on one line)
-                line = result;
-            } else {
-                writeln!(&mut output, "{result}").unwrap();
-                break;
-            }
+    #[test_log::test]
+    fn test_from_file() {
+        let prefixes = Namespaces::empty().unwrap();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/queries/count.rq");
+        let statement = Statement::from_file_without_comments(&prefixes, &path).unwrap();
+        assert!(!statement.as_str().contains("# The number"), "{statement}");
+        assert_eq!(statement.source(), Some(path.as_path()));
+
+        match Statement::from_file(&prefixes, &path.with_file_name("missing.rq")) {
+            Err(ekg_error::Error::Exception { action, .. }) => {
+                assert!(action.ends_with("missing.rq"), "{action}")
+            },
+            result => panic!("unexpected {result:?}"),
         }
     }
-    output
-}
 
-#[cfg(test)]
-mod tests {
     #[test_log::test]
-    fn test_no_comments() {
-        let sparql = indoc::formatdoc! {r##"
-            PREFIX abc: <https://whatever.org#> # focus on this and the next line
-            PREFIX owl: <http://www.w3.org/2002/07/owl#>
-            SELECT DISTINCT ?thing
-            WHERE {{
-                {{ # some comment
-                    GRAPH ?graph {{ # more # and more
-                        ?thing a Whatever#
-                    }}
-                }} UNION {{
-                    ?thing a Whatever .# abc
-                                       # def
-                    BIND(graph:Graph AS ?graph)
-                }}
-            }}
-            "##
+    fn test_invalid_sparql() {
+        let prefixes = Namespaces::default_namespaces().unwrap();
+        let statement = Statement::new(
+            &prefixes,
+            "\nSELECT ?s\nWHERE {\n    ?s ?p ?o ..\n}".into(),
+        )
+        .unwrap();
+        let prelude_lines = statement.line_offset.0;
+        assert_eq!(prelude_lines, 5);
+        let parsing_exception = |line: usize| -> ekg_error::Error {
+            RDFoxException {
+                action:  "Starting a cursor".to_string(),
+                name:    "ParsingException".to_string(),
+                message: format!("Line {line}, column 14: unexpected token '.'\n"),
+                kind:    ExceptionKind::InvalidSparql { line: Some(line), column: Some(14) },
+            }
+            .into()
         };
-        let expected = indoc::formatdoc! {r##"
-            PREFIX abc: <https://whatever.org#>
-            PREFIX owl: <http://www.w3.org/2002/07/owl#>
-            SELECT DISTINCT ?thing
-            WHERE {{
-                {{
-                    GRAPH ?graph {{
-                        ?thing a Whatever
-                    }}
-                }} UNION {{
-                    ?thing a Whatever .
+        // Line 4 of the given text, which started with an empty line
+        let err = statement.invalid_sparql(parsing_exception(prelude_lines + 3));
+        let invalid = InvalidSparql::from_error(&err).unwrap();
+        assert_eq!((invalid.line, invalid.column), (Some(4), Some(14)));
+        assert_eq!(invalid.line_text.as_deref(), Some("    ?s ?p ?o .."));
+        assert_eq!(
+            invalid.to_string(),
+            "InvalidSparql: line 4, column 14: Line 8, column 14: unexpected token '.'\n0004:     \
+             ?s ?p ?o .."
+        );
+        // Not in the given text but in the prefixes
+        let invalid = InvalidSparql::from_error(&statement.invalid_sparql(parsing_exception(2)));
+        assert_eq!(invalid.map(|invalid| invalid.line), Some(None));
+    }
 
-                    BIND(graph:Graph AS ?graph)
-                }}
-            }}
-            "##
-        };
-        let actual = crate::statement::no_comments(sparql.as_str());
-        assert_eq!(actual.as_str(), expected.as_str());
+    #[test_log::test]
+    fn test_statement_size_limit() {
+        assert!(statement_of_size(DEFAULT_MAX_STATEMENT_SIZE).is_ok());
+        let err = statement_of_size(DEFAULT_MAX_STATEMENT_SIZE + 1).unwrap_err();
+        assert_eq!(
+            StatementTooLarge::from_error(&err),
+            Some(StatementTooLarge {
+                size:  DEFAULT_MAX_STATEMENT_SIZE + 1,
+                limit: DEFAULT_MAX_STATEMENT_SIZE,
+            })
+        );
+    }
+
+    #[test_log::test]
+    fn test_display_of_huge_literal_is_bounded() {
+        let literal = "x".repeat(2 * 1024 * 1024);
+        let statement = Statement::new(
+            &Namespaces::empty().unwrap(),
+            format!("SELECT * WHERE {{ ?s ?p \"{literal}\" }}").into(),
+        )
+        .unwrap();
+        let shown = format!("{statement}");
+        assert!(shown.len() < 1024, "{} bytes shown", shown.len());
+        assert!(shown.contains(format!("[{} bytes]", literal.len() + 2).as_str()));
+        assert_eq!(
+            statement.as_c_string().unwrap().to_bytes().len(),
+            statement.as_str().len()
+        );
+    }
+
+    #[test_log::test]
+    fn test_c_string_is_cached() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Statement>();
+
+        let prefixes = Namespaces::empty().unwrap();
+        let statement =
+            Statement::new(&prefixes, "SELECT ?s WHERE { ?s ?p ?o }".into()).unwrap();
+        let pointer = statement.as_c_string().unwrap().as_ptr();
+        let clone = statement.clone();
+        assert_eq!(clone.as_c_string().unwrap().as_ptr(), pointer);
+        assert_eq!(statement.as_c_string().unwrap().as_ptr(), pointer);
+
+        // A statement derived from another one has a cache of its own
+        let derived =
+            Statement::new(&prefixes, format!("{}\nLIMIT 1", statement.as_str()).into()).unwrap();
+        assert_ne!(derived.as_c_string().unwrap().as_ptr(), pointer);
+        assert_eq!(derived.as_c_string().unwrap().to_str().unwrap(), derived.as_str());
+        assert!(derived.as_str().ends_with("LIMIT 1"));
+        assert_eq!(statement.as_c_string().unwrap().to_str().unwrap(), statement.as_str());
+        assert!(!statement.as_str().contains("LIMIT"));
     }
 }
@@ -0,0 +1,86 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{Namespaces, Statement},
+    std::{collections::HashMap, path::Path, sync::Arc},
+};
+
+/// The statements in the `.rq` files of a directory, by file stem, so
+/// `labels.rq` is the statement named `labels`.
+#[derive(Debug, Clone, Default)]
+pub struct StatementLibrary {
+    statements: HashMap<String, Statement>,
+}
+
+impl StatementLibrary {
+    /// Read all `.rq` files in the given directory (not in its
+    /// subdirectories) with [`Statement::from_file`]
+    pub fn load(prefixes: &Arc<Namespaces>, dir: &Path) -> Result<Self, ekg_error::Error> {
+        let error = |err: std::io::Error| {
+            ekg_error::Error::Exception {
+                action:  format!("loading SPARQL statements from {}", dir.display()),
+                message: err.to_string(),
+            }
+        };
+        let mut statements = HashMap::new();
+        for entry in std::fs::read_dir(dir).map_err(error)? {
+            let path = entry.map_err(error)?.path();
+            if !path.is_file() || path.extension().map_or(true, |extension| extension != "rq") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            tracing::debug!("Loading SPARQL statement {name} from {}", path.display());
+            statements.insert(name.to_string(), Statement::from_file(prefixes, &path)?);
+        }
+        Ok(Self { statements })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Statement> { self.statements.get(name) }
+
+    /// Like [`StatementLibrary::get`] but fails if there is no such
+    /// statement
+    pub fn statement(&self, name: &str) -> Result<&Statement, ekg_error::Error> {
+        self.get(name).ok_or_else(|| {
+            ekg_error::Error::Exception {
+                action:  format!("getting SPARQL statement {name}"),
+                message: "there is no such statement in the library".to_string(),
+            }
+        })
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> { self.statements.keys().map(String::as_str) }
+
+    pub fn len(&self) -> usize { self.statements.len() }
+
+    pub fn is_empty(&self) -> bool { self.statements.is_empty() }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::StatementLibrary,
+        crate::Namespaces,
+        std::path::Path,
+    };
+
+    #[test_log::test]
+    fn test_load() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/queries");
+        let library = StatementLibrary::load(&Namespaces::default_namespaces().unwrap(), &dir)
+            .unwrap();
+        let mut names = library.names().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["count", "labels"]);
+
+        let labels = library.statement("labels").unwrap();
+        assert_eq!(labels.source(), Some(dir.join("labels.rq").as_path()));
+        assert!(!labels.as_str().contains('\u{feff}'));
+        assert!(labels.to_string().starts_with("SPARQL Statement from "));
+        assert!(labels.to_string().lines().next().unwrap().ends_with("labels.rq:"));
+        assert_eq!(labels.projection(), Some(vec!["concept".to_string(), "label".to_string()]));
+        assert!(library.statement("missing").is_err());
+    }
+}
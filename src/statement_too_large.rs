@@ -0,0 +1,48 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// The text of a statement, prefixes included, is larger than the maximum
+/// statement size, see [`Statement::set_max_size`](crate::Statement::set_max_size).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatementTooLarge {
+    /// The size of the text in bytes
+    pub size:  usize,
+    /// The maximum size in bytes at the time
+    pub limit: usize,
+}
+
+impl Display for StatementTooLarge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "StatementTooLarge: statement too large: {} bytes (limit is {} bytes)",
+            self.size, self.limit
+        )
+    }
+}
+
+impl std::error::Error for StatementTooLarge {}
+
+impl TypedError for StatementTooLarge {
+    const NAME: &'static str = "StatementTooLarge";
+
+    fn action(&self) -> String { "creating a SPARQL statement".to_string() }
+}
+
+impl From<StatementTooLarge> for ekg_error::Error {
+    fn from(value: StatementTooLarge) -> Self { value.to_error() }
+}
+
+impl StatementTooLarge {
+    /// The [`StatementTooLarge`] error that the given error was made from, if
+    /// any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
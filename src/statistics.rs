@@ -0,0 +1,371 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{
+        database_call,
+        exception::CException,
+        rdfox_api::{
+            CComponentInfo,
+            CComponentInfoPropertyType,
+            CComponentInfo_destroy,
+            CComponentInfo_getName,
+            CComponentInfo_getNumberOfPropertyValues,
+            CComponentInfo_getNumberOfSubComponents,
+            CComponentInfo_getNumericPropertyValue,
+            CComponentInfo_getPropertyName,
+            CComponentInfo_getPropertyType,
+            CComponentInfo_getStringPropertyValue,
+            CComponentInfo_getSubComponent,
+        },
+    },
+    serde::Serialize,
+    std::{
+        collections::{BTreeMap, HashMap},
+        ffi::CStr,
+        os::raw::c_char,
+        ptr,
+    },
+};
+
+/// Read the component info that the given function returns and destroy it
+pub(crate) fn read_component_info<F>(
+    action: &str,
+    get: F,
+) -> Result<ComponentInfo, ekg_error::Error>
+    where F: FnOnce(*mut *mut CComponentInfo) -> *const CException {
+    let mut info: *mut CComponentInfo = ptr::null_mut();
+    database_call!(action, get(&mut info))?;
+    let result = ComponentInfo::from_c(info.cast_const());
+    unsafe { CComponentInfo_destroy(info) };
+    result
+}
+
+/// The names under which RDFox reports the number of explicitly asserted
+/// (EDB) facts, depending on the version and the level of the component
+const ASSERTED_FACTS: &[&str] = &["Aggregate number of EDB facts", "Number of EDB facts"];
+/// The same for all (IDB) facts, i.e. asserted or derived
+const ALL_FACTS: &[&str] = &["Aggregate number of IDB facts", "Number of IDB facts"];
+const RULES: &[&str] = &["Number of rules"];
+const RESOURCES: &[&str] = &["End resource ID", "Number of resources"];
+const MEMORY: &[&str] = &["Aggregate size", "Size"];
+
+/// The value of a property of a [`ComponentInfo`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum ComponentValue {
+    Numeric(u64),
+    String(String),
+}
+
+/// What RDFox tells about (a component of) a server or data store, the raw
+/// material of [`DataStoreStatistics`] and [`ServerStatistics`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentInfo {
+    pub name:       String,
+    pub properties: BTreeMap<String, ComponentValue>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<ComponentInfo>,
+}
+
+impl ComponentInfo {
+    /// Read the given component (and all its subcomponents), which stays
+    /// owned by the caller
+    pub(crate) fn from_c(info: *const CComponentInfo) -> Result<Self, ekg_error::Error> {
+        let text = |value: *const c_char| {
+            unsafe { CStr::from_ptr(value) }
+                .to_string_lossy()
+                .into_owned()
+        };
+
+        let mut name: *const c_char = ptr::null();
+        database_call!(
+            "getting the name of a component",
+            CComponentInfo_getName(info, &mut name)
+        )?;
+
+        let mut number_of_properties = 0_usize;
+        database_call!(
+            "getting the number of properties of a component",
+            CComponentInfo_getNumberOfPropertyValues(info, &mut number_of_properties)
+        )?;
+        let mut properties = BTreeMap::new();
+        for index in 0..number_of_properties {
+            let mut property_name: *const c_char = ptr::null();
+            database_call!(
+                "getting the name of a property of a component",
+                CComponentInfo_getPropertyName(info, index, &mut property_name)
+            )?;
+            let mut property_type =
+                CComponentInfoPropertyType::COMPONENT_INFO_PROPERTY_TYPE_NUMERIC;
+            database_call!(
+                "getting the type of a property of a component",
+                CComponentInfo_getPropertyType(info, index, &mut property_type)
+            )?;
+            let value = match property_type {
+                CComponentInfoPropertyType::COMPONENT_INFO_PROPERTY_TYPE_NUMERIC => {
+                    let mut value = 0_u64;
+                    database_call!(
+                        "getting a numeric property of a component",
+                        CComponentInfo_getNumericPropertyValue(info, index, &mut value)
+                    )?;
+                    ComponentValue::Numeric(value)
+                },
+                CComponentInfoPropertyType::COMPONENT_INFO_PROPERTY_TYPE_STRING => {
+                    let mut value: *const c_char = ptr::null();
+                    database_call!(
+                        "getting a string property of a component",
+                        CComponentInfo_getStringPropertyValue(info, index, &mut value)
+                    )?;
+                    ComponentValue::String(text(value))
+                },
+            };
+            properties.insert(text(property_name), value);
+        }
+
+        let mut number_of_components = 0_usize;
+        database_call!(
+            "getting the number of subcomponents of a component",
+            CComponentInfo_getNumberOfSubComponents(info, &mut number_of_components)
+        )?;
+        let components = (0..number_of_components)
+            .map(|index| {
+                let mut component: *const CComponentInfo = ptr::null();
+                database_call!(
+                    "getting a subcomponent of a component",
+                    CComponentInfo_getSubComponent(info, index, &mut component)
+                )?;
+                Self::from_c(component)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { name: text(name), properties, components })
+    }
+
+    /// The numeric value of the first of the given properties that this
+    /// component has, or else the sum of the values of its subcomponents
+    /// (as far as they have one)
+    pub fn numeric(&self, names: &[&str]) -> Option<u64> {
+        let own = names.iter().find_map(|name| {
+            match self.properties.get(*name) {
+                Some(ComponentValue::Numeric(value)) => Some(*value),
+                _ => None,
+            }
+        });
+        own.or_else(|| {
+            self.components
+                .iter()
+                .filter_map(|component| component.numeric(names))
+                .reduce(|sum, value| sum + value)
+        })
+    }
+
+    /// The parameters of the data store with the given name, as reported in
+    /// the `Parameters` subcomponent of its component in the component info
+    /// of a server, `None` if there's no such data store or subcomponent
+    pub(crate) fn data_store_parameters(&self, name: &str) -> Option<HashMap<String, String>> {
+        let data_store = self
+            .components
+            .iter()
+            .find(|component| component.string("Name") == Some(name))?;
+        let parameters = data_store
+            .components
+            .iter()
+            .find(|component| component.name == "Parameters")?;
+        Some(
+            parameters
+                .properties
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        ComponentValue::Numeric(value) => value.to_string(),
+                        ComponentValue::String(value) => value.clone(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        )
+    }
+
+    /// The string value of the given property of this component
+    pub fn string(&self, name: &str) -> Option<&str> {
+        match self.properties.get(name) {
+            Some(ComponentValue::String(value)) => Some(value.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// The numbers of a data store, see
+/// [`DataStoreConnection::statistics`](crate::DataStoreConnection::statistics).
+///
+/// Numbers that the RDFox version at hand does not report are `None`, look
+/// for them in `component_info`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DataStoreStatistics {
+    pub name:           String,
+    /// The number of explicitly asserted (EDB) facts
+    pub asserted_facts: Option<u64>,
+    /// The number of all (IDB) facts, asserted or derived
+    pub all_facts:      Option<u64>,
+    /// The number of facts that are only there because they were derived
+    pub derived_facts:  Option<u64>,
+    pub rules:          Option<u64>,
+    /// The number of resources (IRIs, literals and blank nodes) in the
+    /// dictionary
+    pub resources:      Option<u64>,
+    /// The memory used by the data store in bytes
+    pub memory_bytes:   Option<u64>,
+    pub component_info: ComponentInfo,
+}
+
+impl DataStoreStatistics {
+    pub(crate) fn new(name: &str, component_info: ComponentInfo) -> Self {
+        let asserted_facts = component_info.numeric(ASSERTED_FACTS);
+        let all_facts = component_info.numeric(ALL_FACTS);
+        Self {
+            name: name.to_string(),
+            asserted_facts,
+            all_facts,
+            derived_facts: all_facts
+                .zip(asserted_facts)
+                .map(|(all, asserted)| all.saturating_sub(asserted)),
+            rules: component_info.numeric(RULES),
+            resources: component_info.numeric(RESOURCES),
+            memory_bytes: component_info.numeric(MEMORY),
+            component_info,
+        }
+    }
+}
+
+/// The memory use of a server, see
+/// [`ServerConnection::memory_use`](crate::ServerConnection::memory_use).
+///
+/// The C API reports the same two numbers in all supported versions of
+/// RDFox, the memory of each data store is in
+/// [`DataStoreStatistics::memory_bytes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MemoryUse {
+    /// The maximum number of bytes that the server used so far
+    pub max_used_bytes:  usize,
+    /// The number of bytes that the server can still use
+    pub available_bytes: usize,
+}
+
+impl std::fmt::Display for MemoryUse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "max_used_bytes={} available_bytes={}",
+            self.max_used_bytes, self.available_bytes
+        )
+    }
+}
+
+/// The numbers of a server, see
+/// [`ServerConnection::server_statistics`](crate::ServerConnection::server_statistics).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ServerStatistics {
+    pub version:           String,
+    pub number_of_threads: u32,
+    /// The maximum number of bytes that the server used so far
+    pub max_used_bytes:    usize,
+    /// The number of bytes that the server can still use
+    pub available_bytes:   usize,
+    pub data_stores:       Vec<String>,
+    pub component_info:    ComponentInfo,
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{ComponentInfo, ComponentValue, DataStoreStatistics},
+        std::collections::BTreeMap,
+    };
+
+    fn component(
+        name: &str,
+        properties: &[(&str, u64)],
+        components: Vec<ComponentInfo>,
+    ) -> ComponentInfo {
+        ComponentInfo {
+            name: name.to_string(),
+            properties: properties
+                .iter()
+                .map(|(name, value)| (name.to_string(), ComponentValue::Numeric(*value)))
+                .collect::<BTreeMap<_, _>>(),
+            components,
+        }
+    }
+
+    #[test_log::test]
+    fn test_data_store_statistics() {
+        // Without aggregates at the top the tuple tables are summed up
+        let info = component(
+            "RDFStore",
+            &[("Number of rules", 2)],
+            vec![
+                component(
+                    "DefaultTriples",
+                    &[("Number of EDB facts", 10), ("Number of IDB facts", 12)],
+                    vec![],
+                ),
+                component(
+                    "Quads",
+                    &[("Number of EDB facts", 5), ("Number of IDB facts", 5)],
+                    vec![],
+                ),
+            ],
+        );
+        let statistics = DataStoreStatistics::new("test", info);
+        assert_eq!(statistics.asserted_facts, Some(15));
+        assert_eq!(statistics.all_facts, Some(17));
+        assert_eq!(statistics.derived_facts, Some(2));
+        assert_eq!(statistics.rules, Some(2));
+        assert_eq!(statistics.resources, None);
+
+        let json = serde_json::to_value(&statistics).unwrap();
+        assert_eq!(json["asserted_facts"], 15);
+        assert_eq!(json["component_info"]["properties"]["Number of rules"], 2);
+
+        // Aggregates win
+        let info = component(
+            "RDFStore",
+            &[("Aggregate number of EDB facts", 3)],
+            vec![component("DefaultTriples", &[("Number of EDB facts", 10)], vec![])],
+        );
+        assert_eq!(DataStoreStatistics::new("test", info).asserted_facts, Some(3));
+    }
+
+    #[test_log::test]
+    fn test_data_store_parameters() {
+        let string = |name: &str, value: &str| {
+            (name.to_string(), ComponentValue::String(value.to_string()))
+        };
+        let parameters = ComponentInfo {
+            name:       "Parameters".to_string(),
+            properties: BTreeMap::from([
+                string("equality", "off"),
+                (
+                    "max-data-pool-size".to_string(),
+                    ComponentValue::Numeric(1024),
+                ),
+            ]),
+            components: vec![],
+        };
+        let data_store = ComponentInfo {
+            name:       "RDFStore".to_string(),
+            properties: BTreeMap::from([string("Name", "family")]),
+            components: vec![parameters],
+        };
+        let server = component("Server", &[], vec![data_store]);
+
+        let parameters = server.data_store_parameters("family").unwrap();
+        assert_eq!(parameters.get("equality").map(String::as_str), Some("off"));
+        assert_eq!(
+            parameters.get("max-data-pool-size").map(String::as_str),
+            Some("1024")
+        );
+        assert_eq!(server.data_store_parameters("other"), None);
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::{Display, Formatter},
+        io::ErrorKind,
+    },
+};
+
+/// A [`Streamer`](crate::Streamer) stopped writing the results of a
+/// statement, because its writer failed, because the writer did not accept
+/// so much data that more than
+/// [`Streamer::max_remaining_buffer_size`](crate::Streamer::max_remaining_buffer_size)
+/// bytes had to be kept around (`ErrorKind::OutOfMemory`), or because the
+/// operation was cancelled (`ErrorKind::Interrupted`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamAborted {
+    /// The kind of the `std::io::Error` that aborted the stream
+    #[serde(with = "error_kind_name")]
+    pub kind:    ErrorKind,
+    /// The message of that error
+    pub message: String,
+}
+
+impl Display for StreamAborted {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "StreamAborted: {:?}: {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for StreamAborted {}
+
+impl TypedError for StreamAborted {
+    const NAME: &'static str = "StreamAborted";
+
+    fn action(&self) -> String { "streaming the results of a statement".to_string() }
+}
+
+impl From<StreamAborted> for ekg_error::Error {
+    fn from(value: StreamAborted) -> Self { value.to_error() }
+}
+
+impl From<&std::io::Error> for StreamAborted {
+    fn from(err: &std::io::Error) -> Self { Self { kind: err.kind(), message: err.to_string() } }
+}
+
+impl StreamAborted {
+    /// The [`StreamAborted`] error that the given error was made from, if
+    /// any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+
+    /// Whether the stream was aborted because the writer did not accept
+    /// the data fast enough, rather than because it failed
+    pub fn is_buffer_limit_exceeded(&self) -> bool { self.kind == ErrorKind::OutOfMemory }
+}
+
+/// An [`ErrorKind`] by its name, kinds that are not listed here come back
+/// as `ErrorKind::Other`
+mod error_kind_name {
+    use {
+        serde::{Deserialize, Deserializer, Serializer},
+        std::io::ErrorKind,
+    };
+
+    const KINDS: &[ErrorKind] = &[
+        ErrorKind::NotFound,
+        ErrorKind::PermissionDenied,
+        ErrorKind::ConnectionRefused,
+        ErrorKind::ConnectionReset,
+        ErrorKind::ConnectionAborted,
+        ErrorKind::NotConnected,
+        ErrorKind::AddrInUse,
+        ErrorKind::AddrNotAvailable,
+        ErrorKind::BrokenPipe,
+        ErrorKind::AlreadyExists,
+        ErrorKind::WouldBlock,
+        ErrorKind::InvalidInput,
+        ErrorKind::InvalidData,
+        ErrorKind::TimedOut,
+        ErrorKind::WriteZero,
+        ErrorKind::Interrupted,
+        ErrorKind::Unsupported,
+        ErrorKind::UnexpectedEof,
+        ErrorKind::OutOfMemory,
+        ErrorKind::Other,
+    ];
+
+    pub(super) fn serialize<S: Serializer>(
+        kind: &ErrorKind,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(format!("{kind:?}").as_str())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ErrorKind, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(KINDS
+            .iter()
+            .copied()
+            .find(|kind| format!("{kind:?}") == name)
+            .unwrap_or(ErrorKind::Other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::StreamAborted, std::io::ErrorKind};
+
+    #[test_log::test]
+    fn test_from_error() {
+        for kind in [ErrorKind::BrokenPipe, ErrorKind::OutOfMemory, ErrorKind::Interrupted] {
+            let aborted = StreamAborted::from(&std::io::Error::new(kind, "writing"));
+            let back = StreamAborted::from_error(&aborted.clone().into()).unwrap();
+            assert_eq!(back, aborted);
+            assert_eq!(back.is_buffer_limit_exceeded(), kind == ErrorKind::OutOfMemory);
+        }
+    }
+}
@@ -0,0 +1,14 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+/// The complete serialized results of a statement, see
+/// [`DataStoreConnection::evaluate_to_string`](crate::DataStoreConnection::evaluate_to_string)
+/// and [`DataStoreConnection::evaluate_to_vec`](crate::DataStoreConnection::evaluate_to_vec).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamedResult<B> {
+    /// The results in the requested format
+    pub body:                B,
+    /// The number of solutions (query answers) that RDFox reported, so an
+    /// empty result can be told apart without parsing the body
+    pub number_of_solutions: usize,
+}
@@ -5,27 +5,44 @@ use {
     crate::{
         database_call,
         DataStoreConnection,
+        operation_registry::OperationKind,
+        Outcome,
         Parameters,
+        ProjectionMismatch,
+        QueryTimedOut,
         rdfox_api::{
             CDataStoreConnection,
             CDataStoreConnection_evaluateStatement,
             COutputStream,
             CStatementResult,
         },
+        sparql_text::abbreviate,
+        statement::MAX_TOKEN_SIZE_IN_LOG,
         Statement,
+        StreamAborted,
     },
-    ekg_namespace::{Namespace, ptr_to_cstr},
+    ekg_namespace::Namespace,
     mime::Mime,
     std::{
+        cell::{Cell, RefCell},
         ffi::{c_void, CString},
         fmt::Debug,
         io::Write,
         mem::MaybeUninit,
         ptr,
-        sync::Arc,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        },
     },
 };
 
+/// The default maximum number of bytes that a [`Streamer`] keeps around
+/// when its writer does not accept all data it's given
+pub const DEFAULT_MAX_REMAINING_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+
+static MAX_REMAINING_BUFFER_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_REMAINING_BUFFER_SIZE);
+
 #[derive(PartialEq, Debug)]
 struct RefToSelf<'a, W: 'a + Write> {
     streamer: *mut Streamer<'a, W>,
@@ -47,15 +64,36 @@ pub struct Streamer<'a, W: 'a + Write> {
     pub connection: Arc<DataStoreConnection>,
     pub writer: W,
     pub statement: &'a Statement,
+    /// The parameters of the evaluation, `None` means all facts (i.e.
+    /// fact domain `ALL`) and default settings otherwise
+    pub parameters: Option<&'a Parameters>,
     pub mime_type: &'static Mime,
     pub base_iri: Namespace,
     pub instant: std::time::Instant,
     self_p: String,
-    remaining_buffer: std::cell::RefCell<Option<String>>,
+    remaining_buffer: RefCell<Vec<u8>>,
+    /// The largest size of `remaining_buffer` so far
+    peak_remaining_buffer_size: Cell<usize>,
+    /// The error that made the last call to the writer fail, if any
+    write_error: RefCell<Option<std::io::Error>>,
+    /// The number of solutions (query answers) that RDFox reported
+    number_of_solutions: usize,
+    /// How long the evaluation of the statement took
+    elapsed: std::time::Duration,
+    /// Raised when the operation is cancelled, see
+    /// [`DataStoreConnection::cancel_operation`]
+    cancelled: Arc<AtomicBool>,
 }
 
 impl<'a, W: 'a + Write> Drop for Streamer<'a, W> {
     fn drop(&mut self) {
+        // Not to lose what the writer did not accept when the stream ended
+        // without RDFox flushing it
+        if !self.remaining_buffer.borrow().is_empty() {
+            if let Err(err) = self.drain_remaining() {
+                tracing::error!("{}: could not write remaining data: {err:?}", self.self_p);
+            }
+        }
         tracing::trace!("{}: Dropped streamer", self.self_p);
     }
 }
@@ -65,6 +103,7 @@ impl<'a, W: 'a + Write> Streamer<'a, W> {
         connection: &Arc<DataStoreConnection>,
         writer: W,
         statement: &'a Statement,
+        parameters: Option<&'a Parameters>,
         mime_type: &'static Mime,
         base_iri: Namespace,
     ) -> Result<Self, ekg_error::Error> {
@@ -72,21 +111,74 @@ impl<'a, W: 'a + Write> Streamer<'a, W> {
             connection: connection.clone(),
             writer,
             statement,
+            parameters,
             mime_type,
             base_iri,
             instant: std::time::Instant::now(),
             self_p: "".to_string(),
-            remaining_buffer: std::cell::RefCell::default(),
+            remaining_buffer: RefCell::default(),
+            peak_remaining_buffer_size: Cell::default(),
+            write_error: RefCell::default(),
+            number_of_solutions: 0,
+            elapsed: std::time::Duration::ZERO,
+            cancelled: Arc::default(),
         };
-        streamer.evaluate()
+        let event = connection.statement_started(OperationKind::Stream, statement);
+        let started = std::time::Instant::now();
+        let result = streamer.evaluate();
+        let rows = result.as_ref().ok().map(|streamer| streamer.number_of_solutions);
+        connection
+            .instrumentation()
+            .on_statement_finish(&event, &Outcome::new(started, &result, rows));
+        result
+    }
+
+    /// The maximum number of bytes that a streamer keeps around when its
+    /// writer does not accept all data it's given, beyond which the stream
+    /// is aborted with an error.
+    pub fn max_remaining_buffer_size() -> usize { MAX_REMAINING_BUFFER_SIZE.load(Ordering::Relaxed) }
+
+    /// Change the maximum number of bytes that a streamer keeps around
+    /// (see [`Streamer::max_remaining_buffer_size`])
+    pub fn set_max_remaining_buffer_size(max_size: usize) {
+        MAX_REMAINING_BUFFER_SIZE.store(max_size, Ordering::Relaxed)
+    }
+
+    /// The largest number of bytes that were kept around so far because
+    /// the writer did not accept all data it was given
+    pub fn peak_remaining_buffer_size(&self) -> usize { self.peak_remaining_buffer_size.get() }
+
+    /// The number of solutions (query answers) that RDFox reported, which
+    /// tells an empty result apart without parsing the output
+    pub fn number_of_solutions(&self) -> usize { self.number_of_solutions }
+
+    /// How long it took to evaluate the statement and stream its results
+    pub fn elapsed(&self) -> std::time::Duration { self.elapsed }
+
+    /// The error of the writer that aborted the stream, if any, which
+    /// [`Streamer::run`] returns as a [`StreamAborted`] error
+    pub fn last_write_error(&self) -> Option<std::cell::Ref<'_, std::io::Error>> {
+        std::cell::Ref::filter_map(self.write_error.borrow(), Option::as_ref).ok()
     }
 
     /// Evaluate/execute the statement and stream all content to the given
     /// writer, then return the streamer (i.e. self).
     fn evaluate(mut self) -> Result<Self, ekg_error::Error> {
-        let statement_text = self.statement.as_c_string()?;
-        let statement_text_len = statement_text.as_bytes().len();
-        let parameters = Parameters::empty()?.fact_domain(crate::FactDomain::ALL)?;
+        let statement = self.statement;
+        let connection = self.connection.clone();
+        let operation = connection.operations.register(OperationKind::Stream, statement);
+        self.cancelled = operation.cancelled();
+        self.check_projection()?;
+        let statement_text = statement.as_c_string()?;
+        let statement_text_len = statement_text.to_bytes().len();
+        let default_parameters;
+        let parameters = match self.parameters {
+            Some(parameters) => parameters,
+            None => {
+                default_parameters = Parameters::empty()?.fact_domain(crate::FactDomain::ALL)?;
+                &default_parameters
+            },
+        };
         let query_answer_format_name = CString::new(self.mime_type.as_ref())?;
         let mut statement_result = MaybeUninit::<CStatementResult>::uninit();
         let connection_ptr = self.connection_ptr();
@@ -125,14 +217,78 @@ impl<'a, W: 'a + Write> Streamer<'a, W> {
             ptr::drop_in_place(stream_raw_ptr);
         }
 
+        if let Some(err) = self.last_write_error() {
+            // We refused to write because the stream was cancelled
+            operation.check()?;
+            // RDFox fails with its own exception when we refuse to write,
+            // the writer's error is the more useful one
+            tracing::error!("{self_p}: aborted stream: {err}");
+            return Err(StreamAborted::from(&*err).into());
+        }
+
         let statement_result = unsafe { statement_result.assume_init() };
 
-        result?; // we're doing this after the drop_in_place calls to avoid memory leak
+        // we're doing this after the drop_in_place calls to avoid memory leak
+        result.map_err(|err| {
+            QueryTimedOut::map(
+                err,
+                self.instant.elapsed(),
+                abbreviate(statement.as_str(), MAX_TOKEN_SIZE_IN_LOG).as_ref(),
+            )
+        })?;
 
-        tracing::debug!("{self_p}: statement_result={statement_result:?}");
+        // RDFox does not have to flush at the end of the stream
+        if let Err(err) = self.drain_remaining() {
+            tracing::error!("{self_p}: aborted stream: {err}");
+            return Err(StreamAborted::from(&err).into());
+        }
+
+        self.number_of_solutions = number_of_query_answers(&statement_result);
+        self.elapsed = self.instant.elapsed();
+        tracing::debug!(
+            peak_remaining_buffer_size = self.peak_remaining_buffer_size(),
+            number_of_solutions = self.number_of_solutions,
+            elapsed = ?self.elapsed,
+            "{self_p}: statement_result={statement_result:?}"
+        );
         Ok(self)
     }
 
+    /// Check the answer variables of a `SELECT` query, as far as they can
+    /// be found in its text, before RDFox writes anything: they have to be
+    /// the expected ones (see [`Statement::with_expected_variables`]) and
+    /// there has to be at least one for CSV or TSV.
+    fn check_projection(&self) -> Result<(), ProjectionMismatch> {
+        let Some(actual) = self.statement.projection() else {
+            return Ok(());
+        };
+        match self.statement.expected_variables() {
+            Some(expected) => ProjectionMismatch::check(expected, &actual),
+            None if actual.is_empty() &&
+                matches!(self.mime_type.subtype().as_str(), "csv" | "tab-separated-values") =>
+            {
+                Err(ProjectionMismatch { expected: Vec::new(), actual })
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Remember why the stream has to be aborted, which is reported when
+    /// the evaluation of the statement returns
+    fn abort(&self, err: std::io::Error) -> bool {
+        tracing::error!("{:p}: could not write: {err:?}", self);
+        self.remaining_buffer.replace(Vec::new());
+        self.write_error.replace(Some(err));
+        false
+    }
+
+    /// Write whatever the writer did not accept so far and flush it
+    fn drain_remaining(&mut self) -> std::io::Result<()> {
+        let remaining = self.remaining_buffer.take();
+        self.writer.write_all(&remaining)?;
+        self.writer.flush()
+    }
+
     unsafe fn context_as_ref_to_self(context: *mut c_void) -> &'a mut RefToSelf<'a, W> {
         let ref_to_self = context as *mut RefToSelf<'a, W>;
         &mut *ref_to_self
@@ -155,57 +311,36 @@ impl<'a, W: 'a + Write> Streamer<'a, W> {
 
         tracing::trace!("{streamer:p}: write_function");
 
-        let result = match ptr_to_cstr(data as *const u8, number_of_bytes_to_write) {
-            Ok(data_c_str) => {
-                tracing::trace!("{streamer:p}: writing {number_of_bytes_to_write} bytes (a)");
-                let data = if streamer.remaining_buffer.borrow().is_some() {
-                    // If we have some remaining bytes from the previous call to `write_function`
-                    // then concatenate them here with the new buffer..
-                    [
-                        streamer
-                            .remaining_buffer
-                            .borrow()
-                            .as_ref()
-                            .unwrap()
-                            .as_bytes(),
-                        data_c_str.to_bytes_with_nul(),
-                    ]
-                        .concat()
-                } else {
-                    data_c_str.to_bytes_with_nul().to_vec()
-                };
-                let data_len = data.len();
-                match streamer.writer.write(&data) {
-                    Ok(len) => {
-                        tracing::trace!(
-                            "{streamer:p}: wrote {len} bytes out of {}",
-                            data_len
-                        );
-                        if len < data_len {
-                            // When we didn't process the last part of the buffer (probably because
-                            // the last N-Triple line was not complete), then save the remainder
-                            // in `remaining_buffer` for the next call to `write_function`
-                            streamer.remaining_buffer.replace(Some(unsafe {
-                                String::from_utf8_unchecked(data[len..].to_vec())
-                            }));
-                            tracing::trace!(
-                                "{streamer:p}: remaining buffer: {}",
-                                streamer.remaining_buffer.borrow().as_ref().unwrap()
-                            );
-                        } else {
-                            streamer.remaining_buffer.replace(None);
-                        }
-                        true
-                    }
-                    Err(err) => {
-                        panic!("{streamer:p}: could not write: {err:?}")
-                    }
-                }
-            }
-            Err(error) => {
-                tracing::error!("{streamer:p}: could not write: {error:?}");
-                false
-            }
+        if streamer.write_error.borrow().is_some() {
+            return false;
+        }
+        if streamer.cancelled.load(Ordering::Relaxed) {
+            return streamer.abort(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "Cancelled",
+            ));
+        }
+        if number_of_bytes_to_write == 0 {
+            return true;
+        }
+        // The data is just bytes, not a (NUL-terminated) C string, in
+        // whatever (possibly binary) format the results were asked for
+        let data = unsafe { std::slice::from_raw_parts(data as *const u8, number_of_bytes_to_write) };
+        tracing::trace!("{streamer:p}: writing {number_of_bytes_to_write} bytes");
+        let result = write_with_remainder(
+            &mut streamer.writer,
+            &mut streamer.remaining_buffer.borrow_mut(),
+            data,
+            Self::max_remaining_buffer_size(),
+        );
+        let result = match result {
+            Ok(remaining) => {
+                streamer
+                    .peak_remaining_buffer_size
+                    .set(streamer.peak_remaining_buffer_size().max(remaining));
+                true
+            },
+            Err(err) => streamer.abort(err),
         };
         tracing::trace!("{streamer:p}: write_function result={result}");
         result
@@ -214,6 +349,55 @@ impl<'a, W: 'a + Write> Streamer<'a, W> {
     fn connection_ptr(&self) -> *mut CDataStoreConnection { self.connection.inner }
 }
 
+/// Write the remainder of the previous call (if any) followed by the given
+/// data, calling the writer for as long as it accepts something (like
+/// `Write::write_all` does), and keep whatever the writer did not accept
+/// (because it returned `Ok(0)`) as the remainder for the next call.
+/// Returns the size of that new remainder.
+///
+/// Fails if the remainder would become larger than `max_remaining` bytes.
+fn write_with_remainder<W: Write>(
+    writer: &mut W,
+    remaining: &mut Vec<u8>,
+    data: &[u8],
+    max_remaining: usize,
+) -> std::io::Result<usize> {
+    let carried_over = !remaining.is_empty();
+    if carried_over {
+        remaining.extend_from_slice(data);
+    }
+    let pending = if carried_over { remaining.as_slice() } else { data };
+    let mut written = 0;
+    while written < pending.len() {
+        match writer.write(&pending[written..]) {
+            Ok(0) => break,
+            Ok(len) => written += len,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {},
+            Err(err) => return Err(err),
+        }
+    }
+    let left = pending.len() - written;
+    if left > max_remaining {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::OutOfMemory,
+            format!(
+                "writer did not accept {left} bytes, more than the maximum of {max_remaining} \
+                 bytes that can be kept around"
+            ),
+        ));
+    }
+    if carried_over {
+        remaining.drain(..written);
+    } else {
+        remaining.extend_from_slice(&data[written..]);
+    }
+    Ok(left)
+}
+
+/// The number of query answers in the given statement result, its element
+/// following the statement type
+fn number_of_query_answers(statement_result: &CStatementResult) -> usize { statement_result[1] }
+
 trait StreamerWithCallbacks {
     fn flush(&mut self) -> bool;
     // fn write(&mut self, data: &[u8]) -> bool;
@@ -222,8 +406,9 @@ trait StreamerWithCallbacks {
 impl<'a, W: 'a + Write> StreamerWithCallbacks for Streamer<'a, W> {
     fn flush(&mut self) -> bool {
         tracing::trace!("{self:p}: flush");
-        let y = if let Err(err) = self.writer.flush() {
-            panic!("{self:p}: Could not flush: {err:?}")
+        // Whatever the writer did not accept so far has to go now
+        let y = if let Err(err) = self.drain_remaining() {
+            self.abort(err)
         } else {
             true
         };
@@ -244,3 +429,55 @@ impl<'a, W: 'a + Write> StreamerWithCallbacks for Streamer<'a, W> {
     //     }
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::write_with_remainder;
+
+    /// A writer that accepts at most the given number of bytes per call
+    struct ChunkWriter(Vec<u8>, usize);
+
+    impl std::io::Write for ChunkWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(self.1);
+            self.0.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+    }
+
+    #[test_log::test]
+    fn test_write_with_remainder_is_byte_exact() {
+        // Binary data with embedded (and trailing) NUL bytes that is not
+        // valid UTF-8
+        let chunks: [&[u8]; 4] = [b"\0abc\0", b"\xff\xfe\0", b"", b"xyz\0\0"];
+        let expected = chunks.concat();
+        for per_call in [0, 1, 3, 100] {
+            let mut writer = ChunkWriter(Vec::new(), per_call);
+            let mut remaining = Vec::new();
+            for chunk in chunks {
+                let left = write_with_remainder(&mut writer, &mut remaining, chunk, 1024).unwrap();
+                assert_eq!(left, remaining.len());
+            }
+            writer.0.extend_from_slice(&remaining);
+            assert_eq!(writer.0, expected, "{per_call} bytes per call");
+        }
+    }
+
+    #[test_log::test]
+    fn test_write_with_remainder_is_bounded() {
+        // A writer that keeps accepting a little is written to until
+        // everything is gone
+        let mut writer = ChunkWriter(Vec::new(), 1);
+        let mut remaining = Vec::new();
+        assert_eq!(write_with_remainder(&mut writer, &mut remaining, b"abcd", 0).unwrap(), 0);
+        assert_eq!(writer.0, b"abcd");
+
+        // A writer that is full leaves everything in the remainder
+        let mut writer = ChunkWriter(Vec::new(), 0);
+        assert_eq!(write_with_remainder(&mut writer, &mut remaining, b"abc", 3).unwrap(), 3);
+        let err = write_with_remainder(&mut writer, &mut remaining, b"d", 3).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::OutOfMemory);
+    }
+}
@@ -0,0 +1,214 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::{CursorRow, Namespaces, Term},
+    iref::Iri,
+    std::{
+        fmt::{Display, Formatter},
+        sync::Arc,
+    },
+};
+
+/// The default maximum number of rows that a [`TableWriter`] shows
+pub const DEFAULT_TABLE_MAX_ROWS: usize = 100;
+
+/// The default maximum width (in characters) of a column of a
+/// [`TableWriter`]
+pub const DEFAULT_TABLE_MAX_WIDTH: usize = 40;
+
+/// Collects the rows of a `SELECT` query and shows them as an aligned text
+/// table, with IRIs shortened to CURIEs where possible, for instance:
+///
+/// ```no_run
+/// # use {rdfox_rs::{Cursor, Namespaces, TableWriter, Transaction}, std::sync::Arc};
+/// # fn f(cursor: &mut Cursor, tx: &Arc<Transaction>) -> Result<(), ekg_error::Error> {
+/// let mut table = TableWriter::new(&Namespaces::default_namespaces()?).max_rows(10);
+/// cursor.consume(tx, usize::MAX, |row| table.add_row(row))?;
+/// table.write_to(&mut std::io::stdout())?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Unbound values are empty cells, values wider than the maximum width
+/// are cut short with an ellipsis and a `#` column with the multiplicity
+/// of each row is added if any row occurs more than once.
+#[derive(Debug)]
+pub struct TableWriter {
+    namespaces: Arc<Namespaces>,
+    max_rows:   usize,
+    max_width:  usize,
+    variables:  Vec<String>,
+    /// The cells and multiplicity of each of the first `max_rows` rows
+    rows:       Vec<(Vec<String>, usize)>,
+    /// The number of rows that did not fit
+    skipped:    usize,
+}
+
+impl TableWriter {
+    pub fn new(namespaces: &Arc<Namespaces>) -> Self {
+        Self {
+            namespaces: namespaces.clone(),
+            max_rows:   DEFAULT_TABLE_MAX_ROWS,
+            max_width:  DEFAULT_TABLE_MAX_WIDTH,
+            variables:  Vec::new(),
+            rows:       Vec::new(),
+            skipped:    0,
+        }
+    }
+
+    /// Show at most the given number of rows, the others are only counted
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// Cut values wider than the given number of characters (at least 2)
+    /// short
+    pub fn max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width.max(2);
+        self
+    }
+
+    /// Add the given row, to be called from [`Cursor::consume`](crate::Cursor::consume)
+    pub fn add_row(&mut self, row: &CursorRow) -> Result<(), ekg_error::Error> {
+        if self.variables.is_empty() {
+            self.variables = row.opened.variable_names().to_vec();
+        }
+        if self.rows.len() >= self.max_rows {
+            self.skipped += 1;
+            return Ok(());
+        }
+        let terms = (0..row.opened.arity)
+            .map(|index| row.term(index))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.push(terms.as_slice(), *row.multiplicity);
+        Ok(())
+    }
+
+    fn push(&mut self, terms: &[Option<Term>], multiplicity: usize) {
+        let cells = terms
+            .iter()
+            .map(|term| term.as_ref().map_or(String::new(), |term| self.cell(term)))
+            .collect();
+        self.rows.push((cells, multiplicity));
+    }
+
+    /// The given term as a CURIE, or in SPARQL syntax if that's not possible,
+    /// cut short if it's too wide
+    fn cell(&self, term: &Term) -> String {
+        let shorten = |iri: &Iri| {
+            self.namespaces
+                .shorten(iri)
+                .unwrap_or_else(|| format!("<{}>", iri.as_str()))
+        };
+        let cell = match term {
+            Term::Iri(iri) => shorten(iri.as_iri()),
+            Term::Literal { lexical_form, datatype: Some(datatype), language: None } => {
+                format!("{}^^{}", Term::string(lexical_form), shorten(datatype.as_iri()))
+            },
+            term => term.to_string(),
+        };
+        if cell.chars().count() <= self.max_width {
+            return cell;
+        }
+        let mut cell = cell.chars().take(self.max_width - 1).collect::<String>();
+        cell.push('…');
+        cell
+    }
+
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write!(writer, "{self}")
+    }
+}
+
+impl Display for TableWriter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let with_multiplicity = self.rows.iter().any(|(_, multiplicity)| *multiplicity > 1);
+        let mut header = self
+            .variables
+            .iter()
+            .map(|variable| format!("?{variable}"))
+            .collect::<Vec<_>>();
+        let rows = self
+            .rows
+            .iter()
+            .map(|(cells, multiplicity)| {
+                let mut cells = cells.clone();
+                if with_multiplicity {
+                    cells.push(multiplicity.to_string());
+                }
+                cells
+            })
+            .collect::<Vec<_>>();
+        if with_multiplicity {
+            header.push("#".to_string());
+        }
+        let widths = header
+            .iter()
+            .enumerate()
+            .map(|(column, name)| {
+                rows.iter()
+                    .map(|cells| cells[column].chars().count())
+                    .chain([name.chars().count()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let write_row = |f: &mut Formatter<'_>, cells: &[String]| {
+            for (cell, width) in cells.iter().zip(widths.iter()) {
+                write!(f, "| {cell:<width$} ")?;
+            }
+            writeln!(f, "|")
+        };
+        write_row(f, header.as_slice())?;
+        for width in widths.iter() {
+            write!(f, "|{}", "-".repeat(width + 2))?;
+        }
+        writeln!(f, "|")?;
+        for cells in rows.iter() {
+            write_row(f, cells.as_slice())?;
+        }
+        let shown = self.rows.len();
+        match self.skipped {
+            0 => writeln!(f, "{shown} rows"),
+            skipped => writeln!(f, "{shown} rows, {skipped} more not shown"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::TableWriter,
+        crate::{Namespaces, Term},
+        iref::Iri,
+    };
+
+    #[test_log::test]
+    fn test_display() {
+        let mut table = TableWriter::new(&Namespaces::default_namespaces().unwrap()).max_width(12);
+        table.variables = vec!["thing".to_string(), "value".to_string()];
+        let integer = Iri::new("http://www.w3.org/2001/XMLSchema#integer").unwrap();
+        table.push(
+            &[
+                Some(Term::iri("http://www.w3.org/2002/07/owl#Thing").unwrap()),
+                Some(Term::typed_literal("42", integer)),
+            ],
+            1,
+        );
+        table.push(&[Some(Term::iri("https://ekgf.org/a").unwrap()), None], 3);
+        table.push(&[None, Some(Term::language_tagged_literal("Bonjour", "fr"))], 1);
+        assert_eq!(
+            table.to_string(),
+            concat!(
+                "| ?thing       | ?value       | # |\n",
+                "|--------------|--------------|---|\n",
+                "| owl:Thing    | \"42\"^^xsd:i… | 1 |\n",
+                "| <https://ek… |              | 3 |\n",
+                "|              | \"Bonjour\"@fr | 1 |\n",
+                "3 rows\n",
+            )
+        );
+    }
+}
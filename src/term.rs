@@ -0,0 +1,412 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    iref::{Iri, IriBuf},
+    std::{
+        fmt::{Display, Formatter},
+        str::FromStr,
+    },
+};
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+/// An RDF term: an IRI, a blank node or a literal, shown in SPARQL (and
+/// N-Triples) syntax, so that it can be put in a SPARQL statement as is.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Term {
+    Iri(IriBuf),
+    BlankNode(String),
+    Literal {
+        lexical_form: String,
+        /// The datatype IRI, `None` means `xsd:string` (or `rdf:langString`
+        /// if there's a language tag)
+        datatype:     Option<IriBuf>,
+        language:     Option<String>,
+    },
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Term::Iri(iri) => write!(f, "<{}>", iri.as_str()),
+            Term::BlankNode(label) => write!(f, "_:{label}"),
+            Term::Literal { lexical_form, datatype, language } => {
+                f.write_str("\"")?;
+                for c in lexical_form.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                f.write_str("\"")?;
+                if let Some(language) = language {
+                    write!(f, "@{language}")
+                } else if let Some(datatype) = datatype {
+                    write!(f, "^^<{}>", datatype.as_str())
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+impl From<IriBuf> for Term {
+    fn from(iri: IriBuf) -> Self { Term::Iri(iri) }
+}
+
+impl From<&Iri> for Term {
+    fn from(iri: &Iri) -> Self { Term::Iri(iri.to_owned()) }
+}
+
+impl Term {
+    pub fn iri(iri: &str) -> Result<Self, ekg_error::Error> {
+        IriBuf::new(iri.to_string())
+            .map(Term::Iri)
+            .map_err(|_| {
+                ekg_error::Error::Exception {
+                    action:  "creating an IRI term".to_string(),
+                    message: format!("<{iri}> is not a valid IRI"),
+                }
+            })
+    }
+
+    pub fn blank_node(label: &str) -> Self { Term::BlankNode(label.to_string()) }
+
+    /// A plain `xsd:string` literal
+    pub fn string(value: &str) -> Self {
+        Term::Literal {
+            lexical_form: value.to_string(),
+            datatype:     None,
+            language:     None,
+        }
+    }
+
+    pub fn typed_literal(lexical_form: &str, datatype: &Iri) -> Self {
+        Term::Literal {
+            lexical_form: lexical_form.to_string(),
+            datatype:     Some(datatype.to_owned()),
+            language:     None,
+        }
+    }
+
+    pub fn language_tagged_literal(value: &str, language: &str) -> Self {
+        Term::Literal {
+            lexical_form: value.to_string(),
+            datatype:     None,
+            language:     Some(language.to_string()),
+        }
+    }
+
+    /// Check the parts of this term that are written as is, the label of a
+    /// blank node and the language tag of a literal, so that the term can't
+    /// change the meaning of the SPARQL statement it's put in
+    pub(crate) fn check_syntax(&self) -> Result<(), String> {
+        match self {
+            Term::BlankNode(label) if !is_blank_node_label(label) => {
+                Err(format!("{label:?} is not a valid blank node label"))
+            },
+            Term::Literal { language: Some(language), .. } if !is_language_tag(language) => {
+                Err(format!("{language:?} is not a valid language tag"))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `PN_CHARS_BASE` of the SPARQL (and Turtle) grammar
+fn is_pn_chars_base(c: char) -> bool {
+    matches!(c,
+        'A'..='Z' |
+        'a'..='z' |
+        '\u{C0}'..='\u{D6}' |
+        '\u{D8}'..='\u{F6}' |
+        '\u{F8}'..='\u{2FF}' |
+        '\u{370}'..='\u{37D}' |
+        '\u{37F}'..='\u{1FFF}' |
+        '\u{200C}'..='\u{200D}' |
+        '\u{2070}'..='\u{218F}' |
+        '\u{2C00}'..='\u{2FEF}' |
+        '\u{3001}'..='\u{D7FF}' |
+        '\u{F900}'..='\u{FDCF}' |
+        '\u{FDF0}'..='\u{FFFD}' |
+        '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+/// `PN_CHARS` of the SPARQL (and Turtle) grammar
+fn is_pn_chars(c: char) -> bool {
+    is_pn_chars_base(c) ||
+        matches!(c,
+            '_' | '-' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}'
+        )
+}
+
+/// Whether the given text (without `_:`) is a blank node label in the
+/// SPARQL grammar: `(PN_CHARS_U | [0-9]) ((PN_CHARS | '.')* PN_CHARS)?`
+fn is_blank_node_label(label: &str) -> bool {
+    let mut chars = label.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    (is_pn_chars_base(first) || first == '_' || first.is_ascii_digit()) &&
+        chars.all(|c| is_pn_chars(c) || c == '.') &&
+        !label.ends_with('.')
+}
+
+/// Whether the given text is a language tag in the SPARQL grammar:
+/// `[a-zA-Z]+ ('-' [a-zA-Z0-9]+)*`
+fn is_language_tag(language: &str) -> bool {
+    let mut parts = language.split('-');
+    let first = parts.next().unwrap_or_default();
+    !first.is_empty() &&
+        first.chars().all(|c| c.is_ascii_alphabetic()) &&
+        parts.all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Parses a term in N-Triples syntax, `<iri>`, `_:label`, `"literal"`,
+/// `"literal"@lang` or `"literal"^^<datatype>`, where the datatype may also
+/// be written as `xsd:name`. Literals typed `xsd:string` are plain literals.
+impl FromStr for Term {
+    type Err = ekg_error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> { TermParser { text: s, pos: 0 }.parse() }
+}
+
+impl TryFrom<&str> for Term {
+    type Error = ekg_error::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> { value.parse() }
+}
+
+struct TermParser<'a> {
+    text: &'a str,
+    pos:  usize,
+}
+
+impl<'a> TermParser<'a> {
+    fn error<T>(&self, message: &str) -> Result<T, ekg_error::Error> {
+        Err(ekg_error::Error::Exception {
+            action:  format!("parsing term {:?}", self.text),
+            message: format!("{message} at position {}", self.pos),
+        })
+    }
+
+    fn rest(&self) -> &'a str { &self.text[self.pos..] }
+
+    fn parse(mut self) -> Result<Term, ekg_error::Error> {
+        self.text = self.text.trim();
+        let term = match self.rest().chars().next() {
+            Some('<') => Term::Iri(self.iri()?),
+            Some('_') if self.rest().starts_with("_:") => {
+                self.pos += 2;
+                let label = self.rest();
+                if !is_blank_node_label(label) {
+                    return self.error("invalid blank node label");
+                }
+                self.pos = self.text.len();
+                Term::blank_node(label)
+            }
+            Some('"') => self.literal()?,
+            Some(_) => return self.error("expected '<', '_:' or '\"'"),
+            None => return self.error("empty term"),
+        };
+        if !self.rest().is_empty() {
+            return self.error("unexpected trailing characters");
+        }
+        Ok(term)
+    }
+
+    fn iri(&mut self) -> Result<IriBuf, ekg_error::Error> {
+        let Some(end) = self.rest().find('>') else {
+            return self.error("missing '>'");
+        };
+        let iri = &self.rest()[1..end];
+        let iri = IriBuf::new(iri.to_string()).or_else(|_| self.error("invalid IRI"))?;
+        self.pos += end + 1;
+        Ok(iri)
+    }
+
+    fn literal(&mut self) -> Result<Term, ekg_error::Error> {
+        self.pos += 1;
+        let mut lexical_form = String::new();
+        let mut chars = self.rest().char_indices();
+        loop {
+            let Some((offset, c)) = chars.next() else {
+                return self.error("missing closing '\"'");
+            };
+            match c {
+                '"' => {
+                    self.pos += offset + 1;
+                    break;
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('\'') => '\'',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some(u @ ('u' | 'U')) => {
+                            let len = if u == 'u' { 4 } else { 8 };
+                            let hex: String = chars.by_ref().take(len).map(|(_, c)| c).collect();
+                            let c = u32::from_str_radix(hex.as_str(), 16)
+                                .ok()
+                                .and_then(char::from_u32);
+                            match c {
+                                Some(c) if hex.len() == len => c,
+                                _ => {
+                                    self.pos += offset;
+                                    return self.error("invalid unicode escape");
+                                }
+                            }
+                        }
+                        _ => {
+                            self.pos += offset;
+                            return self.error("invalid escape");
+                        }
+                    };
+                    lexical_form.push(escaped);
+                }
+                c => lexical_form.push(c),
+            }
+        }
+        let rest = self.rest();
+        if let Some(language) = rest.strip_prefix('@') {
+            self.pos += 1;
+            if !is_language_tag(language) {
+                return self.error("invalid language tag");
+            }
+            self.pos = self.text.len();
+            Ok(Term::language_tagged_literal(lexical_form.as_str(), language))
+        } else if let Some(datatype) = rest.strip_prefix("^^") {
+            self.pos += 2;
+            let datatype = if datatype.starts_with('<') {
+                self.iri()?
+            } else if let Some(local_name) = datatype.strip_prefix("xsd:") {
+                self.pos = self.text.len();
+                IriBuf::new(format!("{XSD}{local_name}"))
+                    .or_else(|_| self.error("invalid datatype"))?
+            } else {
+                return self.error("expected '<' or 'xsd:'");
+            };
+            if datatype.as_str() == XSD_STRING {
+                Ok(Term::string(lexical_form.as_str()))
+            } else {
+                Ok(Term::typed_literal(lexical_form.as_str(), datatype.as_iri()))
+            }
+        } else {
+            Ok(Term::string(lexical_form.as_str()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Term;
+
+    #[test_log::test]
+    fn test_display() {
+        assert_eq!(
+            Term::iri("https://ekgf.org/a").unwrap().to_string(),
+            "<https://ekgf.org/a>"
+        );
+        assert_eq!(Term::blank_node("b0").to_string(), "_:b0");
+        assert_eq!(
+            Term::string("say \"hi\"\n").to_string(),
+            r#""say \"hi\"\n""#
+        );
+        assert_eq!(
+            Term::language_tagged_literal("chat", "fr").to_string(),
+            r#""chat"@fr"#
+        );
+        assert_eq!(
+            Term::typed_literal(
+                "42",
+                iref::Iri::new("http://www.w3.org/2001/XMLSchema#integer").unwrap()
+            )
+            .to_string(),
+            r#""42"^^<http://www.w3.org/2001/XMLSchema#integer>"#
+        );
+        assert!(Term::iri("not an iri").is_err());
+    }
+
+    #[test_log::test]
+    fn test_check_syntax() {
+        for label in ["b0", "_b", "0", "b.0", "été"] {
+            assert!(Term::blank_node(label).check_syntax().is_ok(), "{label}");
+        }
+        for label in ["", "b0 } ; DROP ALL #", "b0.", "-b", "b>"] {
+            assert!(Term::blank_node(label).check_syntax().is_err(), "{label}");
+        }
+        for language in ["en", "en-GB", "zh-Hant-TW", "de-1996"] {
+            assert!(
+                Term::language_tagged_literal("x", language).check_syntax().is_ok(),
+                "{language}"
+            );
+        }
+        for language in ["", "en } ; DROP ALL #", "en-", "1en", "en--GB", "en_GB"] {
+            assert!(
+                Term::language_tagged_literal("x", language).check_syntax().is_err(),
+                "{language}"
+            );
+        }
+    }
+
+    #[test_log::test]
+    fn test_from_str() {
+        let round_trip = |text: &str| text.parse::<Term>().unwrap().to_string();
+        for text in [
+            "<https://ekgf.org/a>",
+            "_:b0",
+            r#""plain""#,
+            r#""Bonjour"@fr"#,
+            r#""colour"@en-GB"#,
+            r#""42"^^<http://www.w3.org/2001/XMLSchema#integer>"#,
+            r#""1.5E0"^^<http://www.w3.org/2001/XMLSchema#double>"#,
+            r#""say \"hi\"\n""#,
+        ] {
+            assert_eq!(round_trip(text), text);
+        }
+        assert_eq!(
+            Term::try_from(r#""some string"^^xsd:string"#).unwrap(),
+            Term::string("some string")
+        );
+        assert_eq!(round_trip(r#""\u00e9t\u00e9""#), r#""été""#);
+        assert_eq!(
+            round_trip(r#""3"^^xsd:int"#),
+            r#""3"^^<http://www.w3.org/2001/XMLSchema#int>"#
+        );
+        for malformed in [
+            "",
+            "plain",
+            "<https://ekgf.org/a",
+            r#""unterminated"#,
+            r#""bad"@"#,
+            r#""bad"^^ex:type"#,
+            r#""bad" trailing"#,
+            r#""\q""#,
+            "_:b0.",
+            "_:-b0",
+            "_:b0>",
+            r#""bad"@1en"#,
+        ] {
+            assert!(malformed.parse::<Term>().is_err(), "{malformed}");
+        }
+        match r#""x"@"#.parse::<Term>() {
+            Err(ekg_error::Error::Exception { message, .. }) => {
+                assert_eq!(message, "invalid language tag at position 4")
+            }
+            result => panic!("unexpected {result:?}"),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+//! Async facades (feature `tokio`) for the blocking calls into RDFox that
+//! take long enough to matter in an async service: they run on tokio's
+//! blocking thread pool and only take and return owned values, so nothing
+//! that borrows from a cursor or a transaction is held across an `.await`.
+
+use {
+    crate::{
+        sparql_text::abbreviate,
+        statement::MAX_TOKEN_SIZE_IN_LOG,
+        Cursor,
+        DataStoreConnection,
+        OwnedCursorRow,
+        Parameters,
+        Statement,
+        Transaction,
+    },
+    ekg_namespace::Graph,
+    std::{path::PathBuf, sync::Arc},
+};
+
+/// Run the given blocking work on tokio's blocking thread pool, a panic in
+/// it is reported as an error
+async fn run_blocking<T, F>(action: &'static str, f: F) -> Result<T, ekg_error::Error>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, ekg_error::Error> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await.map_err(|err| {
+        ekg_error::Error::Exception { action: action.to_string(), message: err.to_string() }
+    })?
+}
+
+impl DataStoreConnection {
+    /// Async version of [`DataStoreConnection::import_data_from_file`]
+    pub async fn import_data_from_file_async(
+        self: &Arc<Self>,
+        file: impl Into<PathBuf>,
+        graph: &Graph,
+    ) -> Result<(), ekg_error::Error> {
+        let (connection, file, graph) = (self.clone(), file.into(), graph.clone());
+        run_blocking("importing a file", move || {
+            connection.import_data_from_file(file, &graph)
+        })
+        .await
+    }
+}
+
+impl Statement {
+    /// Evaluate this query in a read-only transaction of its own and
+    /// return (at most `max_row`) rows, see [`Cursor::consume_async`] to
+    /// read a cursor in an existing transaction.
+    pub async fn evaluate_async(
+        &self,
+        connection: &Arc<DataStoreConnection>,
+        parameters: Parameters,
+        max_row: usize,
+    ) -> Result<Vec<OwnedCursorRow>, ekg_error::Error> {
+        let (statement, connection) = (self.clone(), connection.clone());
+        run_blocking("evaluating a statement", move || {
+            let mut cursor = statement.cursor(&connection, &parameters)?;
+            Transaction::begin_read_only(&connection)?
+                .execute_and_rollback(|tx| cursor.collect_owned(&tx, max_row))
+        })
+        .await
+    }
+}
+
+impl Cursor {
+    /// Async version of [`Cursor::consume`] that returns (at most
+    /// `max_row`) owned rows rather than passing borrowed ones to a
+    /// function, together with the cursor itself so that it can be reused.
+    pub async fn consume_async(
+        mut self,
+        tx: &Arc<Transaction>,
+        max_row: usize,
+    ) -> Result<(Cursor, Vec<OwnedCursorRow>), ekg_error::Error> {
+        let tx = tx.clone();
+        run_blocking("consuming a cursor", move || {
+            let rows = self.collect_owned(&tx, max_row)?;
+            Ok((self, rows))
+        })
+        .await
+    }
+
+    /// Read all rows as [`OwnedCursorRow`]s, failing like
+    /// [`Cursor::consume`] when there are `max_row` of them or more
+    fn collect_owned(
+        &mut self,
+        tx: &Arc<Transaction>,
+        max_row: usize,
+    ) -> Result<Vec<OwnedCursorRow>, ekg_error::Error> {
+        let query = abbreviate(self.statement().as_str(), MAX_TOKEN_SIZE_IN_LOG).into_owned();
+        let mut rows = Vec::new();
+        for row in self.iter(tx)? {
+            let row = row?;
+            if row.rowid >= max_row {
+                return Err(ekg_error::Error::ExceededMaximumNumberOfRows {
+                    maxrow: max_row,
+                    query,
+                });
+            }
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+}
@@ -5,39 +5,84 @@ use {
     crate::{
         database_call,
         DataStoreConnection,
+        ExceptionKind,
+        Parameters,
+        RDFoxException,
+        RetryPolicy,
+        server::ServerDependent,
         rdfox_api::{
             CDataStoreConnection_beginTransaction,
             CDataStoreConnection_commitTransaction,
             CDataStoreConnection_rollbackTransaction,
+            CDataStoreConnection_updateMaterialization,
             CTransactionType,
         },
+        Statement,
+        TransactionAlreadyFinished,
+        TransactionEvent,
+        UpdateResult,
     }
     ,
     std::{
         fmt::{Display, Formatter},
         sync::{Arc, atomic::AtomicBool},
+        time::{Duration, Instant},
     },
 };
 
 #[derive(Debug)]
 pub struct Transaction {
+    /// The connection, which is kept alive until this transaction has been
+    /// dropped (and so committed or rolled back), even when all other
+    /// references to it are gone. A transaction that is leaked (with
+    /// `std::mem::forget`) leaks its connection too rather than leaving it
+    /// to be destroyed underneath the transaction.
     pub connection: Arc<DataStoreConnection>,
     committed: AtomicBool,
     tx_type: CTransactionType,
-    number: usize,
+    /// The number of this transaction, unique within the process, as
+    /// logged in the `txno` field
+    pub number: usize,
+    /// The outermost transaction if this is a nested one
+    outer: Option<Arc<Transaction>>,
+    /// A nested transaction has been rolled back, so this (outermost) one
+    /// can only be rolled back too
+    rollback_only: AtomicBool,
+    started_at: Instant,
+    dependent: ServerDependent,
 }
 
 impl Drop for Transaction {
     fn drop(&mut self) {
+        if self.outer.is_some() {
+            if !self.committed.load(std::sync::atomic::Ordering::Relaxed) {
+                self.poison_outer();
+            }
+            return;
+        }
         if self.committed.load(std::sync::atomic::Ordering::Relaxed) {
             tracing::debug!(
                 target: ekg_namespace::consts::LOG_TARGET_DATABASE,
                 txno = self.number,
                 conn = self.connection.number,
+                duration = ?self.elapsed(),
                 "Ended {self:}"
             );
+        } else if self.dependent.is_stopped() {
+            // RDFox has already freed the connection of a stopped server
+            tracing::warn!(
+                target: ekg_namespace::consts::LOG_TARGET_DATABASE,
+                txno = self.number,
+                conn = self.connection.number,
+                "Not rolling back {self:} since the server has been stopped"
+            );
         } else if let Err(err) = self._rollback() {
-            panic!("{self:} could not be rolled back: {err}", );
+            tracing::error!(
+                target: ekg_namespace::consts::LOG_TARGET_DATABASE,
+                txno = self.number,
+                conn = self.connection.number,
+                "{self:} could not be rolled back: {err}"
+            );
         }
     }
 }
@@ -52,7 +97,15 @@ impl Transaction {
         tx_type: CTransactionType,
     ) -> Result<Arc<Self>, ekg_error::Error> {
         assert!(!connection.inner.is_null());
+        let dependent = connection
+            .server_connection
+            .server()
+            .dependent("beginning a transaction")?;
         let number = Self::get_number();
+        let active = connection.active_transaction.lock().unwrap().upgrade();
+        if let Some(outer) = active.filter(|outer| !outer.has_ended()) {
+            return Self::nested(outer, tx_type, number, dependent);
+        }
         tracing::trace!(
             target: ekg_namespace::consts::LOG_TARGET_DATABASE,
             txno = number,
@@ -60,25 +113,112 @@ impl Transaction {
             "Starting {}",
             Self::get_title_for(tx_type, number, connection.number)
         );
-        database_call!(CDataStoreConnection_beginTransaction(
-            connection.inner,
-            tx_type
-        ))?;
+        connection
+            .stats
+            .record(database_call!(CDataStoreConnection_beginTransaction(
+                connection.inner,
+                tx_type
+            )))?;
+        connection.stats.transaction_begun();
         let tx = Arc::new(Self {
             connection: connection.clone(),
             committed: AtomicBool::new(false),
             number,
             tx_type,
+            outer: None,
+            rollback_only: AtomicBool::new(false),
+            started_at: Instant::now(),
+            dependent,
         });
+        *connection.active_transaction.lock().unwrap() = Arc::downgrade(&tx);
         tracing::debug!(
             target: ekg_namespace::consts::LOG_TARGET_DATABASE,
             txno = tx.number,
             conn = tx.connection.number,
             "Started {tx:}",
         );
+        if connection.online_check.is_enabled() {
+            connection.online_check.check(connection, Some(&tx))?;
+        }
+        Ok(tx)
+    }
+
+    /// A handle on the given (outermost) transaction that is active on the
+    /// same connection, see [`Transaction::is_nested`]
+    fn nested(
+        outer: Arc<Transaction>,
+        tx_type: CTransactionType,
+        number: usize,
+        dependent: ServerDependent,
+    ) -> Result<Arc<Self>, ekg_error::Error> {
+        if outer.is_read_only() &&
+            !matches!(tx_type, CTransactionType::TRANSACTION_TYPE_READ_ONLY)
+        {
+            return Err(ekg_error::Error::Exception {
+                action:  format!(
+                    "beginning {}",
+                    Self::get_title_for(tx_type, number, outer.connection.number)
+                ),
+                message: format!("{outer} is read-only"),
+            });
+        }
+        let tx = Arc::new(Self {
+            connection: outer.connection.clone(),
+            committed: AtomicBool::new(false),
+            number,
+            tx_type,
+            outer: Some(outer),
+            rollback_only: AtomicBool::new(false),
+            started_at: Instant::now(),
+            dependent,
+        });
+        tracing::debug!(
+            target: ekg_namespace::consts::LOG_TARGET_DATABASE,
+            txno = tx.number,
+            conn = tx.connection.number,
+            "Started {tx:} nested in {}",
+            tx.outermost()
+        );
         Ok(tx)
     }
 
+    /// Whether this transaction was begun while another one was active on
+    /// the same connection. Committing a nested transaction does nothing
+    /// until the outermost one is committed, rolling back a nested
+    /// read/write transaction makes the outermost one roll back (rather
+    /// than commit) in the end.
+    pub fn is_nested(&self) -> bool { self.outer.is_some() }
+
+    pub fn is_read_only(&self) -> bool {
+        matches!(self.tx_type, CTransactionType::TRANSACTION_TYPE_READ_ONLY)
+    }
+
+    /// When this transaction was begun
+    pub fn started_at(&self) -> Instant { self.started_at }
+
+    /// How long ago this transaction was begun
+    pub fn elapsed(&self) -> Duration { self.started_at.elapsed() }
+
+    /// Make the outermost transaction roll back in the end, since this
+    /// nested one (which is not read-only, so it may have written) is
+    /// rolled back
+    fn poison_outer(&self) {
+        if let Some(outer) = &self.outer {
+            if !self.is_read_only() {
+                tracing::debug!(
+                    target: ekg_namespace::consts::LOG_TARGET_DATABASE,
+                    txno = self.number,
+                    conn = self.connection.number,
+                    "Rolled back {self:}, so {outer} will be rolled back too"
+                );
+                outer.rollback_only.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// The transaction that actually runs in RDFox
+    fn outermost(&self) -> &Transaction { self.outer.as_deref().unwrap_or(self) }
+
     fn get_title(&self) -> String {
         Self::get_title_for(self.tx_type, self.number, self.connection.number)
     }
@@ -130,32 +270,248 @@ impl Transaction {
             F: FnOnce(Arc<Transaction>) -> Result<T, ekg_error::Error>,
     {
         let tx = Self::begin_read_write(connection)?;
-        let result = f(tx.clone());
-        tx.commit()?;
-        result
+        match f(tx.clone()) {
+            Ok(result) => {
+                tx.commit()?;
+                Ok(result)
+            },
+            Err(err) => Err(tx.rollback_after(err)),
+        }
+    }
+
+    /// Roll back after the given error, which is returned with the rollback
+    /// error attached if the rollback fails too
+    fn rollback_after(self: &Arc<Self>, err: ekg_error::Error) -> ekg_error::Error {
+        let Err(rollback_err) = self.rollback() else {
+            return err;
+        };
+        tracing::error!(
+            target: ekg_namespace::consts::LOG_TARGET_DATABASE,
+            txno = self.number,
+            conn = self.connection.number,
+            "Could not roll back {self:} after {err}: {rollback_err}"
+        );
+        match err {
+            ekg_error::Error::Exception { action, message } => {
+                ekg_error::Error::Exception {
+                    action,
+                    message: format!("{message} (rolling back failed too: {rollback_err})"),
+                }
+            },
+            err => err,
+        }
+    }
+
+    /// Run the given function in a read/write transaction that is committed
+    /// when it succeeds, beginning all over again (after a jittered
+    /// exponential backoff according to the given policy) when the
+    /// transaction fails with a [`ExceptionKind::TransactionConflict`].
+    ///
+    /// The function is given the (1-based) attempt number. Since it may run
+    /// more than once it has to be idempotent: its updates are rolled back
+    /// before a retry, but anything it does outside the transaction is not.
+    ///
+    /// When a transaction is already active on the connection the function
+    /// runs in a nested one (see [`Transaction::is_nested`]) just once: a
+    /// conflict can only be resolved by beginning the outer transaction all
+    /// over again, so it is returned straight away and the outer transaction
+    /// can only be rolled back.
+    pub fn begin_read_write_with_retry<T, F>(
+        connection: &Arc<DataStoreConnection>,
+        policy: &RetryPolicy,
+        mut f: F,
+    ) -> Result<T, ekg_error::Error>
+        where
+            F: FnMut(&Arc<Transaction>, u32) -> Result<T, ekg_error::Error>,
+    {
+        let started_at = Instant::now();
+        let deadline = started_at + policy.deadline;
+        let mut attempt = 0_u32;
+        loop {
+            attempt += 1;
+            let mut nested = false;
+            let result = Self::begin_read_write(connection).and_then(|tx| {
+                nested = tx.is_nested();
+                tx.update_and_commit(|ref tx| f(tx, attempt))
+            });
+            let err = match result {
+                Err(err) if !nested &&
+                    RDFoxException::kind_of(&err) == Some(ExceptionKind::TransactionConflict) =>
+                {
+                    err
+                },
+                result => {
+                    if attempt > 1 {
+                        tracing::debug!(
+                            target: ekg_namespace::consts::LOG_TARGET_DATABASE,
+                            conn = connection.number,
+                            attempts = attempt,
+                            "Read/write transaction ended after retrying"
+                        );
+                    }
+                    return result;
+                },
+            };
+            let now = Instant::now();
+            if attempt >= policy.max_attempts || now >= deadline {
+                tracing::warn!(
+                    target: ekg_namespace::consts::LOG_TARGET_DATABASE,
+                    conn = connection.number,
+                    attempts = attempt,
+                    waited = ?started_at.elapsed(),
+                    "Giving up on conflicting read/write transaction: {err}"
+                );
+                return Err(err);
+            }
+            tracing::debug!(
+                target: ekg_namespace::consts::LOG_TARGET_DATABASE,
+                conn = connection.number,
+                attempt,
+                "Retrying conflicting read/write transaction: {err}"
+            );
+            std::thread::sleep(policy.backoff(attempt).min(deadline - now));
+        }
+    }
+
+    /// Make the effects of all updates done so far in this transaction
+    /// visible to cursors that are opened after this call, in this same
+    /// transaction.
+    ///
+    /// In all supported RDFox versions, explicitly asserted (or retracted)
+    /// facts are visible to subsequent queries in the same transaction
+    /// straight away, but the facts derived from them by reasoning are only
+    /// brought up-to-date at commit time. So queries with
+    /// [`FactDomain::ASSERTED`](crate::FactDomain::ASSERTED) see their own
+    /// writes without this call, queries that (also) look at derived facts
+    /// do not. This call updates the materialisation so that they do.
+    ///
+    /// Does nothing for a read-only transaction.
+    pub fn flush_writes(self: &Arc<Self>) -> Result<(), ekg_error::Error> {
+        if matches!(
+            self.tx_type,
+            CTransactionType::TRANSACTION_TYPE_READ_ONLY
+        ) {
+            return Ok(());
+        }
+        assert!(!self.connection.inner.is_null());
+        database_call!(
+            format!("Updating the materialisation in {self:}").as_str(),
+            CDataStoreConnection_updateMaterialization(self.connection.inner)
+        )?;
+        tracing::trace!(
+            target: ekg_namespace::consts::LOG_TARGET_DATABASE,
+            txno = self.number,
+            conn = self.connection.number,
+            "Flushed writes of {self:}"
+        );
+        Ok(())
+    }
+
+    /// Evaluate the given update statement in this transaction.
+    ///
+    /// If `immediately_visible` is true then [`Transaction::flush_writes`]
+    /// is called afterwards so that cursors opened later in this
+    /// transaction also see the facts derived from the update.
+    pub fn evaluate_update(
+        self: &Arc<Self>,
+        statement: &Statement,
+        parameters: &Parameters,
+        immediately_visible: bool,
+    ) -> Result<UpdateResult, ekg_error::Error> {
+        self.check_active("evaluating an update")?;
+        let result = self.connection.evaluate_update(statement, parameters)?;
+        if immediately_visible {
+            self.flush_writes()?;
+        }
+        Ok(result)
     }
 
     pub fn commit(self: &Arc<Self>) -> Result<(), ekg_error::Error> {
+        if self.is_nested() {
+            self.committed.store(true, std::sync::atomic::Ordering::Relaxed);
+            return Ok(());
+        }
+        if self.rollback_only.load(std::sync::atomic::Ordering::Relaxed) {
+            self.rollback()?;
+            return Err(ekg_error::Error::Exception {
+                action:  format!("committing {self:}"),
+                message: "a nested transaction was rolled back, so this one was too".to_string(),
+            });
+        }
         if !self.committed.load(std::sync::atomic::Ordering::Relaxed) {
+            self.dependent.check_not_stopped("committing a transaction")?;
             self.committed
                 .store(true, std::sync::atomic::Ordering::Relaxed);
             tracing::trace!(
                 target: ekg_namespace::consts::LOG_TARGET_DATABASE,
                 "Committing {self:}"
             );
-            database_call!(CDataStoreConnection_commitTransaction(
-                self.connection.inner
-            ))?;
+            self.connection
+                .stats
+                .record(database_call!(CDataStoreConnection_commitTransaction(
+                    self.connection.inner
+                )))?;
+            self.connection.stats.transaction_committed();
+            self.connection.instrumentation().on_transaction_commit(&self.event());
             tracing::trace!(
                 target: ekg_namespace::consts::LOG_TARGET_DATABASE,
+                txno = self.number,
+                conn = self.connection.number,
+                duration = ?self.elapsed(),
                 "Committed {self:}",
             );
         }
         Ok(())
     }
 
+    fn event(&self) -> TransactionEvent {
+        TransactionEvent {
+            connection:  self.connection.number,
+            transaction: self.number,
+            duration:    self.elapsed(),
+        }
+    }
+
+    /// Whether the transaction (or the outermost one, if it's nested) has
+    /// been committed or rolled back
+    pub fn has_ended(&self) -> bool {
+        self.committed.load(std::sync::atomic::Ordering::Relaxed) ||
+            self.outer.as_ref().is_some_and(|outer| outer.has_ended())
+    }
+
+    /// Fail with [`TransactionAlreadyFinished`] if this transaction has
+    /// ended, or with [`ServerShutDown`](crate::ServerShutDown) if its
+    /// server has been stopped, before using it for the given action
+    pub(crate) fn check_active(&self, action: &str) -> Result<(), ekg_error::Error> {
+        if self.has_ended() {
+            return Err(TransactionAlreadyFinished {
+                transaction: self.get_title(),
+                action:      action.to_string(),
+            }
+            .into());
+        }
+        self.dependent.check_not_stopped(action)
+    }
+
+    /// Commit and let go of this reference to the transaction, see
+    /// [`Transaction::commit`]. Other clones of the `Arc` can still be used
+    /// afterwards, which fails with
+    /// [`TransactionAlreadyFinished`](crate::TransactionAlreadyFinished)
+    pub fn commit_owned(self: Arc<Self>) -> Result<(), ekg_error::Error> { self.commit() }
+
+    /// Roll back and let go of the transaction, so that it cannot be used
+    /// afterwards, see [`Transaction::rollback`]
+    pub fn rollback_owned(self: Arc<Self>) -> Result<(), ekg_error::Error> { self.rollback() }
+
     pub fn rollback(self: &Arc<Self>) -> Result<(), ekg_error::Error> {
+        if self.is_nested() {
+            if !self.committed.swap(true, std::sync::atomic::Ordering::Relaxed) {
+                self.poison_outer();
+            }
+            return Ok(());
+        }
         if !self.committed.load(std::sync::atomic::Ordering::Relaxed) {
+            self.dependent.check_not_stopped("rolling back a transaction")?;
             self.committed
                 .store(true, std::sync::atomic::Ordering::Relaxed);
             assert!(!self.connection.inner.is_null());
@@ -165,13 +521,18 @@ impl Transaction {
                 conn = self.connection.number,
                 "Rolling back {self:}"
             );
-            database_call!(CDataStoreConnection_rollbackTransaction(
-                self.connection.inner
-            ))?;
+            self.connection
+                .stats
+                .record(database_call!(CDataStoreConnection_rollbackTransaction(
+                    self.connection.inner
+                )))?;
+            self.connection.stats.transaction_rolled_back();
+            self.connection.instrumentation().on_transaction_rollback(&self.event());
             tracing::debug!(
                 target: ekg_namespace::consts::LOG_TARGET_DATABASE,
                 txno = self.number,
                 conn = self.connection.number,
+                duration = ?self.elapsed(),
                 "Rolled back {self:}",
             );
         }
@@ -191,13 +552,18 @@ impl Transaction {
                 conn = self.connection.number,
                 "Rolling back {self:}"
             );
-            database_call!(CDataStoreConnection_rollbackTransaction(
-                self.connection.inner
-            ))?;
+            self.connection
+                .stats
+                .record(database_call!(CDataStoreConnection_rollbackTransaction(
+                    self.connection.inner
+                )))?;
+            self.connection.stats.transaction_rolled_back();
+            self.connection.instrumentation().on_transaction_rollback(&self.event());
             tracing::debug!(
                 target: ekg_namespace::consts::LOG_TARGET_DATABASE,
                 txno = self.number,
                 conn = self.connection.number,
+                duration = ?self.elapsed(),
                 "Rolled back {self:}",
             );
         }
@@ -206,17 +572,25 @@ impl Transaction {
 
     pub fn update_and_commit<T, E: From<ekg_error::Error>, F>(self: &Arc<Self>, f: F) -> Result<T, E>
         where F: FnOnce(Arc<Transaction>) -> Result<T, E> {
+        self.check_active("updating")?;
         let result = f(self.clone());
         if result.is_ok() {
             self.commit()?;
-        } else {
-            self.rollback()?;
+        } else if let Err(rollback_err) = self.rollback() {
+            // the original error is the more useful one
+            tracing::error!(
+                target: ekg_namespace::consts::LOG_TARGET_DATABASE,
+                txno = self.number,
+                conn = self.connection.number,
+                "Could not roll back {self:}: {rollback_err}"
+            );
         }
         result
     }
 
     pub fn execute_and_rollback<T, F>(self: &Arc<Self>, f: F) -> Result<T, ekg_error::Error>
         where F: FnOnce(Arc<Transaction>) -> Result<T, ekg_error::Error> {
+        self.check_active("executing")?;
         let result = f(self.clone());
         match &result {
             Err(err) => {
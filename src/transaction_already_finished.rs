@@ -0,0 +1,69 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// A transaction was used after it had been committed or rolled back, for
+/// instance to read a cursor, see
+/// [`Transaction::commit_owned`](crate::Transaction::commit_owned) to make
+/// that impossible.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionAlreadyFinished {
+    /// The title of the transaction, like `R/W Transaction #3 on connection #1`
+    pub transaction: String,
+    /// What it was used for
+    pub action:      String,
+}
+
+impl Display for TransactionAlreadyFinished {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TransactionAlreadyFinished: {} has been committed or rolled back before {}",
+            self.transaction, self.action
+        )
+    }
+}
+
+impl std::error::Error for TransactionAlreadyFinished {}
+
+impl TypedError for TransactionAlreadyFinished {
+    const NAME: &'static str = "TransactionAlreadyFinished";
+
+    fn action(&self) -> String { self.action.clone() }
+}
+
+impl From<TransactionAlreadyFinished> for ekg_error::Error {
+    fn from(value: TransactionAlreadyFinished) -> Self { value.to_error() }
+}
+
+impl TransactionAlreadyFinished {
+    /// The [`TransactionAlreadyFinished`] error that the given error was made from, if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TransactionAlreadyFinished;
+
+    #[test_log::test]
+    fn test_from_error() {
+        let finished = TransactionAlreadyFinished {
+            transaction: "R/O Transaction #3 on connection #1".to_string(),
+            action:      "opening a cursor".to_string(),
+        };
+        let error: ekg_error::Error = finished.clone().into();
+        let ekg_error::Error::Exception { ref action, ref message } = error else {
+            panic!("unexpected {error:?}");
+        };
+        assert_eq!(action, "opening a cursor");
+        assert!(message.starts_with(finished.to_string().as_str()), "{message}");
+        assert_eq!(TransactionAlreadyFinished::from_error(&error), Some(finished));
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    serde::{de::DeserializeOwned, Serialize},
+    std::fmt::Display,
+};
+
+/// Starts the last line of the message of an `ekg_error::Error::Exception`
+/// that was made from one of our error types, followed by the name of the
+/// type and its fields as JSON
+const TYPED_ERROR_MARKER: &str = "\n[rdfox-rs] ";
+
+/// An error of this crate that is returned as an
+/// `ekg_error::Error::Exception`, with its message followed by a line that
+/// holds its fields so that callers can get it back with its `from_error`
+/// function and match on it, rather than on the text of the message.
+pub(crate) trait TypedError: Display + Serialize + DeserializeOwned {
+    /// The name of the type, which tells the encoded errors apart
+    const NAME: &'static str;
+
+    /// What we were doing at the time
+    fn action(&self) -> String;
+
+    fn to_error(&self) -> ekg_error::Error {
+        let fields = serde_json::to_string(self).unwrap_or_else(|_| "null".to_string());
+        ekg_error::Error::Exception {
+            action:  self.action(),
+            message: format!(
+                "{}{TYPED_ERROR_MARKER}{} {fields}",
+                self.to_string().trim_end(),
+                Self::NAME
+            ),
+        }
+    }
+
+    /// The error of this type that the given error was made from, `None` if
+    /// it's any other error, even one with the same text
+    fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        let ekg_error::Error::Exception { message, .. } = error else {
+            return None;
+        };
+        let (_, encoded) = message.rsplit_once(TYPED_ERROR_MARKER)?;
+        let (name, fields) = encoded.split_once(' ')?;
+        if name != Self::NAME {
+            return None;
+        }
+        serde_json::from_str(fields).ok()
+    }
+}
+
+/// The [`TypedError::NAME`] of the type of error that the given error was
+/// made from, if any
+pub(crate) fn typed_error_name(error: &ekg_error::Error) -> Option<&str> {
+    let ekg_error::Error::Exception { message, .. } = error else {
+        return None;
+    };
+    let (_, encoded) = message.rsplit_once(TYPED_ERROR_MARKER)?;
+    encoded.split_once(' ').map(|(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{typed_error_name, TypedError},
+        serde::{Deserialize, Serialize},
+        std::fmt::{Display, Formatter},
+    };
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Example {
+        text: String,
+    }
+
+    impl Display for Example {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Example: {}", self.text)
+        }
+    }
+
+    impl TypedError for Example {
+        const NAME: &'static str = "Example";
+
+        fn action(&self) -> String { "testing".to_string() }
+    }
+
+    #[test_log::test]
+    fn test_round_trip() {
+        let example = Example { text: "multiple\nlines [rdfox-rs] Other {}".to_string() };
+        let error = example.to_error();
+        let ekg_error::Error::Exception { ref action, ref message } = error else {
+            panic!("unexpected {error:?}");
+        };
+        assert_eq!(action, "testing");
+        assert!(message.starts_with("Example: multiple\nlines"), "{message}");
+        assert_eq!(Example::from_error(&error), Some(example));
+        assert_eq!(typed_error_name(&error), Some("Example"));
+
+        // The same text is not enough
+        let text = ekg_error::Error::Exception {
+            action:  "testing".to_string(),
+            message: "Example: multiple\nlines".to_string(),
+        };
+        assert_eq!(Example::from_error(&text), None);
+        assert_eq!(typed_error_name(&text), None);
+        assert_eq!(Example::from_error(&ekg_error::Error::InvalidPrefixName), None);
+    }
+}
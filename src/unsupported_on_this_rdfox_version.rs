@@ -0,0 +1,49 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {
+    crate::typed_error::TypedError,
+    serde::{Deserialize, Serialize},
+    std::fmt::{Display, Formatter},
+};
+
+/// The C API of the RDFox version that we're linked with lacks what's
+/// needed, see
+/// [`DataStoreConnection::explain_fact`](crate::DataStoreConnection::explain_fact).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsupportedOnThisRDFoxVersion {
+    /// What we were doing at the time
+    pub action:  String,
+    /// What the C API lacks, like `explanation facility`
+    pub missing: String,
+}
+
+impl Display for UnsupportedOnThisRDFoxVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "UnsupportedOnThisRDFoxVersion: the C API of this version of RDFox has no {}",
+            self.missing
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedOnThisRDFoxVersion {}
+
+impl TypedError for UnsupportedOnThisRDFoxVersion {
+    const NAME: &'static str = "UnsupportedOnThisRDFoxVersion";
+
+    fn action(&self) -> String { self.action.clone() }
+}
+
+impl From<UnsupportedOnThisRDFoxVersion> for ekg_error::Error {
+    fn from(value: UnsupportedOnThisRDFoxVersion) -> Self { value.to_error() }
+}
+
+impl UnsupportedOnThisRDFoxVersion {
+    /// The [`UnsupportedOnThisRDFoxVersion`] error that the given error was
+    /// made from, if any
+    pub fn from_error(error: &ekg_error::Error) -> Option<Self> {
+        <Self as TypedError>::from_error(error)
+    }
+}
@@ -0,0 +1,48 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use {crate::rdfox_api::CStatementResult, serde::Serialize, std::fmt::Display};
+
+/// What an update statement did, see
+/// [`DataStoreConnection::evaluate_update`](crate::DataStoreConnection::evaluate_update).
+///
+/// The C API of all supported RDFox versions (`rdfox-6-2` up to and
+/// including `rdfox-7-0`) reports the same two numbers: the type of the
+/// statement and the number of facts it changed. Inserted and deleted facts
+/// are not counted separately, so `DELETE { .. } INSERT { .. }` reports their
+/// sum, and facts derived by reasoning are not counted at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct UpdateResult {
+    /// The type of the statement, as numbered by RDFox
+    pub statement_type: usize,
+    /// The number of explicit facts that were inserted or deleted
+    pub changed_facts:  usize,
+}
+
+impl UpdateResult {
+    pub(crate) fn new(statement_result: &CStatementResult) -> Self {
+        Self { statement_type: statement_result[0], changed_facts: statement_result[1] }
+    }
+}
+
+impl Display for UpdateResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "changed {} fact(s)", self.changed_facts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UpdateResult;
+
+    #[test_log::test]
+    fn test_update_result() {
+        let result = UpdateResult::new(&[1, 3]);
+        assert_eq!(result.changed_facts, 3);
+        assert_eq!(result.to_string(), "changed 3 fact(s)");
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"{"statement_type":1,"changed_facts":3}"#
+        );
+    }
+}
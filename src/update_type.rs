@@ -0,0 +1,32 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+
+use crate::rdfox_api::CUpdateType;
+
+/// How imported data updates a datastore.
+///
+/// The C API of the supported RDFox versions has no "schema only" update
+/// type, import axioms (see
+/// [`DataStoreConnection::import_axioms_from_triples_with_update_type`](crate::DataStoreConnection::import_axioms_from_triples_with_update_type))
+/// to only update the schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UpdateType {
+    /// Add the imported facts (or rules or axioms)
+    #[default]
+    Addition,
+    /// Add the imported facts and also add the prefixes declared in the
+    /// imported content to the prefixes of the datastore
+    AdditionUpdatePrefixes,
+    /// Remove the imported facts (or rules or axioms)
+    Deletion,
+}
+
+impl UpdateType {
+    pub(crate) fn as_c_update_type(&self) -> CUpdateType {
+        match self {
+            UpdateType::Addition => CUpdateType::UPDATE_TYPE_ADDITION,
+            UpdateType::AdditionUpdatePrefixes => CUpdateType::UPDATE_TYPE_ADDITION_UPDATE_PREFIXES,
+            UpdateType::Deletion => CUpdateType::UPDATE_TYPE_DELETION,
+        }
+    }
+}
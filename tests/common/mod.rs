@@ -0,0 +1,56 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+// What the integration tests share: where they find their RDFox server,
+// set with the environment variable `RDFOX_TEST_MODE` (`embedded` by
+// default or `external`).
+#![allow(dead_code)]
+
+/// Where the tests find their RDFox server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestMode {
+    /// Start a local RDFox server in the test process
+    Embedded,
+    /// Use the externally managed RDFox server at `RDFOX_ENDPOINT` (with
+    /// the credentials in `RDFOX_ROLE` and `RDFOX_PASSWORD`) over its REST
+    /// API, which needs feature `remote`
+    External,
+}
+
+pub fn test_mode() -> TestMode {
+    match std::env::var("RDFOX_TEST_MODE").as_deref() {
+        Ok("external") => TestMode::External,
+        Ok("embedded") | Err(_) => TestMode::Embedded,
+        Ok(other) => panic!("RDFOX_TEST_MODE should be embedded or external, not {other}"),
+    }
+}
+
+/// What a test needs from its RDFox server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// The server runs in the test process, so the C API can be used to
+    /// manage it, its transactions and cursors, and its files
+    EmbeddedServer,
+    /// Data stores can be created, loaded and queried, through the
+    /// `DataStoreConnectionLike` trait
+    DataStores,
+}
+
+impl Capability {
+    fn is_available(self) -> bool {
+        match (self, test_mode()) {
+            (_, TestMode::Embedded) => true,
+            (Capability::EmbeddedServer, TestMode::External) => false,
+            (Capability::DataStores, TestMode::External) => cfg!(feature = "remote"),
+        }
+    }
+}
+
+/// Skip the calling test (returning `true`) when the server of the current
+/// [`TestMode`] lacks the given capability, rather than failing it
+pub fn skip_unless(test: &str, capability: Capability) -> bool {
+    if capability.is_available() {
+        return false;
+    }
+    eprintln!("skipped test {test}: {capability:?} is not available in {:?} mode", test_mode());
+    true
+}
@@ -0,0 +1,143 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+// Loading, querying and counting through `DataStoreConnectionLike`, against
+// the embedded server or (with `RDFOX_TEST_MODE=external` and feature
+// `remote`) the externally managed server at `RDFOX_ENDPOINT`, in a process
+// of its own since there can be only one local server at a time.
+
+mod common;
+
+use {
+    common::{Capability, TestMode},
+    ekg_namespace::{consts::TEXT_TURTLE, Graph, Namespace},
+    iref::Iri,
+    rdfox_rs::{
+        DataStore,
+        DataStoreConnectionLike,
+        Namespaces,
+        Parameters,
+        PersistenceMode,
+        RoleCreds,
+        Server,
+        Statement,
+        Term,
+    },
+    std::ops::Deref,
+};
+
+const DATA_STORE: &str = "data-stores-test";
+
+#[test_log::test]
+fn load_query_count() -> Result<(), ekg_error::Error> {
+    if common::skip_unless("load_query_count", Capability::DataStores) {
+        return Ok(());
+    }
+    let data_store = DataStore::declare_with_parameters(DATA_STORE, Parameters::empty()?)?;
+    match common::test_mode() {
+        TestMode::Embedded => {
+            #[cfg(feature = "rdfox-7-0")]
+            let server_params = Parameters::empty()?.persist_datastore(PersistenceMode::Off)?;
+            #[cfg(not(feature = "rdfox-7-0"))]
+            let server_params = Parameters::empty()?
+                .persist_datastore(PersistenceMode::Off)?
+                .persist_roles(PersistenceMode::Off)?;
+            let server =
+                Server::start_with_parameters(RoleCreds::insecure_default(), Some(server_params))?;
+            let server_connection = server.connection_with_default_role()?;
+            server_connection.create_data_store(&data_store)?;
+            let connection = server_connection.connect_to_data_store(&data_store)?;
+            run(&connection)?;
+            drop(connection);
+            server_connection.delete_data_store(&data_store)?;
+        },
+        TestMode::External => {
+            #[cfg(feature = "remote")]
+            {
+                let server = rdfox_rs::remote::RemoteServerConnection::from_env()?
+                    .expect("RDFOX_ENDPOINT should be set with RDFOX_TEST_MODE=external");
+                if server.data_store_exists(DATA_STORE)? {
+                    server.delete_data_store(&data_store)?;
+                }
+                server.create_data_store(&data_store)?;
+                run(server.connect_to_data_store(&data_store).deref())?;
+                server.delete_data_store(&data_store)?;
+            }
+        },
+    }
+    Ok(())
+}
+
+/// The tests that only need data stores, run against either backend
+fn run(connection: &impl DataStoreConnectionLike) -> Result<(), ekg_error::Error> {
+    let graph = Graph::declare(
+        Namespace::declare_iref_iri("graph:", Iri::new("https://ekgf.org/graph/").unwrap())?,
+        "data-stores",
+    );
+    connection.import_data_from_str(
+        "<https://ekgf.org/a> <https://ekgf.org/p> \"one\"@en , 2 .\n\
+         <https://ekgf.org/b> <https://ekgf.org/p> 3 .",
+        TEXT_TURTLE.deref(),
+        &graph,
+    )?;
+    assert_eq!(count(connection, &graph)?, 3);
+
+    let prefixes = Namespaces::empty()?;
+    let select = Statement::new(
+        &prefixes,
+        format!(
+            "SELECT ?o WHERE {{ GRAPH {} {{ <https://ekgf.org/a> ?p ?o }} }} ORDER BY ?o",
+            graph.as_display_iri()
+        )
+        .into(),
+    )?;
+    let result = connection.select(&select, &Parameters::empty()?)?;
+    assert_eq!(result.variables, vec!["o".to_string()]);
+    assert_eq!(result.solutions.len(), 2);
+    assert_eq!(connection.count(&select, &Parameters::empty()?)?, 2);
+    let mut values = Vec::new();
+    let mut collect = |row: &[Option<Term>], _multiplicity: usize| {
+        values.push(row[0].clone());
+        Ok::<(), ekg_error::Error>(())
+    };
+    assert_eq!(connection.consume(&select, &Parameters::empty()?, 1, &mut collect)?, 1);
+    assert_eq!(values.len(), 1);
+
+    static TEXT_CSV: rdfox_rs::Mime = mime::TEXT_CSV;
+    let csv = connection.evaluate_to_string(&select, &TEXT_CSV)?;
+    assert_eq!(csv.lines().next(), Some("o"));
+    assert_eq!(csv.lines().count(), 3);
+    let mut streamed = Vec::new();
+    connection.evaluate_to_stream(&mut streamed, &select, &TEXT_CSV)?;
+    assert_eq!(String::from_utf8_lossy(streamed.as_slice()), csv);
+
+    let delete = Statement::new(
+        &prefixes,
+        format!(
+            "DELETE {{ GRAPH {graph} {{ ?s ?p ?o }} }} WHERE {{ GRAPH {graph} {{ ?s ?p 3 }} }}",
+            graph = graph.as_display_iri()
+        )
+        .into(),
+    )?;
+    connection.update(&delete, &Parameters::empty()?)?;
+    assert_eq!(count(connection, &graph)?, 2);
+    Ok(())
+}
+
+fn count(
+    connection: &impl DataStoreConnectionLike,
+    graph: &Graph,
+) -> Result<usize, ekg_error::Error> {
+    let statement = Statement::new(
+        &Namespaces::empty()?,
+        format!(
+            "SELECT (COUNT(*) AS ?count) WHERE {{ GRAPH {} {{ ?s ?p ?o }} }}",
+            graph.as_display_iri()
+        )
+        .into(),
+    )?;
+    let result = connection.select(&statement, &Parameters::empty()?)?;
+    match result.solutions.first().and_then(|solution| solution.get("count")) {
+        Some(Term::Literal { lexical_form, .. }) => Ok(lexical_form.parse().unwrap()),
+        other => panic!("unexpected count {other:?}"),
+    }
+}
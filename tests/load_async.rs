@@ -0,0 +1,82 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+// The async facades of feature `tokio`, run with
+// `cargo test --features tokio --test load_async`.
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use {
+    common::Capability,
+    ekg_namespace::{Graph, Namespace},
+    iref::Iri,
+    rdfox_rs::{
+        DataStore,
+        FactDomain,
+        Namespaces,
+        Parameters,
+        PersistenceMode,
+        RoleCreds,
+        Server,
+        Statement,
+        Transaction,
+    },
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn load_rdfox_async() -> Result<(), ekg_error::Error> {
+    if common::skip_unless("load_rdfox_async", Capability::EmbeddedServer) {
+        return Ok(());
+    }
+    #[cfg(feature = "rdfox-7-0")]
+    let server_params = Parameters::empty()?.persist_datastore(PersistenceMode::Off)?;
+    #[cfg(not(feature = "rdfox-7-0"))]
+    let server_params = Parameters::empty()?
+        .persist_datastore(PersistenceMode::Off)?
+        .persist_roles(PersistenceMode::Off)?;
+    let server = Server::start_with_parameters(RoleCreds::insecure_default(), Some(server_params))?;
+    let server_connection = server.connection_with_default_role()?;
+    let data_store = DataStore::declare_with_parameters("async", Parameters::empty()?)?;
+    server_connection.create_data_store(&data_store)?;
+    {
+        let conn = server_connection.connect_to_data_store(&data_store)?;
+        let graph_base_iri = Namespace::declare_iref_iri(
+            "graph:",
+            Iri::new("https://whatever.kom/graph/").unwrap(),
+        )?;
+        let graph = Graph::declare(graph_base_iri, "async");
+        conn.import_data_from_file_async("tests/formats/triples.ttl", &graph).await?;
+
+        let statement = Statement::new(
+            &Namespaces::empty()?,
+            format!(
+                "SELECT ?s ?p ?o WHERE {{ GRAPH {} {{ ?s ?p ?o }} }}",
+                graph.as_display_iri()
+            )
+            .into(),
+        )?;
+        let rows = statement
+            .evaluate_async(&conn, Parameters::empty()?.fact_domain(FactDomain::ASSERTED)?, 100)
+            .await?;
+        assert_eq!(rows.len(), 4);
+        assert!(rows.iter().all(|row| row.values.len() == 3));
+        assert!(statement
+            .evaluate_async(&conn, Parameters::empty()?, 2)
+            .await
+            .is_err());
+
+        // The same cursor, consumed twice in one transaction
+        let parameters = Parameters::empty()?;
+        let cursor = statement.cursor(&conn, &parameters)?;
+        let tx = Transaction::begin_read_only(&conn)?;
+        let (cursor, first) = cursor.consume_async(&tx, 100).await?;
+        let (_, second) = cursor.consume_async(&tx, 100).await?;
+        tx.rollback()?;
+        assert_eq!(first.len(), 4);
+        assert_eq!(
+            format!("{:?}", first.iter().map(|row| &row.values).collect::<Vec<_>>()),
+            format!("{:?}", second.iter().map(|row| &row.values).collect::<Vec<_>>())
+        );
+    }
+    server_connection.delete_data_store(&data_store)
+}
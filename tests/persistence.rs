@@ -0,0 +1,93 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+// Maintenance of persisted data stores, in a process of its own since its
+// server persists to a temporary directory.
+
+mod common;
+
+use {
+    common::Capability,
+    ekg_namespace::{Graph, Namespace},
+    iref::Iri,
+    rdfox_rs::{DataStore, Parameters, PersistenceMode, PersistenceRequired, RoleCreds, Server},
+    std::path::Path,
+};
+
+/// The total size of all files in the given directory and below
+fn size_of_files(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        size_of_files(path.as_path())
+                    } else {
+                        entry.metadata().map_or(0, |metadata| metadata.len())
+                    }
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+#[test_log::test]
+fn compact_persisted_data_store() -> Result<(), ekg_error::Error> {
+    if common::skip_unless("compact_persisted_data_store", Capability::EmbeddedServer) {
+        return Ok(());
+    }
+    let server_directory =
+        std::env::temp_dir().join(format!("rdfox-rs-persistence-{}", std::process::id()));
+    std::fs::create_dir_all(&server_directory).unwrap();
+
+    #[cfg(feature = "rdfox-7-0")]
+    let server_params = Parameters::empty()?
+        .persist_datastore(PersistenceMode::File)?
+        .server_directory(server_directory.as_path())?;
+    #[cfg(not(feature = "rdfox-7-0"))]
+    let server_params = Parameters::empty()?
+        .persist_datastore(PersistenceMode::File)?
+        .persist_roles(PersistenceMode::Off)?
+        .server_directory(server_directory.as_path())?;
+    let server = Server::start_with_parameters(RoleCreds::insecure_default(), Some(server_params))?;
+    let server_connection = server.connection_with_default_role()?;
+    assert_eq!(server.persistence(), PersistenceMode::File);
+
+    let data_store = DataStore::declare_with_parameters(
+        "persisted",
+        Parameters::empty()?.persist_datastore(PersistenceMode::File)?,
+    )?;
+    server_connection.create_data_store(&data_store)?;
+    let in_memory = DataStore::declare_with_parameters(
+        "in-memory",
+        Parameters::empty()?.persist_datastore(PersistenceMode::Off)?,
+    )?;
+    server_connection.create_data_store(&in_memory)?;
+    {
+        let conn = server_connection.connect_to_data_store(&data_store)?;
+        let graph_base_iri = Namespace::declare_iref_iri(
+            "graph:",
+            Iri::new("https://whatever.kom/graph/").unwrap(),
+        )?;
+        let graph = Graph::declare(graph_base_iri, "persisted");
+        conn.import_data_from_file("tests/formats/triples.ttl", &graph)?;
+        conn.compact()?;
+        assert!(
+            size_of_files(server_directory.as_path()) > 0,
+            "no persisted files in {}",
+            server_directory.display()
+        );
+
+        let conn = server_connection.connect_to_data_store(&in_memory)?;
+        assert_eq!(conn.persistence(), PersistenceMode::Off);
+        let refused = PersistenceRequired::from_error(&conn.compact().unwrap_err()).unwrap();
+        assert_eq!(refused.data_store, in_memory.name);
+    }
+    server_connection.delete_data_store(&in_memory)?;
+    server_connection.delete_data_store(&data_store)?;
+    drop(server_connection);
+    server.stop();
+    std::fs::remove_dir_all(&server_directory).unwrap();
+    Ok(())
+}
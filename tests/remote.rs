@@ -0,0 +1,79 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+// The REST API backend of feature `remote`, run against an externally
+// managed RDFox server with
+// `RDFOX_TEST_ENDPOINT=http://localhost:12110 cargo test --features remote --test remote`
+// (and `RDFOX_TEST_ROLE` and `RDFOX_TEST_PASSWORD` unless it's admin/admin).
+#![cfg(feature = "remote")]
+
+use {
+    ekg_namespace::{consts::TEXT_TURTLE, Graph, Namespace},
+    iref::Iri,
+    rdfox_rs::{
+        remote::RemoteServerConnection,
+        DataStore,
+        DataStoreConnectionLike,
+        Namespaces,
+        Parameters,
+        RoleCreds,
+        Statement,
+    },
+    std::ops::Deref,
+};
+
+#[test_log::test]
+fn remote_rdfox() -> Result<(), ekg_error::Error> {
+    let Ok(endpoint) = std::env::var("RDFOX_TEST_ENDPOINT") else {
+        eprintln!("skipped test remote_rdfox: RDFOX_TEST_ENDPOINT is not set");
+        return Ok(());
+    };
+    let role_creds = RoleCreds::new(
+        std::env::var("RDFOX_TEST_ROLE").as_deref().unwrap_or("admin"),
+        std::env::var("RDFOX_TEST_PASSWORD").as_deref().unwrap_or("admin"),
+    );
+    let server = RemoteServerConnection::connect(endpoint.as_str(), &role_creds);
+    let data_store = DataStore::declare_with_parameters("remote-test", Parameters::empty()?)?;
+    if server.data_store_exists(data_store.name.as_str())? {
+        server.delete_data_store(&data_store)?;
+    }
+    server.create_data_store(&data_store)?;
+    assert!(server.list_data_stores()?.contains(&data_store.name));
+
+    let conn = server.connect_to_data_store(&data_store);
+    let graph = Graph::declare(
+        Namespace::declare_iref_iri("graph:", Iri::new("https://ekgf.org/graph/").unwrap())?,
+        "remote",
+    );
+    conn.import_data_from_str(
+        "<https://ekgf.org/a> <https://ekgf.org/p> \"one\"@en , 2 .",
+        TEXT_TURTLE.deref(),
+        &graph,
+    )?;
+    let prefixes = Namespaces::empty()?;
+    let select = Statement::new(
+        &prefixes,
+        format!(
+            "SELECT ?s ?o WHERE {{ GRAPH {} {{ ?s ?p ?o }} }} ORDER BY ?o",
+            graph.as_display_iri()
+        )
+        .into(),
+    )?;
+    let result = conn.select(&select, &Parameters::empty()?)?;
+    assert_eq!(result.variables, vec!["s".to_string(), "o".to_string()]);
+    assert_eq!(result.solutions.len(), 2);
+
+    let delete = Statement::new(
+        &prefixes,
+        format!(
+            "DELETE {{ GRAPH {graph} {{ ?s ?p ?o }} }} WHERE {{ GRAPH {graph} {{ ?s ?p 2 }} }}",
+            graph = graph.as_display_iri()
+        )
+        .into(),
+    )?;
+    conn.update(&delete, &Parameters::empty()?)?;
+    assert_eq!(conn.select(&select, &Parameters::empty()?)?.solutions.len(), 1);
+
+    server.delete_data_store(&data_store)?;
+    assert!(!server.data_store_exists(data_store.name.as_str())?);
+    Ok(())
+}
@@ -0,0 +1,102 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+// Starting, stopping and restarting the local server, in a process of its
+// own since there can be only one local server at a time.
+
+mod common;
+
+use {
+    common::Capability,
+    rdfox_rs::{
+        DataStore,
+        Parameters,
+        PersistenceMode,
+        RoleCreds,
+        Server,
+        ServerAlreadyRunning,
+        ServerInUse,
+        ServerState,
+    },
+    std::path::{Path, PathBuf},
+};
+
+fn server_parameters(server_directory: &Path) -> Result<Parameters, ekg_error::Error> {
+    #[cfg(feature = "rdfox-7-0")]
+    let parameters = Parameters::empty()?
+        .persist_datastore(PersistenceMode::File)?
+        .server_directory(server_directory)?;
+    #[cfg(not(feature = "rdfox-7-0"))]
+    let parameters = Parameters::empty()?
+        .persist_datastore(PersistenceMode::File)?
+        .persist_roles(PersistenceMode::Off)?
+        .server_directory(server_directory)?;
+    Ok(parameters)
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rdfox-rs-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Create a data store, which the server persists in its server directory
+fn create_data_store(server: &std::sync::Arc<Server>, name: &str) -> Result<(), ekg_error::Error> {
+    let server_connection = server.connection_with_default_role()?;
+    let data_store = DataStore::declare_with_parameters(name, Parameters::empty()?)?;
+    server_connection.create_data_store(&data_store)?;
+    assert!(server_connection.list_data_stores()?.contains(&name.to_string()));
+    Ok(())
+}
+
+#[test_log::test]
+fn start_stop_and_restart_server() -> Result<(), ekg_error::Error> {
+    if common::skip_unless("start_stop_and_restart_server", Capability::EmbeddedServer) {
+        return Ok(());
+    }
+    let (first, second) = (temp_dir("first"), temp_dir("second"));
+
+    let server = Server::start_with_parameters(
+        RoleCreds::insecure_default(),
+        Some(server_parameters(first.as_path())?),
+    )?;
+    assert_eq!(server.server_directory(), Some(first.as_path()));
+    create_data_store(&server, "first")?;
+
+    // Only one local server at a time
+    let err = Server::start(RoleCreds::insecure_default()).unwrap_err();
+    assert_eq!(ServerAlreadyRunning::from_error(&err), Some(ServerAlreadyRunning), "{err:?}");
+    assert!(server.is_running());
+
+    // Not while it's still in use
+    let server_connection = server.connection_with_default_role()?;
+    let err = server.restart(Some(server_parameters(second.as_path())?)).unwrap_err();
+    assert_eq!(ServerInUse::from_error(&err), Some(ServerInUse { dependents: 1 }), "{err:?}");
+    assert!(server.is_running());
+    drop(server_connection);
+
+    // Restart with another server directory, which starts out empty
+    let restarted = server.restart(Some(server_parameters(second.as_path())?))?;
+    assert_eq!(server.state(), ServerState::Stopped);
+    assert!(restarted.is_running());
+    assert_eq!(restarted.server_directory(), Some(second.as_path()));
+    let server_connection = restarted.connection_with_default_role()?;
+    assert!(!server_connection.list_data_stores()?.contains(&"first".to_string()));
+    drop(server_connection);
+    create_data_store(&restarted, "second")?;
+
+    // Stop, and start again in the first directory, which still has its
+    // data store
+    restarted.stop();
+    let server = Server::start_with_parameters(
+        RoleCreds::insecure_default(),
+        Some(server_parameters(first.as_path())?),
+    )?;
+    let server_connection = server.connection_with_default_role()?;
+    assert!(server_connection.list_data_stores()?.contains(&"first".to_string()));
+    drop(server_connection);
+    server.stop();
+
+    std::fs::remove_dir_all(&first).unwrap();
+    std::fs::remove_dir_all(&second).unwrap();
+    Ok(())
+}
@@ -0,0 +1,59 @@
+// Copyright (c) 2018-2023, agnos.ai UK Ltd, all rights reserved.
+//---------------------------------------------------------------
+// Using and dropping connections, transactions and cursors after their
+// server has been stopped, in a process of its own since there can be only
+// one local server at a time.
+
+mod common;
+
+use {
+    common::Capability,
+    rdfox_rs::{
+        DataStore,
+        Namespaces,
+        Parameters,
+        RoleCreds,
+        Server,
+        ServerShutDown,
+        ServerState,
+        Statement,
+        Transaction,
+    },
+};
+
+#[test_log::test]
+fn drop_dependents_after_stop() -> Result<(), ekg_error::Error> {
+    if common::skip_unless("drop_dependents_after_stop", Capability::EmbeddedServer) {
+        return Ok(());
+    }
+    let server = Server::start(RoleCreds::insecure_default())?;
+    let server_connection = server.connection_with_default_role()?;
+    let data_store = DataStore::declare_with_parameters("stopped", Parameters::empty()?)?;
+    server_connection.create_data_store(&data_store)?;
+    let connection = server_connection.connect_to_data_store(&data_store)?;
+    let tx = Transaction::begin_read_only(&connection)?;
+    let statement = Statement::new(
+        &Namespaces::empty()?,
+        "SELECT ?s WHERE { ?s ?p ?o }".into(),
+    )?;
+    let mut cursor = statement.cursor(&connection, &Parameters::empty()?)?;
+    assert_eq!(cursor.count(&tx)?, 0);
+
+    server.stop();
+    assert_eq!(server.state(), ServerState::Stopped);
+
+    // Everything that would call into RDFox fails...
+    let stopped = |err: ekg_error::Error| ServerShutDown::from_error(&err).map(|err| err.state);
+    assert_eq!(stopped(cursor.count(&tx).unwrap_err()), Some(ServerState::Stopped));
+    assert_eq!(stopped(connection.statistics().unwrap_err()), Some(ServerState::Stopped));
+    assert_eq!(stopped(server_connection.get_version().unwrap_err()), Some(ServerState::Stopped));
+
+    // ...and dropping it leaks rather than destroys what RDFox has already
+    // freed, without rolling back the transaction
+    drop(cursor);
+    drop(tx);
+    drop(connection);
+    drop(server_connection);
+    assert_eq!(server.number_of_dependents(), 0);
+    Ok(())
+}